@@ -0,0 +1,118 @@
+//! The running app's local HTTP API. It applies the same rules as the app:
+//! workspaces, sharing, secret scanning and private snippets.
+
+use reqwest::{Client, Method, RequestBuilder};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::{
+    error::{CliError, CliResult},
+    model::{Snippet, SnippetFilter, SnippetForm, Tag},
+};
+
+pub const DEFAULT_API_URL: &str = "http://127.0.0.1:27183/api/v1";
+
+pub struct ApiBackend {
+    client: Client,
+    base_url: String,
+    token: String,
+}
+
+/// The `IpcResponse` envelope every route answers with.
+#[derive(Deserialize)]
+struct Envelope<T> {
+    result: Option<EnvelopeResult<T>>,
+    error: Option<EnvelopeError>,
+}
+
+#[derive(Deserialize)]
+struct EnvelopeResult<T> {
+    data: T,
+}
+
+#[derive(Deserialize)]
+struct EnvelopeError {
+    message: String,
+}
+
+#[derive(Serialize)]
+struct TagForm<'a> {
+    name: &'a str,
+    color: Option<String>,
+}
+
+impl ApiBackend {
+    pub fn new(base_url: &str, token: String) -> Self {
+        Self {
+            client: Client::new(),
+            base_url: base_url.trim_end_matches('/').to_owned(),
+            token,
+        }
+    }
+
+    fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        self.client
+            .request(method, format!("{}{}", self.base_url, path))
+            .bearer_auth(&self.token)
+    }
+
+    async fn send<T: DeserializeOwned>(&self, request: RequestBuilder) -> CliResult<T> {
+        let response = request.send().await.map_err(|e| {
+            if e.is_connect() {
+                CliError::Api(format!(
+                    "Could not reach the app at {}; is it running with the local API enabled?",
+                    self.base_url
+                ))
+            } else {
+                e.into()
+            }
+        })?;
+        let envelope: Envelope<T> = response.json().await?;
+        match (envelope.result, envelope.error) {
+            (Some(result), _) => Ok(result.data),
+            (None, Some(error)) => Err(CliError::Api(error.message)),
+            (None, None) => Err(CliError::Api("The app sent an empty response".into())),
+        }
+    }
+
+    pub async fn list_snippets(&self, filter: SnippetFilter) -> CliResult<Vec<Snippet>> {
+        self.send(self.request(Method::POST, "/snippets/search").json(&filter))
+            .await
+    }
+
+    pub async fn get_snippet(&self, id: &str) -> CliResult<Snippet> {
+        let snippet: Option<Snippet> = self
+            .send(self.request(Method::GET, &format!("/snippets/{}", id)))
+            .await?;
+        snippet.ok_or_else(|| CliError::NotFound(id.to_owned()))
+    }
+
+    pub async fn create_snippet(&self, form: SnippetForm) -> CliResult<Snippet> {
+        self.send(self.request(Method::POST, "/snippets").json(&form))
+            .await
+    }
+
+    pub async fn update_snippet(&self, id: &str, form: SnippetForm) -> CliResult<Snippet> {
+        self.send(
+            self.request(Method::PUT, &format!("/snippets/{}", id))
+                .json(&form),
+        )
+        .await
+    }
+
+    pub async fn delete_snippet(&self, id: &str) -> CliResult<Snippet> {
+        self.send(self.request(Method::DELETE, &format!("/snippets/{}", id)))
+            .await
+    }
+
+    pub async fn list_tags(&self) -> CliResult<Vec<Tag>> {
+        self.send(self.request(Method::GET, "/tags")).await
+    }
+
+    pub async fn create_tag(&self, name: &str) -> CliResult<Tag> {
+        self.send(
+            self.request(Method::POST, "/tags")
+                .json(&TagForm { name, color: None }),
+        )
+        .await
+    }
+}
@@ -0,0 +1,275 @@
+//! Direct access to the MongoDB vault through Prisma, using `DATABASE_URL`,
+//! for when the app isn't running.
+//!
+//! There is no signed-in user here: every snippet of the chosen workspace
+//! can be read, as its owner could. Changes are made as the user named by
+//! email, who becomes the author of new snippets and must be able to write
+//! to the workspace; without one nothing is changed. Private snippets stay
+//! encrypted and can't be edited, and nothing is mirrored to git sync; use
+//! the app's API for those.
+
+use prisma::{PrismaClient, WorkspaceRole};
+use serde::{de::DeserializeOwned, Serialize};
+use snippet_vault_shared::languages;
+
+use crate::{
+    error::{CliError, CliResult},
+    model::{Snippet, SnippetFilter, SnippetForm, Tag},
+};
+
+pub struct DatabaseBackend {
+    client: PrismaClient,
+    /// None for the default workspace.
+    workspace_id: Option<String>,
+    /// Id of the user changes are made as.
+    author_id: Option<String>,
+}
+
+/// Converts a Prisma record into the API's shape, which it serializes to.
+fn to_model<D: Serialize, M: DeserializeOwned>(data: D) -> CliResult<M> {
+    Ok(serde_json::from_value(serde_json::to_value(data)?)?)
+}
+
+impl DatabaseBackend {
+    /// Opens the database as the user with email `user`, if any. The user
+    /// must exist and, in a workspace, be a member who may write to it.
+    pub async fn connect(workspace_id: Option<String>, user: Option<String>) -> CliResult<Self> {
+        let client = PrismaClient::_builder().build().await?;
+        let author_id = match user {
+            Some(email) => Some(find_author(&client, &email, workspace_id.as_deref()).await?),
+            None => None,
+        };
+        Ok(Self {
+            client,
+            workspace_id,
+            author_id,
+        })
+    }
+
+    /// The user changes are made as, refusing to change anything without one
+    /// so that no snippet is left without an author.
+    fn author_id(&self) -> CliResult<&str> {
+        self.author_id.as_deref().ok_or_else(|| {
+            CliError::Other(
+                "Changing the database directly needs a user: set --user or SNIPPET_VAULT_USER \
+                 to your email, or use an API token"
+                    .into(),
+            )
+        })
+    }
+
+    /// Records from before workspaces have no workspaceId at all, which an
+    /// equality filter on null doesn't match, so the default workspace is
+    /// checked after the query.
+    fn in_workspace(&self, workspace_id: Option<&str>) -> bool {
+        self.workspace_id.as_deref() == workspace_id
+    }
+
+    async fn find_snippet(&self, id: &str) -> CliResult<prisma::snippet::Data> {
+        self.client
+            .snippet()
+            .find_unique(prisma::snippet::id::equals(id.to_owned()))
+            .with(prisma::snippet::state::fetch())
+            .exec()
+            .await?
+            .filter(|snippet| self.in_workspace(snippet.workspace_id.as_deref()))
+            .ok_or_else(|| CliError::NotFound(id.to_owned()))
+    }
+
+    pub async fn list_snippets(&self, filter: SnippetFilter) -> CliResult<Vec<Snippet>> {
+        let mut where_params = vec![];
+        if let Some(id) = &self.workspace_id {
+            where_params.push(prisma::snippet::workspace_id::equals(Some(id.clone())));
+        }
+        if let Some(search) = filter.search {
+            where_params.push(prisma::or![
+                prisma::snippet::title::contains(search.clone()),
+                prisma::snippet::description::contains(search.clone()),
+                prisma::snippet::code::contains(search.clone()),
+                prisma::snippet::language::contains(search),
+            ]);
+        }
+        if let Some(language) = filter.language {
+            where_params.push(prisma::snippet::language::equals(languages::normalize(
+                &language,
+            )));
+        }
+        if let Some(tags) = filter.tags.filter(|tags| !tags.is_empty()) {
+            where_params.push(prisma::snippet::tags::some(vec![
+                prisma::tag::name::in_vec(tags),
+            ]));
+        }
+        if let Some(is_favorite) = filter.state.and_then(|state| state.is_favorite) {
+            where_params.push(prisma::snippet::state::is(vec![
+                prisma::snippet_state::is_favorite::equals(is_favorite),
+            ]));
+        }
+
+        let snippets = self
+            .client
+            .snippet()
+            .find_many(where_params)
+            .with(prisma::snippet::state::fetch())
+            .exec()
+            .await?;
+        snippets
+            .into_iter()
+            .filter(|snippet| self.in_workspace(snippet.workspace_id.as_deref()))
+            .map(to_model)
+            .collect()
+    }
+
+    pub async fn get_snippet(&self, id: &str) -> CliResult<Snippet> {
+        to_model(self.find_snippet(id).await?)
+    }
+
+    pub async fn create_snippet(&self, form: SnippetForm) -> CliResult<Snippet> {
+        let author_id = self.author_id()?.to_owned();
+        let language = languages::resolve(&form.language, &form.code);
+        let state = self.client.snippet_state().create(vec![]).exec().await?;
+        let snippet = self
+            .client
+            .snippet()
+            .create(
+                form.title,
+                form.description,
+                language,
+                form.code,
+                prisma::snippet_state::id::equals(state.id),
+                vec![
+                    prisma::snippet::tag_ids::set(form.tag_ids),
+                    prisma::snippet::workspace_id::set(self.workspace_id.clone()),
+                    prisma::snippet::author::connect(prisma::user::id::equals(author_id)),
+                ],
+            )
+            .exec()
+            .await?;
+        self.get_snippet(&snippet.id).await
+    }
+
+    pub async fn update_snippet(&self, id: &str, form: SnippetForm) -> CliResult<Snippet> {
+        self.author_id()?;
+        let language = languages::resolve(&form.language, &form.code);
+        let existing: Snippet = to_model(self.find_snippet(id).await?)?;
+        if existing.is_private() {
+            return Err(CliError::Other(
+                "Private snippets can only be edited through the app".into(),
+            ));
+        }
+        self.client
+            .snippet()
+            .update(
+                prisma::snippet::id::equals(id.to_owned()),
+                vec![
+                    prisma::snippet::title::set(form.title),
+                    prisma::snippet::description::set(form.description),
+                    prisma::snippet::language::set(language),
+                    prisma::snippet::code::set(form.code),
+                    prisma::snippet::tag_ids::set(form.tag_ids),
+                ],
+            )
+            .exec()
+            .await?;
+        self.get_snippet(id).await
+    }
+
+    /// Deletes the snippet and, as the app does, takes it out of collections
+    /// and drops its shares.
+    pub async fn delete_snippet(&self, id: &str) -> CliResult<Snippet> {
+        self.author_id()?;
+        let snippet: Snippet = to_model(self.find_snippet(id).await?)?;
+        self.client
+            .snippet()
+            .delete(prisma::snippet::id::equals(id.to_owned()))
+            .exec()
+            .await?;
+
+        let collections = self
+            .client
+            .collection()
+            .find_many(vec![prisma::collection::snippet_ids::has(id.to_owned())])
+            .exec()
+            .await?;
+        for collection in collections {
+            let snippet_ids: Vec<String> = collection
+                .snippet_ids
+                .into_iter()
+                .filter(|snippet_id| snippet_id != id)
+                .collect();
+            self.client
+                .collection()
+                .update(
+                    prisma::collection::id::equals(collection.id),
+                    vec![prisma::collection::snippet_ids::set(snippet_ids)],
+                )
+                .exec()
+                .await?;
+        }
+        self.client
+            .share()
+            .delete_many(vec![prisma::share::snippet_id::equals(Some(id.to_owned()))])
+            .exec()
+            .await?;
+        Ok(snippet)
+    }
+
+    pub async fn list_tags(&self) -> CliResult<Vec<Tag>> {
+        let mut where_params = vec![];
+        if let Some(id) = &self.workspace_id {
+            where_params.push(prisma::tag::workspace_id::equals(Some(id.clone())));
+        }
+        let tags = self.client.tag().find_many(where_params).exec().await?;
+        tags.into_iter()
+            .filter(|tag| self.in_workspace(tag.workspace_id.as_deref()))
+            .map(to_model)
+            .collect()
+    }
+
+    pub async fn create_tag(&self, name: &str) -> CliResult<Tag> {
+        self.author_id()?;
+        let tag = self
+            .client
+            .tag()
+            .create(
+                name.to_owned(),
+                vec![prisma::tag::workspace_id::set(self.workspace_id.clone())],
+            )
+            .exec()
+            .await?;
+        to_model(tag)
+    }
+}
+
+/// The id of the user with `email`, checking that they may write to the
+/// workspace: anyone may write to the default workspace, but viewers of a
+/// shared one may not.
+async fn find_author(
+    client: &PrismaClient,
+    email: &str,
+    workspace_id: Option<&str>,
+) -> CliResult<String> {
+    let user = client
+        .user()
+        .find_unique(prisma::user::email::equals(email.to_owned()))
+        .exec()
+        .await?
+        .ok_or_else(|| CliError::Other(format!("No user with email {}", email)))?;
+
+    if let Some(workspace_id) = workspace_id {
+        let membership = client
+            .workspace_membership()
+            .find_first(vec![
+                prisma::workspace_membership::workspace_id::equals(workspace_id.to_owned()),
+                prisma::workspace_membership::user_id::equals(user.id.clone()),
+            ])
+            .exec()
+            .await?;
+        if !membership.is_some_and(|membership| membership.role != WorkspaceRole::Viewer) {
+            return Err(CliError::Other(format!(
+                "{} may not change workspace {}",
+                email, workspace_id
+            )));
+        }
+    }
+    Ok(user.id)
+}
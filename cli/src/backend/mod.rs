@@ -0,0 +1,109 @@
+//! Where the CLI reads and writes snippets: the running app's local API, or
+//! the database directly when the app isn't running.
+
+pub mod api;
+pub mod database;
+
+use crate::{
+    error::CliResult,
+    model::{Snippet, SnippetFilter, SnippetForm, Tag},
+};
+
+pub use api::ApiBackend;
+pub use database::DatabaseBackend;
+
+pub enum Backend {
+    Api(ApiBackend),
+    Database(DatabaseBackend),
+}
+
+impl Backend {
+    /// The app's API when there is a token for it, otherwise the database,
+    /// changed as the user with email `user`.
+    pub async fn connect(
+        token: Option<String>,
+        api_url: &str,
+        workspace_id: Option<String>,
+        user: Option<String>,
+    ) -> CliResult<Self> {
+        Ok(match token {
+            Some(token) => Backend::Api(ApiBackend::new(api_url, token)),
+            None => Backend::Database(DatabaseBackend::connect(workspace_id, user).await?),
+        })
+    }
+
+    pub async fn list_snippets(&self, filter: SnippetFilter) -> CliResult<Vec<Snippet>> {
+        match self {
+            Backend::Api(api) => api.list_snippets(filter).await,
+            Backend::Database(database) => database.list_snippets(filter).await,
+        }
+    }
+
+    pub async fn get_snippet(&self, id: &str) -> CliResult<Snippet> {
+        match self {
+            Backend::Api(api) => api.get_snippet(id).await,
+            Backend::Database(database) => database.get_snippet(id).await,
+        }
+    }
+
+    pub async fn create_snippet(&self, form: SnippetForm) -> CliResult<Snippet> {
+        match self {
+            Backend::Api(api) => api.create_snippet(form).await,
+            Backend::Database(database) => database.create_snippet(form).await,
+        }
+    }
+
+    pub async fn update_snippet(&self, id: &str, form: SnippetForm) -> CliResult<Snippet> {
+        match self {
+            Backend::Api(api) => api.update_snippet(id, form).await,
+            Backend::Database(database) => database.update_snippet(id, form).await,
+        }
+    }
+
+    pub async fn delete_snippet(&self, id: &str) -> CliResult<Snippet> {
+        match self {
+            Backend::Api(api) => api.delete_snippet(id).await,
+            Backend::Database(database) => database.delete_snippet(id).await,
+        }
+    }
+
+    pub async fn list_tags(&self) -> CliResult<Vec<Tag>> {
+        match self {
+            Backend::Api(api) => api.list_tags().await,
+            Backend::Database(database) => database.list_tags().await,
+        }
+    }
+
+    async fn create_tag(&self, name: &str) -> CliResult<Tag> {
+        match self {
+            Backend::Api(api) => api.create_tag(name).await,
+            Backend::Database(database) => database.create_tag(name).await,
+        }
+    }
+
+    /// The ids of the named tags, creating those that don't exist yet.
+    /// Names match case-insensitively.
+    pub async fn resolve_tag_ids(&self, names: &[String]) -> CliResult<Vec<String>> {
+        let mut tags = self.list_tags().await?;
+        let mut ids = vec![];
+        for name in names {
+            let name = name.trim();
+            if name.is_empty() {
+                continue;
+            }
+            let id = match tags.iter().find(|tag| tag.name.eq_ignore_ascii_case(name)) {
+                Some(tag) => tag.id.clone(),
+                None => {
+                    let tag = self.create_tag(name).await?;
+                    let id = tag.id.clone();
+                    tags.push(tag);
+                    id
+                }
+            };
+            if !ids.contains(&id) {
+                ids.push(id);
+            }
+        }
+        Ok(ids)
+    }
+}
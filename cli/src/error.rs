@@ -0,0 +1,31 @@
+#[derive(Debug, thiserror::Error)]
+pub enum CliError {
+    #[error("HTTP error: {0}")]
+    HttpError(#[from] reqwest::Error),
+    #[error("IO error: {0}")]
+    IoError(#[from] std::io::Error),
+    #[error("Prisma client error: {0}")]
+    PrismaClientError(#[from] prisma::NewClientError),
+    #[error("Prisma query error: {0}")]
+    PrismaQueryError(#[from] prisma::QueryError),
+    #[error("Serde error: {0}")]
+    SerdeError(#[from] serde_json::Error),
+    #[error("Highlighting error: {0}")]
+    HighlightError(#[from] syntect::Error),
+
+    /// An error the app's API answered with.
+    #[error("{0}")]
+    Api(String),
+    #[error("Snippet {0} not found")]
+    NotFound(String),
+    #[error("Error: {0}")]
+    Other(String),
+}
+
+impl From<&str> for CliError {
+    fn from(s: &str) -> Self {
+        CliError::Other(s.to_string())
+    }
+}
+
+pub type CliResult<T> = Result<T, CliError>;
@@ -0,0 +1,8 @@
+//! What the `snippet-vault` command-line client and the
+//! `snippet-vault-lsp` language server share: the vault backends, the
+//! records and terminal output.
+
+pub mod backend;
+pub mod error;
+pub mod model;
+pub mod output;
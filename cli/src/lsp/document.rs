@@ -0,0 +1,87 @@
+//! The open documents, kept in sync with full-text changes, so that code
+//! actions can read the selected text.
+
+use std::collections::HashMap;
+
+use snippet_vault_shared::languages;
+use tokio::sync::Mutex;
+use tower_lsp::lsp_types::{Position, Range, Url};
+
+/// The vault language for an editor's language id. The vault uses Monaco's
+/// ids, which mostly match the ones editors send; the registry knows the
+/// others as aliases.
+pub fn vault_language(language_id: &str) -> String {
+    languages::normalize(language_id)
+}
+
+pub struct Document {
+    pub language_id: String,
+    pub text: String,
+}
+
+impl Document {
+    /// The text between two positions, which count UTF-16 code units as the
+    /// protocol does.
+    pub fn text_in(&self, range: Range) -> Option<&str> {
+        let start = self.offset_of(range.start)?;
+        let end = self.offset_of(range.end)?;
+        self.text.get(start.min(end)..start.max(end))
+    }
+
+    fn offset_of(&self, position: Position) -> Option<usize> {
+        let mut line_start = 0;
+        for _ in 0..position.line {
+            line_start += self.text[line_start..].find('\n')? + 1;
+        }
+        let line = &self.text[line_start..];
+        let line = &line[..line.find('\n').unwrap_or(line.len())];
+
+        let mut units = 0;
+        for (index, c) in line.char_indices() {
+            if units >= position.character as usize {
+                return Some(line_start + index);
+            }
+            units += c.len_utf16();
+        }
+        // Positions past the end of a line mean its end.
+        Some(line_start + line.len())
+    }
+}
+
+#[derive(Default)]
+pub struct Documents(Mutex<HashMap<Url, Document>>);
+
+impl Documents {
+    pub async fn open(&self, uri: Url, language_id: String, text: String) {
+        self.0
+            .lock()
+            .await
+            .insert(uri, Document { language_id, text });
+    }
+
+    pub async fn change(&self, uri: &Url, text: String) {
+        if let Some(document) = self.0.lock().await.get_mut(uri) {
+            document.text = text;
+        }
+    }
+
+    pub async fn close(&self, uri: &Url) {
+        self.0.lock().await.remove(uri);
+    }
+
+    pub async fn language_id(&self, uri: &Url) -> Option<String> {
+        self.0
+            .lock()
+            .await
+            .get(uri)
+            .map(|document| document.language_id.clone())
+    }
+
+    /// The selected text and the document's language id.
+    pub async fn selection(&self, uri: &Url, range: Range) -> Option<(String, String)> {
+        let documents = self.0.lock().await;
+        let document = documents.get(uri)?;
+        let text = document.text_in(range)?;
+        Some((text.to_owned(), document.language_id.clone()))
+    }
+}
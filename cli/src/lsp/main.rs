@@ -0,0 +1,436 @@
+//! `snippet-vault-lsp`: vault snippets in any editor with a language client.
+//!
+//! Snippets in the document's language are offered as completions, and a
+//! code action saves the selection as a new snippet. The vault is reached as
+//! the CLI reaches it: through the running app's local API when there is a
+//! token, set in `initializationOptions` or `SNIPPET_VAULT_TOKEN`, or
+//! through the database in `DATABASE_URL` otherwise. Saving needs the API,
+//! so the snippet gets the same checks as one saved in the app.
+
+mod document;
+
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use serde::Deserialize;
+use serde_json::Value;
+use snippet_vault_shared::placeholders::{self, has_placeholders};
+use tokio::sync::{Mutex, OnceCell};
+use tower_lsp::{
+    jsonrpc::{self, ErrorCode},
+    lsp_types::*,
+    Client, LanguageServer, LspService, Server,
+};
+
+use snippet_vault_cli::{
+    backend::{api::DEFAULT_API_URL, Backend},
+    error::CliError,
+    model::{Snippet, SnippetFilter, SnippetForm},
+};
+
+use document::{vault_language, Documents};
+
+const SAVE_SELECTION_COMMAND: &str = "snippetVault.saveSelection";
+
+/// How long listed snippets are reused for completions before the vault is
+/// asked again.
+const SNIPPET_CACHE_TTL: Duration = Duration::from_secs(30);
+
+/// Titles of saved selections are their first line, cut to this many
+/// characters.
+const MAX_TITLE_LENGTH: usize = 60;
+
+/// What the client may pass as `initializationOptions`; anything left out
+/// is read from the environment, as the CLI reads it.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct InitializationOptions {
+    token: Option<String>,
+    api_url: Option<String>,
+    workspace: Option<String>,
+}
+
+struct SnippetServer {
+    client: Client,
+    backend: OnceCell<Backend>,
+    documents: Documents,
+    snippets: Mutex<Option<(Instant, Arc<Vec<Snippet>>)>>,
+    /// Whether the client expands placeholders in completions.
+    snippet_support: AtomicBool,
+}
+
+fn rpc_error(error: CliError) -> jsonrpc::Error {
+    jsonrpc::Error {
+        code: ErrorCode::InternalError,
+        message: error.to_string().into(),
+        data: None,
+    }
+}
+
+/// The title for a saved selection: its first non-blank line.
+fn title_for(code: &str, uri: &Url) -> String {
+    let line = code
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .unwrap_or_default();
+    if line.chars().count() > MAX_TITLE_LENGTH {
+        let cut: String = line.chars().take(MAX_TITLE_LENGTH - 1).collect();
+        format!("{}…", cut.trim_end())
+    } else if line.is_empty() {
+        file_name(uri)
+    } else {
+        line.to_owned()
+    }
+}
+
+fn file_name(uri: &Url) -> String {
+    uri.path_segments()
+        .and_then(|mut segments| segments.next_back())
+        .filter(|name| !name.is_empty())
+        .unwrap_or("Untitled")
+        .to_owned()
+}
+
+impl SnippetServer {
+    /// Requests before `initialize` are answered with the protocol's
+    /// ServerNotInitialized code.
+    fn backend(&self) -> jsonrpc::Result<&Backend> {
+        self.backend.get().ok_or_else(|| jsonrpc::Error {
+            code: ErrorCode::ServerError(-32002),
+            message: "The server isn't initialized".into(),
+            data: None,
+        })
+    }
+
+    /// Every snippet of the vault, listed at most once per
+    /// [`SNIPPET_CACHE_TTL`].
+    async fn snippets(&self) -> jsonrpc::Result<Arc<Vec<Snippet>>> {
+        let mut cache = self.snippets.lock().await;
+        if let Some((listed_at, snippets)) = cache.as_ref() {
+            if listed_at.elapsed() < SNIPPET_CACHE_TTL {
+                return Ok(snippets.clone());
+            }
+        }
+        let snippets = Arc::new(
+            self.backend()?
+                .list_snippets(SnippetFilter::default())
+                .await
+                .map_err(rpc_error)?,
+        );
+        *cache = Some((Instant::now(), snippets.clone()));
+        Ok(snippets)
+    }
+
+    fn completion_item(&self, snippet: &Snippet) -> CompletionItem {
+        let (insert_text, format) = if !has_placeholders(&snippet.code) {
+            (snippet.code.clone(), InsertTextFormat::PLAIN_TEXT)
+        } else if self.snippet_support.load(Ordering::Relaxed) {
+            (snippet.code.clone(), InsertTextFormat::SNIPPET)
+        } else {
+            (
+                placeholders::render(&snippet.code),
+                InsertTextFormat::PLAIN_TEXT,
+            )
+        };
+        CompletionItem {
+            label: snippet.title.clone(),
+            kind: Some(CompletionItemKind::SNIPPET),
+            detail: Some(format!("{} snippet", snippet.language)),
+            // Favorites are listed first.
+            sort_text: Some(format!(
+                "{}{}",
+                if snippet.is_favorite() { 0 } else { 1 },
+                snippet.title.to_lowercase()
+            )),
+            filter_text: Some(snippet.title.clone()),
+            insert_text: Some(insert_text),
+            insert_text_format: Some(format),
+            data: Some(Value::String(snippet.id.clone())),
+            ..CompletionItem::default()
+        }
+    }
+
+    /// Saves the selected text as a snippet in the document's language,
+    /// through the app's API, as the app's "New snippet" does.
+    async fn save_selection(&self, uri: Url, range: Range) -> jsonrpc::Result<Snippet> {
+        let api = match self.backend()? {
+            Backend::Api(api) => api,
+            Backend::Database(_) => {
+                return Err(jsonrpc::Error {
+                    code: ErrorCode::InvalidRequest,
+                    message: "Saving snippets needs the running app: set an API token in \
+                              initializationOptions or SNIPPET_VAULT_TOKEN"
+                        .into(),
+                    data: None,
+                })
+            }
+        };
+        let (code, language_id) = self
+            .documents
+            .selection(&uri, range)
+            .await
+            .filter(|(code, _)| !code.trim().is_empty())
+            .ok_or_else(|| jsonrpc::Error::invalid_params("Nothing is selected"))?;
+        let form = SnippetForm {
+            title: title_for(&code, &uri),
+            description: format!("Saved from {}", file_name(&uri)),
+            language: vault_language(&language_id),
+            code,
+            tag_ids: vec![],
+            snippet_state_id: String::new(),
+        };
+        let snippet = api.create_snippet(form).await.map_err(rpc_error)?;
+        *self.snippets.lock().await = None;
+        Ok(snippet)
+    }
+}
+
+#[tower_lsp::async_trait]
+impl LanguageServer for SnippetServer {
+    async fn initialize(&self, params: InitializeParams) -> jsonrpc::Result<InitializeResult> {
+        let options: InitializationOptions = params
+            .initialization_options
+            .map(serde_json::from_value)
+            .transpose()
+            .map_err(|e| jsonrpc::Error::invalid_params(e.to_string()))?
+            .unwrap_or_default();
+        let token = options
+            .token
+            .or_else(|| std::env::var("SNIPPET_VAULT_TOKEN").ok());
+        let api_url = options
+            .api_url
+            .or_else(|| std::env::var("SNIPPET_VAULT_API_URL").ok())
+            .unwrap_or_else(|| DEFAULT_API_URL.to_owned());
+        let workspace = options
+            .workspace
+            .or_else(|| std::env::var("SNIPPET_VAULT_WORKSPACE").ok());
+        // Saving goes through the API, so the database is only read.
+        let backend = Backend::connect(token, &api_url, workspace, None)
+            .await
+            .map_err(rpc_error)?;
+        // A client that initializes twice keeps its first vault.
+        let _ = self.backend.set(backend);
+
+        let snippet_support = params
+            .capabilities
+            .text_document
+            .and_then(|text_document| text_document.completion)
+            .and_then(|completion| completion.completion_item)
+            .and_then(|item| item.snippet_support)
+            .unwrap_or(false);
+        self.snippet_support
+            .store(snippet_support, Ordering::Relaxed);
+
+        Ok(InitializeResult {
+            capabilities: ServerCapabilities {
+                text_document_sync: Some(TextDocumentSyncCapability::Kind(
+                    TextDocumentSyncKind::FULL,
+                )),
+                completion_provider: Some(CompletionOptions {
+                    resolve_provider: Some(true),
+                    ..CompletionOptions::default()
+                }),
+                code_action_provider: Some(CodeActionProviderCapability::Options(
+                    CodeActionOptions {
+                        code_action_kinds: Some(vec![CodeActionKind::REFACTOR_EXTRACT]),
+                        ..CodeActionOptions::default()
+                    },
+                )),
+                execute_command_provider: Some(ExecuteCommandOptions {
+                    commands: vec![SAVE_SELECTION_COMMAND.to_owned()],
+                    ..ExecuteCommandOptions::default()
+                }),
+                ..ServerCapabilities::default()
+            },
+            server_info: Some(ServerInfo {
+                name: "snippet-vault-lsp".to_owned(),
+                version: Some(env!("CARGO_PKG_VERSION").to_owned()),
+            }),
+        })
+    }
+
+    async fn shutdown(&self) -> jsonrpc::Result<()> {
+        Ok(())
+    }
+
+    async fn did_open(&self, params: DidOpenTextDocumentParams) {
+        let document = params.text_document;
+        self.documents
+            .open(document.uri, document.language_id, document.text)
+            .await;
+    }
+
+    async fn did_change(&self, params: DidChangeTextDocumentParams) {
+        // With full sync, the last change holds the whole text.
+        if let Some(change) = params.content_changes.into_iter().last() {
+            self.documents
+                .change(&params.text_document.uri, change.text)
+                .await;
+        }
+    }
+
+    async fn did_close(&self, params: DidCloseTextDocumentParams) {
+        self.documents.close(&params.text_document.uri).await;
+    }
+
+    async fn completion(
+        &self,
+        params: CompletionParams,
+    ) -> jsonrpc::Result<Option<CompletionResponse>> {
+        let uri = params.text_document_position.text_document.uri;
+        let Some(language_id) = self.documents.language_id(&uri).await else {
+            return Ok(None);
+        };
+        let language = vault_language(&language_id);
+
+        let snippets = match self.snippets().await {
+            Ok(snippets) => snippets,
+            Err(e) => {
+                // Completions are asked for on every keystroke; a vault that
+                // can't be reached is logged rather than shown each time.
+                self.client
+                    .log_message(MessageType::WARNING, e.message)
+                    .await;
+                return Ok(None);
+            }
+        };
+        let items = snippets
+            .iter()
+            // Private snippets stay encrypted outside the app.
+            .filter(|snippet| snippet.language == language && !snippet.is_private())
+            .map(|snippet| self.completion_item(snippet))
+            .collect();
+        Ok(Some(CompletionResponse::Array(items)))
+    }
+
+    async fn completion_resolve(
+        &self,
+        mut item: CompletionItem,
+    ) -> jsonrpc::Result<CompletionItem> {
+        let Some(Value::String(id)) = item.data.as_ref() else {
+            return Ok(item);
+        };
+        let cached = self
+            .snippets()
+            .await?
+            .iter()
+            .find(|snippet| &snippet.id == id)
+            .cloned();
+        let snippet = match cached {
+            Some(snippet) => snippet,
+            None => self.backend()?.get_snippet(id).await.map_err(rpc_error)?,
+        };
+
+        let mut documentation = String::new();
+        if !snippet.description.trim().is_empty() {
+            documentation.push_str(snippet.description.trim_end());
+            documentation.push_str("\n\n");
+        }
+        documentation.push_str(&format!(
+            "```{}\n{}\n```",
+            snippet.language,
+            placeholders::render(snippet.code.trim_end())
+        ));
+        item.documentation = Some(Documentation::MarkupContent(MarkupContent {
+            kind: MarkupKind::Markdown,
+            value: documentation,
+        }));
+        Ok(item)
+    }
+
+    async fn code_action(
+        &self,
+        params: CodeActionParams,
+    ) -> jsonrpc::Result<Option<CodeActionResponse>> {
+        if params.range.start == params.range.end {
+            return Ok(None);
+        }
+        let arguments = vec![
+            serde_json::to_value(&params.text_document.uri).unwrap_or_default(),
+            serde_json::to_value(params.range).unwrap_or_default(),
+        ];
+        let action = CodeAction {
+            title: "Save selection as snippet".to_owned(),
+            kind: Some(CodeActionKind::REFACTOR_EXTRACT),
+            command: Some(Command {
+                title: "Save selection as snippet".to_owned(),
+                command: SAVE_SELECTION_COMMAND.to_owned(),
+                arguments: Some(arguments),
+            }),
+            ..CodeAction::default()
+        };
+        Ok(Some(vec![CodeActionOrCommand::CodeAction(action)]))
+    }
+
+    async fn execute_command(
+        &self,
+        params: ExecuteCommandParams,
+    ) -> jsonrpc::Result<Option<Value>> {
+        if params.command != SAVE_SELECTION_COMMAND {
+            return Err(jsonrpc::Error::invalid_params(format!(
+                "Unknown command {}",
+                params.command
+            )));
+        }
+        let (uri, range): (Url, Range) = serde_json::from_value(Value::Array(params.arguments))
+            .map_err(|e| jsonrpc::Error::invalid_params(e.to_string()))?;
+
+        match self.save_selection(uri, range).await {
+            Ok(snippet) => {
+                self.client
+                    .show_message(
+                        MessageType::INFO,
+                        format!("Saved \"{}\" to the vault", snippet.title),
+                    )
+                    .await;
+                if !snippet.secret_findings.is_empty() {
+                    let lines: Vec<String> = snippet
+                        .secret_findings
+                        .iter()
+                        .map(|finding| finding.line.to_string())
+                        .collect();
+                    self.client
+                        .show_message(
+                            MessageType::WARNING,
+                            format!(
+                                "\"{}\" may contain credentials on line {}",
+                                snippet.title,
+                                lines.join(", ")
+                            ),
+                        )
+                        .await;
+                }
+                Ok(Some(Value::String(snippet.id)))
+            }
+            Err(e) => {
+                self.client
+                    .show_message(
+                        MessageType::ERROR,
+                        format!("Couldn't save the snippet: {}", e.message),
+                    )
+                    .await;
+                Err(e)
+            }
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() {
+    let (service, socket) = LspService::new(|client| SnippetServer {
+        client,
+        backend: OnceCell::new(),
+        documents: Documents::default(),
+        snippets: Mutex::new(None),
+        snippet_support: AtomicBool::new(false),
+    });
+    Server::new(tokio::io::stdin(), tokio::io::stdout(), socket)
+        .serve(service)
+        .await;
+}
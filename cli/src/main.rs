@@ -0,0 +1,396 @@
+//! `snippet-vault`: the vault from the terminal.
+//!
+//! With an API token (`--token` or `SNIPPET_VAULT_TOKEN`, created in the
+//! app) it talks to the running app's local API; without one it opens the
+//! database in `DATABASE_URL` directly, changing it as the user given by
+//! `--user` or `SNIPPET_VAULT_USER`.
+
+use std::{
+    io::{IsTerminal, Read, Write},
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use clap::{Args, Parser, Subcommand};
+
+use snippet_vault_cli::{
+    backend::{api::DEFAULT_API_URL, Backend},
+    error::{CliError, CliResult},
+    model::{ExportFile, ExportedSnippet, SnippetFilter, SnippetForm, StateFilter, EXPORT_VERSION},
+    output::{extension_for, Output},
+};
+
+#[derive(Parser)]
+#[command(
+    name = "snippet-vault",
+    version,
+    about = "Work with the Snippet Vault from the terminal"
+)]
+struct Cli {
+    /// API token for the running app's local API. Without one, the
+    /// database in DATABASE_URL is used directly.
+    #[arg(
+        long,
+        env = "SNIPPET_VAULT_TOKEN",
+        global = true,
+        hide_env_values = true
+    )]
+    token: Option<String>,
+    /// Base URL of the app's local API.
+    #[arg(long, env = "SNIPPET_VAULT_API_URL", global = true, default_value = DEFAULT_API_URL)]
+    api_url: String,
+    /// Workspace to use with direct database access; the default workspace
+    /// when left out. Tokens carry their own workspace.
+    #[arg(long, env = "SNIPPET_VAULT_WORKSPACE", global = true)]
+    workspace: Option<String>,
+    /// Email of the user to make changes as with direct database access;
+    /// new snippets are theirs. Without one the database is only read.
+    #[arg(long, env = "SNIPPET_VAULT_USER", global = true)]
+    user: Option<String>,
+    /// Print JSON instead of text.
+    #[arg(long, global = true)]
+    json: bool,
+    /// Don't color the output, even on a terminal.
+    #[arg(long, global = true)]
+    no_color: bool,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// List snippets.
+    List(FilterArgs),
+    /// Find snippets whose title, description, code or language contain the
+    /// query.
+    Search {
+        query: String,
+        #[command(flatten)]
+        filter: FilterArgs,
+    },
+    /// Print a snippet.
+    Show {
+        id: String,
+        /// Print only the code, without color.
+        #[arg(long)]
+        raw: bool,
+    },
+    /// Add a snippet from a file, or from stdin when no file is given.
+    Add {
+        file: Option<PathBuf>,
+        /// Defaults to the file name.
+        #[arg(long)]
+        title: Option<String>,
+        #[arg(long, default_value = "")]
+        description: String,
+        /// Defaults to the file extension, or is detected by the app.
+        #[arg(long)]
+        language: Option<String>,
+        #[arg(long = "tag")]
+        tags: Vec<String>,
+    },
+    /// Edit a snippet's code in $VISUAL or $EDITOR.
+    Edit { id: String },
+    /// Delete snippets.
+    Rm {
+        #[arg(required = true)]
+        ids: Vec<String>,
+    },
+    /// Add or remove tags of a snippet, or list them.
+    Tag {
+        id: String,
+        #[arg(long)]
+        add: Vec<String>,
+        #[arg(long)]
+        remove: Vec<String>,
+    },
+    /// Write snippets to a JSON file, or to stdout.
+    Export {
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+        #[command(flatten)]
+        filter: FilterArgs,
+    },
+    /// Add the snippets of a file written by `export`.
+    Import { file: PathBuf },
+}
+
+#[derive(Args)]
+struct FilterArgs {
+    #[arg(long)]
+    language: Option<String>,
+    /// Only snippets with this tag; may be repeated to match any of them.
+    #[arg(long = "tag")]
+    tags: Vec<String>,
+    /// Only favorites.
+    #[arg(long)]
+    favorites: bool,
+}
+
+impl FilterArgs {
+    fn into_filter(self, search: Option<String>) -> SnippetFilter {
+        SnippetFilter {
+            search,
+            language: self.language,
+            tags: if self.tags.is_empty() {
+                None
+            } else {
+                Some(self.tags)
+            },
+            state: if self.favorites {
+                Some(StateFilter {
+                    is_favorite: Some(true),
+                })
+            } else {
+                None
+            },
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+    match run(cli).await {
+        Ok(()) => ExitCode::SUCCESS,
+        // The reader went away, as with `snippet-vault export | head`.
+        Err(CliError::IoError(e)) if e.kind() == std::io::ErrorKind::BrokenPipe => {
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("snippet-vault: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn run(cli: Cli) -> CliResult<()> {
+    let backend = Backend::connect(cli.token, &cli.api_url, cli.workspace, cli.user).await?;
+    let color = !cli.no_color && !cli.json && std::io::stdout().is_terminal();
+    let output = Output::new(cli.json, color, &backend.list_tags().await?);
+
+    match cli.command {
+        Command::List(filter) => {
+            let snippets = backend.list_snippets(filter.into_filter(None)).await?;
+            output.snippets(&snippets)
+        }
+        Command::Search { query, filter } => {
+            let snippets = backend
+                .list_snippets(filter.into_filter(Some(query)))
+                .await?;
+            output.snippets(&snippets)
+        }
+        Command::Show { id, raw } => {
+            let snippet = backend.get_snippet(&id).await?;
+            if raw {
+                std::io::stdout().write_all(snippet.code.as_bytes())?;
+                Ok(())
+            } else {
+                output.snippet(&snippet)
+            }
+        }
+        Command::Add {
+            file,
+            title,
+            description,
+            language,
+            tags,
+        } => {
+            let (code, file_title, file_language) = read_source(file.as_deref())?;
+            let title = title
+                .or(file_title)
+                .ok_or("Give a --title for snippets read from stdin")?;
+            let form = SnippetForm {
+                title,
+                description,
+                language: language.or(file_language).unwrap_or_default(),
+                code,
+                tag_ids: backend.resolve_tag_ids(&tags).await?,
+                snippet_state_id: String::new(),
+            };
+            let snippet = backend.create_snippet(form).await?;
+            output.secret_warnings(&snippet);
+            if output.json {
+                output.print_json(&snippet)
+            } else {
+                println!("Added {} ({})", snippet.title, snippet.id);
+                Ok(())
+            }
+        }
+        Command::Edit { id } => {
+            let snippet = backend.get_snippet(&id).await?;
+            if snippet.is_private() {
+                return Err("Unlock the vault in the app to edit private snippets".into());
+            }
+            let code = edit_in_editor(&snippet.code, &snippet.language)?;
+            if code == snippet.code {
+                println!("No changes to {}", snippet.title);
+                return Ok(());
+            }
+            let mut form = SnippetForm::from(&snippet);
+            form.code = code;
+            let snippet = backend.update_snippet(&id, form).await?;
+            output.secret_warnings(&snippet);
+            if output.json {
+                output.print_json(&snippet)
+            } else {
+                println!("Saved {}", snippet.title);
+                Ok(())
+            }
+        }
+        Command::Rm { ids } => {
+            let mut deleted = vec![];
+            for id in ids {
+                deleted.push(backend.delete_snippet(&id).await?);
+            }
+            if output.json {
+                return output.print_json(&deleted);
+            }
+            for snippet in deleted {
+                println!("Deleted {} ({})", snippet.title, snippet.id);
+            }
+            Ok(())
+        }
+        Command::Tag { id, add, remove } => {
+            let snippet = backend.get_snippet(&id).await?;
+            if add.is_empty() && remove.is_empty() {
+                let tags = output.tag_names(&snippet);
+                return if output.json {
+                    output.print_json(&tags)
+                } else {
+                    tags.iter().for_each(|tag| println!("{}", tag));
+                    Ok(())
+                };
+            }
+            if snippet.is_private() {
+                return Err("Unlock the vault in the app to retag private snippets".into());
+            }
+
+            let mut names: Vec<String> = output
+                .tag_names(&snippet)
+                .into_iter()
+                .filter(|name| !remove.iter().any(|r| r.eq_ignore_ascii_case(name)))
+                .collect();
+            names.extend(add);
+            let mut form = SnippetForm::from(&snippet);
+            form.tag_ids = backend.resolve_tag_ids(&names).await?;
+            let snippet = backend.update_snippet(&id, form).await?;
+            let output = Output::new(output.json, output.color, &backend.list_tags().await?);
+            output.snippet(&snippet)
+        }
+        Command::Export {
+            output: path,
+            filter,
+        } => {
+            let snippets = backend.list_snippets(filter.into_filter(None)).await?;
+            let mut exported = vec![];
+            for snippet in &snippets {
+                if snippet.is_private() {
+                    eprintln!(
+                        "Skipping private snippet {}; unlock the vault in the app to export it",
+                        snippet.title
+                    );
+                    continue;
+                }
+                exported.push(ExportedSnippet {
+                    title: snippet.title.clone(),
+                    description: snippet.description.clone(),
+                    language: snippet.language.clone(),
+                    code: snippet.code.clone(),
+                    tags: output.tag_names(snippet),
+                });
+            }
+            let count = exported.len();
+            let file = ExportFile {
+                version: EXPORT_VERSION,
+                snippets: exported,
+            };
+            match path {
+                Some(path) => {
+                    std::fs::write(&path, serde_json::to_string_pretty(&file)?)?;
+                    eprintln!("Exported {} snippets to {}", count, path.display());
+                    Ok(())
+                }
+                None => output.print_json(&file),
+            }
+        }
+        Command::Import { file } => {
+            let file: ExportFile = serde_json::from_str(&std::fs::read_to_string(&file)?)?;
+            if file.version > EXPORT_VERSION {
+                return Err(CliError::Other(format!(
+                    "Export version {} is newer than the supported version {}",
+                    file.version, EXPORT_VERSION
+                )));
+            }
+            let mut created = vec![];
+            for snippet in file.snippets {
+                let form = SnippetForm {
+                    title: snippet.title,
+                    description: snippet.description,
+                    language: snippet.language,
+                    code: snippet.code,
+                    tag_ids: backend.resolve_tag_ids(&snippet.tags).await?,
+                    snippet_state_id: String::new(),
+                };
+                let snippet = backend.create_snippet(form).await?;
+                output.secret_warnings(&snippet);
+                created.push(snippet);
+            }
+            if output.json {
+                output.print_json(&created)
+            } else {
+                println!("Imported {} snippets", created.len());
+                Ok(())
+            }
+        }
+    }
+}
+
+/// The code to add, with the title and language its file name suggests.
+fn read_source(file: Option<&Path>) -> CliResult<(String, Option<String>, Option<String>)> {
+    match file {
+        Some(path) => {
+            let code = std::fs::read_to_string(path)?;
+            let title = path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned());
+            let language = path
+                .extension()
+                .map(|extension| extension.to_string_lossy().to_lowercase());
+            Ok((code, title, language))
+        }
+        None => {
+            let mut code = String::new();
+            std::io::stdin().read_to_string(&mut code)?;
+            Ok((code, None, None))
+        }
+    }
+}
+
+/// Opens the code in the user's editor and returns it once the editor
+/// exits.
+fn edit_in_editor(code: &str, language: &str) -> CliResult<String> {
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".to_owned());
+    let file = tempfile::Builder::new()
+        .prefix("snippet-")
+        .suffix(&format!(".{}", extension_for(language)))
+        .tempfile()?;
+    std::fs::write(file.path(), code)?;
+
+    // EDITOR may carry arguments, such as `code --wait`.
+    let mut words = editor.split_whitespace();
+    let program = words.next().ok_or("EDITOR is empty")?;
+    let status = std::process::Command::new(program)
+        .args(words)
+        .arg(file.path())
+        .status()?;
+    if !status.success() {
+        return Err(CliError::Other(format!(
+            "{} exited with {}",
+            program, status
+        )));
+    }
+    Ok(std::fs::read_to_string(file.path())?)
+}
@@ -0,0 +1,140 @@
+//! The records as the app's API returns them. Records read straight from
+//! the database are converted through the same JSON shape.
+
+use chrono::{DateTime, FixedOffset};
+use serde::{Deserialize, Serialize};
+
+/// Marks code and descriptions encrypted by the app; they can only be read
+/// through the app while the vault is unlocked.
+const ENCRYPTED_PREFIX: &str = "$svault$v1$";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Snippet {
+    pub id: String,
+    pub created_at: DateTime<FixedOffset>,
+    pub updated_at: DateTime<FixedOffset>,
+    pub title: String,
+    pub description: String,
+    pub language: String,
+    pub code: String,
+    pub tag_ids: Vec<String>,
+    pub snippet_state_id: String,
+    #[serde(default)]
+    pub state: Option<SnippetState>,
+    #[serde(default)]
+    pub workspace_id: Option<String>,
+    /// What the app found in the code that looks like a credential; only
+    /// set on snippets it just saved.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub secret_findings: Vec<SecretFinding>,
+}
+
+impl Snippet {
+    pub fn is_private(&self) -> bool {
+        self.code.starts_with(ENCRYPTED_PREFIX)
+    }
+
+    pub fn is_favorite(&self) -> bool {
+        self.state.as_ref().is_some_and(|state| state.is_favorite)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SecretFinding {
+    pub description: String,
+    pub line: usize,
+    pub column: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SnippetState {
+    pub id: String,
+    pub is_favorite: bool,
+    pub is_dark: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Tag {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub color: Option<String>,
+    #[serde(default)]
+    pub parent_id: Option<String>,
+}
+
+/// The fields of a snippet that can be written, as the app's `SnippetForm`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SnippetForm {
+    pub title: String,
+    pub description: String,
+    /// Detected from the code by the app when empty.
+    pub language: String,
+    pub code: String,
+    pub tag_ids: Vec<String>,
+    /// Empty for new snippets.
+    pub snippet_state_id: String,
+}
+
+impl From<&Snippet> for SnippetForm {
+    fn from(snippet: &Snippet) -> Self {
+        Self {
+            title: snippet.title.clone(),
+            description: snippet.description.clone(),
+            language: snippet.language.clone(),
+            code: snippet.code.clone(),
+            tag_ids: snippet.tag_ids.clone(),
+            snippet_state_id: snippet.snippet_state_id.clone(),
+        }
+    }
+}
+
+/// Which snippets to list, as the app's `SnippetFilter`.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SnippetFilter {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub search: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    /// Tag names; snippets with any of them match.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tags: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub state: Option<StateFilter>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StateFilter {
+    pub is_favorite: Option<bool>,
+}
+
+/// The file `export` writes and `import` reads. Tags are kept by name, so
+/// the file can move between vaults.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportFile {
+    pub version: u32,
+    pub snippets: Vec<ExportedSnippet>,
+}
+
+pub const EXPORT_VERSION: u32 = 1;
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportedSnippet {
+    pub title: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub language: String,
+    pub code: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
@@ -0,0 +1,194 @@
+//! Printing snippets as text, with highlighted code on terminals, or as
+//! JSON for scripts.
+
+use std::{
+    collections::HashMap,
+    io::{self, Write},
+};
+
+use serde::Serialize;
+use syntect::{
+    easy::HighlightLines,
+    highlighting::{Theme, ThemeSet},
+    parsing::{SyntaxReference, SyntaxSet},
+    util::{as_24_bit_terminal_escaped, LinesWithEndings},
+};
+
+use crate::{
+    error::CliResult,
+    model::{Snippet, Tag},
+};
+
+const ANSI_RESET: &str = "\x1b[0m";
+const ANSI_BOLD: &str = "\x1b[1m";
+const ANSI_DIM: &str = "\x1b[2m";
+const DEFAULT_THEME: &str = "base16-ocean.dark";
+
+/// Languages without a bundled grammar, highlighted with a close relative,
+/// as the app does.
+const GRAMMAR_FALLBACKS: &[(&str, &str)] = &[("typescript", "js"), ("scss", "css")];
+
+pub struct Output {
+    pub json: bool,
+    pub color: bool,
+    tag_names: HashMap<String, String>,
+}
+
+impl Output {
+    pub fn new(json: bool, color: bool, tags: &[Tag]) -> Self {
+        Self {
+            json,
+            color,
+            tag_names: tags
+                .iter()
+                .map(|tag| (tag.id.clone(), tag.name.clone()))
+                .collect(),
+        }
+    }
+
+    pub fn tag_names(&self, snippet: &Snippet) -> Vec<String> {
+        snippet
+            .tag_ids
+            .iter()
+            .filter_map(|id| self.tag_names.get(id).cloned())
+            .collect()
+    }
+
+    fn style(&self, code: &str, text: &str) -> String {
+        if self.color {
+            format!("{}{}{}", code, text, ANSI_RESET)
+        } else {
+            text.to_owned()
+        }
+    }
+
+    /// Warns on stderr about credentials the app found in a snippet it
+    /// saved, so they show up even when the output is piped.
+    pub fn secret_warnings(&self, snippet: &Snippet) {
+        for finding in &snippet.secret_findings {
+            eprintln!(
+                "warning: {}: {} on line {}, column {}",
+                snippet.title, finding.description, finding.line, finding.column
+            );
+        }
+    }
+
+    pub fn print_json<T: Serialize>(&self, value: &T) -> CliResult<()> {
+        writeln!(io::stdout(), "{}", serde_json::to_string_pretty(value)?)?;
+        Ok(())
+    }
+
+    /// One line per snippet: id, language, title and tags.
+    pub fn snippets(&self, snippets: &[Snippet]) -> CliResult<()> {
+        if self.json {
+            return self.print_json(&snippets);
+        }
+        let mut stdout = io::stdout().lock();
+        for snippet in snippets {
+            let tags = self.tag_names(snippet);
+            writeln!(
+                stdout,
+                "{}  {:<12} {}{}{}",
+                self.style(ANSI_DIM, &snippet.id),
+                snippet.language,
+                snippet.title,
+                if snippet.is_favorite() { " ★" } else { "" },
+                if tags.is_empty() {
+                    String::new()
+                } else {
+                    self.style(ANSI_DIM, &format!("  [{}]", tags.join(", ")))
+                }
+            )?;
+        }
+        Ok(())
+    }
+
+    pub fn snippet(&self, snippet: &Snippet) -> CliResult<()> {
+        if self.json {
+            return self.print_json(snippet);
+        }
+        println!("{}", self.style(ANSI_BOLD, &snippet.title));
+        println!(
+            "{}",
+            self.style(ANSI_DIM, &format!("id:       {}", snippet.id))
+        );
+        println!(
+            "{}",
+            self.style(ANSI_DIM, &format!("language: {}", snippet.language))
+        );
+        let tags = self.tag_names(snippet);
+        if !tags.is_empty() {
+            println!(
+                "{}",
+                self.style(ANSI_DIM, &format!("tags:     {}", tags.join(", ")))
+            );
+        }
+        if snippet.is_private() {
+            println!();
+            println!("Private snippet; unlock the vault in the app to read it.");
+            return Ok(());
+        }
+        if !snippet.description.trim().is_empty() {
+            println!();
+            println!("{}", snippet.description.trim_end());
+        }
+        println!();
+        self.code(snippet)
+    }
+
+    /// Only the code, for piping into other programs.
+    pub fn code(&self, snippet: &Snippet) -> CliResult<()> {
+        let code = if self.color && !snippet.is_private() {
+            code_to_ansi(&snippet.code, &snippet.language)?
+        } else {
+            snippet.code.clone()
+        };
+        let mut stdout = io::stdout().lock();
+        write!(stdout, "{}", code)?;
+        if !code.ends_with('\n') {
+            writeln!(stdout)?;
+        }
+        Ok(())
+    }
+}
+
+fn syntax_for<'a>(syntaxes: &'a SyntaxSet, language: &str) -> &'a SyntaxReference {
+    let fallback = GRAMMAR_FALLBACKS
+        .iter()
+        .find(|(id, _)| *id == language)
+        .map(|(_, token)| *token);
+    syntaxes
+        .find_syntax_by_token(language)
+        .or_else(|| fallback.and_then(|token| syntaxes.find_syntax_by_token(token)))
+        .unwrap_or_else(|| syntaxes.find_syntax_plain_text())
+}
+
+fn theme() -> Theme {
+    let mut themes = ThemeSet::load_defaults();
+    themes.themes.remove(DEFAULT_THEME).unwrap_or_default()
+}
+
+/// The code with 24-bit color escapes, for terminals.
+pub fn code_to_ansi(code: &str, language: &str) -> CliResult<String> {
+    let syntaxes = SyntaxSet::load_defaults_newlines();
+    let theme = theme();
+    let mut highlighter = HighlightLines::new(syntax_for(&syntaxes, language), &theme);
+
+    let mut output = String::new();
+    for line in LinesWithEndings::from(code) {
+        let ranges = highlighter.highlight_line(line, &syntaxes)?;
+        output.push_str(&as_24_bit_terminal_escaped(&ranges, false));
+    }
+    output.push_str(ANSI_RESET);
+    Ok(output)
+}
+
+/// The usual file extension for a language, for naming temporary files.
+pub fn extension_for(language: &str) -> String {
+    let syntaxes = SyntaxSet::load_defaults_newlines();
+    syntax_for(&syntaxes, language)
+        .file_extensions
+        .first()
+        .cloned()
+        .unwrap_or_else(|| "txt".to_owned())
+}
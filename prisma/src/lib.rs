@@ -2,4 +2,4 @@
 mod prisma;
 
 pub use prisma::*;
-pub use prisma_client_rust::{or, Direction, NewClientError, QueryError};
+pub use prisma_client_rust::{not, or, Direction, NewClientError, QueryError};
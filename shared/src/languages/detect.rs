@@ -0,0 +1,276 @@
+//! Guesses the language of a piece of code. A file name or shebang settles
+//! it; otherwise every language scores points for the keywords and syntax
+//! it is known by, and the scores are turned into ranked candidates.
+
+#[cfg(feature = "schema")]
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use super::registry::{self, PLAINTEXT};
+
+const FILE_NAME_SCORE: f32 = 20.0;
+const SHEBANG_SCORE: f32 = 16.0;
+const VALID_JSON_SCORE: f32 = 12.0;
+/// Added to the total when turning scores into confidences, so that a
+/// single weak hint never makes a confident guess.
+const SMOOTHING: f32 = 6.0;
+/// Below this the code is left as plain text.
+const MIN_CONFIDENCE: f32 = 0.3;
+const MAX_CANDIDATES: usize = 5;
+
+#[derive(Debug, Deserialize)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct DetectLanguageForm {
+    pub code: String,
+    /// Name of the file the code came from, if any.
+    pub file_name: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct LanguageCandidate {
+    pub language: &'static str,
+    pub name: &'static str,
+    /// Between 0 and 1.
+    #[cfg_attr(feature = "schema", schemars(range(min = 0, max = 1)))]
+    pub confidence: f32,
+}
+
+#[derive(Clone, Copy)]
+enum Hint {
+    /// The code contains the text.
+    Contains,
+    /// The code contains the text in any case.
+    ContainsIgnoreCase,
+    /// A line starts with the text, after indentation.
+    LineStart,
+    /// A line is exactly the text, after trimming.
+    Line,
+}
+
+use Hint::*;
+
+const HINTS: &[(&str, Hint, &str, f32)] = &[
+    ("c", Contains, "#include <", 3.0),
+    ("c", Contains, "printf(", 2.0),
+    ("c", Contains, "malloc(", 3.0),
+    ("c", Contains, "int main(", 2.0),
+    ("cpp", Contains, "#include <", 3.0),
+    ("cpp", Contains, "std::", 4.0),
+    ("cpp", Contains, "cout <<", 5.0),
+    ("cpp", Contains, "nullptr", 4.0),
+    ("cpp", Contains, "template <", 4.0),
+    ("cpp", Contains, "template<", 4.0),
+    ("cpp", Contains, "int main(", 2.0),
+    ("csharp", Contains, "using System", 6.0),
+    ("csharp", Contains, "Console.WriteLine", 6.0),
+    ("csharp", Contains, "{ get; set; }", 6.0),
+    ("csharp", Contains, "async Task", 4.0),
+    ("csharp", LineStart, "namespace ", 2.0),
+    ("css", Contains, "color:", 2.0),
+    ("css", Contains, "margin:", 2.0),
+    ("css", Contains, "padding:", 2.0),
+    ("css", Contains, "display:", 2.0),
+    ("css", Contains, "font-size:", 2.0),
+    ("css", LineStart, "@media", 3.0),
+    ("dart", Contains, "import 'package:", 6.0),
+    ("dart", Contains, "void main()", 2.0),
+    ("dart", Contains, "Widget build(", 6.0),
+    ("dockerfile", LineStart, "FROM ", 3.0),
+    ("dockerfile", LineStart, "RUN ", 3.0),
+    ("dockerfile", LineStart, "WORKDIR ", 4.0),
+    ("dockerfile", LineStart, "ENTRYPOINT ", 4.0),
+    ("dockerfile", LineStart, "CMD [", 4.0),
+    ("dockerfile", LineStart, "COPY ", 2.0),
+    ("go", LineStart, "package ", 3.0),
+    ("go", LineStart, "func ", 3.0),
+    ("go", Contains, ":= ", 2.0),
+    ("go", Contains, "fmt.", 4.0),
+    ("go", Line, "import (", 4.0),
+    ("go", Contains, "go func", 4.0),
+    ("go", Contains, "chan ", 2.0),
+    ("go", Contains, "err != nil", 5.0),
+    ("html", ContainsIgnoreCase, "<!doctype html", 12.0),
+    ("html", Contains, "<html", 6.0),
+    ("html", Contains, "<head>", 4.0),
+    ("html", Contains, "<body", 4.0),
+    ("html", Contains, "<div", 3.0),
+    ("html", Contains, "</div>", 2.0),
+    ("java", Contains, "public class ", 3.0),
+    ("java", Contains, "public static void main", 6.0),
+    ("java", Contains, "System.out.println", 6.0),
+    ("java", LineStart, "import java.", 6.0),
+    ("java", Contains, "@Override", 3.0),
+    ("javascript", Contains, "console.log", 3.0),
+    ("javascript", Contains, "function ", 2.0),
+    ("javascript", Contains, "=> ", 1.0),
+    ("javascript", Contains, "===", 2.0),
+    ("javascript", Contains, "require(", 3.0),
+    ("javascript", Contains, "module.exports", 4.0),
+    ("javascript", Contains, "document.", 3.0),
+    ("javascript", LineStart, "const ", 1.0),
+    ("javascript", LineStart, "let ", 1.0),
+    ("kotlin", LineStart, "fun ", 4.0),
+    ("kotlin", LineStart, "val ", 2.0),
+    ("kotlin", Contains, "data class ", 5.0),
+    ("kotlin", Contains, "println(", 1.0),
+    ("lua", LineStart, "local ", 3.0),
+    ("lua", Contains, "~=", 3.0),
+    ("lua", LineStart, "elseif ", 2.0),
+    ("lua", Contains, "function(", 1.0),
+    ("makefile", LineStart, ".PHONY:", 8.0),
+    ("makefile", Contains, "$(MAKE)", 6.0),
+    ("markdown", LineStart, "# ", 2.0),
+    ("markdown", LineStart, "## ", 3.0),
+    ("markdown", LineStart, "```", 4.0),
+    ("markdown", Contains, "](", 2.0),
+    ("perl", Contains, "my $", 5.0),
+    ("perl", Line, "use strict;", 6.0),
+    ("php", Contains, "<?php", 16.0),
+    ("php", Contains, "$this->", 5.0),
+    ("powershell", Contains, "Write-Host", 6.0),
+    ("powershell", Contains, "Get-", 2.0),
+    ("powershell", Contains, " -eq ", 3.0),
+    ("powershell", LineStart, "param(", 3.0),
+    ("python", LineStart, "def ", 3.0),
+    ("python", LineStart, "elif ", 4.0),
+    ("python", LineStart, "from ", 2.0),
+    ("python", LineStart, "import ", 1.0),
+    ("python", Contains, "self.", 2.0),
+    ("python", Contains, "__init__", 4.0),
+    ("python", Contains, "if __name__ ==", 6.0),
+    ("python", Contains, "print(", 1.0),
+    ("python", Contains, "None", 1.0),
+    ("ruby", LineStart, "def ", 1.0),
+    ("ruby", Line, "end", 2.0),
+    ("ruby", LineStart, "puts ", 4.0),
+    ("ruby", LineStart, "require '", 4.0),
+    ("ruby", Contains, "attr_accessor", 6.0),
+    ("ruby", Contains, " do |", 5.0),
+    ("rust", Contains, "fn ", 2.0),
+    ("rust", Contains, "let mut ", 4.0),
+    ("rust", LineStart, "impl ", 3.0),
+    ("rust", Contains, "pub fn ", 3.0),
+    ("rust", Contains, "println!", 4.0),
+    ("rust", LineStart, "use std::", 5.0),
+    ("rust", Contains, "#[derive(", 6.0),
+    ("rust", Contains, "&mut ", 2.0),
+    ("rust", Contains, "-> ", 1.0),
+    ("rust", Contains, "::", 1.0),
+    ("scss", LineStart, "@mixin ", 6.0),
+    ("scss", LineStart, "@include ", 5.0),
+    ("scss", Contains, "&:", 3.0),
+    ("shell", LineStart, "echo ", 2.0),
+    ("shell", Line, "fi", 4.0),
+    ("shell", Line, "done", 3.0),
+    ("shell", Line, "esac", 5.0),
+    ("shell", Contains, "; then", 4.0),
+    ("shell", Contains, "$(", 1.0),
+    ("shell", LineStart, "export ", 1.0),
+    ("sql", ContainsIgnoreCase, "select ", 3.0),
+    ("sql", ContainsIgnoreCase, " from ", 2.0),
+    ("sql", ContainsIgnoreCase, " where ", 2.0),
+    ("sql", ContainsIgnoreCase, "insert into ", 5.0),
+    ("sql", ContainsIgnoreCase, "create table ", 6.0),
+    ("sql", ContainsIgnoreCase, " join ", 2.0),
+    ("swift", LineStart, "import SwiftUI", 8.0),
+    ("swift", LineStart, "import UIKit", 8.0),
+    ("swift", LineStart, "import Foundation", 4.0),
+    ("swift", Contains, "guard let ", 6.0),
+    ("swift", LineStart, "func ", 2.0),
+    ("toml", Line, "[package]", 6.0),
+    ("toml", Line, "[dependencies]", 6.0),
+    ("toml", Contains, " = \"", 1.0),
+    ("typescript", Contains, ": string", 4.0),
+    ("typescript", Contains, ": number", 4.0),
+    ("typescript", Contains, ": boolean", 4.0),
+    ("typescript", LineStart, "interface ", 3.0),
+    ("typescript", LineStart, "export interface ", 5.0),
+    ("typescript", LineStart, "type ", 2.0),
+    ("typescript", LineStart, "import type ", 6.0),
+    ("typescript", Contains, " as const", 4.0),
+    ("typescript", Contains, "console.log", 2.0),
+    ("typescript", Contains, "=> ", 1.0),
+    ("typescript", LineStart, "const ", 1.0),
+    ("xml", LineStart, "<?xml", 12.0),
+    ("yaml", Line, "---", 3.0),
+    ("yaml", LineStart, "- name: ", 4.0),
+    ("yaml", LineStart, "apiVersion: ", 6.0),
+];
+
+/// Ranks the languages the code could be in, most likely first. Empty when
+/// nothing hints at any language.
+pub fn detect(code: &str, file_name: Option<&str>) -> Vec<LanguageCandidate> {
+    let mut scores: Vec<(&'static str, f32)> = vec![];
+    let mut add = |language: &'static str, score: f32| match scores
+        .iter_mut()
+        .find(|(id, _)| *id == language)
+    {
+        Some((_, total)) => *total += score,
+        None => scores.push((language, score)),
+    };
+
+    if let Some(language) = file_name.and_then(registry::by_file_name) {
+        add(language.id, FILE_NAME_SCORE);
+    }
+    if let Some(language) = language_from_shebang(code) {
+        add(language, SHEBANG_SCORE);
+    }
+    let trimmed = code.trim();
+    if (trimmed.starts_with('{') || trimmed.starts_with('['))
+        && serde_json::from_str::<serde_json::Value>(trimmed).is_ok()
+    {
+        add("json", VALID_JSON_SCORE);
+    }
+
+    let lowercase = code.to_lowercase();
+    for &(language, hint, text, weight) in HINTS {
+        let found = match hint {
+            Contains => code.contains(text),
+            ContainsIgnoreCase => lowercase.contains(text),
+            LineStart => code.lines().any(|line| line.trim_start().starts_with(text)),
+            Line => code.lines().any(|line| line.trim() == text),
+        };
+        if found {
+            add(language, weight);
+        }
+    }
+
+    scores.retain(|(language, _)| *language != PLAINTEXT);
+    scores.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+    let total: f32 = scores.iter().map(|(_, score)| score).sum::<f32>() + SMOOTHING;
+
+    scores
+        .into_iter()
+        .take(MAX_CANDIDATES)
+        .filter_map(|(id, score)| {
+            let language = registry::find(id)?;
+            Some(LanguageCandidate {
+                language: language.id,
+                name: language.name,
+                confidence: (score / total * 100.0).round() / 100.0,
+            })
+        })
+        .collect()
+}
+
+/// The most likely language, or plain text when no guess is confident.
+pub fn detect_best(code: &str, file_name: Option<&str>) -> &'static str {
+    detect(code, file_name)
+        .first()
+        .filter(|candidate| candidate.confidence >= MIN_CONFIDENCE)
+        .map_or(PLAINTEXT, |candidate| candidate.language)
+}
+
+/// The language named by a `#!` line, including `#!/usr/bin/env` ones.
+fn language_from_shebang(code: &str) -> Option<&'static str> {
+    let shebang = code.lines().next()?.strip_prefix("#!")?;
+    let mut words = shebang.split_whitespace();
+    let mut interpreter = words.next()?.rsplit('/').next()?;
+    if interpreter == "env" {
+        interpreter = words.find(|word| !word.starts_with('-'))?;
+    }
+    registry::by_interpreter(interpreter).map(|language| language.id)
+}
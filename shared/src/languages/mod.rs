@@ -0,0 +1,18 @@
+//! Which language a snippet is in: the registry of known languages and
+//! detection for code that doesn't say.
+
+pub mod detect;
+pub mod registry;
+
+pub use detect::{detect, detect_best, DetectLanguageForm, LanguageCandidate};
+pub use registry::{normalize, Language, LANGUAGES};
+
+/// The language to store for a snippet: the given one normalized, or the one
+/// detected from its code when none was given.
+pub fn resolve(language: &str, code: &str) -> String {
+    if language.trim().is_empty() {
+        detect_best(code, None).to_string()
+    } else {
+        normalize(language)
+    }
+}
@@ -0,0 +1,190 @@
+//! The languages the vault knows by name. Every snippet's `language` is
+//! stored as one of these ids, whichever alias or extension it was entered
+//! as; ids follow Monaco's language ids so the editor can use them directly.
+
+use std::path::Path;
+
+#[cfg(feature = "schema")]
+use schemars::JsonSchema;
+use serde::Serialize;
+
+pub const PLAINTEXT: &str = "plaintext";
+const DEFAULT_EXTENSION: &str = "txt";
+
+#[derive(Debug, Serialize)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct Language {
+    pub id: &'static str,
+    pub name: &'static str,
+    /// Other names the language is entered as.
+    pub aliases: &'static [&'static str],
+    /// File extensions, the conventional one first.
+    pub extensions: &'static [&'static str],
+    /// Whole file names, for files without an extension.
+    pub file_names: &'static [&'static str],
+    /// Programs named in a shebang line.
+    pub interpreters: &'static [&'static str],
+}
+
+const fn language(
+    id: &'static str,
+    name: &'static str,
+    aliases: &'static [&'static str],
+    extensions: &'static [&'static str],
+) -> Language {
+    Language {
+        id,
+        name,
+        aliases,
+        extensions,
+        file_names: &[],
+        interpreters: &[],
+    }
+}
+
+pub static LANGUAGES: &[Language] = &[
+    language("c", "C", &[], &["c", "h"]),
+    language(
+        "cpp",
+        "C++",
+        &["c++", "cplusplus"],
+        &["cpp", "cc", "cxx", "hpp"],
+    ),
+    language("csharp", "C#", &["c#", "cs"], &["cs"]),
+    language("css", "CSS", &[], &["css"]),
+    language("dart", "Dart", &[], &["dart"]),
+    Language {
+        file_names: &["dockerfile"],
+        ..language("dockerfile", "Dockerfile", &["docker"], &["dockerfile"])
+    },
+    language("go", "Go", &["golang"], &["go"]),
+    language("html", "HTML", &["htm"], &["html", "htm"]),
+    language("java", "Java", &[], &["java"]),
+    Language {
+        interpreters: &["node", "deno", "bun"],
+        ..language(
+            "javascript",
+            "JavaScript",
+            &["js", "jsx", "javascriptreact", "node", "nodejs"],
+            &["js", "mjs", "cjs", "jsx"],
+        )
+    },
+    language("json", "JSON", &["jsonc"], &["json"]),
+    language("kotlin", "Kotlin", &["kt"], &["kt", "kts"]),
+    Language {
+        interpreters: &["lua"],
+        ..language("lua", "Lua", &[], &["lua"])
+    },
+    Language {
+        file_names: &["makefile", "gnumakefile"],
+        ..language("makefile", "Makefile", &["make"], &["mk"])
+    },
+    language("markdown", "Markdown", &["md"], &["md", "markdown"]),
+    Language {
+        interpreters: &["perl"],
+        ..language("perl", "Perl", &["pl"], &["pl", "pm"])
+    },
+    Language {
+        interpreters: &["php"],
+        ..language("php", "PHP", &[], &["php"])
+    },
+    language(
+        "plaintext",
+        "Plain text",
+        &["text", "txt", "plain"],
+        &["txt"],
+    ),
+    Language {
+        interpreters: &["pwsh", "powershell"],
+        ..language("powershell", "PowerShell", &["ps", "pwsh"], &["ps1"])
+    },
+    Language {
+        interpreters: &["python"],
+        ..language("python", "Python", &["py", "python3"], &["py", "pyw"])
+    },
+    Language {
+        interpreters: &["ruby"],
+        ..language("ruby", "Ruby", &["rb"], &["rb"])
+    },
+    language("rust", "Rust", &["rs"], &["rs"]),
+    language("scss", "SCSS", &["sass"], &["scss"]),
+    Language {
+        interpreters: &["sh", "bash", "zsh", "dash", "ksh"],
+        ..language(
+            "shell",
+            "Shell",
+            &["sh", "bash", "zsh", "shellscript"],
+            &["sh", "bash", "zsh"],
+        )
+    },
+    language("sql", "SQL", &[], &["sql"]),
+    language("swift", "Swift", &[], &["swift"]),
+    language("toml", "TOML", &[], &["toml"]),
+    language(
+        "typescript",
+        "TypeScript",
+        &["ts", "tsx", "typescriptreact"],
+        &["ts", "tsx", "mts", "cts"],
+    ),
+    language("xml", "XML", &[], &["xml"]),
+    language("yaml", "YAML", &["yml"], &["yml", "yaml"]),
+];
+
+/// The language with this id, name, alias or extension, ignoring case.
+pub fn find(name: &str) -> Option<&'static Language> {
+    let name = name.trim().to_lowercase();
+    let name = name.strip_prefix('.').unwrap_or(&name);
+    if name.is_empty() {
+        return None;
+    }
+
+    LANGUAGES.iter().find(|language| {
+        language.id == name
+            || language.name.eq_ignore_ascii_case(name)
+            || language.aliases.contains(&name)
+            || language.extensions.contains(&name)
+    })
+}
+
+/// The canonical id for a language as entered. Languages the registry
+/// doesn't know are kept, trimmed and lowercased, so they still group
+/// together.
+pub fn normalize(name: &str) -> String {
+    match find(name) {
+        Some(language) => language.id.to_string(),
+        None => name.trim().to_lowercase(),
+    }
+}
+
+/// The language of a file, by its name or extension.
+pub fn by_file_name(file_name: &str) -> Option<&'static Language> {
+    let lowercase = file_name.to_lowercase();
+    if let Some(language) = LANGUAGES
+        .iter()
+        .find(|language| language.file_names.contains(&lowercase.as_str()))
+    {
+        return Some(language);
+    }
+
+    let extension = Path::new(&lowercase).extension()?.to_str()?.to_string();
+    LANGUAGES
+        .iter()
+        .find(|language| language.extensions.contains(&extension.as_str()))
+}
+
+/// The language of a script run by `interpreter`, ignoring a version suffix
+/// such as the one in `python3.11`.
+pub fn by_interpreter(interpreter: &str) -> Option<&'static Language> {
+    let interpreter = interpreter.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.');
+    LANGUAGES
+        .iter()
+        .find(|language| language.interpreters.contains(&interpreter))
+}
+
+/// File extension used for code in `language`.
+pub fn extension_for(language: &str) -> &'static str {
+    find(language)
+        .and_then(|language| language.extensions.first().copied())
+        .unwrap_or(DEFAULT_EXTENSION)
+}
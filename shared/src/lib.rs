@@ -0,0 +1,6 @@
+//! What the app and the `snippet-vault` command-line client must agree on,
+//! so that a snippet saved by one reads the same in the other: the language
+//! registry and the placeholder syntax.
+
+pub mod languages;
+pub mod placeholders;
@@ -0,0 +1,88 @@
+//! Snippet placeholders: the `${1:default}` tab stops and `${TM_...}`
+//! variables that imported templates keep in their code.
+//!
+//! Editors expand them; copying a snippet, or sending it to an editor that
+//! can't expand them, renders it instead, as it reads with every placeholder
+//! at its default.
+
+/// Whether the code has tab stops such as `$1`, `${2:name}` or `$0`, or an
+/// editor variable such as `${TM_SELECTED_TEXT}`. Code without them is sent
+/// to editors as plain text, so that a `$` in shell or PHP code isn't taken
+/// for a tab stop.
+pub fn has_placeholders(code: &str) -> bool {
+    let bytes = code.as_bytes();
+    bytes.iter().enumerate().any(|(index, &byte)| {
+        if byte != b'$' || (index > 0 && bytes[index - 1] == b'\\') {
+            return false;
+        }
+        match bytes.get(index + 1) {
+            Some(next) if next.is_ascii_digit() => true,
+            Some(b'{') => match bytes.get(index + 2) {
+                Some(next) if next.is_ascii_digit() => true,
+                Some(b'T') => code[index + 2..].starts_with("TM_"),
+                _ => false,
+            },
+            _ => false,
+        }
+    })
+}
+
+/// The code with each placeholder replaced by its default, variables and
+/// bare tab stops such as `$0` removed, and `\$` unescaped.
+pub fn render(code: &str) -> String {
+    let mut output = String::with_capacity(code.len());
+    let mut chars = code.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if matches!(chars.peek(), Some('$') | Some('}') | Some('\\')) => {
+                output.push(chars.next().unwrap_or(c));
+            }
+            '$' if chars.peek().is_some_and(char::is_ascii_digit) => {
+                while chars.peek().is_some_and(char::is_ascii_digit) {
+                    chars.next();
+                }
+            }
+            '$' if chars.peek() == Some(&'{') => {
+                chars.next();
+                output.push_str(&render(&placeholder_default(&mut chars)));
+            }
+            _ => output.push(c),
+        }
+    }
+    output
+}
+
+/// Reads a `${...}` placeholder up to its closing brace, whose opening
+/// brace was consumed, and returns the text after its first `:`.
+fn placeholder_default(chars: &mut std::iter::Peekable<std::str::Chars>) -> String {
+    let mut body = String::new();
+    let mut depth = 1;
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                body.push(c);
+                if let Some(escaped) = chars.next() {
+                    body.push(escaped);
+                }
+            }
+            '{' => {
+                depth += 1;
+                body.push(c);
+            }
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    break;
+                }
+                body.push(c);
+            }
+            _ => body.push(c),
+        }
+    }
+
+    match body.split_once(':') {
+        Some((_, default)) => default.to_owned(),
+        None => String::new(),
+    }
+}
@@ -1,3 +1,45 @@
+use std::{env, fs, path::Path};
+
+/// Monaco editor themes shared with the frontend, embedded so snippets can be
+/// highlighted without the editor.
+const THEMES_DIR: &str = "../src/assets/themes";
+const THEME_LIST: &str = "themelist.json";
+
 fn main() {
+    embed_themes();
     tauri_build::build()
 }
+
+fn embed_themes() {
+    println!("cargo:rerun-if-changed={}", THEMES_DIR);
+
+    let dir = fs::canonicalize(THEMES_DIR).expect("the Monaco themes directory is missing");
+    let mut themes: Vec<(String, String)> = fs::read_dir(&dir)
+        .expect("failed to read the Monaco themes directory")
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.extension().map_or(false, |extension| extension == "json")
+                && path.file_name().map_or(false, |name| name != THEME_LIST)
+        })
+        .map(|path| {
+            let name = path.file_stem().unwrap().to_string_lossy().into_owned();
+            (name, path.to_string_lossy().into_owned())
+        })
+        .collect();
+    themes.sort();
+
+    let entries: String = themes
+        .iter()
+        .map(|(name, path)| format!("    ({:?}, include_str!({:?})),\n", name, path))
+        .collect();
+    let source = format!(
+        "pub static THEME_LIST: &str = include_str!({:?});\n\
+         pub static THEMES: &[(&str, &str)] = &[\n{}];\n",
+        dir.join(THEME_LIST).to_string_lossy(),
+        entries
+    );
+
+    let out = Path::new(&env::var("OUT_DIR").unwrap()).join("monaco_themes.rs");
+    fs::write(out, source).expect("failed to write the embedded theme list");
+}
@@ -0,0 +1,187 @@
+//! The local HTTP API: the snippet, tag and language commands over HTTP on
+//! 127.0.0.1, for editor plugins and scripts that can't call Tauri.
+//!
+//! Every route runs the same IPC command the app does and answers with its
+//! `IpcResponse` envelope, so authentication, permissions and workspaces
+//! behave the same. Requests authenticate with `Authorization: Bearer` and
+//! an API token from `create_api_token`; session tokens aren't accepted.
+
+mod openapi;
+mod routes;
+pub mod tokens;
+
+use std::net::{Ipv4Addr, SocketAddr};
+
+use axum::{
+    extract::FromRequestParts,
+    http::{header::AUTHORIZATION, request::Parts, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use log::{error, info};
+use serde::Serialize;
+use tauri::{AppHandle, Manager};
+use tokio::net::TcpListener;
+
+use crate::{
+    auth::{token::TokenType, Principal},
+    error::AppError,
+    ipc::responses::{IpcResponse, Response as IpcResult},
+    prelude::AppResult,
+    state::AppState,
+};
+
+pub const API_PREFIX: &str = "/api/v1";
+
+/// Serves the API when it is enabled in settings. Only the loopback
+/// interface is bound, whatever the settings say.
+pub fn spawn_api_server(app: AppHandle) {
+    let config = app.state::<AppState>().config.api.clone();
+    if !config.enabled {
+        return;
+    }
+
+    let address = SocketAddr::from((Ipv4Addr::LOCALHOST, config.port));
+    tauri::async_runtime::spawn(async move {
+        let listener = match TcpListener::bind(address).await {
+            Ok(listener) => listener,
+            Err(e) => {
+                error!(
+                    "The local API is disabled, binding {} failed: {}",
+                    address, e
+                );
+                return;
+            }
+        };
+        info!("Local API listening on http://{}{}", address, API_PREFIX);
+        if let Err(e) = axum::serve(listener, routes::router(app)).await {
+            error!("The local API stopped: {}", e);
+        }
+    });
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiToken {
+    /// Only ever shown here; the app keeps a hash of it.
+    pub token: String,
+    pub id: String,
+    pub expires_at: DateTime<Utc>,
+    /// Base URL of the API; none while it is disabled in settings.
+    pub base_url: Option<String>,
+}
+
+/// Issues an API token for the caller's workspace, to hand to scripts and
+/// editor plugins.
+pub async fn issue_token(app: &AppHandle, principal: &Principal) -> AppResult<ApiToken> {
+    let state = app.state::<AppState>();
+    let config = &state.config.api;
+    let (token, record) = state.api_tokens.issue(principal, config.token_days).await?;
+
+    Ok(ApiToken {
+        token,
+        id: record.id,
+        expires_at: record.expires_at,
+        base_url: if config.enabled {
+            Some(format!("http://127.0.0.1:{}{}", config.port, API_PREFIX))
+        } else {
+            None
+        },
+    })
+}
+
+/// How long the session token a request runs under lasts.
+const REQUEST_TOKEN_HOURS: i64 = 1;
+
+/// The caller of a request, as a session token for the commands to check.
+/// The API token from the `Authorization: Bearer` header is exchanged for
+/// one; commands reject requests without a token themselves.
+pub struct BearerToken(pub Option<String>);
+
+impl FromRequestParts<AppHandle> for BearerToken {
+    type Rejection = ApiResponse<()>;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        app: &AppHandle,
+    ) -> Result<Self, Self::Rejection> {
+        let token = parts
+            .headers
+            .get(AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .map(|token| token.trim().to_owned());
+        let token = match token {
+            Some(token) => token,
+            None => return Ok(BearerToken(None)),
+        };
+
+        let state = app.state::<AppState>();
+        let record = state.api_tokens.resolve(&token).await.ok_or_else(|| {
+            rejected(AppError::Unauthorized(
+                "invalid, expired or revoked API token".into(),
+            ))
+        })?;
+        let session = state
+            .token_secret
+            .create_token(
+                &record.user_id,
+                TokenType::Access,
+                record.workspace.as_ref(),
+                REQUEST_TOKEN_HOURS,
+            )
+            .map_err(|e| rejected(e.into()))?;
+        Ok(BearerToken(Some(session)))
+    }
+}
+
+fn rejected(error: AppError) -> ApiResponse<()> {
+    ApiResponse::from(IpcResponse::from(AppResult::<()>::Err(error)))
+}
+
+/// An `IpcResponse` with the HTTP status that goes with it.
+pub struct ApiResponse<T: Serialize> {
+    status: StatusCode,
+    body: IpcResponse<T>,
+}
+
+impl<T: Serialize> ApiResponse<T> {
+    /// Answers a successful request with 201 Created.
+    fn created(mut self) -> Self {
+        if self.status == StatusCode::OK {
+            self.status = StatusCode::CREATED;
+        }
+        self
+    }
+}
+
+impl<T: Serialize> ApiResponse<Option<T>> {
+    /// Answers with 404 when the command found nothing.
+    fn or_not_found(mut self) -> Self {
+        if let IpcResult::Success { result } = &self.body.response {
+            if result.data.is_none() {
+                self.status = StatusCode::NOT_FOUND;
+            }
+        }
+        self
+    }
+}
+
+impl<T: Serialize> From<IpcResponse<T>> for ApiResponse<T> {
+    fn from(body: IpcResponse<T>) -> Self {
+        let status = match &body.response {
+            IpcResult::Success { .. } => StatusCode::OK,
+            IpcResult::Error { error } => {
+                StatusCode::from_u16(error.http_status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR)
+            }
+        };
+        Self { status, body }
+    }
+}
+
+impl<T: Serialize> IntoResponse for ApiResponse<T> {
+    fn into_response(self) -> Response {
+        (self.status, Json(self.body)).into_response()
+    }
+}
@@ -0,0 +1,214 @@
+//! The OpenAPI 3 document of the API, built from the endpoint table.
+//!
+//! The app's own forms and responses derive their component schemas. The
+//! models are generated by Prisma and can't, so theirs are written out here;
+//! keep them in step with `prisma/schema.prisma`.
+
+use schemars::gen::SchemaSettings;
+use serde_json::{json, Map, Value};
+
+use super::{
+    routes::{Endpoint, Health},
+    API_PREFIX,
+};
+use crate::{
+    database::models::{CollectionForm, SnippetFilter, SnippetForm, TagForm},
+    languages::{DetectLanguageForm, Language, LanguageCandidate},
+    secrets::SecretFinding,
+};
+
+pub fn document(endpoints: &[Endpoint]) -> Value {
+    let mut paths = Map::new();
+    for endpoint in endpoints {
+        let path = paths
+            .entry(format!("{}{}", API_PREFIX, endpoint.path))
+            .or_insert_with(|| json!({}));
+        path[endpoint.method.as_str().to_lowercase()] = operation(endpoint);
+    }
+
+    json!({
+        "openapi": "3.0.3",
+        "info": {
+            "title": "Snippet Vault local API",
+            "version": env!("CARGO_PKG_VERSION"),
+            "description": "The app's commands over HTTP on 127.0.0.1. Every response is an \
+                            envelope with `status` and either `result.data` or `error.message`.",
+        },
+        "servers": [{ "url": "http://127.0.0.1" }],
+        "paths": paths,
+        "components": {
+            "securitySchemes": {
+                "bearer": { "type": "http", "scheme": "bearer", "bearerFormat": "JWT" },
+            },
+            "schemas": schemas(),
+        },
+    })
+}
+
+fn operation(endpoint: &Endpoint) -> Value {
+    let mut parameters: Vec<Value> = path_parameters(endpoint.path)
+        .map(|name| {
+            json!({
+                "name": name,
+                "in": "path",
+                "required": true,
+                "schema": { "type": "string" },
+            })
+        })
+        .collect();
+    parameters.extend(endpoint.query.iter().map(|parameter| {
+        json!({
+            "name": parameter.name,
+            "in": "query",
+            "required": false,
+            "description": parameter.description,
+            "schema": schema(parameter.schema),
+        })
+    }));
+
+    let mut operation = json!({
+        "summary": endpoint.summary,
+        "parameters": parameters,
+        "responses": {
+            "2XX": {
+                "description": "Success",
+                "content": { "application/json": { "schema": envelope(endpoint.response) } },
+            },
+            "default": {
+                "description": "The command failed",
+                "content": { "application/json": { "schema": { "$ref": "#/components/schemas/Error" } } },
+            },
+        },
+    });
+    if let Some(body) = endpoint.body {
+        operation["requestBody"] = json!({
+            "required": true,
+            "content": { "application/json": { "schema": schema(body) } },
+        });
+    }
+    if endpoint.authenticated {
+        operation["security"] = json!([{ "bearer": [] }]);
+    }
+    operation
+}
+
+fn path_parameters(path: &str) -> impl Iterator<Item = &str> {
+    path.split('/')
+        .filter_map(|segment| segment.strip_prefix('{')?.strip_suffix('}'))
+}
+
+/// `Name[]` is an array of `Name`; lowercase names are JSON types and the
+/// rest refer to component schemas.
+fn schema(name: &str) -> Value {
+    if let Some(item) = name.strip_suffix("[]") {
+        return json!({ "type": "array", "items": schema(item) });
+    }
+    match name {
+        "null" => json!({ "nullable": true }),
+        "string" | "boolean" | "integer" | "number" | "object" => json!({ "type": name }),
+        _ => json!({ "$ref": format!("#/components/schemas/{}", name) }),
+    }
+}
+
+fn envelope(data: &str) -> Value {
+    json!({
+        "type": "object",
+        "required": ["status", "result"],
+        "properties": {
+            "status": { "type": "string", "enum": ["Success"] },
+            "result": {
+                "type": "object",
+                "required": ["data"],
+                "properties": { "data": schema(data) },
+            },
+        },
+    })
+}
+
+fn object(required: &[&str], properties: Value) -> Value {
+    json!({ "type": "object", "required": required, "properties": properties })
+}
+
+/// Component schemas of the types that derive one, along with the types they
+/// refer to.
+fn derived_schemas() -> Map<String, Value> {
+    let mut generator = SchemaSettings::openapi3().into_generator();
+    generator.subschema_for::<Health>();
+    generator.subschema_for::<SnippetForm>();
+    generator.subschema_for::<SnippetFilter>();
+    generator.subschema_for::<TagForm>();
+    generator.subschema_for::<CollectionForm>();
+    generator.subschema_for::<Language>();
+    generator.subschema_for::<LanguageCandidate>();
+    generator.subschema_for::<DetectLanguageForm>();
+    generator.subschema_for::<SecretFinding>();
+    generator
+        .take_definitions()
+        .into_iter()
+        .map(|(name, schema)| (name, serde_json::to_value(schema).unwrap_or_default()))
+        .collect()
+}
+
+fn schemas() -> Value {
+    let string = json!({ "type": "string" });
+    let nullable_string = json!({ "type": "string", "nullable": true });
+    let date = json!({ "type": "string", "format": "date-time" });
+    let strings = json!({ "type": "array", "items": { "type": "string" } });
+
+    let mut schemas = derived_schemas();
+    if let Value::Object(models) = json!({
+        "Error": object(&["status", "error"], json!({
+            "status": { "type": "string", "enum": ["Error"] },
+            "error": object(&["message"], json!({ "message": string })),
+        })),
+        "Snippet": object(
+            &["id", "createdAt", "updatedAt", "title", "description", "language", "code", "tagIds", "snippetStateId"],
+            json!({
+                "id": string,
+                "createdAt": date,
+                "updatedAt": date,
+                "title": string,
+                "description": string,
+                "language": string,
+                "code": string,
+                "tagIds": strings,
+                "snippetStateId": string,
+                "state": { "$ref": "#/components/schemas/SnippetState" },
+                "metadata": { "type": "object", "nullable": true },
+                "authorId": nullable_string,
+                "workspaceId": nullable_string,
+            }),
+        ),
+        "SavedSnippet": {
+            "description": "A snippet just saved, with the possible secrets found in its code.",
+            "allOf": [
+                { "$ref": "#/components/schemas/Snippet" },
+                object(&["secretFindings"], json!({
+                    "secretFindings": { "type": "array", "items": { "$ref": "#/components/schemas/SecretFinding" } },
+                })),
+            ],
+        },
+        "SnippetState": object(&["id", "isFavorite", "isDark"], json!({
+            "id": string,
+            "isFavorite": { "type": "boolean" },
+            "isDark": { "type": "boolean" },
+            "copyCount": { "type": "integer", "nullable": true },
+            "lastCopiedAt": { "type": "string", "format": "date-time", "nullable": true },
+        })),
+        "Tag": object(&["id", "createdAt", "updatedAt", "name", "count", "snippetIds"], json!({
+            "id": string,
+            "createdAt": date,
+            "updatedAt": date,
+            "name": string,
+            "color": nullable_string,
+            "emoji": nullable_string,
+            "count": { "type": "integer" },
+            "parentId": nullable_string,
+            "snippetIds": strings,
+            "workspaceId": nullable_string,
+        })),
+    }) {
+        schemas.extend(models);
+    }
+    Value::Object(schemas)
+}
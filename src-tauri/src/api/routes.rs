@@ -0,0 +1,449 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::Method,
+    routing::{on, MethodFilter, MethodRouter},
+    Json, Router,
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use super::{openapi, ApiResponse, BearerToken, API_PREFIX};
+use crate::{
+    database::models::{
+        Snippet, SnippetFilter, SnippetForm, SnippetSort, SnippetState, SnippetStateUpdate, Tag,
+        TagFilter, TagForm,
+    },
+    ipc::{
+        commands::{language, snippet, tag},
+        params::{DeleteParams, GetParams, ListParams, PostParams, PutParams},
+    },
+    languages::{DetectLanguageForm, Language, LanguageCandidate},
+    snippets::SavedSnippet,
+};
+
+/// A parameter of an endpoint, for the OpenAPI document.
+pub struct Parameter {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub schema: &'static str,
+}
+
+/// One route of the API. The router and the OpenAPI document are both built
+/// from these, so they can't drift apart.
+pub struct Endpoint {
+    pub method: Method,
+    /// Relative to [`API_PREFIX`], with path parameters in braces.
+    pub path: &'static str,
+    pub summary: &'static str,
+    pub authenticated: bool,
+    pub query: &'static [Parameter],
+    /// Component schema of the JSON request body.
+    pub body: Option<&'static str>,
+    /// Schema of the `data` of a successful response.
+    pub response: &'static str,
+    pub handler: fn(MethodFilter) -> MethodRouter<AppHandle>,
+}
+
+const SNIPPET_QUERY: &[Parameter] = &[
+    Parameter {
+        name: "search",
+        description: "Words to find in the title, description or code",
+        schema: "string",
+    },
+    Parameter {
+        name: "title",
+        description: "Part of the title",
+        schema: "string",
+    },
+    Parameter {
+        name: "language",
+        description: "Language id or alias",
+        schema: "string",
+    },
+    Parameter {
+        name: "tags",
+        description: "Comma-separated tag names; snippets with any of them match",
+        schema: "string",
+    },
+    Parameter {
+        name: "collectionId",
+        description: "Only snippets in this collection, in its order",
+        schema: "string",
+    },
+    Parameter {
+        name: "favorite",
+        description: "Only favorites, or only the others",
+        schema: "boolean",
+    },
+    Parameter {
+        name: "sort",
+        description: "`newest` (the default) or `mostUsed`, most often copied first",
+        schema: "string",
+    },
+];
+
+const TAG_QUERY: &[Parameter] = &[Parameter {
+    name: "name",
+    description: "Part of the tag name",
+    schema: "string",
+}];
+
+pub fn endpoints() -> Vec<Endpoint> {
+    vec![
+        Endpoint {
+            method: Method::GET,
+            path: "/health",
+            summary: "Whether the API is up, and the app version",
+            authenticated: false,
+            query: &[],
+            body: None,
+            response: "Health",
+            handler: |filter| on(filter, health),
+        },
+        Endpoint {
+            method: Method::GET,
+            path: "/openapi.json",
+            summary: "This document",
+            authenticated: false,
+            query: &[],
+            body: None,
+            response: "object",
+            handler: |filter| on(filter, openapi_document),
+        },
+        Endpoint {
+            method: Method::GET,
+            path: "/snippets",
+            summary: "List snippets",
+            authenticated: true,
+            query: SNIPPET_QUERY,
+            body: None,
+            response: "Snippet[]",
+            handler: |filter| on(filter, list_snippets),
+        },
+        Endpoint {
+            method: Method::POST,
+            path: "/snippets/search",
+            summary: "Search snippets with a full filter",
+            authenticated: true,
+            query: &[],
+            body: Some("SnippetFilter"),
+            response: "Snippet[]",
+            handler: |filter| on(filter, search_snippets),
+        },
+        Endpoint {
+            method: Method::POST,
+            path: "/snippets",
+            summary: "Create a snippet",
+            authenticated: true,
+            query: &[],
+            body: Some("SnippetForm"),
+            response: "SavedSnippet",
+            handler: |filter| on(filter, create_snippet),
+        },
+        Endpoint {
+            method: Method::GET,
+            path: "/snippets/{id}",
+            summary: "Get a snippet",
+            authenticated: true,
+            query: &[],
+            body: None,
+            response: "Snippet",
+            handler: |filter| on(filter, get_snippet),
+        },
+        Endpoint {
+            method: Method::PUT,
+            path: "/snippets/{id}",
+            summary: "Update a snippet",
+            authenticated: true,
+            query: &[],
+            body: Some("SnippetForm"),
+            response: "SavedSnippet",
+            handler: |filter| on(filter, update_snippet),
+        },
+        Endpoint {
+            method: Method::DELETE,
+            path: "/snippets/{id}",
+            summary: "Delete a snippet",
+            authenticated: true,
+            query: &[],
+            body: None,
+            response: "Snippet",
+            handler: |filter| on(filter, delete_snippet),
+        },
+        Endpoint {
+            method: Method::PUT,
+            path: "/snippet-states/{id}",
+            summary: "Mark a snippet as favorite or dark, by its state id",
+            authenticated: true,
+            query: &[],
+            body: Some("SnippetStateUpdate"),
+            response: "SnippetState",
+            handler: |filter| on(filter, update_snippet_state),
+        },
+        Endpoint {
+            method: Method::GET,
+            path: "/tags",
+            summary: "List tags",
+            authenticated: true,
+            query: TAG_QUERY,
+            body: None,
+            response: "Tag[]",
+            handler: |filter| on(filter, list_tags),
+        },
+        Endpoint {
+            method: Method::POST,
+            path: "/tags",
+            summary: "Create a tag",
+            authenticated: true,
+            query: &[],
+            body: Some("TagForm"),
+            response: "Tag",
+            handler: |filter| on(filter, create_tag),
+        },
+        Endpoint {
+            method: Method::GET,
+            path: "/tags/{id}",
+            summary: "Get a tag",
+            authenticated: true,
+            query: &[],
+            body: None,
+            response: "Tag",
+            handler: |filter| on(filter, get_tag),
+        },
+        Endpoint {
+            method: Method::PUT,
+            path: "/tags/{id}",
+            summary: "Update a tag",
+            authenticated: true,
+            query: &[],
+            body: Some("TagForm"),
+            response: "Tag",
+            handler: |filter| on(filter, update_tag),
+        },
+        Endpoint {
+            method: Method::DELETE,
+            path: "/tags/{id}",
+            summary: "Delete a tag",
+            authenticated: true,
+            query: &[],
+            body: None,
+            response: "null",
+            handler: |filter| on(filter, delete_tag),
+        },
+        Endpoint {
+            method: Method::GET,
+            path: "/languages",
+            summary: "The languages snippets are stored as",
+            authenticated: false,
+            query: &[],
+            body: None,
+            response: "Language[]",
+            handler: |filter| on(filter, list_languages),
+        },
+        Endpoint {
+            method: Method::POST,
+            path: "/languages/detect",
+            summary: "Rank the languages some code could be in",
+            authenticated: false,
+            query: &[],
+            body: Some("DetectLanguageForm"),
+            response: "LanguageCandidate[]",
+            handler: |filter| on(filter, detect_language),
+        },
+    ]
+}
+
+pub fn router(app: AppHandle) -> Router {
+    let mut api = Router::new();
+    for endpoint in endpoints() {
+        let filter = MethodFilter::try_from(endpoint.method).expect("standard HTTP method");
+        api = api.route(endpoint.path, (endpoint.handler)(filter));
+    }
+    Router::new().nest(API_PREFIX, api).with_state(app)
+}
+
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(super) struct Health {
+    status: &'static str,
+    app_version: &'static str,
+}
+
+async fn health() -> Json<Health> {
+    Json(Health {
+        status: "ok",
+        app_version: env!("CARGO_PKG_VERSION"),
+    })
+}
+
+async fn openapi_document() -> Json<serde_json::Value> {
+    Json(openapi::document(&endpoints()))
+}
+
+/// The filters `GET /snippets` takes as query parameters.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SnippetQuery {
+    search: Option<String>,
+    title: Option<String>,
+    language: Option<String>,
+    tags: Option<String>,
+    collection_id: Option<String>,
+    favorite: Option<bool>,
+    sort: Option<SnippetSort>,
+}
+
+impl From<SnippetQuery> for SnippetFilter {
+    fn from(query: SnippetQuery) -> Self {
+        SnippetFilter {
+            search: query.search,
+            title: query.title,
+            language: query.language,
+            tags: query.tags.map(|tags| {
+                tags.split(',')
+                    .map(|tag| tag.trim().to_owned())
+                    .filter(|tag| !tag.is_empty())
+                    .collect()
+            }),
+            collection_id: query.collection_id,
+            state: query.favorite.map(|is_favorite| SnippetStateUpdate {
+                is_favorite: Some(is_favorite),
+                is_dark: None,
+            }),
+            sort: query.sort,
+            ..SnippetFilter::default()
+        }
+    }
+}
+
+fn list<F>(filter: F) -> ListParams<F> {
+    ListParams {
+        filter: Some(filter),
+        page: None,
+        page_size: None,
+    }
+}
+
+async fn list_snippets(
+    State(app): State<AppHandle>,
+    BearerToken(token): BearerToken,
+    Query(query): Query<SnippetQuery>,
+) -> ApiResponse<Vec<Snippet>> {
+    snippet::list_snippets(app, token, list(query.into()))
+        .await
+        .into()
+}
+
+async fn search_snippets(
+    State(app): State<AppHandle>,
+    BearerToken(token): BearerToken,
+    Json(filter): Json<SnippetFilter>,
+) -> ApiResponse<Vec<Snippet>> {
+    snippet::list_snippets(app, token, list(filter))
+        .await
+        .into()
+}
+
+async fn create_snippet(
+    State(app): State<AppHandle>,
+    BearerToken(token): BearerToken,
+    Json(data): Json<SnippetForm>,
+) -> ApiResponse<SavedSnippet> {
+    ApiResponse::from(snippet::create_snippet(app, token, PostParams { data }).await).created()
+}
+
+async fn get_snippet(
+    State(app): State<AppHandle>,
+    BearerToken(token): BearerToken,
+    Path(id): Path<String>,
+) -> ApiResponse<Option<Snippet>> {
+    ApiResponse::from(snippet::get_snippet(app, token, GetParams { id }).await).or_not_found()
+}
+
+async fn update_snippet(
+    State(app): State<AppHandle>,
+    BearerToken(token): BearerToken,
+    Path(id): Path<String>,
+    Json(data): Json<SnippetForm>,
+) -> ApiResponse<SavedSnippet> {
+    snippet::update_snippet(app, token, PutParams { id, data })
+        .await
+        .into()
+}
+
+async fn delete_snippet(
+    State(app): State<AppHandle>,
+    BearerToken(token): BearerToken,
+    Path(id): Path<String>,
+) -> ApiResponse<Snippet> {
+    snippet::delete_snippet(app, token, DeleteParams { id })
+        .await
+        .into()
+}
+
+async fn update_snippet_state(
+    State(app): State<AppHandle>,
+    BearerToken(token): BearerToken,
+    Path(id): Path<String>,
+    Json(data): Json<SnippetStateUpdate>,
+) -> ApiResponse<SnippetState> {
+    snippet::update_snippet_state(app, token, PutParams { id, data })
+        .await
+        .into()
+}
+
+async fn list_tags(
+    State(app): State<AppHandle>,
+    BearerToken(token): BearerToken,
+    Query(filter): Query<TagFilter>,
+) -> ApiResponse<Vec<Tag>> {
+    tag::list_tags(app, token, list(filter)).await.into()
+}
+
+async fn create_tag(
+    State(app): State<AppHandle>,
+    BearerToken(token): BearerToken,
+    Json(data): Json<TagForm>,
+) -> ApiResponse<Tag> {
+    ApiResponse::from(tag::create_tag(app, token, PostParams { data }).await).created()
+}
+
+async fn get_tag(
+    State(app): State<AppHandle>,
+    BearerToken(token): BearerToken,
+    Path(id): Path<String>,
+) -> ApiResponse<Option<Tag>> {
+    ApiResponse::from(tag::get_tag(app, token, GetParams { id }).await).or_not_found()
+}
+
+async fn update_tag(
+    State(app): State<AppHandle>,
+    BearerToken(token): BearerToken,
+    Path(id): Path<String>,
+    Json(data): Json<TagForm>,
+) -> ApiResponse<Tag> {
+    tag::update_tag(app, token, PutParams { id, data })
+        .await
+        .into()
+}
+
+async fn delete_tag(
+    State(app): State<AppHandle>,
+    BearerToken(token): BearerToken,
+    Path(id): Path<String>,
+) -> ApiResponse<()> {
+    tag::delete_tag(app, token, DeleteParams { id })
+        .await
+        .into()
+}
+
+async fn list_languages() -> ApiResponse<&'static [Language]> {
+    language::list_languages().await.into()
+}
+
+async fn detect_language(
+    Json(data): Json<DetectLanguageForm>,
+) -> ApiResponse<Vec<LanguageCandidate>> {
+    language::detect_language(PostParams { data }).await.into()
+}
@@ -0,0 +1,183 @@
+//! Tokens for the local API. Unlike session tokens they are random strings
+//! recorded in the app data directory, so each one can be listed and revoked.
+//! Only a hash of each token is kept; the token itself is shown once.
+
+use std::{
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, Duration, Utc};
+use log::debug;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tokio::sync::RwLock;
+
+use crate::{
+    auth::{token::WorkspaceClaim, Principal},
+    error::AppError,
+    prelude::AppResult,
+};
+
+pub const TOKENS_FILE_NAME: &str = "api-tokens.json";
+/// Tells API tokens apart from session tokens at a glance.
+const TOKEN_PREFIX: &str = "svapi_";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiTokenRecord {
+    pub id: String,
+    pub user_id: String,
+    /// The workspace the token works in; none for the default workspace.
+    pub workspace: Option<WorkspaceClaim>,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    /// SHA-256 of the token.
+    hash: String,
+}
+
+/// What the owner of a token gets to see of it.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiTokenInfo {
+    pub id: String,
+    pub workspace_id: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl From<&ApiTokenRecord> for ApiTokenInfo {
+    fn from(record: &ApiTokenRecord) -> Self {
+        Self {
+            id: record.id.clone(),
+            workspace_id: record.workspace.as_ref().map(|claim| claim.id.clone()),
+            created_at: record.created_at,
+            expires_at: record.expires_at,
+        }
+    }
+}
+
+pub struct ApiTokens {
+    /// None when tokens only last as long as the process.
+    path: Option<PathBuf>,
+    records: RwLock<Vec<ApiTokenRecord>>,
+}
+
+fn hash(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+fn random_hex(len: usize) -> String {
+    let mut bytes = vec![0u8; len];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    hex::encode(bytes)
+}
+
+impl ApiTokens {
+    /// Reads the tokens recorded in `dir`, dropping expired ones.
+    pub async fn open(dir: &Path) -> AppResult<Self> {
+        let path = dir.join(TOKENS_FILE_NAME);
+        let mut records: Vec<ApiTokenRecord> = match tokio::fs::read(&path).await {
+            Ok(bytes) => serde_json::from_slice(&bytes)?,
+            Err(e) if e.kind() == ErrorKind::NotFound => vec![],
+            Err(e) => return Err(e.into()),
+        };
+        records.retain(|record| record.expires_at > Utc::now());
+        Ok(Self {
+            path: Some(path),
+            records: RwLock::new(records),
+        })
+    }
+
+    /// Tokens that are forgotten when the app quits.
+    pub fn in_memory() -> Self {
+        Self {
+            path: None,
+            records: RwLock::new(vec![]),
+        }
+    }
+
+    async fn persist(&self, records: &[ApiTokenRecord]) -> AppResult<()> {
+        let path = match &self.path {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+        let tmp_path = path.with_extension("json.tmp");
+        tokio::fs::write(&tmp_path, serde_json::to_vec_pretty(records)?).await?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            tokio::fs::set_permissions(&tmp_path, std::fs::Permissions::from_mode(0o600)).await?;
+        }
+        tokio::fs::rename(&tmp_path, path).await?;
+        debug!("Persisted API tokens to {}", path.display());
+        Ok(())
+    }
+
+    /// Creates a token for the caller's active workspace; returns it along
+    /// with its record.
+    pub async fn issue(
+        &self,
+        principal: &Principal,
+        days: i64,
+    ) -> AppResult<(String, ApiTokenRecord)> {
+        let token = format!("{}{}", TOKEN_PREFIX, random_hex(32));
+        let now = Utc::now();
+        let record = ApiTokenRecord {
+            id: random_hex(8),
+            user_id: principal.user_id.clone(),
+            workspace: match (&principal.workspace_id, principal.workspace_role) {
+                (Some(id), Some(role)) => Some(WorkspaceClaim {
+                    id: id.clone(),
+                    role,
+                }),
+                _ => None,
+            },
+            created_at: now,
+            expires_at: now + Duration::days(days.max(1)),
+            hash: hash(&token),
+        };
+
+        let mut records = self.records.write().await;
+        records.push(record.clone());
+        self.persist(&records).await?;
+        Ok((token, record))
+    }
+
+    /// The record of a token that is neither revoked nor expired.
+    pub async fn resolve(&self, token: &str) -> Option<ApiTokenRecord> {
+        if !token.starts_with(TOKEN_PREFIX) {
+            return None;
+        }
+        let token_hash = hash(token);
+        self.records
+            .read()
+            .await
+            .iter()
+            .find(|record| record.hash == token_hash && record.expires_at > Utc::now())
+            .cloned()
+    }
+
+    pub async fn list(&self, user_id: &str) -> Vec<ApiTokenInfo> {
+        self.records
+            .read()
+            .await
+            .iter()
+            .filter(|record| record.user_id == user_id && record.expires_at > Utc::now())
+            .map(ApiTokenInfo::from)
+            .collect()
+    }
+
+    /// Revokes one of `user_id`'s tokens.
+    pub async fn revoke(&self, user_id: &str, id: &str) -> AppResult<ApiTokenInfo> {
+        let mut records = self.records.write().await;
+        let index = records
+            .iter()
+            .position(|record| record.id == id && record.user_id == user_id)
+            .ok_or_else(|| AppError::NotFound(format!("API token with ID {}", id)))?;
+        let record = records.remove(index);
+        self.persist(&records).await?;
+        Ok(ApiTokenInfo::from(&record))
+    }
+}
@@ -0,0 +1,122 @@
+pub mod permissions;
+pub mod token;
+
+pub use permissions::{effective_role, Permission, RolePermissions};
+pub use token::TokenSecret;
+
+use prisma::{Role, WorkspaceRole};
+
+use crate::{
+    database::models::WorkspaceScope, error::AppError, prelude::AppResult, storage::Storage,
+    workspaces,
+};
+
+/// The authenticated user on whose behalf a command runs.
+#[derive(Debug, Clone)]
+pub struct Principal {
+    pub user_id: String,
+    pub email: String,
+    pub role: Role,
+    /// The active workspace; none for the default workspace.
+    pub workspace_id: Option<String>,
+    pub workspace_role: Option<WorkspaceRole>,
+}
+
+impl Principal {
+    pub fn can(&self, permission: Permission) -> bool {
+        self.role.allows(permission)
+            && self
+                .workspace_role
+                .map_or(true, |role| role.allows(permission))
+    }
+
+    /// The records the caller's list and search commands cover.
+    pub fn workspace(&self) -> WorkspaceScope {
+        WorkspaceScope::from(self.workspace_id.clone())
+    }
+
+    /// Refuses records outside the caller's active workspace.
+    pub fn require_workspace(&self, workspace_id: Option<&str>) -> AppResult<()> {
+        if self.workspace().contains(workspace_id) {
+            return Ok(());
+        }
+        Err(AppError::Forbidden(
+            "this record belongs to another workspace".into(),
+        ))
+    }
+
+    pub fn is(&self, user_id: &str) -> bool {
+        self.user_id == user_id
+    }
+
+    pub fn require(&self, permission: Permission) -> AppResult<()> {
+        if self.can(permission) {
+            return Ok(());
+        }
+        Err(AppError::Forbidden(format!(
+            "missing permission '{}'",
+            permission.as_str()
+        )))
+    }
+
+    /// Allows the call when it targets the caller's own account, or when the
+    /// caller holds `permission` otherwise.
+    pub fn require_self_or(&self, user_id: &str, permission: Permission) -> AppResult<()> {
+        if self.is(user_id) {
+            return Ok(());
+        }
+        self.require(permission)
+    }
+}
+
+/// Resolves the access token sent along with a command into the current user.
+///
+/// The user and their workspace membership are re-read on every call so that
+/// role changes and deletions take effect immediately rather than when the
+/// token expires.
+pub async fn authenticate(
+    storage: &dyn Storage,
+    secret: &TokenSecret,
+    token: Option<String>,
+) -> AppResult<Principal> {
+    let token = token.ok_or_else(|| AppError::Unauthorized("missing access token".into()))?;
+    let claims = secret
+        .decode_token(&token, token::TokenType::Access)
+        .map_err(|_| AppError::Unauthorized("invalid or expired access token".into()))?;
+
+    let user = storage
+        .get_user(claims.sub)
+        .await?
+        .ok_or_else(|| AppError::Unauthorized("unknown user".into()))?;
+    storage.signed_in(&user).await;
+
+    let (workspace_id, workspace_role) = match claims.workspace {
+        Some(claim) => {
+            // Memberships live in MongoDB; while it can't be reached, trust
+            // the role the token was issued with.
+            let membership = match storage.prisma() {
+                Ok(client) => workspaces::role_in(&client, &user.id, &claim.id).await,
+                Err(e) => Err(e),
+            };
+            let role = match membership {
+                Ok(Some(role)) => role,
+                Ok(None) => {
+                    return Err(AppError::Unauthorized(
+                        "no longer a member of this workspace".into(),
+                    ))
+                }
+                Err(_) => claim.role,
+            };
+            (Some(claim.id), Some(role))
+        }
+        None => (None, None),
+    };
+
+    Ok(Principal {
+        user_id: user.id,
+        email: user.email,
+        role: effective_role(user.role),
+        workspace_id,
+        workspace_role,
+    })
+}
@@ -0,0 +1,57 @@
+use prisma::{Role, WorkspaceRole};
+
+/// An action a command may require the caller to be allowed to perform.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Permission {
+    /// Read snippets, tags and the caller's own profile.
+    ReadVault,
+    /// Create, update and delete snippets and tags.
+    WriteVault,
+    /// List, update, delete and change the role of any user.
+    ManageUsers,
+    /// Back up, restore and git-sync the whole vault.
+    ManageVault,
+}
+
+impl Permission {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Permission::ReadVault => "read_vault",
+            Permission::WriteVault => "write_vault",
+            Permission::ManageUsers => "manage_users",
+            Permission::ManageVault => "manage_vault",
+        }
+    }
+}
+
+/// The role an account holds. Accounts created before roles existed have
+/// none stored and are members.
+pub fn effective_role(role: Option<Role>) -> Role {
+    role.unwrap_or(Role::Member)
+}
+
+/// Maps each role onto the permissions it grants.
+pub trait RolePermissions {
+    fn allows(&self, permission: Permission) -> bool;
+}
+
+impl RolePermissions for Role {
+    fn allows(&self, permission: Permission) -> bool {
+        match self {
+            Role::Admin => true,
+            Role::Member => matches!(permission, Permission::ReadVault | Permission::WriteVault),
+            Role::ReadOnly => matches!(permission, Permission::ReadVault),
+        }
+    }
+}
+
+/// A workspace role narrows what the account's role allows inside the
+/// workspace; it never grants more.
+impl RolePermissions for WorkspaceRole {
+    fn allows(&self, permission: Permission) -> bool {
+        match self {
+            WorkspaceRole::Owner | WorkspaceRole::Admin | WorkspaceRole::Member => true,
+            WorkspaceRole::Viewer => permission != Permission::WriteVault,
+        }
+    }
+}
@@ -0,0 +1,138 @@
+//! Signed session tokens. Each installation signs with its own random key,
+//! generated on first run and kept in the app data directory.
+
+use std::{fs::OpenOptions, io::Write, path::Path};
+
+use chrono::{Duration, Utc};
+use jsonwebtoken::{
+    decode, encode, errors::ErrorKind, DecodingKey, EncodingKey, Header, Validation,
+};
+use prisma::WorkspaceRole;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+
+use crate::{error::AppError, prelude::AppResult};
+
+pub const SECRET_FILE_NAME: &str = "token-secret.key";
+const SECRET_LEN: usize = 32;
+
+pub const ACCESS_TOKEN_EXPIRATION_HOURS: i64 = 12; // 12 hours
+pub const REFRESH_TOKEN_EXPIRATION_HOURS: i64 = 24 * 7; // 1 week
+
+/// What a token is for. Each kind is only accepted where it is expected, so
+/// a refresh token can't stand in for an access token.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TokenType {
+    Access,
+    Refresh,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Claims {
+    /// The user's id. Emails can change hands, ids never do.
+    pub sub: String,
+    pub typ: TokenType,
+    pub exp: i64,
+    pub iat: i64,
+    /// The active workspace; absent for the default workspace.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub workspace: Option<WorkspaceClaim>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkspaceClaim {
+    pub id: String,
+    /// The role when the token was issued, used while memberships can't be
+    /// checked.
+    pub role: WorkspaceRole,
+}
+
+/// The key this installation signs and checks tokens with.
+pub struct TokenSecret {
+    key: Vec<u8>,
+}
+
+fn secret_error(reason: &str) -> AppError {
+    AppError::EncryptionError(format!("Token secret: {}", reason))
+}
+
+impl TokenSecret {
+    /// Reads the key stored in `dir`, creating it on first run. The file is
+    /// only readable by the current user.
+    pub fn load_or_create(dir: &Path) -> AppResult<Self> {
+        let path = dir.join(SECRET_FILE_NAME);
+        if path.exists() {
+            let key = hex::decode(std::fs::read_to_string(&path)?.trim())
+                .map_err(|_| secret_error("the key file is corrupt"))?;
+            if key.len() != SECRET_LEN {
+                return Err(secret_error("the key file is corrupt"));
+            }
+            return Ok(Self { key });
+        }
+
+        let secret = Self::generate();
+        std::fs::create_dir_all(dir)?;
+        let mut options = OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        options
+            .open(&path)?
+            .write_all(hex::encode(&secret.key).as_bytes())?;
+        Ok(secret)
+    }
+
+    /// A key that only lasts as long as the process.
+    pub fn generate() -> Self {
+        let mut key = vec![0u8; SECRET_LEN];
+        rand::thread_rng().fill_bytes(&mut key);
+        Self { key }
+    }
+
+    pub fn create_token(
+        &self,
+        user_id: &str,
+        typ: TokenType,
+        workspace: Option<&WorkspaceClaim>,
+        expiration_hours: i64,
+    ) -> Result<String, jsonwebtoken::errors::Error> {
+        let claims = Claims {
+            sub: user_id.to_owned(),
+            typ,
+            workspace: workspace.cloned(),
+            exp: Utc::now()
+                .checked_add_signed(Duration::hours(expiration_hours))
+                .expect("valid timestamp")
+                .timestamp(),
+            iat: Utc::now().timestamp(),
+        };
+
+        encode(
+            &Header::default(),
+            &claims,
+            &EncodingKey::from_secret(&self.key),
+        )
+    }
+
+    /// Checks the signature, expiry and kind of `token`.
+    pub fn decode_token(
+        &self,
+        token: &str,
+        typ: TokenType,
+    ) -> Result<Claims, jsonwebtoken::errors::Error> {
+        let claims = decode::<Claims>(
+            token,
+            &DecodingKey::from_secret(&self.key),
+            &Validation::default(),
+        )?
+        .claims;
+        if claims.typ != typ {
+            return Err(ErrorKind::InvalidToken.into());
+        }
+        Ok(claims)
+    }
+}
@@ -0,0 +1,145 @@
+//! The on-disk format of a backup: a gzip-compressed tar archive holding
+//! `manifest.json` followed by the snapshot as `vault.json`.
+
+use std::{
+    fs::File,
+    io::{BufReader, Read},
+    path::Path,
+};
+
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use sha2::{Digest, Sha256};
+use tar::{Archive, Builder, Header};
+
+use super::{BackupCounts, BackupInfo, BackupManifest, BACKUP_FORMAT_VERSION};
+use crate::{database::models::VaultSnapshot, error::AppError, prelude::AppResult};
+
+const MANIFEST_ENTRY: &str = "manifest.json";
+const SNAPSHOT_ENTRY: &str = "vault.json";
+
+/// Writes `snapshot` to `path` along with the manifest built from its
+/// checksum and counts. The archive only appears at `path` once complete.
+pub fn write(
+    path: &Path,
+    snapshot: &VaultSnapshot,
+    manifest: impl FnOnce(String, BackupCounts) -> BackupManifest,
+) -> AppResult<BackupInfo> {
+    let snapshot_bytes = serde_json::to_vec(snapshot)?;
+    let manifest = manifest(checksum(&snapshot_bytes), BackupCounts::from(snapshot));
+    let manifest_bytes = serde_json::to_vec_pretty(&manifest)?;
+    let modified = manifest.created_at.timestamp().max(0) as u64;
+
+    let partial_path = path.with_extension("partial");
+    let encoder = GzEncoder::new(File::create(&partial_path)?, Compression::default());
+    let mut builder = Builder::new(encoder);
+    append(&mut builder, MANIFEST_ENTRY, &manifest_bytes, modified)?;
+    append(&mut builder, SNAPSHOT_ENTRY, &snapshot_bytes, modified)?;
+    builder.into_inner()?.finish()?.sync_all()?;
+    std::fs::rename(&partial_path, path)?;
+
+    Ok(BackupInfo {
+        path: path.to_path_buf(),
+        size_bytes: std::fs::metadata(path)?.len(),
+        manifest,
+    })
+}
+
+/// Reads a backup, refusing archives from a newer format version or whose
+/// snapshot doesn't match the manifest's checksum.
+pub fn read(path: &Path) -> AppResult<(BackupManifest, VaultSnapshot)> {
+    let mut manifest = None;
+    let mut snapshot_bytes = None;
+
+    for entry in open(path)?.entries()? {
+        let mut entry = entry?;
+        let name = entry.path()?.to_string_lossy().into_owned();
+        match name.as_str() {
+            MANIFEST_ENTRY => {
+                manifest = Some(serde_json::from_reader::<_, BackupManifest>(&mut entry)?)
+            }
+            SNAPSHOT_ENTRY => {
+                let mut bytes = vec![];
+                entry.read_to_end(&mut bytes)?;
+                snapshot_bytes = Some(bytes);
+            }
+            _ => {}
+        }
+    }
+
+    let manifest = manifest.ok_or_else(|| invalid(path, "it has no manifest"))?;
+    check_version(path, &manifest)?;
+    let snapshot_bytes = snapshot_bytes.ok_or_else(|| invalid(path, "it has no snapshot"))?;
+    if checksum(&snapshot_bytes) != manifest.checksum {
+        return Err(invalid(
+            path,
+            "its checksum doesn't match, it may be corrupted",
+        ));
+    }
+
+    let snapshot = serde_json::from_slice(&snapshot_bytes)?;
+    Ok((manifest, snapshot))
+}
+
+/// Reads only the manifest, which comes first in the archive.
+pub fn read_info(path: &Path) -> AppResult<BackupInfo> {
+    let mut archive = open(path)?;
+    let mut entries = archive.entries()?;
+    let entry = entries
+        .next()
+        .ok_or_else(|| invalid(path, "it is empty"))??;
+    if entry.path()?.as_os_str() != MANIFEST_ENTRY {
+        return Err(invalid(path, "it has no manifest"));
+    }
+
+    let manifest: BackupManifest = serde_json::from_reader(entry)?;
+    Ok(BackupInfo {
+        path: path.to_path_buf(),
+        size_bytes: std::fs::metadata(path)?.len(),
+        manifest,
+    })
+}
+
+fn open(path: &Path) -> AppResult<Archive<GzDecoder<BufReader<File>>>> {
+    let file = File::open(path)?;
+    Ok(Archive::new(GzDecoder::new(BufReader::new(file))))
+}
+
+fn append(
+    builder: &mut Builder<GzEncoder<File>>,
+    name: &str,
+    bytes: &[u8],
+    modified: u64,
+) -> AppResult<()> {
+    let mut header = Header::new_gnu();
+    header.set_size(bytes.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(modified);
+    header.set_cksum();
+    builder.append_data(&mut header, name, bytes)?;
+    Ok(())
+}
+
+fn check_version(path: &Path, manifest: &BackupManifest) -> AppResult<()> {
+    if manifest.format_version > BACKUP_FORMAT_VERSION {
+        return Err(invalid(
+            path,
+            &format!(
+                "it uses format version {}, but this version of the app only reads up to {}",
+                manifest.format_version, BACKUP_FORMAT_VERSION
+            ),
+        ));
+    }
+    Ok(())
+}
+
+fn checksum(bytes: &[u8]) -> String {
+    hex::encode(Sha256::digest(bytes))
+}
+
+fn invalid(path: &Path, reason: &str) -> AppError {
+    AppError::Other(format!(
+        "{} is not a valid backup: {}",
+        path.display(),
+        reason
+    ))
+}
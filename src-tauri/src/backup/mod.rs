@@ -0,0 +1,289 @@
+pub mod archive;
+mod remap;
+pub mod schedule;
+
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, Utc};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::{
+    config::BackupConfig, database::models::VaultSnapshot, error::AppError, prelude::AppResult,
+    storage::Storage,
+};
+
+/// Bumped whenever the archive layout or the snapshot shape changes in a way
+/// older versions can't read.
+pub const BACKUP_FORMAT_VERSION: u32 = 1;
+pub const BACKUP_EXTENSION: &str = ".tar.gz";
+const BACKUP_DIR_NAME: &str = "backups";
+const TIMESTAMP_FORMAT: &str = "%Y%m%d-%H%M%S";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum BackupKind {
+    /// Requested through `create_backup`.
+    Manual,
+    /// Taken by the scheduler; only these are rotated.
+    Automatic,
+    /// Taken right before a restore replaces the vault.
+    PreRestore,
+}
+
+impl BackupKind {
+    fn file_prefix(&self) -> &'static str {
+        match self {
+            BackupKind::Manual => "backup-",
+            BackupKind::Automatic => "auto-backup-",
+            BackupKind::PreRestore => "pre-restore-backup-",
+        }
+    }
+
+    fn file_name(&self, created_at: DateTime<Utc>) -> String {
+        format!(
+            "{}{}{}",
+            self.file_prefix(),
+            created_at.format(TIMESTAMP_FORMAT),
+            BACKUP_EXTENSION
+        )
+    }
+}
+
+/// Describes a backup archive; stored next to the snapshot inside it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupManifest {
+    pub format_version: u32,
+    pub app_version: String,
+    pub created_at: DateTime<Utc>,
+    pub kind: BackupKind,
+    /// Backend the snapshot was read from.
+    pub backend: String,
+    pub counts: BackupCounts,
+    /// Hex-encoded SHA-256 of the snapshot.
+    pub checksum: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupCounts {
+    pub snippets: usize,
+    pub states: usize,
+    pub tags: usize,
+    pub snippet_ratings: usize,
+    pub tag_usages: usize,
+    pub activity_logs: usize,
+    /// Absent from manifests written before collections were backed up.
+    #[serde(default)]
+    pub collections: usize,
+}
+
+impl From<&VaultSnapshot> for BackupCounts {
+    fn from(snapshot: &VaultSnapshot) -> Self {
+        Self {
+            snippets: snapshot.snippets.len(),
+            states: snapshot.states.len(),
+            tags: snapshot.tags.len(),
+            snippet_ratings: snapshot.snippet_ratings.len(),
+            tag_usages: snapshot.tag_usages.len(),
+            activity_logs: snapshot.activity_logs.len(),
+            collections: snapshot.collections.len(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupInfo {
+    pub path: PathBuf,
+    pub size_bytes: u64,
+    pub manifest: BackupManifest,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RestoreMode {
+    /// Adds the backup's records to the vault. Records whose id is already
+    /// taken get a new one, and references to them are rewritten.
+    Merge,
+    /// Removes all snippets, tags and their related records first. A backup of
+    /// the current vault is taken beforehand.
+    Replace,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupForm {
+    /// Defaults to the configured backup directory.
+    pub directory: Option<PathBuf>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RestoreForm {
+    pub path: PathBuf,
+    pub mode: RestoreMode,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RestoreReport {
+    pub mode: RestoreMode,
+    pub restored: BackupCounts,
+    /// Records that were given a new id because theirs was already taken.
+    pub remapped_ids: usize,
+    /// Ratings and tag usages dropped because what they point at is missing.
+    pub dropped_records: usize,
+    /// Snippets whose author no longer exists and that were restored without
+    /// one.
+    pub detached_authors: usize,
+    pub safety_backup: Option<BackupInfo>,
+}
+
+/// Directory backups are written to and listed from.
+pub fn backup_dir(app: &AppHandle, config: &BackupConfig) -> AppResult<PathBuf> {
+    config
+        .directory
+        .clone()
+        .or_else(|| {
+            app.path_resolver()
+                .app_data_dir()
+                .map(|dir| dir.join(BACKUP_DIR_NAME))
+        })
+        .ok_or_else(|| "Could not resolve the backup directory".into())
+}
+
+/// Snapshots the vault into a new archive in `dir`.
+pub async fn create_backup(
+    storage: &dyn Storage,
+    dir: &Path,
+    kind: BackupKind,
+) -> AppResult<BackupInfo> {
+    let snapshot = storage.export_snapshot().await?;
+    let created_at = Utc::now();
+    let path = dir.join(kind.file_name(created_at));
+    let backend = storage.backend_name().to_string();
+
+    tokio::fs::create_dir_all(dir).await?;
+    let backup = run_blocking(move || {
+        archive::write(&path, &snapshot, |checksum, counts| BackupManifest {
+            format_version: BACKUP_FORMAT_VERSION,
+            app_version: env!("CARGO_PKG_VERSION").to_string(),
+            created_at,
+            kind,
+            backend,
+            counts,
+            checksum,
+        })
+    })
+    .await?;
+
+    info!(
+        "Created backup at {} ({} snippets, {} bytes)",
+        backup.path.display(),
+        backup.manifest.counts.snippets,
+        backup.size_bytes
+    );
+    Ok(backup)
+}
+
+/// Restores the archive at `path`, taking a safety backup into `backup_dir`
+/// first when the vault is about to be replaced.
+pub async fn restore_backup(
+    storage: &dyn Storage,
+    path: PathBuf,
+    mode: RestoreMode,
+    backup_dir: &Path,
+) -> AppResult<RestoreReport> {
+    let (manifest, mut snapshot) = run_blocking(move || archive::read(&path)).await?;
+
+    let (taken_ids, safety_backup) = match mode {
+        RestoreMode::Merge => (remap::record_ids(&storage.export_snapshot().await?), None),
+        RestoreMode::Replace => {
+            let backup = create_backup(storage, backup_dir, BackupKind::PreRestore).await?;
+            (HashSet::new(), Some(backup))
+        }
+    };
+    let user_ids = storage
+        .list_users(None)
+        .await?
+        .into_iter()
+        .map(|user| user.id)
+        .collect();
+
+    let outcome = remap::prepare(&mut snapshot, &taken_ids, &user_ids);
+    if outcome.dropped_records > 0 {
+        warn!(
+            "Dropped {} records referring to data missing from the backup",
+            outcome.dropped_records
+        );
+    }
+
+    let restored = BackupCounts::from(&snapshot);
+    storage
+        .import_snapshot(snapshot, mode == RestoreMode::Replace)
+        .await?;
+
+    info!(
+        "Restored backup from {} ({} snippets, {} remapped ids)",
+        manifest.created_at, restored.snippets, outcome.remapped_ids
+    );
+    Ok(RestoreReport {
+        mode,
+        restored,
+        remapped_ids: outcome.remapped_ids,
+        dropped_records: outcome.dropped_records,
+        detached_authors: outcome.detached_authors,
+        safety_backup,
+    })
+}
+
+/// Lists the archives in `dir`, newest first. Files that can't be read as a
+/// backup are skipped.
+pub async fn list_backups(dir: PathBuf) -> AppResult<Vec<BackupInfo>> {
+    run_blocking(move || {
+        let entries = match std::fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut backups = vec![];
+        for entry in entries {
+            let path = entry?.path();
+            if !is_backup_file(&path) {
+                continue;
+            }
+            match archive::read_info(&path) {
+                Ok(backup) => backups.push(backup),
+                Err(e) => warn!("Skipping unreadable backup {}: {}", path.display(), e),
+            }
+        }
+
+        backups.sort_by(|a, b| b.manifest.created_at.cmp(&a.manifest.created_at));
+        Ok(backups)
+    })
+    .await
+}
+
+fn is_backup_file(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .map_or(false, |name| name.ends_with(BACKUP_EXTENSION))
+}
+
+/// Archives are compressed and hashed synchronously, off the async runtime.
+async fn run_blocking<T, F>(operation: F) -> AppResult<T>
+where
+    F: FnOnce() -> AppResult<T> + Send + 'static,
+    T: Send + 'static,
+{
+    tokio::task::spawn_blocking(operation)
+        .await
+        .map_err(|e| AppError::Other(format!("Backup task failed: {}", e)))?
+}
@@ -0,0 +1,347 @@
+//! Prepares a backup's records for writing into a vault that may already
+//! hold some of them.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::{database::models::VaultSnapshot, storage::new_object_id};
+
+pub struct RemapOutcome {
+    pub remapped_ids: usize,
+    pub dropped_records: usize,
+    pub detached_authors: usize,
+}
+
+/// Ids of every record in the snapshot.
+pub fn record_ids(snapshot: &VaultSnapshot) -> HashSet<String> {
+    snapshot
+        .snippets
+        .iter()
+        .map(|snippet| &snippet.id)
+        .chain(snapshot.states.iter().map(|state| &state.id))
+        .chain(snapshot.tags.iter().map(|tag| &tag.id))
+        .chain(snapshot.snippet_ratings.iter().map(|rating| &rating.id))
+        .chain(snapshot.tag_usages.iter().map(|usage| &usage.id))
+        .chain(snapshot.activity_logs.iter().map(|activity| &activity.id))
+        .chain(snapshot.collections.iter().map(|collection| &collection.id))
+        .cloned()
+        .collect()
+}
+
+/// Gives every record whose id is in `taken_ids` a fresh ObjectId and
+/// rewrites the references to it. References the target can't satisfy are
+/// cut: authors missing from `user_ids` are cleared, missing snippets and
+/// parents are taken out of tags and collections, and ratings and tag usages
+/// whose snippet, tag or user is missing are dropped.
+pub fn prepare(
+    snapshot: &mut VaultSnapshot,
+    taken_ids: &HashSet<String>,
+    user_ids: &HashSet<String>,
+) -> RemapOutcome {
+    let ids: HashMap<String, String> = record_ids(snapshot)
+        .into_iter()
+        .filter(|id| taken_ids.contains(id))
+        .map(|id| (id, new_object_id()))
+        .collect();
+    let remap = |id: &mut String| {
+        if let Some(new_id) = ids.get(id) {
+            *id = new_id.clone();
+        }
+    };
+
+    for state in &mut snapshot.states {
+        remap(&mut state.id);
+    }
+    for tag in &mut snapshot.tags {
+        remap(&mut tag.id);
+        tag.parent_id.iter_mut().for_each(remap);
+        tag.snippet_ids.iter_mut().for_each(remap);
+    }
+    for snippet in &mut snapshot.snippets {
+        remap(&mut snippet.id);
+        remap(&mut snippet.snippet_state_id);
+        snippet.tag_ids.iter_mut().for_each(remap);
+    }
+    for rating in &mut snapshot.snippet_ratings {
+        remap(&mut rating.id);
+        remap(&mut rating.snippet_id);
+    }
+    for usage in &mut snapshot.tag_usages {
+        remap(&mut usage.id);
+        remap(&mut usage.tag_id);
+        remap(&mut usage.snippet_id);
+    }
+    for activity in &mut snapshot.activity_logs {
+        remap(&mut activity.id);
+        activity.snippet_id.iter_mut().for_each(remap);
+    }
+    for collection in &mut snapshot.collections {
+        remap(&mut collection.id);
+        collection.parent_id.iter_mut().for_each(remap);
+        collection.snippet_ids.iter_mut().for_each(remap);
+    }
+
+    let snippet_ids: HashSet<String> = snapshot.snippets.iter().map(|s| s.id.clone()).collect();
+    let tag_ids: HashSet<String> = snapshot.tags.iter().map(|t| t.id.clone()).collect();
+
+    for tag in &mut snapshot.tags {
+        if tag
+            .parent_id
+            .as_ref()
+            .map_or(false, |id| !tag_ids.contains(id))
+        {
+            tag.parent_id = None;
+        }
+        tag.snippet_ids.retain(|id| snippet_ids.contains(id));
+    }
+
+    let collection_ids: HashSet<String> = snapshot
+        .collections
+        .iter()
+        .map(|collection| collection.id.clone())
+        .collect();
+    for collection in &mut snapshot.collections {
+        if collection
+            .parent_id
+            .as_ref()
+            .map_or(false, |id| !collection_ids.contains(id))
+        {
+            collection.parent_id = None;
+        }
+        collection.snippet_ids.retain(|id| snippet_ids.contains(id));
+    }
+
+    let mut detached_authors = 0;
+    for snippet in &mut snapshot.snippets {
+        snippet.tag_ids.retain(|id| tag_ids.contains(id));
+        if snippet
+            .author_id
+            .as_ref()
+            .map_or(false, |id| !user_ids.contains(id))
+        {
+            snippet.author_id = None;
+            detached_authors += 1;
+        }
+    }
+
+    let records_before = snapshot.snippet_ratings.len() + snapshot.tag_usages.len();
+    snapshot.snippet_ratings.retain(|rating| {
+        snippet_ids.contains(&rating.snippet_id) && user_ids.contains(&rating.user_id)
+    });
+    snapshot
+        .tag_usages
+        .retain(|usage| tag_ids.contains(&usage.tag_id) && snippet_ids.contains(&usage.snippet_id));
+    let records_after = snapshot.snippet_ratings.len() + snapshot.tag_usages.len();
+
+    RemapOutcome {
+        remapped_ids: ids.len(),
+        dropped_records: records_before - records_after,
+        detached_authors,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::de::DeserializeOwned;
+    use serde_json::{json, Value};
+
+    use super::*;
+
+    const AT: &str = "2024-05-01T10:00:00+00:00";
+
+    // Ids shaped like ObjectIds, by what they name.
+    const SNIPPET: &str = "000000000000000000000001";
+    const OTHER_SNIPPET: &str = "000000000000000000000002";
+    const STATE: &str = "000000000000000000000011";
+    const OTHER_STATE: &str = "000000000000000000000012";
+    const TAG: &str = "000000000000000000000021";
+    const CHILD_TAG: &str = "000000000000000000000022";
+    const COLLECTION: &str = "000000000000000000000031";
+    const RATING: &str = "000000000000000000000041";
+    const USAGE: &str = "000000000000000000000051";
+    const AUTHOR: &str = "0000000000000000000000a1";
+    const GONE_USER: &str = "0000000000000000000000a2";
+    const GONE_SNIPPET: &str = "0000000000000000000000f1";
+    const GONE_TAG: &str = "0000000000000000000000f2";
+    const GONE_COLLECTION: &str = "0000000000000000000000f3";
+
+    /// Records are built the way backups are read, from JSON.
+    fn record<T: DeserializeOwned>(value: Value) -> T {
+        serde_json::from_value(value).unwrap()
+    }
+
+    fn snippet(id: &str, state_id: &str, tag_ids: &[&str], author_id: &str) -> Value {
+        json!({
+            "id": id,
+            "createdAt": AT,
+            "updatedAt": AT,
+            "title": "Title",
+            "description": "",
+            "language": "rust",
+            "code": "fn main() {}",
+            "tagIds": tag_ids,
+            "snippetStateId": state_id,
+            "authorId": author_id,
+        })
+    }
+
+    fn tag(id: &str, parent_id: Option<&str>, snippet_ids: &[&str]) -> Value {
+        json!({
+            "id": id,
+            "createdAt": AT,
+            "updatedAt": AT,
+            "name": id,
+            "count": snippet_ids.len(),
+            "parentId": parent_id,
+            "snippetIds": snippet_ids,
+        })
+    }
+
+    fn snapshot() -> VaultSnapshot {
+        VaultSnapshot {
+            snippets: vec![
+                record(snippet(SNIPPET, STATE, &[TAG, GONE_TAG], AUTHOR)),
+                record(snippet(OTHER_SNIPPET, OTHER_STATE, &[CHILD_TAG], GONE_USER)),
+            ],
+            states: [STATE, OTHER_STATE]
+                .iter()
+                .map(|id| record(json!({ "id": id, "isFavorite": false, "isDark": false })))
+                .collect(),
+            tags: vec![
+                record(tag(TAG, None, &[SNIPPET, GONE_SNIPPET])),
+                record(tag(CHILD_TAG, Some(TAG), &[OTHER_SNIPPET])),
+            ],
+            snippet_ratings: vec![
+                record(json!({
+                    "id": RATING,
+                    "snippetId": SNIPPET,
+                    "userId": AUTHOR,
+                    "rating": 5,
+                    "createdAt": AT,
+                })),
+                record(json!({
+                    "id": "000000000000000000000042",
+                    "snippetId": SNIPPET,
+                    "userId": GONE_USER,
+                    "rating": 1,
+                    "createdAt": AT,
+                })),
+            ],
+            tag_usages: vec![
+                record(json!({ "id": USAGE, "tagId": TAG, "snippetId": SNIPPET, "count": 3 })),
+                record(json!({
+                    "id": "000000000000000000000052",
+                    "tagId": GONE_TAG,
+                    "snippetId": SNIPPET,
+                    "count": 1,
+                })),
+            ],
+            activity_logs: vec![record(json!({
+                "id": "000000000000000000000061",
+                "userId": AUTHOR,
+                "action": "copy",
+                "timestamp": AT,
+                "snippetId": SNIPPET,
+            }))],
+            collections: vec![record(json!({
+                "id": COLLECTION,
+                "createdAt": AT,
+                "updatedAt": AT,
+                "name": "Favorites",
+                "description": "",
+                "position": 0,
+                "parentId": GONE_COLLECTION,
+                "snippetIds": [OTHER_SNIPPET, GONE_SNIPPET, SNIPPET],
+            }))],
+        }
+    }
+
+    fn ids(ids: &[&str]) -> HashSet<String> {
+        ids.iter().map(|id| id.to_string()).collect()
+    }
+
+    #[test]
+    fn record_ids_lists_every_record() {
+        let ids = record_ids(&snapshot());
+
+        assert_eq!(ids.len(), 12);
+        for id in [SNIPPET, STATE, TAG, CHILD_TAG, COLLECTION, RATING, USAGE] {
+            assert!(ids.contains(id), "missing {}", id);
+        }
+    }
+
+    #[test]
+    fn ids_that_are_free_are_kept() {
+        let mut snapshot = snapshot();
+        let outcome = prepare(&mut snapshot, &HashSet::new(), &ids(&[AUTHOR, GONE_USER]));
+
+        assert_eq!(outcome.remapped_ids, 0);
+        assert_eq!(outcome.detached_authors, 0);
+        assert_eq!(snapshot.snippets[0].id, SNIPPET);
+        assert_eq!(snapshot.snippets[0].snippet_state_id, STATE);
+        assert_eq!(snapshot.tags[1].parent_id.as_deref(), Some(TAG));
+    }
+
+    #[test]
+    fn taken_ids_are_replaced_everywhere_they_are_used() {
+        let mut snapshot = snapshot();
+        let outcome = prepare(
+            &mut snapshot,
+            &ids(&[SNIPPET, STATE, TAG]),
+            &ids(&[AUTHOR, GONE_USER]),
+        );
+
+        assert_eq!(outcome.remapped_ids, 3);
+        let snippet = &snapshot.snippets[0];
+        assert_ne!(snippet.id, SNIPPET);
+        assert_eq!(snippet.id.len(), SNIPPET.len());
+        assert_ne!(snippet.snippet_state_id, STATE);
+        assert_eq!(snapshot.states[0].id, snippet.snippet_state_id);
+
+        let tag = &snapshot.tags[0];
+        assert_ne!(tag.id, TAG);
+        assert_eq!(snippet.tag_ids, [tag.id.clone()]);
+        assert_eq!(tag.snippet_ids, [snippet.id.clone()]);
+        assert_eq!(snapshot.tags[1].parent_id.as_ref(), Some(&tag.id));
+
+        assert_eq!(snapshot.snippet_ratings[0].snippet_id, snippet.id);
+        assert_eq!(snapshot.tag_usages[0].tag_id, tag.id);
+        assert_eq!(snapshot.tag_usages[0].snippet_id, snippet.id);
+        assert_eq!(
+            snapshot.activity_logs[0].snippet_id.as_ref(),
+            Some(&snippet.id)
+        );
+        assert_eq!(
+            snapshot.collections[0].snippet_ids,
+            [OTHER_SNIPPET.to_string(), snippet.id.clone()]
+        );
+
+        // Records that were free keep their ids.
+        assert_eq!(snapshot.snippets[1].id, OTHER_SNIPPET);
+        assert_eq!(snapshot.collections[0].id, COLLECTION);
+    }
+
+    #[test]
+    fn references_to_missing_records_are_cut() {
+        let mut snapshot = snapshot();
+        let outcome = prepare(&mut snapshot, &HashSet::new(), &ids(&[AUTHOR]));
+
+        assert_eq!(snapshot.snippets[0].tag_ids, [TAG.to_string()]);
+        assert_eq!(snapshot.tags[0].snippet_ids, [SNIPPET.to_string()]);
+        assert_eq!(snapshot.collections[0].parent_id, None);
+        assert_eq!(
+            snapshot.collections[0].snippet_ids,
+            [OTHER_SNIPPET.to_string(), SNIPPET.to_string()]
+        );
+
+        assert_eq!(outcome.detached_authors, 1);
+        assert_eq!(snapshot.snippets[0].author_id.as_deref(), Some(AUTHOR));
+        assert_eq!(snapshot.snippets[1].author_id, None);
+
+        // The rating by the missing user and the usage of the missing tag.
+        assert_eq!(outcome.dropped_records, 2);
+        assert_eq!(snapshot.snippet_ratings.len(), 1);
+        assert_eq!(snapshot.snippet_ratings[0].id, RATING);
+        assert_eq!(snapshot.tag_usages.len(), 1);
+        assert_eq!(snapshot.tag_usages[0].id, USAGE);
+    }
+}
@@ -0,0 +1,112 @@
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use chrono::{DateTime, NaiveDateTime, TimeZone, Utc};
+use log::{error, info, warn};
+use tauri::{AppHandle, Manager};
+
+use super::{backup_dir, create_backup, BackupKind, BACKUP_EXTENSION, TIMESTAMP_FORMAT};
+use crate::{prelude::AppResult, state::AppState};
+
+const RETRY_DELAY: Duration = Duration::from_secs(5 * 60);
+const SECONDS_PER_HOUR: u64 = 60 * 60;
+
+/// Takes automatic backups at the configured interval and deletes the oldest
+/// ones beyond the configured count. Does nothing when disabled in settings.
+///
+/// The schedule survives restarts: the next backup is due one interval after
+/// the newest automatic backup on disk.
+pub fn spawn_backup_scheduler(app: AppHandle) {
+    let config = app.state::<AppState>().config.backup.clone();
+    if !config.enabled {
+        return;
+    }
+
+    let dir = match backup_dir(&app, &config) {
+        Ok(dir) => dir,
+        Err(e) => {
+            error!("Automatic backups are disabled: {}", e);
+            return;
+        }
+    };
+    let interval = Duration::from_secs(config.interval_hours.max(1) * SECONDS_PER_HOUR);
+    info!(
+        "Automatic backups every {}h into {}",
+        config.interval_hours,
+        dir.display()
+    );
+
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(time_until_due(&dir, interval)).await;
+
+            let storage = app.state::<AppState>().storage.clone();
+            match create_backup(storage.as_ref(), &dir, BackupKind::Automatic).await {
+                Ok(_) => {
+                    if let Err(e) = rotate(&dir, config.keep) {
+                        warn!("Failed to rotate automatic backups: {}", e);
+                    }
+                }
+                Err(e) => {
+                    warn!(
+                        "Automatic backup failed, retrying in {}s: {}",
+                        RETRY_DELAY.as_secs(),
+                        e
+                    );
+                    tokio::time::sleep(RETRY_DELAY).await;
+                }
+            }
+        }
+    });
+}
+
+fn time_until_due(dir: &Path, interval: Duration) -> Duration {
+    let last_backup = automatic_backups(dir)
+        .unwrap_or_default()
+        .last()
+        .map(|(created_at, _)| *created_at);
+
+    match last_backup {
+        Some(created_at) => {
+            let elapsed = (Utc::now() - created_at).to_std().unwrap_or_default();
+            interval.saturating_sub(elapsed)
+        }
+        None => Duration::ZERO,
+    }
+}
+
+/// Deletes all but the newest `keep` automatic backups.
+fn rotate(dir: &Path, keep: usize) -> AppResult<()> {
+    let backups = automatic_backups(dir)?;
+    let excess = backups.len().saturating_sub(keep.max(1));
+
+    for (_, path) in backups.into_iter().take(excess) {
+        std::fs::remove_file(&path)?;
+        info!("Removed old backup {}", path.display());
+    }
+    Ok(())
+}
+
+/// Automatic backups in `dir`, oldest first, dated by their file name.
+fn automatic_backups(dir: &Path) -> AppResult<Vec<(DateTime<Utc>, PathBuf)>> {
+    let prefix = BackupKind::Automatic.file_prefix();
+    let mut backups = vec![];
+
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        let created_at = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| name.strip_prefix(prefix))
+            .and_then(|name| name.strip_suffix(BACKUP_EXTENSION))
+            .and_then(|stamp| NaiveDateTime::parse_from_str(stamp, TIMESTAMP_FORMAT).ok());
+        if let Some(created_at) = created_at {
+            backups.push((Utc.from_utc_datetime(&created_at), path));
+        }
+    }
+
+    backups.sort();
+    Ok(backups)
+}
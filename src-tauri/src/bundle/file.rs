@@ -0,0 +1,88 @@
+//! The on-disk format of a bundle: an eight byte header followed by the
+//! zstd-compressed bundle, serialized as JSON or CBOR.
+//!
+//! The header is `SVBNDL`, the format version and the encoding, `J` or `C`.
+
+use sha2::{Digest, Sha256};
+
+use super::{Bundle, BundleContents, BundleEncoding, BUNDLE_FORMAT_VERSION};
+use crate::{error::AppError, prelude::AppResult};
+
+const MAGIC: &[u8; 6] = b"SVBNDL";
+const HEADER_LENGTH: usize = MAGIC.len() + 2;
+const ZSTD_LEVEL: i32 = 9;
+
+impl BundleEncoding {
+    fn tag(self) -> u8 {
+        match self {
+            BundleEncoding::Json => b'J',
+            BundleEncoding::Cbor => b'C',
+        }
+    }
+
+    fn from_tag(tag: u8) -> AppResult<Self> {
+        match tag {
+            b'J' => Ok(BundleEncoding::Json),
+            b'C' => Ok(BundleEncoding::Cbor),
+            _ => Err(invalid("unknown encoding")),
+        }
+    }
+}
+
+fn invalid(reason: &str) -> AppError {
+    AppError::Other(format!("Not a valid snippet bundle: {}", reason))
+}
+
+pub fn encode(bundle: &Bundle, encoding: BundleEncoding) -> AppResult<Vec<u8>> {
+    let body = match encoding {
+        BundleEncoding::Json => serde_json::to_vec(bundle)?,
+        BundleEncoding::Cbor => {
+            let mut body = vec![];
+            ciborium::into_writer(bundle, &mut body)
+                .map_err(|e| AppError::Other(format!("Failed to encode the bundle: {}", e)))?;
+            body
+        }
+    };
+
+    let mut bytes = Vec::with_capacity(HEADER_LENGTH + body.len() / 4);
+    bytes.extend_from_slice(MAGIC);
+    bytes.push(BUNDLE_FORMAT_VERSION);
+    bytes.push(encoding.tag());
+    bytes.extend(zstd::encode_all(body.as_slice(), ZSTD_LEVEL)?);
+    Ok(bytes)
+}
+
+/// Reads a bundle, refusing files from a newer format version. Its hash and
+/// signature are checked separately.
+pub fn decode(bytes: &[u8]) -> AppResult<(Bundle, BundleEncoding)> {
+    if bytes.len() < HEADER_LENGTH || &bytes[..MAGIC.len()] != MAGIC {
+        return Err(invalid("missing header"));
+    }
+    let version = bytes[MAGIC.len()];
+    if version > BUNDLE_FORMAT_VERSION {
+        return Err(AppError::Other(format!(
+            "Bundle format version {} is newer than the supported version {}",
+            version, BUNDLE_FORMAT_VERSION
+        )));
+    }
+    let encoding = BundleEncoding::from_tag(bytes[MAGIC.len() + 1])?;
+
+    let body = zstd::decode_all(&bytes[HEADER_LENGTH..])
+        .map_err(|e| invalid(&format!("decompression failed ({})", e)))?;
+    let bundle = match encoding {
+        BundleEncoding::Json => {
+            serde_json::from_slice(&body).map_err(|e| invalid(&e.to_string()))?
+        }
+        BundleEncoding::Cbor => {
+            ciborium::from_reader(body.as_slice()).map_err(|e| invalid(&e.to_string()))?
+        }
+    };
+    Ok((bundle, encoding))
+}
+
+/// Hex-encoded SHA-256 of the contents. It is taken over their JSON form
+/// whatever the file's encoding, so re-encoding a bundle keeps its hash.
+pub fn content_hash(contents: &BundleContents) -> AppResult<String> {
+    let json = serde_json::to_vec(contents)?;
+    Ok(hex::encode(Sha256::digest(&json)))
+}
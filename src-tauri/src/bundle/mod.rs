@@ -0,0 +1,788 @@
+//! Snippet bundles: single `.svault` files holding a pack of snippets with
+//! their tags and collections, for sending to teammates.
+//!
+//! A bundle carries a SHA-256 hash of its contents and may be signed, so an
+//! import can tell a damaged or altered file and who made it. Snippets that
+//! already exist in the workspace, by title and language, are skipped,
+//! overwritten or imported alongside as the importer chooses.
+
+pub mod file;
+pub mod signing;
+
+use std::{
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+};
+
+use chrono::{DateTime, FixedOffset, Utc};
+use log::info;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    auth::Principal,
+    collections,
+    config::BundleConfig,
+    database::models::{
+        Collection, CollectionForm, Snippet, SnippetFilter, SnippetForm, SnippetStateUpdate,
+        TagFilter, TagForm,
+    },
+    encryption::{self, VaultLock},
+    error::AppError,
+    languages,
+    prelude::AppResult,
+    secrets::SnippetSecrets,
+    sharing::{Access, Grants},
+    snippets::SnippetSaver,
+    storage::Storage,
+};
+
+use self::signing::BundleSigner;
+
+/// Bumped whenever the file layout or the contents change in a way older
+/// versions can't read.
+pub const BUNDLE_FORMAT_VERSION: u8 = 1;
+pub const BUNDLE_EXTENSION: &str = "svault";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum BundleEncoding {
+    /// Readable once decompressed.
+    Json,
+    /// Smaller, and the default.
+    Cbor,
+}
+
+impl Default for BundleEncoding {
+    fn default() -> Self {
+        BundleEncoding::Cbor
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ConflictResolution {
+    /// Keeps the existing record and leaves the bundle's out.
+    Skip,
+    /// Replaces the existing record's contents with the bundle's.
+    Overwrite,
+    /// Imports the bundle's record next to the existing one.
+    Duplicate,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Bundle {
+    pub manifest: BundleManifest,
+    pub contents: BundleContents,
+    pub signature: Option<BundleSignature>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BundleManifest {
+    pub format_version: u8,
+    pub app_version: String,
+    pub created_at: DateTime<Utc>,
+    /// Email of the user who exported the bundle.
+    pub created_by: String,
+    pub name: String,
+    pub description: String,
+    pub counts: BundleCounts,
+    /// Hex-encoded SHA-256 of the contents.
+    pub content_hash: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BundleCounts {
+    pub snippets: usize,
+    pub tags: usize,
+    pub collections: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BundleSignature {
+    /// Hex-encoded Ed25519 public key of the signer.
+    pub public_key: String,
+    pub signature: String,
+}
+
+/// The records of a bundle. Ids are those of the exporting vault and only
+/// link the records to each other.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BundleContents {
+    pub snippets: Vec<BundleSnippet>,
+    /// The snippets' tags and every tag above them.
+    pub tags: Vec<BundleTag>,
+    pub collections: Vec<BundleCollection>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BundleSnippet {
+    pub id: String,
+    pub title: String,
+    pub description: String,
+    pub language: String,
+    pub code: String,
+    pub tag_ids: Vec<String>,
+    pub is_favorite: bool,
+    pub created_at: DateTime<FixedOffset>,
+    pub updated_at: DateTime<FixedOffset>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BundleTag {
+    pub id: String,
+    pub name: String,
+    pub color: Option<String>,
+    pub emoji: Option<String>,
+    pub parent_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BundleCollection {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    /// Absent for the collections at the top of the bundle.
+    pub parent_id: Option<String>,
+    pub position: i32,
+    pub snippet_ids: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BundleExportForm {
+    /// Where to write the bundle; `.svault` is added when there is no
+    /// extension.
+    pub path: PathBuf,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub snippet_ids: Vec<String>,
+    /// Collections to include with everything nested in them.
+    #[serde(default)]
+    pub collection_ids: Vec<String>,
+    #[serde(default)]
+    pub encoding: BundleEncoding,
+    /// Sign the bundle with this installation's key.
+    #[serde(default)]
+    pub sign: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BundleImportForm {
+    pub path: PathBuf,
+    pub on_conflict: ConflictResolution,
+    /// Reports what would be imported without changing the vault.
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BundleInfo {
+    pub path: PathBuf,
+    pub size_bytes: u64,
+    pub encoding: BundleEncoding,
+    pub manifest: BundleManifest,
+    pub signature: Option<BundleSignature>,
+}
+
+/// Who signed a bundle, as far as the importer can tell.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BundleTrust {
+    /// Public key of the signer; none for unsigned bundles.
+    pub signed_by: Option<String>,
+    /// Whether the signer is this installation or one of the trusted keys.
+    pub trusted: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ConflictKind {
+    Snippet,
+    Collection,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BundleConflict {
+    pub kind: ConflictKind,
+    /// Title of the snippet or name of the collection.
+    pub title: String,
+    pub existing_id: String,
+    pub resolution: ConflictResolution,
+}
+
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BundleImportCounts {
+    pub snippets_created: usize,
+    pub snippets_overwritten: usize,
+    pub snippets_skipped: usize,
+    pub tags_created: usize,
+    pub collections_created: usize,
+    pub collections_overwritten: usize,
+    pub collections_skipped: usize,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BundleImportReport {
+    pub dry_run: bool,
+    pub manifest: BundleManifest,
+    pub trust: BundleTrust,
+    /// What was, or in a dry run would be, done.
+    pub counts: BundleImportCounts,
+    pub conflicts: Vec<BundleConflict>,
+    /// Snippets saved, or in a dry run that would be, whose code may contain
+    /// secrets.
+    pub secrets: Vec<SnippetSecrets>,
+}
+
+fn snippet_not_found(id: &str) -> AppError {
+    AppError::NotFound(format!("Snippet with ID {}", id))
+}
+
+/// Writes the chosen snippets and collections, with their tags, to a bundle
+/// file. Private snippets are written decrypted, so the vault must be
+/// unlocked to include them.
+pub async fn export(
+    storage: &dyn Storage,
+    principal: &Principal,
+    vault_lock: &VaultLock,
+    signer: Option<&BundleSigner>,
+    form: BundleExportForm,
+) -> AppResult<BundleInfo> {
+    let name = form.name.trim().to_owned();
+    if name.is_empty() {
+        return Err(AppError::Other("Bundle names can't be empty".into()));
+    }
+    if form.snippet_ids.is_empty() && form.collection_ids.is_empty() {
+        return Err(AppError::Other(
+            "Choose at least one snippet or collection to export".into(),
+        ));
+    }
+
+    // The chosen collections and every collection below them.
+    let workspace_collections = storage
+        .list_collections(Some(principal.workspace()))
+        .await?;
+    let mut chosen: Vec<&Collection> = vec![];
+    let mut pending = form.collection_ids.clone();
+    while let Some(id) = pending.pop() {
+        if chosen.iter().any(|collection| collection.id == id) {
+            continue;
+        }
+        let collection = workspace_collections
+            .iter()
+            .find(|collection| collection.id == id)
+            .ok_or_else(|| AppError::NotFound(format!("Collection with ID {}", id)))?;
+        chosen.push(collection);
+        pending.extend(
+            workspace_collections
+                .iter()
+                .filter(|child| child.parent_id.as_deref() == Some(id.as_str()))
+                .map(|child| child.id.clone()),
+        );
+    }
+
+    // Snippets chosen by hand must be readable; those that only come with a
+    // collection are left out when they aren't.
+    let grants = Grants::load(storage, principal).await?;
+    let mut snippets: Vec<Snippet> = vec![];
+    for id in &form.snippet_ids {
+        let snippet = storage
+            .get_snippet(id.clone())
+            .await?
+            .ok_or_else(|| snippet_not_found(id))?;
+        grants
+            .access(principal, &snippet)
+            .require(Access::Read, &snippet.id)?;
+        if !snippets.iter().any(|existing| existing.id == snippet.id) {
+            snippets.push(snippet);
+        }
+    }
+    for collection in &chosen {
+        for id in &collection.snippet_ids {
+            if snippets.iter().any(|existing| existing.id == *id) {
+                continue;
+            }
+            if let Some(snippet) = storage.get_snippet(id.clone()).await? {
+                if grants.access(principal, &snippet) >= Access::Read {
+                    snippets.push(snippet);
+                }
+            }
+        }
+    }
+
+    let mut bundle_snippets = Vec::with_capacity(snippets.len());
+    for snippet in snippets {
+        let snippet = vault_lock.require_revealed(principal, snippet)?;
+        bundle_snippets.push(BundleSnippet {
+            is_favorite: snippet
+                .state
+                .as_ref()
+                .map_or(false, |state| state.is_favorite),
+            id: snippet.id,
+            title: snippet.title,
+            description: snippet.description,
+            language: snippet.language,
+            code: snippet.code,
+            tag_ids: snippet.tag_ids,
+            created_at: snippet.created_at,
+            updated_at: snippet.updated_at,
+        });
+    }
+    let snippet_ids: HashSet<&str> = bundle_snippets
+        .iter()
+        .map(|snippet| snippet.id.as_str())
+        .collect();
+
+    let chosen_ids: HashSet<&str> = chosen
+        .iter()
+        .map(|collection| collection.id.as_str())
+        .collect();
+    let bundle_collections: Vec<BundleCollection> = chosen
+        .iter()
+        .map(|collection| BundleCollection {
+            id: collection.id.clone(),
+            name: collection.name.clone(),
+            description: collection.description.clone(),
+            parent_id: collection
+                .parent_id
+                .clone()
+                .filter(|parent_id| chosen_ids.contains(parent_id.as_str())),
+            position: collection.position,
+            snippet_ids: collection
+                .snippet_ids
+                .iter()
+                .filter(|id| snippet_ids.contains(id.as_str()))
+                .cloned()
+                .collect(),
+        })
+        .collect();
+
+    // The snippets' tags, and their ancestors so the hierarchy survives.
+    let workspace_tags = storage
+        .list_tags(Some(TagFilter {
+            name: None,
+            workspace: Some(principal.workspace()),
+        }))
+        .await?;
+    let mut bundle_tags: Vec<BundleTag> = vec![];
+    let mut pending: Vec<String> = bundle_snippets
+        .iter()
+        .flat_map(|snippet| snippet.tag_ids.iter().cloned())
+        .collect();
+    while let Some(id) = pending.pop() {
+        if bundle_tags.iter().any(|tag| tag.id == id) {
+            continue;
+        }
+        if let Some(tag) = workspace_tags.iter().find(|tag| tag.id == id) {
+            pending.extend(tag.parent_id.clone());
+            bundle_tags.push(BundleTag {
+                id: tag.id.clone(),
+                name: tag.name.clone(),
+                color: tag.color.clone(),
+                emoji: tag.emoji.clone(),
+                parent_id: tag.parent_id.clone(),
+            });
+        }
+    }
+    let tag_ids: HashSet<String> = bundle_tags.iter().map(|tag| tag.id.clone()).collect();
+    for snippet in &mut bundle_snippets {
+        snippet.tag_ids.retain(|id| tag_ids.contains(id));
+    }
+
+    let contents = BundleContents {
+        snippets: bundle_snippets,
+        tags: bundle_tags,
+        collections: bundle_collections,
+    };
+    let manifest = BundleManifest {
+        format_version: BUNDLE_FORMAT_VERSION,
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        created_at: Utc::now(),
+        created_by: principal.email.clone(),
+        name,
+        description: form.description,
+        counts: BundleCounts {
+            snippets: contents.snippets.len(),
+            tags: contents.tags.len(),
+            collections: contents.collections.len(),
+        },
+        content_hash: file::content_hash(&contents)?,
+    };
+    let signature = match (form.sign, signer) {
+        (true, Some(signer)) => Some(signer.sign(&manifest)?),
+        (true, None) => {
+            return Err(AppError::Other(
+                "No signing key is available to sign the bundle".into(),
+            ))
+        }
+        (false, _) => None,
+    };
+
+    let bundle = Bundle {
+        manifest,
+        contents,
+        signature,
+    };
+    let bytes = file::encode(&bundle, form.encoding)?;
+    let mut path = form.path;
+    if path.extension().is_none() {
+        path.set_extension(BUNDLE_EXTENSION);
+    }
+    tokio::fs::write(&path, &bytes).await?;
+
+    info!(
+        "Exported {} snippets to bundle {}",
+        bundle.manifest.counts.snippets,
+        path.display()
+    );
+    Ok(BundleInfo {
+        path,
+        size_bytes: bytes.len() as u64,
+        encoding: form.encoding,
+        manifest: bundle.manifest,
+        signature: bundle.signature,
+    })
+}
+
+/// Reads a bundle and checks that its contents match the hash and, when it
+/// is signed, that the signature holds.
+pub async fn open(
+    path: &std::path::Path,
+    config: &BundleConfig,
+    signer: Option<&BundleSigner>,
+) -> AppResult<(Bundle, BundleTrust)> {
+    let bytes = tokio::fs::read(path).await?;
+    let (bundle, _) = tokio::task::spawn_blocking(move || file::decode(&bytes))
+        .await
+        .map_err(|e| AppError::Other(format!("Reading the bundle failed: {}", e)))??;
+
+    if file::content_hash(&bundle.contents)? != bundle.manifest.content_hash {
+        return Err(AppError::Other(
+            "The bundle's contents don't match its hash; it was damaged or altered".into(),
+        ));
+    }
+
+    let trust = match &bundle.signature {
+        Some(signature) => {
+            signing::verify(&bundle.manifest, signature)?;
+            let own_key = signer.map(|signer| signer.public_key());
+            let trusted = own_key.as_deref() == Some(signature.public_key.as_str())
+                || config
+                    .trusted_keys
+                    .iter()
+                    .any(|key| key.eq_ignore_ascii_case(&signature.public_key));
+            BundleTrust {
+                signed_by: Some(signature.public_key.clone()),
+                trusted,
+            }
+        }
+        None => BundleTrust {
+            signed_by: None,
+            trusted: false,
+        },
+    };
+    if config.require_trusted_signature && !trust.trusted {
+        return Err(AppError::Forbidden(
+            "only bundles signed by a trusted key can be imported".into(),
+        ));
+    }
+    Ok((bundle, trust))
+}
+
+/// What happens to one of the bundle's snippets.
+enum SnippetAction<'a> {
+    Create,
+    Overwrite(&'a Snippet),
+    Skip(&'a Snippet),
+}
+
+/// Imports a bundle into the workspace of the saver's principal. Tags are
+/// matched by name and reused; snippets are matched by title and language,
+/// and collections by name under the same parent, and handled as
+/// `on_conflict` says.
+pub async fn import(
+    saver: &SnippetSaver<'_>,
+    vault_lock: &VaultLock,
+    config: &BundleConfig,
+    signer: Option<&BundleSigner>,
+    form: BundleImportForm,
+) -> AppResult<BundleImportReport> {
+    let storage = saver.storage();
+    let principal = saver.principal();
+    let (bundle, trust) = open(&form.path, config, signer).await?;
+    let contents = bundle.contents;
+    let workspace = principal.workspace();
+    let dry_run = form.dry_run;
+    let mut counts = BundleImportCounts::default();
+    let mut conflicts = vec![];
+
+    // Every change is checked before the first write, so an import that
+    // fails doesn't stop halfway.
+    let existing_snippets = storage
+        .list_snippets(Some(SnippetFilter {
+            workspace: Some(workspace.clone()),
+            ..Default::default()
+        }))
+        .await?;
+    let grants = Grants::load(storage, principal).await?;
+    let mut actions = Vec::with_capacity(contents.snippets.len());
+    for snippet in &contents.snippets {
+        let language = languages::resolve(&snippet.language, &snippet.code);
+        let existing = existing_snippets.iter().find(|existing| {
+            existing.title.eq_ignore_ascii_case(&snippet.title) && existing.language == language
+        });
+        let action = match (existing, form.on_conflict) {
+            (None, _) | (Some(_), ConflictResolution::Duplicate) => SnippetAction::Create,
+            (Some(existing), ConflictResolution::Skip) => SnippetAction::Skip(existing),
+            (Some(existing), ConflictResolution::Overwrite) => {
+                grants
+                    .access(principal, existing)
+                    .require(Access::Edit, &existing.id)?;
+                if encryption::is_private(existing) {
+                    if !encryption::is_author(principal, existing) {
+                        return Err(AppError::Forbidden(format!(
+                            "snippet {} is private to its author",
+                            existing.id
+                        )));
+                    }
+                    if !vault_lock.is_unlocked(principal) {
+                        return Err(AppError::VaultLocked);
+                    }
+                }
+                SnippetAction::Overwrite(existing)
+            }
+        };
+        let findings = match action {
+            SnippetAction::Skip(_) => vec![],
+            _ => saver.check(&snippet.code)?,
+        };
+        if let Some(existing) = existing {
+            conflicts.push(BundleConflict {
+                kind: ConflictKind::Snippet,
+                title: snippet.title.clone(),
+                existing_id: existing.id.clone(),
+                resolution: form.on_conflict,
+            });
+        }
+        actions.push((language, action, findings));
+    }
+
+    // Tags: reuse those with the same name, create the rest with their place
+    // in the hierarchy.
+    let existing_tags = storage
+        .list_tags(Some(TagFilter {
+            name: None,
+            workspace: Some(workspace.clone()),
+        }))
+        .await?;
+    let mut tag_ids: HashMap<&str, String> = HashMap::new();
+    let mut new_tags: Vec<&BundleTag> = vec![];
+    for tag in &contents.tags {
+        match existing_tags
+            .iter()
+            .find(|existing| existing.name.eq_ignore_ascii_case(&tag.name))
+        {
+            Some(existing) => {
+                tag_ids.insert(tag.id.as_str(), existing.id.clone());
+            }
+            None => new_tags.push(tag),
+        }
+    }
+    counts.tags_created = new_tags.len();
+    while !dry_run && !new_tags.is_empty() {
+        // Parents first, so children can be placed under them. A cycle in a
+        // tampered bundle leaves nothing ready; the rest goes to the top
+        // level.
+        let ready = new_tags.iter().position(|tag| {
+            tag.parent_id.as_deref().map_or(true, |parent_id| {
+                !new_tags.iter().any(|other| other.id == parent_id)
+            })
+        });
+        let tag = new_tags.remove(ready.unwrap_or(0));
+        let created = storage
+            .create_tag(TagForm {
+                name: tag.name.clone(),
+                color: tag.color.clone(),
+                emoji: tag.emoji.clone(),
+                parent_id: tag
+                    .parent_id
+                    .as_deref()
+                    .and_then(|parent_id| tag_ids.get(parent_id).cloned()),
+                workspace_id: principal.workspace_id.clone(),
+            })
+            .await?;
+        tag_ids.insert(tag.id.as_str(), created.id);
+    }
+
+    let mut snippet_ids: HashMap<&str, String> = HashMap::new();
+    let mut secrets = vec![];
+    for (snippet, (language, action, findings)) in contents.snippets.iter().zip(actions) {
+        let snippet_form = SnippetForm {
+            title: snippet.title.clone(),
+            description: snippet.description.clone(),
+            language,
+            code: snippet.code.clone(),
+            tag_ids: snippet
+                .tag_ids
+                .iter()
+                .filter_map(|id| tag_ids.get(id.as_str()).cloned())
+                .collect(),
+            snippet_state_id: String::new(),
+            is_private: None,
+            workspace_id: principal.workspace_id.clone(),
+        };
+
+        let id = match action {
+            SnippetAction::Skip(existing) => {
+                counts.snippets_skipped += 1;
+                existing.id.clone()
+            }
+            SnippetAction::Overwrite(existing) => {
+                counts.snippets_overwritten += 1;
+                if !dry_run {
+                    let snippet_form = SnippetForm {
+                        snippet_state_id: existing.snippet_state_id.clone(),
+                        ..snippet_form
+                    };
+                    saver.update(existing, snippet_form).await?;
+                }
+                existing.id.clone()
+            }
+            SnippetAction::Create if dry_run => {
+                counts.snippets_created += 1;
+                snippet.id.clone()
+            }
+            SnippetAction::Create => {
+                counts.snippets_created += 1;
+                let created = saver.create(snippet_form).await?.snippet;
+                if snippet.is_favorite {
+                    storage
+                        .update_snippet_state(
+                            created.snippet_state_id.clone(),
+                            SnippetStateUpdate {
+                                is_favorite: Some(true),
+                                is_dark: None,
+                            },
+                        )
+                        .await?;
+                }
+                created.id
+            }
+        };
+        if !findings.is_empty() {
+            secrets.push(SnippetSecrets {
+                snippet_id: id.clone(),
+                title: snippet.title.clone(),
+                findings,
+            });
+        }
+        snippet_ids.insert(snippet.id.as_str(), id);
+    }
+
+    // Collections, parents first so children can be placed under them.
+    let existing_collections = storage.list_collections(Some(workspace)).await?;
+    let mut collection_ids: HashMap<&str, String> = HashMap::new();
+    let mut remaining: Vec<&BundleCollection> = contents.collections.iter().collect();
+    remaining.sort_by_key(|collection| collection.position);
+    while !remaining.is_empty() {
+        let ready = remaining.iter().position(|collection| {
+            collection.parent_id.as_deref().map_or(true, |parent_id| {
+                collection_ids.contains_key(parent_id)
+                    || !contents
+                        .collections
+                        .iter()
+                        .any(|other| other.id == parent_id)
+            })
+        });
+        // A cycle in a tampered bundle leaves nothing ready; the rest goes
+        // to the top level.
+        let collection = remaining.remove(ready.unwrap_or(0));
+        let parent_id = collection
+            .parent_id
+            .as_deref()
+            .and_then(|parent_id| collection_ids.get(parent_id).cloned());
+        let members: Vec<String> = collection
+            .snippet_ids
+            .iter()
+            .filter_map(|id| snippet_ids.get(id.as_str()).cloned())
+            .collect();
+
+        let existing = existing_collections.iter().find(|existing| {
+            existing.name.eq_ignore_ascii_case(&collection.name) && existing.parent_id == parent_id
+        });
+        if let Some(existing) = existing {
+            conflicts.push(BundleConflict {
+                kind: ConflictKind::Collection,
+                title: collection.name.clone(),
+                existing_id: existing.id.clone(),
+                resolution: form.on_conflict,
+            });
+        }
+
+        let id = match (existing, form.on_conflict) {
+            (Some(existing), ConflictResolution::Skip) => {
+                counts.collections_skipped += 1;
+                existing.id.clone()
+            }
+            (Some(existing), ConflictResolution::Overwrite) => {
+                counts.collections_overwritten += 1;
+                if !dry_run {
+                    let mut collection_form = CollectionForm::from(existing);
+                    collection_form.description = collection.description.clone();
+                    collection_form.snippet_ids = members;
+                    storage
+                        .update_collection(existing.id.clone(), collection_form)
+                        .await?;
+                }
+                existing.id.clone()
+            }
+            _ if dry_run => {
+                counts.collections_created += 1;
+                collection.id.clone()
+            }
+            _ => {
+                counts.collections_created += 1;
+                let collection_form = CollectionForm {
+                    name: collection.name.clone(),
+                    description: collection.description.clone(),
+                    parent_id,
+                    position: 0,
+                    snippet_ids: members,
+                    workspace_id: None,
+                };
+                collections::create(storage, principal, collection_form)
+                    .await?
+                    .id
+            }
+        };
+        collection_ids.insert(collection.id.as_str(), id);
+    }
+
+    info!(
+        "Imported bundle \"{}\" (dry run: {}): {:?}",
+        bundle.manifest.name, dry_run, counts
+    );
+    Ok(BundleImportReport {
+        dry_run,
+        manifest: bundle.manifest,
+        trust,
+        counts,
+        conflicts,
+        secrets,
+    })
+}
@@ -0,0 +1,90 @@
+//! Ed25519 signatures over a bundle's manifest, which carries the hash of
+//! the contents, so a valid signature vouches for the whole bundle.
+//!
+//! Each installation signs with its own key pair, generated on first use and
+//! kept in the app data directory. Teammates exchange public keys and list
+//! each other's as trusted in the settings.
+
+use std::path::Path;
+
+use ring::{
+    rand::SystemRandom,
+    signature::{Ed25519KeyPair, KeyPair, UnparsedPublicKey, ED25519},
+};
+
+use tauri::AppHandle;
+
+use super::{BundleManifest, BundleSignature};
+use crate::{error::AppError, prelude::AppResult};
+
+pub const KEY_FILE_NAME: &str = "bundle-signing.key";
+
+pub struct BundleSigner {
+    key_pair: Ed25519KeyPair,
+}
+
+fn key_error(reason: &str) -> AppError {
+    AppError::EncryptionError(format!("Bundle signing key: {}", reason))
+}
+
+impl BundleSigner {
+    /// Reads the key pair stored in `dir`, creating it on first use.
+    pub fn load_or_create(dir: &Path) -> AppResult<Self> {
+        let path = dir.join(KEY_FILE_NAME);
+        let pkcs8 = if path.exists() {
+            hex::decode(std::fs::read_to_string(&path)?.trim())
+                .map_err(|_| key_error("the key file is corrupt"))?
+        } else {
+            let document = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new())
+                .map_err(|_| key_error("generating a key failed"))?;
+            std::fs::create_dir_all(dir)?;
+            std::fs::write(&path, hex::encode(document.as_ref()))?;
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::PermissionsExt;
+                std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))?;
+            }
+            document.as_ref().to_vec()
+        };
+
+        let key_pair =
+            Ed25519KeyPair::from_pkcs8(&pkcs8).map_err(|_| key_error("the key file is corrupt"))?;
+        Ok(Self { key_pair })
+    }
+
+    /// This installation's signer, kept in the app data directory.
+    pub fn for_app(app: &AppHandle) -> AppResult<Self> {
+        let dir = app
+            .path_resolver()
+            .app_data_dir()
+            .ok_or_else(|| AppError::from("Could not resolve the app data directory"))?;
+        Self::load_or_create(&dir)
+    }
+
+    /// The hex-encoded public key others trust this installation's bundles by.
+    pub fn public_key(&self) -> String {
+        hex::encode(self.key_pair.public_key().as_ref())
+    }
+
+    pub fn sign(&self, manifest: &BundleManifest) -> AppResult<BundleSignature> {
+        let signature = self.key_pair.sign(&signed_bytes(manifest)?);
+        Ok(BundleSignature {
+            public_key: self.public_key(),
+            signature: hex::encode(signature.as_ref()),
+        })
+    }
+}
+
+fn signed_bytes(manifest: &BundleManifest) -> AppResult<Vec<u8>> {
+    Ok(serde_json::to_vec(manifest)?)
+}
+
+/// Fails unless `signature` was made over `manifest` by the key it names.
+pub fn verify(manifest: &BundleManifest, signature: &BundleSignature) -> AppResult<()> {
+    let invalid = || AppError::Other("The bundle's signature is invalid".into());
+    let public_key = hex::decode(&signature.public_key).map_err(|_| invalid())?;
+    let signature_bytes = hex::decode(&signature.signature).map_err(|_| invalid())?;
+    UnparsedPublicKey::new(&ED25519, public_key)
+        .verify(&signed_bytes(manifest)?, &signature_bytes)
+        .map_err(|_| invalid())
+}
@@ -0,0 +1,197 @@
+//! The system clipboard: copying snippets, counting the copies, and turning
+//! copied code into snippet drafts.
+//!
+//! With watching enabled in settings, code copied in other applications is
+//! offered as a snippet: a notification points at the app, and
+//! `CLIPBOARD_CODE_EVENT` carries the draft to the window, which asks
+//! whether to save it.
+
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use log::{info, warn};
+use serde::Deserialize;
+use snippet_vault_shared::placeholders;
+use tauri::{api::notification::Notification, AppHandle, ClipboardManager, Manager};
+
+use crate::{
+    auth::Principal,
+    config::ClipboardConfig,
+    database::models::SnippetState,
+    error::AppError,
+    interchange::SnippetDraft,
+    languages::{self, registry::PLAINTEXT},
+    prelude::AppResult,
+    sharing::{self, Access},
+    state::AppState,
+    storage::Storage,
+};
+
+pub const CLIPBOARD_CODE_EVENT: &str = "clipboard-code-detected";
+
+/// Polling faster than this only keeps the main thread busy.
+const MIN_POLL_INTERVAL_MS: u64 = 250;
+/// Drafts are titled after their first line, cut to this many characters.
+const MAX_TITLE_LENGTH: usize = 60;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CopySnippetForm {
+    pub id: String,
+    /// Copy the code as it reads with every placeholder at its default,
+    /// rather than as stored.
+    #[serde(default)]
+    pub render_placeholders: bool,
+}
+
+/// Remembers what the app itself put on the clipboard, so the watcher never
+/// offers to save a snippet that was just copied out of the vault.
+#[derive(Default)]
+pub struct ClipboardTracker {
+    last_written: Mutex<Option<u64>>,
+}
+
+impl ClipboardTracker {
+    pub fn write(&self, app: &AppHandle, text: String) -> AppResult<()> {
+        *self.last_written.lock().unwrap() = Some(fingerprint(&text));
+        app.clipboard_manager().write_text(text)?;
+        Ok(())
+    }
+
+    fn wrote(&self, fingerprint: u64) -> bool {
+        *self.last_written.lock().unwrap() == Some(fingerprint)
+    }
+}
+
+fn fingerprint(text: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    text.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Puts a snippet's code on the clipboard and counts the copy, which
+/// "most used" sorting goes by. Private snippets need the vault unlocked.
+pub async fn copy_snippet(
+    app: &AppHandle,
+    storage: Arc<dyn Storage>,
+    principal: &Principal,
+    form: CopySnippetForm,
+) -> AppResult<SnippetState> {
+    let snippet = storage
+        .get_snippet(form.id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Snippet".into()))?;
+    sharing::access_to(storage.as_ref(), principal, &snippet)
+        .await?
+        .require(Access::Read, &snippet.id)?;
+    let state = app.state::<AppState>();
+    let snippet = state.vault_lock.require_revealed(principal, snippet)?;
+
+    let code = if form.render_placeholders {
+        placeholders::render(&snippet.code)
+    } else {
+        snippet.code
+    };
+    state.clipboard.write(app, code)?;
+    storage.record_snippet_copy(snippet.snippet_state_id).await
+}
+
+pub fn read_text(app: &AppHandle) -> AppResult<String> {
+    app.clipboard_manager()
+        .read_text()?
+        .filter(|text| !text.trim().is_empty())
+        .ok_or_else(|| "The clipboard holds no text".into())
+}
+
+/// A snippet draft of the text, titled after its first line, in the
+/// language detected from it.
+pub fn draft_from_text(code: String) -> SnippetDraft {
+    let first_line = code
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .unwrap_or_default();
+    let title = if first_line.chars().count() > MAX_TITLE_LENGTH {
+        let cut: String = first_line.chars().take(MAX_TITLE_LENGTH - 1).collect();
+        format!("{}…", cut.trim_end())
+    } else {
+        first_line.to_string()
+    };
+
+    SnippetDraft {
+        title,
+        description: String::new(),
+        language: languages::detect_best(&code, None).to_string(),
+        code,
+        tags: vec![],
+    }
+}
+
+/// Whether copied text is worth offering: a few lines, not a whole file,
+/// and in a language that detection is confident about.
+fn looks_like_code(text: &str, config: &ClipboardConfig) -> bool {
+    text.chars().count() <= config.max_length
+        && text.lines().filter(|line| !line.trim().is_empty()).count() >= config.min_lines
+        && languages::detect_best(text, None) != PLAINTEXT
+}
+
+/// Polls the clipboard for code copied in other applications. Does nothing
+/// unless watching is enabled in settings.
+pub fn spawn_clipboard_watcher(app: AppHandle) {
+    let config = app.state::<AppState>().config.clipboard.clone();
+    if !config.watch {
+        return;
+    }
+    let interval = Duration::from_millis(config.poll_interval_ms.max(MIN_POLL_INTERVAL_MS));
+    info!("Watching the clipboard for copied code");
+
+    tauri::async_runtime::spawn(async move {
+        // Whatever is on the clipboard already was copied before the app
+        // started; only later copies are offered.
+        let mut last_seen = read_text(&app).ok().map(|text| fingerprint(&text));
+        loop {
+            tokio::time::sleep(interval).await;
+            let text = match app.clipboard_manager().read_text() {
+                Ok(Some(text)) => text,
+                Ok(None) => continue,
+                Err(e) => {
+                    warn!("Failed to read the clipboard: {}", e);
+                    continue;
+                }
+            };
+
+            let seen = fingerprint(&text);
+            if last_seen == Some(seen) {
+                continue;
+            }
+            last_seen = Some(seen);
+            if app.state::<AppState>().clipboard.wrote(seen) || !looks_like_code(&text, &config) {
+                continue;
+            }
+            offer_to_save(&app, draft_from_text(text));
+        }
+    });
+}
+
+fn offer_to_save(app: &AppHandle, draft: SnippetDraft) {
+    let language = languages::registry::find(&draft.language)
+        .map_or(draft.language.as_str(), |language| language.name);
+    let notified = Notification::new(app.config().tauri.bundle.identifier.clone())
+        .title("Save copied code?")
+        .body(format!(
+            "You copied some {}. Open Snippet Vault to save it as a snippet.",
+            language
+        ))
+        .notify(app);
+    if let Err(e) = notified {
+        warn!("Failed to show the copied code notification: {}", e);
+    }
+
+    if let Err(e) = app.emit_all(CLIPBOARD_CODE_EVENT, draft) {
+        warn!("Failed to emit the copied code: {}", e);
+    }
+}
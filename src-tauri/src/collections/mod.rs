@@ -0,0 +1,329 @@
+//! Collections group snippets into a folder tree that is independent of tags.
+//! A collection keeps its snippets in a chosen order and its place among its
+//! siblings, so both can be rearranged by hand.
+//!
+//! Collections belong to a workspace and only hold that workspace's
+//! collections and snippets.
+
+use serde::Deserialize;
+
+use crate::{
+    auth::Principal,
+    database::models::{Collection, CollectionForm},
+    error::AppError,
+    prelude::AppResult,
+    sharing::{Access, Grants},
+    storage::Storage,
+};
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RenameCollectionForm {
+    pub name: String,
+    /// Left unchanged when absent.
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MoveCollectionForm {
+    /// The new parent, or none to move the collection to the top level.
+    pub parent_id: Option<String>,
+    /// Index among the new siblings. Defaults to the end.
+    pub position: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CollectionSnippetsForm {
+    pub snippet_ids: Vec<String>,
+    /// Where to insert added snippets. Defaults to the end.
+    pub position: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReorderCollectionForm {
+    /// Every snippet of the collection, in the new order.
+    pub snippet_ids: Vec<String>,
+}
+
+fn not_found(id: &str) -> AppError {
+    AppError::NotFound(format!("Collection with ID {}", id))
+}
+
+async fn get(storage: &dyn Storage, principal: &Principal, id: &str) -> AppResult<Collection> {
+    let collection = storage
+        .get_collection(id.to_owned())
+        .await?
+        .ok_or_else(|| not_found(id))?;
+    principal.require_workspace(collection.workspace_id.as_deref())?;
+    Ok(collection)
+}
+
+/// The collections of the caller's workspace.
+async fn list(storage: &dyn Storage, principal: &Principal) -> AppResult<Vec<Collection>> {
+    storage.list_collections(Some(principal.workspace())).await
+}
+
+fn validate_name(name: &str) -> AppResult<String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(AppError::Other("Collection names can't be empty".into()));
+    }
+    Ok(name.to_owned())
+}
+
+/// The collections under `parent_id`, in order, leaving out `except`.
+fn siblings<'a>(
+    collections: &'a [Collection],
+    parent_id: Option<&str>,
+    except: &str,
+) -> Vec<&'a Collection> {
+    collections
+        .iter()
+        .filter(|collection| {
+            collection.parent_id.as_deref() == parent_id && collection.id != except
+        })
+        .collect()
+}
+
+/// Saves the positions of `ordered` wherever they differ from their index.
+async fn renumber(storage: &dyn Storage, ordered: &[&Collection]) -> AppResult<()> {
+    for (index, collection) in ordered.iter().enumerate() {
+        if collection.position != index as i32 {
+            let mut form = CollectionForm::from(*collection);
+            form.position = index as i32;
+            storage
+                .update_collection(collection.id.clone(), form)
+                .await?;
+        }
+    }
+    Ok(())
+}
+
+/// The snippets to put in a collection of `workspace_id`, once each, after
+/// checking that they are in that workspace and the caller can read them.
+async fn readable_snippet_ids(
+    storage: &dyn Storage,
+    principal: &Principal,
+    workspace_id: Option<&str>,
+    snippet_ids: Vec<String>,
+) -> AppResult<Vec<String>> {
+    let grants = Grants::load(storage, principal).await?;
+
+    let mut readable: Vec<String> = vec![];
+    for snippet_id in snippet_ids {
+        if readable.contains(&snippet_id) {
+            continue;
+        }
+        let snippet = storage
+            .get_snippet(snippet_id.clone())
+            .await?
+            .ok_or_else(|| AppError::NotFound(format!("Snippet with ID {}", snippet_id)))?;
+        if snippet.workspace_id.as_deref() != workspace_id {
+            return Err(AppError::Other(format!(
+                "Snippet {} belongs to another workspace",
+                snippet_id
+            )));
+        }
+        grants
+            .access(principal, &snippet)
+            .require(Access::Read, &snippet.id)?;
+        readable.push(snippet_id);
+    }
+    Ok(readable)
+}
+
+/// Creates the collection in the caller's workspace, after its future
+/// siblings, holding snippets the caller can read.
+pub async fn create(
+    storage: &dyn Storage,
+    principal: &Principal,
+    mut form: CollectionForm,
+) -> AppResult<Collection> {
+    form.name = validate_name(&form.name)?;
+    form.workspace_id = principal.workspace_id.clone();
+    if let Some(parent_id) = &form.parent_id {
+        get(storage, principal, parent_id).await?;
+    }
+
+    let collections = list(storage, principal).await?;
+    form.position = siblings(&collections, form.parent_id.as_deref(), "")
+        .last()
+        .map_or(0, |last| last.position + 1);
+
+    form.snippet_ids = readable_snippet_ids(
+        storage,
+        principal,
+        form.workspace_id.as_deref(),
+        form.snippet_ids,
+    )
+    .await?;
+
+    storage.create_collection(form).await
+}
+
+pub async fn rename(
+    storage: &dyn Storage,
+    principal: &Principal,
+    id: String,
+    data: RenameCollectionForm,
+) -> AppResult<Collection> {
+    let collection = get(storage, principal, &id).await?;
+    let mut form = CollectionForm::from(&collection);
+    form.name = validate_name(&data.name)?;
+    if let Some(description) = data.description {
+        form.description = description;
+    }
+    storage.update_collection(id, form).await
+}
+
+/// Moves the collection, with everything in it, under another parent or to
+/// another place among its siblings.
+pub async fn move_to(
+    storage: &dyn Storage,
+    principal: &Principal,
+    id: String,
+    data: MoveCollectionForm,
+) -> AppResult<Collection> {
+    let collections = list(storage, principal).await?;
+    let collection = collections
+        .iter()
+        .find(|collection| collection.id == id)
+        .ok_or_else(|| not_found(&id))?;
+
+    // Walking up from the new parent must never reach the moved collection.
+    let mut ancestor = data.parent_id.clone();
+    while let Some(ancestor_id) = ancestor {
+        if ancestor_id == id {
+            return Err(AppError::Other(
+                "A collection can't be moved into itself or one of its children".into(),
+            ));
+        }
+        ancestor = collections
+            .iter()
+            .find(|collection| collection.id == ancestor_id)
+            .ok_or_else(|| not_found(&ancestor_id))?
+            .parent_id
+            .clone();
+    }
+
+    let mut form = CollectionForm::from(collection);
+    form.parent_id = data.parent_id.clone();
+    let mut new_siblings = siblings(&collections, data.parent_id.as_deref(), &id);
+    let position = data
+        .position
+        .unwrap_or(new_siblings.len())
+        .min(new_siblings.len());
+    form.position = position as i32;
+    let moved = storage.update_collection(id.clone(), form).await?;
+
+    if collection.parent_id != data.parent_id {
+        let old_siblings = siblings(&collections, collection.parent_id.as_deref(), &id);
+        renumber(storage, &old_siblings).await?;
+    }
+    new_siblings.insert(position, &moved);
+    renumber(storage, &new_siblings).await?;
+
+    Ok(moved)
+}
+
+/// Deletes the collection. Its children move up to its parent, after the
+/// parent's other children; the snippets themselves are kept.
+pub async fn delete(
+    storage: &dyn Storage,
+    principal: &Principal,
+    id: String,
+) -> AppResult<Collection> {
+    let collections = list(storage, principal).await?;
+    let collection = collections
+        .iter()
+        .find(|collection| collection.id == id)
+        .ok_or_else(|| not_found(&id))?;
+
+    let parent_id = collection.parent_id.as_deref();
+    let mut position = siblings(&collections, parent_id, &id).len() as i32;
+    for child in siblings(&collections, Some(id.as_str()), "") {
+        let mut form = CollectionForm::from(child);
+        form.parent_id = collection.parent_id.clone();
+        form.position = position;
+        storage.update_collection(child.id.clone(), form).await?;
+        position += 1;
+    }
+
+    let deleted = storage.delete_collection(id.clone()).await?;
+    renumber(storage, &siblings(&collections, parent_id, &id)).await?;
+    Ok(deleted)
+}
+
+/// Adds snippets at `position`, which the caller must be able to read.
+/// Snippets already in the collection move there instead of appearing twice.
+pub async fn add_snippets(
+    storage: &dyn Storage,
+    principal: &Principal,
+    id: String,
+    data: CollectionSnippetsForm,
+) -> AppResult<Collection> {
+    let collection = get(storage, principal, &id).await?;
+    let added = readable_snippet_ids(
+        storage,
+        principal,
+        collection.workspace_id.as_deref(),
+        data.snippet_ids,
+    )
+    .await?;
+
+    let mut form = CollectionForm::from(&collection);
+    let position = data.position.map_or(form.snippet_ids.len(), |position| {
+        // Count only the snippets that stay where they are.
+        form.snippet_ids[..position.min(form.snippet_ids.len())]
+            .iter()
+            .filter(|snippet_id| !added.contains(snippet_id))
+            .count()
+    });
+    form.snippet_ids
+        .retain(|snippet_id| !added.contains(snippet_id));
+    let position = position.min(form.snippet_ids.len());
+    form.snippet_ids.splice(position..position, added);
+
+    storage.update_collection(id, form).await
+}
+
+pub async fn remove_snippets(
+    storage: &dyn Storage,
+    principal: &Principal,
+    id: String,
+    snippet_ids: Vec<String>,
+) -> AppResult<Collection> {
+    let collection = get(storage, principal, &id).await?;
+    let mut form = CollectionForm::from(&collection);
+    form.snippet_ids
+        .retain(|snippet_id| !snippet_ids.contains(snippet_id));
+    storage.update_collection(id, form).await
+}
+
+/// Puts the collection's snippets in a new order, which must list each of
+/// them exactly once.
+pub async fn reorder_snippets(
+    storage: &dyn Storage,
+    principal: &Principal,
+    id: String,
+    snippet_ids: Vec<String>,
+) -> AppResult<Collection> {
+    let collection = get(storage, principal, &id).await?;
+
+    let mut current = collection.snippet_ids.clone();
+    let mut requested = snippet_ids.clone();
+    current.sort();
+    requested.sort();
+    if current != requested {
+        return Err(AppError::Other(
+            "The new order must list every snippet of the collection exactly once".into(),
+        ));
+    }
+
+    let mut form = CollectionForm::from(&collection);
+    form.snippet_ids = snippet_ids;
+    storage.update_collection(id, form).await
+}
@@ -0,0 +1,293 @@
+use std::path::PathBuf;
+
+use figment::{
+    providers::{Env, Format, Serialized, Toml},
+    Figment,
+};
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::prelude::AppResult;
+
+const CONFIG_FILE_NAME: &str = "settings.toml";
+const ENV_PREFIX: &str = "SNIPPET_VAULT_";
+
+/// Application settings, read from `settings.toml` in the app config
+/// directory and overridable through `SNIPPET_VAULT_*` environment variables
+/// (nested keys separated by `__`, e.g. `SNIPPET_VAULT_STORAGE__BACKEND=local`).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AppConfig {
+    #[serde(default)]
+    pub storage: StorageConfig,
+    #[serde(default)]
+    pub backup: BackupConfig,
+    #[serde(default)]
+    pub git_sync: GitSyncConfig,
+    #[serde(default)]
+    pub formatter: FormatterConfig,
+    #[serde(default)]
+    pub dedup: DedupConfig,
+    #[serde(default)]
+    pub secret_scan: SecretScanConfig,
+    #[serde(default)]
+    pub encryption: EncryptionConfig,
+    #[serde(default)]
+    pub bundle: BundleConfig,
+    #[serde(default)]
+    pub api: ApiConfig,
+    #[serde(default)]
+    pub palette: PaletteConfig,
+    #[serde(default)]
+    pub clipboard: ClipboardConfig,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageBackend {
+    /// MongoDB through Prisma, using `DATABASE_URL`.
+    Mongodb,
+    /// An embedded store in the app data directory.
+    Local,
+}
+
+impl StorageBackend {
+    /// The name the backend reports in the database status.
+    pub fn name(&self) -> &'static str {
+        match self {
+            StorageBackend::Mongodb => "mongodb",
+            StorageBackend::Local => "local",
+        }
+    }
+}
+
+impl Default for StorageBackend {
+    fn default() -> Self {
+        StorageBackend::Mongodb
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StorageConfig {
+    #[serde(default)]
+    pub backend: StorageBackend,
+    /// Directory of the embedded store. Defaults to the app data directory.
+    pub local_path: Option<PathBuf>,
+}
+
+/// Automatic backups of the vault.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BackupConfig {
+    pub enabled: bool,
+    pub interval_hours: u64,
+    /// Number of automatic backups to keep; older ones are deleted.
+    pub keep: usize,
+    /// Where backups are written. Defaults to `backups` in the app data
+    /// directory.
+    pub directory: Option<PathBuf>,
+}
+
+impl Default for BackupConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_hours: 24,
+            keep: 7,
+            directory: None,
+        }
+    }
+}
+
+/// Mirroring of the vault into a git repository.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GitSyncConfig {
+    pub enabled: bool,
+    /// Working tree of the repository. Defaults to `snippet-repository` in
+    /// the app data directory.
+    pub repository: Option<PathBuf>,
+    /// URL or path of the repository to pull from and push to, set up as
+    /// `origin`.
+    pub remote: Option<String>,
+    pub branch: String,
+}
+
+impl Default for GitSyncConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            repository: None,
+            remote: None,
+            branch: "main".to_string(),
+        }
+    }
+}
+
+/// Reformatting of snippet code.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FormatterConfig {
+    /// Use rustfmt, prettier or black when installed, before the built-in
+    /// formatters.
+    pub use_external: bool,
+    pub timeout_secs: u64,
+}
+
+impl Default for FormatterConfig {
+    fn default() -> Self {
+        Self {
+            use_external: true,
+            timeout_secs: 10,
+        }
+    }
+}
+
+/// Detection of duplicate snippets.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DedupConfig {
+    /// Warn when a new snippet resembles existing ones.
+    pub warn_on_create: bool,
+    /// Lowest similarity, between 0 and 1, for snippets to count as
+    /// duplicates.
+    pub threshold: f32,
+}
+
+impl Default for DedupConfig {
+    fn default() -> Self {
+        Self {
+            warn_on_create: true,
+            threshold: 0.85,
+        }
+    }
+}
+
+/// Scanning of snippet code for credentials when it is saved.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SecretScanConfig {
+    pub enabled: bool,
+    /// Refuse to save snippets with findings, instead of only warning.
+    pub block_on_save: bool,
+    /// Bits of entropy per character above which a string counts as random.
+    pub entropy_threshold: f64,
+    /// Shorter strings are never reported as random.
+    pub min_entropy_length: usize,
+}
+
+impl Default for SecretScanConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            block_on_save: false,
+            entropy_threshold: 4.0,
+            min_entropy_length: 24,
+        }
+    }
+}
+
+/// Private, encrypted snippets.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EncryptionConfig {
+    /// Minutes without using private snippets before the vault locks
+    /// itself; 0 never locks it.
+    pub auto_lock_minutes: u64,
+}
+
+impl Default for EncryptionConfig {
+    fn default() -> Self {
+        Self {
+            auto_lock_minutes: 15,
+        }
+    }
+}
+
+/// Exchange of snippet bundles between installations.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BundleConfig {
+    /// Hex-encoded public keys of teammates whose signed bundles are
+    /// trusted. Bundles signed on this installation always are.
+    pub trusted_keys: Vec<String>,
+    /// Refuse to import bundles that aren't signed by a trusted key.
+    pub require_trusted_signature: bool,
+}
+
+/// The HTTP API on 127.0.0.1 for editor plugins and scripts.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ApiConfig {
+    pub enabled: bool,
+    pub port: u16,
+    /// Days before API tokens expire, unless revoked sooner.
+    pub token_days: i64,
+}
+
+impl Default for ApiConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: 27_183,
+            token_days: 90,
+        }
+    }
+}
+
+/// The quick-search palette and its global shortcut.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PaletteConfig {
+    pub enabled: bool,
+    /// Accelerator that opens and closes the palette from any application,
+    /// such as `CmdOrCtrl+Shift+Space` or `Alt+S`.
+    pub shortcut: String,
+}
+
+impl Default for PaletteConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            shortcut: "CmdOrCtrl+Shift+Space".to_string(),
+        }
+    }
+}
+
+/// Watching the clipboard for code copied in other applications.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ClipboardConfig {
+    /// Offer to save code copied elsewhere as a snippet.
+    pub watch: bool,
+    pub poll_interval_ms: u64,
+    /// Copied text with fewer lines is never offered.
+    pub min_lines: usize,
+    /// Copied text longer than this many characters is never offered.
+    pub max_length: usize,
+}
+
+impl Default for ClipboardConfig {
+    fn default() -> Self {
+        Self {
+            watch: false,
+            poll_interval_ms: 1_000,
+            min_lines: 2,
+            max_length: 20_000,
+        }
+    }
+}
+
+impl AppConfig {
+    pub fn load(app: &AppHandle) -> AppResult<Self> {
+        let mut figment = Figment::from(Serialized::defaults(AppConfig::default()));
+
+        if let Some(config_dir) = app.path_resolver().app_config_dir() {
+            figment = figment.merge(Toml::file(config_dir.join(CONFIG_FILE_NAME)));
+        }
+
+        let config = figment
+            .merge(Env::prefixed(ENV_PREFIX).split("__"))
+            .extract()?;
+        Ok(config)
+    }
+}
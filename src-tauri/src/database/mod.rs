@@ -1,15 +1,23 @@
 pub mod models;
+pub mod monitor;
+pub mod status;
 
 use std::{future::Future, sync::Arc};
 
 use prisma::PrismaClient;
 use serde::Serialize;
-use tauri::AppHandle;
+use tauri::{AppHandle, Manager};
 
-use crate::{error::AppError, ipc::responses::IpcResponse, state::ServiceAccess};
+use crate::{
+    auth::{authenticate, Permission, Principal},
+    error::AppError,
+    ipc::responses::IpcResponse,
+    state::{AppState, ServiceAccess},
+    storage::Storage,
+};
 
-pub async fn init_db() -> PrismaClient {
-    PrismaClient::_builder().build().await.unwrap()
+pub async fn init_db() -> Result<PrismaClient, prisma::NewClientError> {
+    PrismaClient::_builder().build().await
 }
 
 pub async fn handle_db_operation<T, F, Fut>(app: AppHandle, operation: F) -> IpcResponse<T>
@@ -21,3 +29,93 @@ where
     let result = app.db(operation).await;
     result.into()
 }
+
+/// Like [`handle_db_operation`], but first resolves `token` into the calling
+/// user and hands it to the operation. Fails when the token is missing or invalid.
+pub async fn handle_authenticated_db_operation<T, F, Fut>(
+    app: AppHandle,
+    token: Option<String>,
+    operation: F,
+) -> IpcResponse<T>
+where
+    F: FnOnce(Arc<prisma::PrismaClient>, Principal) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<T, AppError>> + Send + 'static,
+    T: Send + 'static + Serialize,
+{
+    handle_authenticated_storage_operation(app, token, move |storage, principal| async move {
+        let client = storage.prisma()?;
+        operation(client, principal).await
+    })
+    .await
+}
+
+/// Like [`handle_authenticated_db_operation`], but also requires the calling
+/// user's role to grant `permission`.
+pub async fn handle_authorized_db_operation<T, F, Fut>(
+    app: AppHandle,
+    token: Option<String>,
+    permission: Permission,
+    operation: F,
+) -> IpcResponse<T>
+where
+    F: FnOnce(Arc<prisma::PrismaClient>, Principal) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<T, AppError>> + Send + 'static,
+    T: Send + 'static + Serialize,
+{
+    handle_authenticated_db_operation(app, token, move |client, principal| async move {
+        principal.require(permission)?;
+        operation(client, principal).await
+    })
+    .await
+}
+
+/// Runs an operation against whichever storage backend is active.
+pub async fn handle_storage_operation<T, F, Fut>(app: AppHandle, operation: F) -> IpcResponse<T>
+where
+    F: FnOnce(Arc<dyn Storage>) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<T, AppError>> + Send + 'static,
+    T: Send + 'static + Serialize,
+{
+    let result = app.storage(operation).await;
+    result.into()
+}
+
+/// Like [`handle_storage_operation`], but first resolves `token` into the
+/// calling user and hands it to the operation.
+pub async fn handle_authenticated_storage_operation<T, F, Fut>(
+    app: AppHandle,
+    token: Option<String>,
+    operation: F,
+) -> IpcResponse<T>
+where
+    F: FnOnce(Arc<dyn Storage>, Principal) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<T, AppError>> + Send + 'static,
+    T: Send + 'static + Serialize,
+{
+    let secret = app.state::<AppState>().token_secret.clone();
+    handle_storage_operation(app, move |storage| async move {
+        let principal = authenticate(storage.as_ref(), &secret, token).await?;
+        operation(storage, principal).await
+    })
+    .await
+}
+
+/// Like [`handle_authenticated_storage_operation`], but also requires the
+/// calling user's role to grant `permission`.
+pub async fn handle_authorized_storage_operation<T, F, Fut>(
+    app: AppHandle,
+    token: Option<String>,
+    permission: Permission,
+    operation: F,
+) -> IpcResponse<T>
+where
+    F: FnOnce(Arc<dyn Storage>, Principal) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<T, AppError>> + Send + 'static,
+    T: Send + 'static + Serialize,
+{
+    handle_authenticated_storage_operation(app, token, move |storage, principal| async move {
+        principal.require(permission)?;
+        operation(storage, principal).await
+    })
+    .await
+}
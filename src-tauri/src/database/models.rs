@@ -1,5 +1,10 @@
+use chrono::{DateTime, FixedOffset, Utc};
+use prisma::{Role, WorkspaceRole};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+use crate::auth::effective_role;
+
 pub type User = prisma::user::Data;
 
 #[derive(Debug, Deserialize)]
@@ -15,11 +20,76 @@ pub struct UserForm {
 pub struct Credentials {
     pub email: String,
     pub password: String,
+    /// The workspace to sign into; the default workspace when absent.
+    #[serde(default)]
+    pub workspace_id: Option<String>,
 }
 
 #[derive(Deserialize)]
 pub struct UserFilter {
     pub email: Option<String>,
+    pub role: Option<Role>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RoleUpdate {
+    pub role: Role,
+}
+
+/// Controls what happens to an account's data when it is deleted.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserDeletionOptions {
+    /// Hands the account's snippets, groups and the shares it granted over to
+    /// this user instead of leaving the snippets without an author and
+    /// dropping the groups and shares.
+    pub transfer_snippets_to: Option<String>,
+}
+
+/// Everything stored about an account, as returned by `export_my_data`.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserDataExport {
+    pub format_version: u32,
+    pub exported_at: DateTime<Utc>,
+    pub user: ExportedUser,
+    pub snippets: Vec<Snippet>,
+    pub snippet_ratings: Vec<SnippetRating>,
+    pub notifications: Vec<Notification>,
+    pub api_rate_limits: Vec<ApiRateLimit>,
+    pub activity_logs: Vec<ActivityLog>,
+}
+
+/// A [`User`] without its credentials.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportedUser {
+    pub id: String,
+    pub name: String,
+    pub email: String,
+    pub email_verified: bool,
+    pub phone: Option<String>,
+    pub phone_verified: bool,
+    pub role: Role,
+    pub created_at: DateTime<FixedOffset>,
+    pub updated_at: DateTime<FixedOffset>,
+}
+
+impl From<User> for ExportedUser {
+    fn from(user: User) -> Self {
+        Self {
+            id: user.id,
+            name: user.name,
+            email: user.email,
+            email_verified: user.email_verified,
+            phone: user.phone,
+            phone_verified: user.phone_verified,
+            role: effective_role(user.role),
+            created_at: user.created_at,
+            updated_at: user.updated_at,
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize)]
@@ -28,24 +98,125 @@ pub struct AuthPayload {
     pub access_token: String,
     pub refresh_token: String,
     pub user: User,
+    /// The workspace the tokens are for; none for the default workspace.
+    #[serde(default)]
+    pub workspace: Option<WorkspaceInfo>,
+    /// Every workspace the user can switch to.
+    #[serde(default)]
+    pub workspaces: Vec<WorkspaceInfo>,
+}
+
+/// A workspace along with the user's role in it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkspaceInfo {
+    pub id: String,
+    pub name: String,
+    pub role: WorkspaceRole,
+}
+
+/// The records a query covers. Commands set it from the caller's active
+/// workspace; the frontend never does.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WorkspaceScope {
+    /// Records without a workspace.
+    Default,
+    Workspace(String),
+}
+
+impl WorkspaceScope {
+    pub fn workspace_id(&self) -> Option<String> {
+        match self {
+            WorkspaceScope::Default => None,
+            WorkspaceScope::Workspace(id) => Some(id.clone()),
+        }
+    }
+
+    /// Whether a record with this `workspace_id` falls in the scope.
+    pub fn contains(&self, workspace_id: Option<&str>) -> bool {
+        match self {
+            WorkspaceScope::Default => workspace_id.is_none(),
+            WorkspaceScope::Workspace(id) => workspace_id == Some(id.as_str()),
+        }
+    }
+}
+
+impl From<Option<String>> for WorkspaceScope {
+    fn from(workspace_id: Option<String>) -> Self {
+        match workspace_id {
+            Some(id) => WorkspaceScope::Workspace(id),
+            None => WorkspaceScope::Default,
+        }
+    }
 }
 
 pub type SnippetState = prisma::snippet_state::Data;
 pub type Snippet = prisma::snippet::Data;
 pub type Tag = prisma::tag::Data;
+pub type Collection = prisma::collection::Data;
+pub type UserGroup = prisma::user_group::Data;
+pub type Share = prisma::share::Data;
+pub type Workspace = prisma::workspace::Data;
+pub type WorkspaceMembership = prisma::workspace_membership::Data;
+pub type SnippetRating = prisma::snippet_rating::Data;
+pub type Notification = prisma::notification::Data;
+pub type ApiRateLimit = prisma::api_rate_limit::Data;
+pub type ActivityLog = prisma::activity_log::Data;
+pub type TagUsage = prisma::tag_usage::Data;
 
-#[derive(Debug, Deserialize)]
+/// The contents of the vault as captured by a backup. Accounts are left out;
+/// records referring to users point at them by id.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VaultSnapshot {
+    pub snippets: Vec<Snippet>,
+    pub states: Vec<SnippetState>,
+    pub tags: Vec<Tag>,
+    pub snippet_ratings: Vec<SnippetRating>,
+    pub tag_usages: Vec<TagUsage>,
+    pub activity_logs: Vec<ActivityLog>,
+    /// Absent from backups taken before collections existed.
+    #[serde(default)]
+    pub collections: Vec<Collection>,
+}
+
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct SnippetForm {
     pub title: String,
     pub description: String,
+    /// Detected from the code when empty.
     pub language: String,
     pub code: String,
     pub tag_ids: Vec<String>,
+    /// Empty for new snippets.
     pub snippet_state_id: String,
+    /// Whether to encrypt the code and description. Left out, a new snippet
+    /// is public and an existing one keeps its setting.
+    pub is_private: Option<bool>,
+    /// Set from the active workspace when the snippet is created; updates
+    /// never move a snippet between workspaces.
+    #[serde(skip)]
+    pub workspace_id: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+/// A form that saves the snippet unchanged, to update only some fields.
+impl From<&Snippet> for SnippetForm {
+    fn from(snippet: &Snippet) -> Self {
+        Self {
+            title: snippet.title.clone(),
+            description: snippet.description.clone(),
+            language: snippet.language.clone(),
+            code: snippet.code.clone(),
+            tag_ids: snippet.tag_ids.clone(),
+            snippet_state_id: snippet.snippet_state_id.clone(),
+            is_private: None,
+            workspace_id: snippet.workspace_id.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct SnippetFilter {
     pub search: Option<String>,
@@ -54,25 +225,83 @@ pub struct SnippetFilter {
     pub language: Option<String>,
     pub code: Option<String>,
     pub state: Option<SnippetStateUpdate>,
+    /// Tag names.
     pub tags: Option<Vec<String>>,
+    /// Only snippets in this collection, in the collection's order.
+    pub collection_id: Option<String>,
+    /// Newest first when left out.
+    pub sort: Option<SnippetSort>,
+    #[serde(skip)]
+    pub workspace: Option<WorkspaceScope>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum SnippetSort {
+    Newest,
+    /// Most often copied first, then most recently copied; a collection's
+    /// order is kept among snippets copied equally often.
+    MostUsed,
+}
+
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct SnippetStateUpdate {
     pub is_favorite: Option<bool>,
     pub is_dark: Option<bool>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct TagForm {
     pub name: String,
     pub color: Option<String>,
+    /// The emoji and parent are only set when the tag is created.
+    #[serde(default)]
+    pub emoji: Option<String>,
+    #[serde(default)]
+    pub parent_id: Option<String>,
+    /// Set from the active workspace when the tag is created.
+    #[serde(skip)]
+    pub workspace_id: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct TagFilter {
     pub name: Option<String>,
+    #[serde(skip)]
+    pub workspace: Option<WorkspaceScope>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CollectionForm {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    pub parent_id: Option<String>,
+    /// Place among the siblings. Assigned by the collection commands.
+    #[serde(default)]
+    pub position: i32,
+    /// The snippets, in order.
+    #[serde(default)]
+    pub snippet_ids: Vec<String>,
+    /// Set from the active workspace when the collection is created.
+    #[serde(skip)]
+    pub workspace_id: Option<String>,
+}
+
+/// A form that saves the collection unchanged, to update only some fields.
+impl From<&Collection> for CollectionForm {
+    fn from(collection: &Collection) -> Self {
+        Self {
+            name: collection.name.clone(),
+            description: collection.description.clone(),
+            parent_id: collection.parent_id.clone(),
+            position: collection.position,
+            snippet_ids: collection.snippet_ids.clone(),
+            workspace_id: collection.workspace_id.clone(),
+        }
+    }
 }
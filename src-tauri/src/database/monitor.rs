@@ -0,0 +1,83 @@
+use std::{cmp::min, sync::Arc, time::Duration};
+
+use log::warn;
+use tauri::{AppHandle, Manager};
+
+use super::status::ConnectionState;
+use crate::{
+    state::AppState,
+    storage::{offline::PENDING_CHANGES_SYNCED_EVENT, OfflineStorage},
+};
+
+const INITIAL_RETRY_DELAY: Duration = Duration::from_secs(1);
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(60);
+const HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Keeps the MongoDB connection alive for the lifetime of the app.
+///
+/// Connects in the background with exponential backoff, then pings the server
+/// periodically and starts reconnecting as soon as a ping fails. The app keeps
+/// running in a degraded mode while disconnected, and changes made in the
+/// meantime are replayed after every successful reconnect.
+pub fn spawn_connection_monitor(app: AppHandle, storage: Arc<OfflineStorage>) {
+    tauri::async_runtime::spawn(async move {
+        let mut retry_delay = INITIAL_RETRY_DELAY;
+
+        loop {
+            let app_state = app.state::<AppState>();
+            let remote = storage.remote();
+
+            if remote.is_connected() {
+                match remote.ping().await {
+                    Ok(()) => {
+                        tokio::time::sleep(HEALTH_CHECK_INTERVAL).await;
+                        continue;
+                    }
+                    Err(e) => {
+                        warn!("Lost connection to the database: {}", e);
+                        remote.disconnect();
+                        app_state.db_status.update(
+                            &app,
+                            ConnectionState::Disconnected,
+                            Some(e.to_string()),
+                            None,
+                        );
+                    }
+                }
+            }
+
+            match remote.connect().await {
+                Ok(()) => {
+                    retry_delay = INITIAL_RETRY_DELAY;
+                    app_state
+                        .db_status
+                        .update(&app, ConnectionState::Connected, None, None);
+
+                    match storage.on_reconnect().await {
+                        Ok(report) => {
+                            if let Err(e) = app.emit_all(PENDING_CHANGES_SYNCED_EVENT, report) {
+                                warn!("Failed to emit the sync report: {}", e);
+                            }
+                        }
+                        Err(e) => warn!("Failed to synchronize offline changes: {}", e),
+                    }
+                }
+                Err(e) => {
+                    warn!(
+                        "Failed to connect to the database, retrying in {}s: {}",
+                        retry_delay.as_secs(),
+                        e
+                    );
+                    app_state.db_status.update(
+                        &app,
+                        ConnectionState::Disconnected,
+                        Some(e.to_string()),
+                        Some(retry_delay.as_secs()),
+                    );
+                    tokio::time::sleep(retry_delay).await;
+                    retry_delay = min(retry_delay * 2, MAX_RETRY_DELAY);
+                }
+            }
+        }
+    });
+}
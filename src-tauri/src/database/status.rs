@@ -0,0 +1,94 @@
+use std::sync::Mutex;
+
+use chrono::{DateTime, Utc};
+use log::{error, info};
+use serde::Serialize;
+use tauri::{AppHandle, Manager};
+
+pub const DB_STATUS_CHANGED_EVENT: &str = "db-status-changed";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ConnectionState {
+    Connecting,
+    Connected,
+    Disconnected,
+}
+
+/// The connection status reported by `get_db_status` and the
+/// `db-status-changed` event.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DbStatus {
+    pub backend: String,
+    pub state: ConnectionState,
+    pub last_error: Option<String>,
+    pub next_retry_in_secs: Option<u64>,
+    pub changed_at: DateTime<Utc>,
+}
+
+impl DbStatus {
+    fn tooltip(&self) -> String {
+        let state = match self.state {
+            ConnectionState::Connecting => "connecting...".to_string(),
+            ConnectionState::Connected => "connected".to_string(),
+            ConnectionState::Disconnected => match self.next_retry_in_secs {
+                Some(secs) => format!("offline, retrying in {}s", secs),
+                None => "offline".to_string(),
+            },
+        };
+        format!("Snippet Vault - {} {}", self.backend, state)
+    }
+}
+
+/// Holds the latest [`DbStatus`] and broadcasts changes to the frontend and
+/// the system tray.
+pub struct DbStatusTracker {
+    status: Mutex<DbStatus>,
+}
+
+impl DbStatusTracker {
+    pub fn new(backend: &str, state: ConnectionState) -> Self {
+        Self {
+            status: Mutex::new(DbStatus {
+                backend: backend.to_string(),
+                state,
+                last_error: None,
+                next_retry_in_secs: None,
+                changed_at: Utc::now(),
+            }),
+        }
+    }
+
+    pub fn current(&self) -> DbStatus {
+        self.status.lock().unwrap().clone()
+    }
+
+    pub fn update(
+        &self,
+        app: &AppHandle,
+        state: ConnectionState,
+        last_error: Option<String>,
+        next_retry_in_secs: Option<u64>,
+    ) {
+        let status = {
+            let mut status = self.status.lock().unwrap();
+            let state_changed = status.state != state;
+            status.state = state;
+            status.last_error = last_error;
+            status.next_retry_in_secs = next_retry_in_secs;
+            if state_changed {
+                status.changed_at = Utc::now();
+                info!("Database status changed to {:?}", state);
+            }
+            status.clone()
+        };
+
+        if let Err(e) = app.emit_all(DB_STATUS_CHANGED_EVENT, &status) {
+            error!("Failed to emit database status: {}", e);
+        }
+        if let Err(e) = app.tray_handle().set_tooltip(&status.tooltip()) {
+            error!("Failed to update tray tooltip: {}", e);
+        }
+    }
+}
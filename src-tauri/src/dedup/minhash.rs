@@ -0,0 +1,160 @@
+//! MinHash signatures over token shingles. The share of positions where two
+//! signatures agree estimates the Jaccard similarity of the snippets'
+//! shingle sets, and banding the signatures finds likely pairs without
+//! comparing every snippet with every other.
+
+use std::collections::HashMap;
+
+/// Tokens per shingle.
+const SHINGLE_SIZE: usize = 4;
+pub const SIGNATURE_LENGTH: usize = 64;
+/// Signature rows per band. Pairs agreeing on every row of any band are
+/// compared; with 16 bands of 4 rows, pairs above about 0.7 similarity
+/// almost always are.
+const ROWS_PER_BAND: usize = 4;
+
+pub type Signature = Vec<u64>;
+
+pub fn signature(tokens: &[String]) -> Signature {
+    let mut signature = vec![u64::MAX; SIGNATURE_LENGTH];
+    if tokens.is_empty() {
+        return signature;
+    }
+
+    let size = SHINGLE_SIZE.min(tokens.len());
+    for shingle in tokens.windows(size) {
+        let base = fnv1a(shingle);
+        for (seed, minimum) in signature.iter_mut().enumerate() {
+            let hash = mix(base ^ mix(seed as u64 + 1));
+            if hash < *minimum {
+                *minimum = hash;
+            }
+        }
+    }
+    signature
+}
+
+/// Estimated Jaccard similarity, between 0 and 1.
+pub fn similarity(a: &[u64], b: &[u64]) -> f32 {
+    if a.is_empty() || a.len() != b.len() {
+        return 0.0;
+    }
+    let matching = a.iter().zip(b).filter(|(a, b)| a == b).count();
+    matching as f32 / a.len() as f32
+}
+
+/// Pairs of indices, smaller first, whose signatures share a band.
+pub fn candidate_pairs(signatures: &[Signature]) -> Vec<(usize, usize)> {
+    let mut buckets: HashMap<(usize, &[u64]), Vec<usize>> = HashMap::new();
+    for (index, signature) in signatures.iter().enumerate() {
+        for (band, rows) in signature.chunks(ROWS_PER_BAND).enumerate() {
+            buckets.entry((band, rows)).or_default().push(index);
+        }
+    }
+
+    let mut pairs: Vec<(usize, usize)> = buckets
+        .values()
+        .filter(|members| members.len() > 1)
+        .flat_map(|members| {
+            members.iter().enumerate().flat_map(move |(position, &a)| {
+                members[position + 1..].iter().map(move |&b| (a, b))
+            })
+        })
+        .collect();
+    pairs.sort_unstable();
+    pairs.dedup();
+    pairs
+}
+
+fn fnv1a(tokens: &[String]) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for token in tokens {
+        for byte in token.bytes().chain(std::iter::once(0)) {
+            hash ^= u64::from(byte);
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+    hash
+}
+
+/// SplitMix64's finalizer, to derive independent hash functions from one.
+fn mix(mut value: u64) -> u64 {
+    value = (value ^ (value >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    value = (value ^ (value >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    value ^ (value >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tokens(text: &str) -> Vec<String> {
+        text.split_whitespace().map(str::to_string).collect()
+    }
+
+    const FETCH: &str = "async function load ( url ) { const response = await fetch ( url ) ; \
+                         if ( ! response . ok ) { throw new Error ( response . status ) ; } \
+                         return response . json ( ) ; }";
+    const QUERY: &str = "SELECT name , email FROM users WHERE created_at > NOW ( ) - \
+                         INTERVAL '7 days' ORDER BY created_at DESC LIMIT 20 ;";
+
+    #[test]
+    fn identical_code_is_fully_similar() {
+        let a = signature(&tokens(FETCH));
+        let b = signature(&tokens(FETCH));
+
+        assert_eq!(a.len(), SIGNATURE_LENGTH);
+        assert_eq!(similarity(&a, &b), 1.0);
+    }
+
+    #[test]
+    fn a_small_edit_stays_similar() {
+        let edited = FETCH.replace("load", "loadJson");
+        let score = similarity(&signature(&tokens(FETCH)), &signature(&tokens(&edited)));
+
+        assert!(score > 0.6, "similarity {}", score);
+        assert!(score < 1.0, "similarity {}", score);
+    }
+
+    #[test]
+    fn unrelated_code_is_dissimilar() {
+        let score = similarity(&signature(&tokens(FETCH)), &signature(&tokens(QUERY)));
+
+        assert!(score < 0.1, "similarity {}", score);
+    }
+
+    #[test]
+    fn code_shorter_than_a_shingle_still_gets_a_signature() {
+        let short = signature(&tokens("ls -la"));
+
+        assert!(short.iter().all(|&value| value != u64::MAX));
+        assert_eq!(similarity(&short, &signature(&tokens("ls -la"))), 1.0);
+        assert!(signature(&[]).iter().all(|&value| value == u64::MAX));
+    }
+
+    #[test]
+    fn signatures_of_different_lengths_are_not_similar() {
+        let a = signature(&tokens(FETCH));
+
+        assert_eq!(similarity(&a, &a[..SIGNATURE_LENGTH / 2]), 0.0);
+        assert_eq!(similarity(&[], &[]), 0.0);
+    }
+
+    #[test]
+    fn candidate_pairs_finds_near_duplicates_only() {
+        let signatures = vec![
+            signature(&tokens(QUERY)),
+            signature(&tokens(FETCH)),
+            signature(&tokens(&QUERY.replace("20", "50"))),
+            signature(&tokens(FETCH)),
+        ];
+
+        assert_eq!(candidate_pairs(&signatures), vec![(0, 2), (1, 3)]);
+    }
+
+    #[test]
+    fn candidate_pairs_of_nothing_is_empty() {
+        assert!(candidate_pairs(&[]).is_empty());
+        assert!(candidate_pairs(&[signature(&tokens(FETCH))]).is_empty());
+    }
+}
@@ -0,0 +1,388 @@
+//! Finding snippets that are copies or variations of each other. Code is
+//! normalized before comparing, so copies that differ only in formatting or
+//! comments are exact duplicates, and MinHash signatures rate how much of
+//! the rest is shared.
+
+pub mod minhash;
+pub mod normalize;
+
+use std::sync::Arc;
+
+use log::warn;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tauri::{AppHandle, Manager};
+
+use crate::{
+    auth::Principal,
+    database::models::{Snippet, SnippetFilter, SnippetForm, SnippetStateUpdate},
+    encryption,
+    error::AppError,
+    prelude::AppResult,
+    sharing::{self, Access, Grants},
+    state::AppState,
+    storage::Storage,
+};
+
+use self::minhash::Signature;
+
+pub const DUPLICATE_SNIPPET_EVENT: &str = "duplicate-snippet-warning";
+
+pub struct Fingerprint {
+    /// SHA-256 of the normalized code; equal for exact duplicates.
+    pub hash: String,
+    pub signature: Signature,
+    /// Whether the code has any tokens at all. Empty snippets aren't
+    /// compared.
+    pub is_empty: bool,
+}
+
+pub fn fingerprint(code: &str, language: &str) -> Fingerprint {
+    let tokens = normalize::tokens(code, language);
+    Fingerprint {
+        hash: hex::encode(Sha256::digest(tokens.join(" ").as_bytes())),
+        signature: minhash::signature(&tokens),
+        is_empty: tokens.is_empty(),
+    }
+}
+
+impl Fingerprint {
+    pub fn similarity(&self, other: &Fingerprint) -> f32 {
+        if self.hash == other.hash {
+            1.0
+        } else {
+            minhash::similarity(&self.signature, &other.signature)
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicateSearchForm {
+    /// Lowest similarity, between 0 and 1, for snippets to count as
+    /// duplicates. Defaults to the setting.
+    pub threshold: Option<f32>,
+    /// Limits the search to these snippets.
+    pub filter: Option<SnippetFilter>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MergeSnippetsForm {
+    /// The snippet to keep.
+    pub keep_id: String,
+    /// Snippets merged into it and then deleted.
+    pub merge_ids: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicateMatch {
+    pub snippet_id: String,
+    pub title: String,
+    pub language: String,
+    pub similarity: f32,
+    /// Identical once whitespace and comments are ignored.
+    pub exact: bool,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicateCluster {
+    /// The oldest snippet first; similarities are to it.
+    pub snippets: Vec<DuplicateMatch>,
+    /// The lowest similarity between the first snippet and the others.
+    pub similarity: f32,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MergeResult {
+    /// The kept snippet, after merging.
+    pub snippet: Snippet,
+    pub deleted: Vec<Snippet>,
+}
+
+/// Sent when a new snippet resembles existing ones.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicateWarning {
+    pub snippet_id: String,
+    pub title: String,
+    pub duplicates: Vec<DuplicateMatch>,
+}
+
+fn duplicate_match(snippet: &Snippet, similarity: f32, exact: bool) -> DuplicateMatch {
+    DuplicateMatch {
+        snippet_id: snippet.id.clone(),
+        title: snippet.title.clone(),
+        language: snippet.language.clone(),
+        similarity: (similarity * 100.0).round() / 100.0,
+        exact,
+    }
+}
+
+/// Snippets among `others` at least `threshold` similar to `snippet`, most
+/// similar first.
+pub fn find_near_duplicates(
+    snippet: &Snippet,
+    others: &[Snippet],
+    threshold: f32,
+) -> Vec<DuplicateMatch> {
+    if encryption::is_private(snippet) {
+        return vec![];
+    }
+    let target = fingerprint(&snippet.code, &snippet.language);
+    if target.is_empty {
+        return vec![];
+    }
+
+    let mut matches: Vec<DuplicateMatch> = others
+        .iter()
+        .filter(|other| other.id != snippet.id && !encryption::is_private(other))
+        .filter_map(|other| {
+            let candidate = fingerprint(&other.code, &other.language);
+            let similarity = target.similarity(&candidate);
+            (!candidate.is_empty && similarity >= threshold)
+                .then(|| duplicate_match(other, similarity, candidate.hash == target.hash))
+        })
+        .collect();
+    sort_by_similarity(&mut matches);
+    matches
+}
+
+/// Groups snippets that are at least `threshold` similar, directly or
+/// through others in the group. Largest groups first. Private snippets are
+/// left out.
+pub fn find_clusters(snippets: &[Snippet], threshold: f32) -> Vec<DuplicateCluster> {
+    let candidates: Vec<(&Snippet, Fingerprint)> = snippets
+        .iter()
+        .filter(|snippet| !encryption::is_private(snippet))
+        .map(|snippet| (snippet, fingerprint(&snippet.code, &snippet.language)))
+        .filter(|(_, fingerprint)| !fingerprint.is_empty)
+        .collect();
+    let signatures: Vec<Signature> = candidates
+        .iter()
+        .map(|(_, fingerprint)| fingerprint.signature.clone())
+        .collect();
+
+    let mut groups = DisjointSet::new(candidates.len());
+    for (a, b) in minhash::candidate_pairs(&signatures) {
+        if candidates[a].1.similarity(&candidates[b].1) >= threshold {
+            groups.union(a, b);
+        }
+    }
+
+    let mut members: Vec<Vec<usize>> = vec![vec![]; candidates.len()];
+    for index in 0..candidates.len() {
+        members[groups.find(index)].push(index);
+    }
+
+    let mut clusters: Vec<DuplicateCluster> = members
+        .into_iter()
+        .filter(|group| group.len() > 1)
+        .map(|mut group| {
+            group.sort_by_key(|&index| candidates[index].0.created_at);
+            let (first, first_fingerprint) = &candidates[group[0]];
+
+            let mut snippets = vec![duplicate_match(first, 1.0, true)];
+            let mut others: Vec<DuplicateMatch> = group[1..]
+                .iter()
+                .map(|&index| {
+                    let (snippet, fingerprint) = &candidates[index];
+                    duplicate_match(
+                        snippet,
+                        first_fingerprint.similarity(fingerprint),
+                        first_fingerprint.hash == fingerprint.hash,
+                    )
+                })
+                .collect();
+            sort_by_similarity(&mut others);
+
+            let similarity = others
+                .iter()
+                .map(|other| other.similarity)
+                .fold(1.0, f32::min);
+            snippets.extend(others);
+            DuplicateCluster {
+                snippets,
+                similarity,
+            }
+        })
+        .collect();
+    clusters.sort_by(|a, b| b.snippets.len().cmp(&a.snippets.len()));
+    clusters
+}
+
+/// Compares a newly created snippet with the rest of its workspace that the
+/// author can read in the background, and emits `DUPLICATE_SNIPPET_EVENT` if
+/// it resembles any.
+pub fn warn_about_duplicates(
+    app: &AppHandle,
+    storage: Arc<dyn Storage>,
+    principal: &Principal,
+    snippet: &Snippet,
+) {
+    let config = app.state::<AppState>().config.dedup.clone();
+    if !config.warn_on_create {
+        return;
+    }
+
+    let app = app.clone();
+    let principal = principal.clone();
+    let snippet = snippet.clone();
+    tauri::async_runtime::spawn(async move {
+        let filter = SnippetFilter {
+            workspace: Some(snippet.workspace_id.clone().into()),
+            ..Default::default()
+        };
+        let others = async {
+            let mut others = storage.list_snippets(Some(filter)).await?;
+            let grants = Grants::load(storage.as_ref(), &principal).await?;
+            others.retain(|other| grants.access(&principal, other) >= Access::Read);
+            Ok::<_, AppError>(others)
+        };
+        let others = match others.await {
+            Ok(others) => others,
+            Err(e) => {
+                warn!(
+                    "Failed to check snippet {} for duplicates: {}",
+                    snippet.id, e
+                );
+                return;
+            }
+        };
+
+        let duplicates = find_near_duplicates(&snippet, &others, config.threshold);
+        if duplicates.is_empty() {
+            return;
+        }
+        warn!(
+            "Snippet {} resembles {} existing snippet(s)",
+            snippet.id,
+            duplicates.len()
+        );
+        let warning = DuplicateWarning {
+            snippet_id: snippet.id,
+            title: snippet.title,
+            duplicates,
+        };
+        if let Err(e) = app.emit_all(DUPLICATE_SNIPPET_EVENT, warning) {
+            warn!("Failed to emit the duplicate warning: {}", e);
+        }
+    });
+}
+
+fn sort_by_similarity(matches: &mut [DuplicateMatch]) {
+    matches.sort_by(|a, b| {
+        b.similarity
+            .partial_cmp(&a.similarity)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+}
+
+/// Folds the duplicates into the kept snippet, which gains their tags, a
+/// description if it has none and their favorite flag, then deletes them.
+/// The caller needs to be able to edit the kept snippet and to own the
+/// duplicates, as deleting them would require.
+pub async fn merge(
+    storage: &dyn Storage,
+    principal: &Principal,
+    keep_id: String,
+    merge_ids: Vec<String>,
+) -> AppResult<MergeResult> {
+    let kept = storage
+        .get_snippet(keep_id.clone())
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Snippet with ID {}", keep_id)))?;
+    let grants = Grants::load(storage, principal).await?;
+    grants
+        .access(principal, &kept)
+        .require(Access::Edit, &kept.id)?;
+
+    let mut duplicates = vec![];
+    for id in merge_ids.into_iter().filter(|id| *id != keep_id) {
+        let duplicate = storage
+            .get_snippet(id.clone())
+            .await?
+            .ok_or_else(|| AppError::NotFound(format!("Snippet with ID {}", id)))?;
+        grants
+            .access(principal, &duplicate)
+            .require(Access::Owner, &duplicate.id)?;
+        duplicates.push(duplicate);
+    }
+
+    let mut form = SnippetForm::from(&kept);
+    for duplicate in &duplicates {
+        for tag_id in &duplicate.tag_ids {
+            if !form.tag_ids.contains(tag_id) {
+                form.tag_ids.push(tag_id.clone());
+            }
+        }
+        if form.description.trim().is_empty() {
+            form.description = duplicate.description.clone();
+        }
+    }
+    let merged = storage.update_snippet(keep_id, form).await?;
+
+    let is_favorite = |snippet: &Snippet| {
+        snippet
+            .state
+            .as_ref()
+            .map_or(false, |state| state.is_favorite)
+    };
+    if !is_favorite(&kept) && duplicates.iter().any(is_favorite) {
+        storage
+            .update_snippet_state(
+                merged.snippet_state_id.clone(),
+                SnippetStateUpdate {
+                    is_favorite: Some(true),
+                    is_dark: None,
+                },
+            )
+            .await?;
+    }
+
+    let mut deleted = vec![];
+    for duplicate in duplicates {
+        let snippet = storage.delete_snippet(duplicate.id).await?;
+        sharing::forget_snippet(storage, &snippet.id).await;
+        deleted.push(snippet);
+    }
+
+    let snippet = storage
+        .get_snippet(merged.id.clone())
+        .await?
+        .unwrap_or(merged);
+    Ok(MergeResult { snippet, deleted })
+}
+
+/// Union-find over indices, for grouping pairs into clusters.
+struct DisjointSet {
+    parents: Vec<usize>,
+}
+
+impl DisjointSet {
+    fn new(size: usize) -> Self {
+        Self {
+            parents: (0..size).collect(),
+        }
+    }
+
+    fn find(&mut self, index: usize) -> usize {
+        let parent = self.parents[index];
+        if parent == index {
+            return index;
+        }
+        let root = self.find(parent);
+        self.parents[index] = root;
+        root
+    }
+
+    fn union(&mut self, a: usize, b: usize) {
+        let (a, b) = (self.find(a), self.find(b));
+        if a != b {
+            self.parents[b] = a;
+        }
+    }
+}
@@ -0,0 +1,145 @@
+//! Reduces code to the tokens that matter for comparing it: comments are
+//! dropped and whitespace no longer counts, so reformatted or re-commented
+//! copies of a snippet normalize to the same text.
+
+struct CommentSyntax {
+    line: &'static [&'static str],
+    block: &'static [(&'static str, &'static str)],
+}
+
+const C_LIKE: CommentSyntax = CommentSyntax {
+    line: &["//"],
+    block: &[("/*", "*/")],
+};
+const CSS: CommentSyntax = CommentSyntax {
+    line: &[],
+    block: &[("/*", "*/")],
+};
+const PHP: CommentSyntax = CommentSyntax {
+    line: &["//", "#"],
+    block: &[("/*", "*/")],
+};
+const HASH: CommentSyntax = CommentSyntax {
+    line: &["#"],
+    block: &[],
+};
+const DASHES: CommentSyntax = CommentSyntax {
+    line: &["--"],
+    block: &[("/*", "*/")],
+};
+const MARKUP: CommentSyntax = CommentSyntax {
+    line: &[],
+    block: &[("<!--", "-->")],
+};
+const NONE: CommentSyntax = CommentSyntax {
+    line: &[],
+    block: &[],
+};
+
+fn comment_syntax(language: &str) -> &'static CommentSyntax {
+    match language {
+        "c" | "cpp" | "csharp" | "dart" | "go" | "java" | "javascript" | "json" | "kotlin"
+        | "rust" | "scss" | "swift" | "typescript" => &C_LIKE,
+        "css" => &CSS,
+        "php" => &PHP,
+        "dockerfile" | "makefile" | "perl" | "powershell" | "python" | "ruby" | "shell"
+        | "toml" | "yaml" => &HASH,
+        "lua" | "sql" => &DASHES,
+        "html" | "markdown" | "xml" => &MARKUP,
+        _ => &NONE,
+    }
+}
+
+/// Quote characters that open strings, in which comment markers don't count.
+fn string_quotes(language: &str) -> &'static [char] {
+    match language {
+        // A single quote starts a lifetime or char, not a string.
+        "rust" => &['"'],
+        "go" | "javascript" | "typescript" => &['"', '\'', '`'],
+        _ => &['"', '\''],
+    }
+}
+
+/// The code's tokens, with comments removed: runs of word characters and
+/// single punctuation characters, each string literal as one token.
+pub fn tokens(code: &str, language: &str) -> Vec<String> {
+    let syntax = comment_syntax(language);
+    let quotes = string_quotes(language);
+    let mut tokens = vec![];
+    let mut word = String::new();
+    let mut rest = code;
+
+    while let Some(c) = rest.chars().next() {
+        if let Some(marker) = syntax.line.iter().find(|marker| rest.starts_with(*marker)) {
+            flush(&mut word, &mut tokens);
+            let end = rest[marker.len()..]
+                .find('\n')
+                .map_or(rest.len(), |index| marker.len() + index);
+            rest = &rest[end..];
+            continue;
+        }
+        if let Some((open, close)) = syntax
+            .block
+            .iter()
+            .find(|(open, _)| rest.starts_with(*open))
+        {
+            flush(&mut word, &mut tokens);
+            let end = rest[open.len()..]
+                .find(close)
+                .map_or(rest.len(), |index| open.len() + index + close.len());
+            rest = &rest[end..];
+            continue;
+        }
+
+        if quotes.contains(&c) {
+            flush(&mut word, &mut tokens);
+            let length = string_length(rest, c);
+            tokens.push(rest[..length].to_string());
+            rest = &rest[length..];
+            continue;
+        }
+
+        if c.is_alphanumeric() || c == '_' {
+            word.push(c);
+        } else {
+            flush(&mut word, &mut tokens);
+            if !c.is_whitespace() {
+                tokens.push(c.to_string());
+            }
+        }
+        rest = &rest[c.len_utf8()..];
+    }
+
+    flush(&mut word, &mut tokens);
+    tokens
+}
+
+/// The tokens joined by single spaces.
+pub fn normalize(code: &str, language: &str) -> String {
+    tokens(code, language).join(" ")
+}
+
+fn flush(word: &mut String, tokens: &mut Vec<String>) {
+    if !word.is_empty() {
+        tokens.push(std::mem::take(word));
+    }
+}
+
+/// Byte length of the string literal at the start of `text`, quotes
+/// included. Unterminated strings end at the line end, except template
+/// literals, which may span lines.
+fn string_length(text: &str, quote: char) -> usize {
+    let mut escaped = false;
+    for (index, c) in text.char_indices().skip(1) {
+        if escaped {
+            escaped = false;
+        } else if c == '\\' {
+            escaped = true;
+        } else if c == quote {
+            return index + c.len_utf8();
+        } else if c == '\n' && quote != '`' {
+            return index;
+        }
+    }
+    text.len()
+}
@@ -0,0 +1,211 @@
+//! The text form of encrypted fields, stored in place of the plaintext:
+//! `$svault$v1$<salt>$<nonce>$<ciphertext>`, each part hex-encoded. The salt
+//! is the Argon2 salt the key was derived with, so a field can be decrypted
+//! on any machine that knows the passphrase.
+
+use argon2::Argon2;
+use chacha20poly1305::{
+    aead::{Aead, KeyInit, Payload},
+    Key, XChaCha20Poly1305, XNonce,
+};
+use rand::RngCore;
+
+use crate::{error::AppError, prelude::AppResult};
+
+const PREFIX: &str = "$svault$v1$";
+pub const SALT_LENGTH: usize = 16;
+const NONCE_LENGTH: usize = 24;
+pub const KEY_LENGTH: usize = 32;
+
+pub type Salt = [u8; SALT_LENGTH];
+pub type DerivedKey = [u8; KEY_LENGTH];
+
+pub fn is_encrypted(text: &str) -> bool {
+    text.starts_with(PREFIX)
+}
+
+pub fn random_salt() -> Salt {
+    let mut salt = [0; SALT_LENGTH];
+    rand::thread_rng().fill_bytes(&mut salt);
+    salt
+}
+
+/// Derives a key with Argon2id's default parameters.
+pub fn derive_key(passphrase: &[u8], salt: &Salt) -> AppResult<DerivedKey> {
+    let mut key = [0; KEY_LENGTH];
+    Argon2::default()
+        .hash_password_into(passphrase, salt, &mut key)
+        .map_err(|e| AppError::EncryptionError(e.to_string()))?;
+    Ok(key)
+}
+
+/// The salt an encrypted field's key was derived with.
+pub fn salt_of(text: &str) -> AppResult<Salt> {
+    let (salt, _, _) = split(text)?;
+    Ok(salt)
+}
+
+/// Encrypts `plaintext`. `field` is authenticated along with it, so
+/// ciphertext moved to another field fails to decrypt.
+pub fn seal(key: &DerivedKey, salt: &Salt, field: &str, plaintext: &str) -> AppResult<String> {
+    let mut nonce = [0; NONCE_LENGTH];
+    rand::thread_rng().fill_bytes(&mut nonce);
+
+    let ciphertext = XChaCha20Poly1305::new(Key::from_slice(key))
+        .encrypt(
+            XNonce::from_slice(&nonce),
+            Payload {
+                msg: plaintext.as_bytes(),
+                aad: field.as_bytes(),
+            },
+        )
+        .map_err(|_| AppError::EncryptionError("Encryption failed".into()))?;
+
+    Ok(format!(
+        "{}{}${}${}",
+        PREFIX,
+        hex::encode(salt),
+        hex::encode(nonce),
+        hex::encode(ciphertext)
+    ))
+}
+
+/// Decrypts a field sealed with `key`. Fails if the key is wrong or the
+/// text was altered.
+pub fn open(key: &DerivedKey, field: &str, text: &str) -> AppResult<String> {
+    let (_, nonce, ciphertext) = split(text)?;
+    let plaintext = XChaCha20Poly1305::new(Key::from_slice(key))
+        .decrypt(
+            XNonce::from_slice(&nonce),
+            Payload {
+                msg: &ciphertext,
+                aad: field.as_bytes(),
+            },
+        )
+        .map_err(|_| AppError::EncryptionError("Incorrect passphrase or damaged data".into()))?;
+    String::from_utf8(plaintext)
+        .map_err(|_| AppError::EncryptionError("Decrypted text is not valid UTF-8".into()))
+}
+
+fn split(text: &str) -> AppResult<(Salt, [u8; NONCE_LENGTH], Vec<u8>)> {
+    let malformed = || AppError::EncryptionError("Malformed encrypted field".into());
+
+    let mut parts = text.strip_prefix(PREFIX).ok_or_else(malformed)?.split('$');
+    let mut next = || -> AppResult<Vec<u8>> {
+        let part = parts.next().ok_or_else(malformed)?;
+        hex::decode(part).map_err(|_| malformed())
+    };
+    let (salt, nonce, ciphertext) = (next()?, next()?, next()?);
+
+    Ok((
+        salt.try_into().map_err(|_| malformed())?,
+        nonce.try_into().map_err(|_| malformed())?,
+        ciphertext,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Deriving a key is deliberately slow, so most tests use fixed ones.
+    const KEY: DerivedKey = [7; KEY_LENGTH];
+    const OTHER_KEY: DerivedKey = [8; KEY_LENGTH];
+    const SALT: Salt = [3; SALT_LENGTH];
+
+    #[test]
+    fn open_returns_what_was_sealed() {
+        let sealed = seal(&KEY, &SALT, "code", "let answer = 42;").unwrap();
+
+        assert!(is_encrypted(&sealed));
+        assert!(!sealed.contains("answer"));
+        assert_eq!(salt_of(&sealed).unwrap(), SALT);
+        assert_eq!(open(&KEY, "code", &sealed).unwrap(), "let answer = 42;");
+    }
+
+    #[test]
+    fn seal_uses_a_fresh_nonce_each_time() {
+        let first = seal(&KEY, &SALT, "code", "same").unwrap();
+        let second = seal(&KEY, &SALT, "code", "same").unwrap();
+
+        assert_ne!(first, second);
+        assert_eq!(open(&KEY, "code", &second).unwrap(), "same");
+    }
+
+    #[test]
+    fn open_fails_with_the_wrong_key() {
+        let sealed = seal(&KEY, &SALT, "code", "secret").unwrap();
+
+        assert!(matches!(
+            open(&OTHER_KEY, "code", &sealed),
+            Err(AppError::EncryptionError(_))
+        ));
+    }
+
+    #[test]
+    fn open_fails_for_another_field() {
+        let sealed = seal(&KEY, &SALT, "description", "secret").unwrap();
+
+        assert!(matches!(
+            open(&KEY, "code", &sealed),
+            Err(AppError::EncryptionError(_))
+        ));
+    }
+
+    #[test]
+    fn open_fails_when_the_ciphertext_was_altered() {
+        let mut sealed = seal(&KEY, &SALT, "code", "secret").unwrap();
+        let last = sealed.pop().unwrap();
+        sealed.push(if last == '0' { '1' } else { '0' });
+
+        assert!(matches!(
+            open(&KEY, "code", &sealed),
+            Err(AppError::EncryptionError(_))
+        ));
+    }
+
+    #[test]
+    fn open_fails_when_the_nonce_was_altered() {
+        let sealed = seal(&KEY, &SALT, "code", "secret").unwrap();
+        let mut parts: Vec<String> = sealed
+            .strip_prefix(PREFIX)
+            .unwrap()
+            .split('$')
+            .map(str::to_string)
+            .collect();
+        parts[1] = "00".repeat(NONCE_LENGTH);
+        let altered = format!("{}{}", PREFIX, parts.join("$"));
+
+        assert!(open(&KEY, "code", &altered).is_err());
+    }
+
+    #[test]
+    fn malformed_text_is_rejected() {
+        let salt = hex::encode(SALT);
+        let nonce = "00".repeat(NONCE_LENGTH);
+        for text in [
+            "plain text".to_string(),
+            PREFIX.to_string(),
+            format!("{}{}", PREFIX, salt),
+            format!("{}{}${}", PREFIX, salt, nonce),
+            format!("{}zz${}$00", PREFIX, nonce),
+            format!("{}00${}$00", PREFIX, nonce),
+            format!("{}{}$00$00", PREFIX, salt),
+        ] {
+            assert!(open(&KEY, "code", &text).is_err(), "opened {:?}", text);
+            assert!(salt_of(&text).is_err(), "read a salt from {:?}", text);
+        }
+    }
+
+    #[test]
+    fn derived_keys_depend_on_the_passphrase_and_salt() {
+        let key = derive_key(b"correct horse", &SALT).unwrap();
+
+        assert_eq!(derive_key(b"correct horse", &SALT).unwrap(), key);
+        assert_ne!(derive_key(b"battery staple", &SALT).unwrap(), key);
+        assert_ne!(
+            derive_key(b"correct horse", &[4; SALT_LENGTH]).unwrap(),
+            key
+        );
+    }
+}
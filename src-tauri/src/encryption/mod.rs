@@ -0,0 +1,411 @@
+//! Private snippets, whose code and description are encrypted with a key
+//! derived from a passphrase only the user knows. Titles, languages and tags
+//! stay readable so private snippets can still be listed and organized.
+//!
+//! Storage only ever sees ciphertext; commands decrypt snippets on the way
+//! out while their author has the vault unlocked. Each user unlocks with
+//! their own passphrase, and nobody else can decrypt what they wrote. Until
+//! then, private snippets are returned with their encrypted fields as
+//! stored, which the frontend shows as locked; saving them back unchanged
+//! keeps them encrypted. Other features treat them as follows:
+//!
+//! - Search matches private snippets by title, language and tag names only.
+//! - Markdown exports and HTML renders include private snippets only while
+//!   unlocked.
+//! - Backups, the git mirror and data exports keep them encrypted.
+//! - Duplicate detection and secret scanning skip them.
+
+pub mod envelope;
+
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+
+use crate::{
+    auth::Principal,
+    database::models::{Snippet, SnippetFilter, SnippetForm},
+    error::AppError,
+    prelude::AppResult,
+    state::AppState,
+    storage::Storage,
+};
+
+use self::envelope::{DerivedKey, Salt};
+
+pub use envelope::is_encrypted;
+
+pub const VAULT_LOCK_CHANGED_EVENT: &str = "vault-lock-changed";
+
+const CODE_FIELD: &str = "code";
+const DESCRIPTION_FIELD: &str = "description";
+const AUTO_LOCK_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+const SECONDS_PER_MINUTE: u64 = 60;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UnlockVaultForm {
+    pub passphrase: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetSnippetPrivateForm {
+    pub id: String,
+    pub private: bool,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VaultLockStatus {
+    pub unlocked: bool,
+    /// Whether the user has encrypted any snippets yet. Until then, unlocking
+    /// accepts any passphrase, so the frontend should have it confirmed.
+    pub has_private_snippets: bool,
+    /// Minutes of inactivity before the vault locks itself; 0 if never.
+    pub auto_lock_minutes: u64,
+}
+
+pub fn is_private(snippet: &Snippet) -> bool {
+    is_encrypted(&snippet.code)
+}
+
+/// The private snippets the user wrote, which are the ones their passphrase
+/// has to open.
+pub async fn own_private_snippets(
+    storage: &dyn Storage,
+    principal: &Principal,
+) -> AppResult<Vec<Snippet>> {
+    let mut snippets = storage.list_snippets(None).await?;
+    snippets.retain(|snippet| is_private(snippet) && is_author(principal, snippet));
+    Ok(snippets)
+}
+
+struct Session {
+    passphrase: Vec<u8>,
+    /// Salt for fields encrypted in this session.
+    salt: Salt,
+    /// Keys by salt, as deriving one takes a noticeable moment.
+    keys: HashMap<Salt, DerivedKey>,
+    last_used: Instant,
+}
+
+impl Drop for Session {
+    fn drop(&mut self) {
+        for byte in self.passphrase.iter_mut() {
+            *byte = 0;
+        }
+    }
+}
+
+/// Whether `principal` wrote the snippet. Only the author's passphrase opens
+/// a private snippet, so nobody else may decrypt or encrypt it.
+pub fn is_author(principal: &Principal, snippet: &Snippet) -> bool {
+    snippet
+        .author_id
+        .as_deref()
+        .map_or(false, |author_id| principal.is(author_id))
+}
+
+/// Holds each user's passphrase while they have the vault unlocked. Users
+/// lock and unlock independently, and a session only ever opens snippets
+/// its user wrote.
+#[derive(Default)]
+pub struct VaultLock {
+    sessions: Mutex<HashMap<String, Session>>,
+}
+
+impl VaultLock {
+    pub fn is_unlocked(&self, principal: &Principal) -> bool {
+        self.sessions
+            .lock()
+            .unwrap()
+            .contains_key(&principal.user_id)
+    }
+
+    /// Unlocks with `passphrase`, checked against `sample`, a field of one of
+    /// the user's existing private snippets, if there is one.
+    pub fn unlock(
+        &self,
+        principal: &Principal,
+        passphrase: String,
+        sample: Option<&str>,
+    ) -> AppResult<()> {
+        let salt = match sample {
+            Some(sample) => envelope::salt_of(sample)?,
+            None => envelope::random_salt(),
+        };
+        let passphrase = passphrase.into_bytes();
+        let key = envelope::derive_key(&passphrase, &salt)?;
+        if let Some(sample) = sample {
+            envelope::open(&key, CODE_FIELD, sample)
+                .map_err(|_| AppError::EncryptionError("Incorrect passphrase".into()))?;
+        }
+
+        let mut keys = HashMap::new();
+        keys.insert(salt, key);
+        self.sessions.lock().unwrap().insert(
+            principal.user_id.clone(),
+            Session {
+                passphrase,
+                salt,
+                keys,
+                last_used: Instant::now(),
+            },
+        );
+        Ok(())
+    }
+
+    pub fn lock(&self, principal: &Principal) {
+        self.sessions.lock().unwrap().remove(&principal.user_id);
+    }
+
+    /// Locks the sessions unused for `idle`. Returns the users whose vault
+    /// it locked.
+    pub fn lock_if_idle(&self, idle: Duration) -> Vec<String> {
+        let mut sessions = self.sessions.lock().unwrap();
+        let idle_users: Vec<String> = sessions
+            .iter()
+            .filter(|(_, session)| session.last_used.elapsed() >= idle)
+            .map(|(user_id, _)| user_id.clone())
+            .collect();
+        for user_id in &idle_users {
+            sessions.remove(user_id);
+        }
+        idle_users
+    }
+
+    /// The key for `salt`, or the session's salt and key. Counts as activity
+    /// for the auto-lock.
+    fn key(&self, principal: &Principal, salt: Option<Salt>) -> AppResult<(Salt, DerivedKey)> {
+        let mut sessions = self.sessions.lock().unwrap();
+        let session = sessions
+            .get_mut(&principal.user_id)
+            .ok_or(AppError::VaultLocked)?;
+        session.last_used = Instant::now();
+
+        let salt = salt.unwrap_or(session.salt);
+        if let Some(key) = session.keys.get(&salt) {
+            return Ok((salt, *key));
+        }
+        let key = envelope::derive_key(&session.passphrase, &salt)?;
+        session.keys.insert(salt, key);
+        Ok((salt, key))
+    }
+
+    fn open(&self, principal: &Principal, field: &str, text: &str) -> AppResult<String> {
+        let (_, key) = self.key(principal, Some(envelope::salt_of(text)?))?;
+        envelope::open(&key, field, text)
+    }
+
+    fn seal(&self, principal: &Principal, field: &str, text: &str) -> AppResult<String> {
+        let (salt, key) = self.key(principal, None)?;
+        envelope::seal(&key, &salt, field, text)
+    }
+
+    /// The snippet decrypted, or unchanged if it isn't private or can't be
+    /// decrypted, because it is someone else's, the caller's vault is locked
+    /// or it was encrypted with another passphrase.
+    pub fn reveal(&self, principal: &Principal, snippet: Snippet) -> Snippet {
+        if !is_private(&snippet) || !is_author(principal, &snippet) || !self.is_unlocked(principal)
+        {
+            return snippet;
+        }
+        match self.try_reveal(principal, &snippet) {
+            Ok((code, description)) => Snippet {
+                code,
+                description,
+                ..snippet
+            },
+            Err(e) => {
+                warn!("Failed to decrypt snippet {}: {}", snippet.id, e);
+                snippet
+            }
+        }
+    }
+
+    fn try_reveal(&self, principal: &Principal, snippet: &Snippet) -> AppResult<(String, String)> {
+        let code = self.open(principal, CODE_FIELD, &snippet.code)?;
+        let description = if is_encrypted(&snippet.description) {
+            self.open(principal, DESCRIPTION_FIELD, &snippet.description)?
+        } else {
+            snippet.description.clone()
+        };
+        Ok((code, description))
+    }
+
+    pub fn reveal_all(&self, principal: &Principal, snippets: Vec<Snippet>) -> Vec<Snippet> {
+        snippets
+            .into_iter()
+            .map(|snippet| self.reveal(principal, snippet))
+            .collect()
+    }
+
+    /// The snippet decrypted, failing if it is private and can't be, for
+    /// operations that need its code.
+    pub fn require_revealed(&self, principal: &Principal, snippet: Snippet) -> AppResult<Snippet> {
+        if !is_private(&snippet) {
+            return Ok(snippet);
+        }
+        if !is_author(principal, &snippet) {
+            return Err(AppError::Forbidden(
+                "only the author of a private snippet can decrypt it".into(),
+            ));
+        }
+        if !self.is_unlocked(principal) {
+            return Err(AppError::VaultLocked);
+        }
+        let (code, description) = self.try_reveal(principal, &snippet)?;
+        Ok(Snippet {
+            code,
+            description,
+            ..snippet
+        })
+    }
+
+    /// Prepares a form for storage: encrypts its plaintext fields with the
+    /// caller's passphrase if the snippet is to be private. Fields still
+    /// encrypted, from a snippet fetched while locked, are kept as they are.
+    /// Only the author of `existing`, the snippet being saved over, may
+    /// encrypt anything into it.
+    pub fn seal_form(
+        &self,
+        principal: &Principal,
+        form: &mut SnippetForm,
+        private: bool,
+        existing: Option<&Snippet>,
+    ) -> AppResult<()> {
+        if !private {
+            if is_encrypted(&form.code) || is_encrypted(&form.description) {
+                return Err(AppError::VaultLocked);
+            }
+            return Ok(());
+        }
+
+        if is_encrypted(&form.code) && is_encrypted(&form.description) {
+            return Ok(());
+        }
+        if existing.map_or(false, |existing| !is_author(principal, existing)) {
+            return Err(AppError::Forbidden(
+                "only the author of a private snippet can change its code or description".into(),
+            ));
+        }
+        if !is_encrypted(&form.code) {
+            form.code = self.seal(principal, CODE_FIELD, &form.code)?;
+        }
+        if !is_encrypted(&form.description) {
+            form.description = self.seal(principal, DESCRIPTION_FIELD, &form.description)?;
+        }
+        Ok(())
+    }
+}
+
+/// Whether a snippet matches `filter` on the fields that stay readable.
+/// Private snippets match the search by title, language and tag names,
+/// ignoring case, and never match filters on their code or description.
+/// Public snippets are left to storage.
+pub fn matches_readable_fields(snippet: &Snippet, filter: &SnippetFilter) -> bool {
+    if !is_private(snippet) {
+        return true;
+    }
+    if filter.code.is_some() || filter.description.is_some() {
+        return false;
+    }
+    let search = match &filter.search {
+        Some(search) => search.to_lowercase(),
+        None => return true,
+    };
+    let tag_names = snippet.tags.iter().flatten().map(|tag| tag.name.as_str());
+    [snippet.title.as_str(), snippet.language.as_str()]
+        .into_iter()
+        .chain(tag_names)
+        .any(|field| field.to_lowercase().contains(&search))
+}
+
+/// The snippets matching `filter`. Storage compares the search with the
+/// stored ciphertext of private snippets, so they are fetched without it and
+/// matched on their readable fields instead.
+pub async fn list_matching(
+    storage: &dyn Storage,
+    filter: &SnippetFilter,
+) -> AppResult<Vec<Snippet>> {
+    let snippets = if filter.search.is_some() {
+        let public_matches: HashSet<String> = storage
+            .list_snippets(Some(filter.clone()))
+            .await?
+            .into_iter()
+            .filter(|snippet| !is_private(snippet))
+            .map(|snippet| snippet.id)
+            .collect();
+        let unsearched = SnippetFilter {
+            search: None,
+            ..filter.clone()
+        };
+        storage
+            .list_snippets(Some(unsearched))
+            .await?
+            .into_iter()
+            .filter(|snippet| is_private(snippet) || public_matches.contains(&snippet.id))
+            .collect()
+    } else {
+        storage.list_snippets(Some(filter.clone())).await?
+    };
+    Ok(snippets
+        .into_iter()
+        .filter(|snippet| matches_readable_fields(snippet, filter))
+        .collect())
+}
+
+/// Whether a saved form should be private: as requested, or else as the
+/// snippet already is.
+pub fn wants_private(form: &SnippetForm, existing: Option<&Snippet>) -> bool {
+    form.is_private
+        .unwrap_or_else(|| existing.map_or(false, is_private))
+}
+
+/// Payload of [`VAULT_LOCK_CHANGED_EVENT`].
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VaultLockChange {
+    pub user_id: String,
+    pub unlocked: bool,
+}
+
+pub fn emit_lock_changed(app: &AppHandle, user_id: &str, unlocked: bool) {
+    let change = VaultLockChange {
+        user_id: user_id.to_owned(),
+        unlocked,
+    };
+    if let Err(e) = app.emit_all(VAULT_LOCK_CHANGED_EVENT, change) {
+        warn!("Failed to emit the vault lock state: {}", e);
+    }
+}
+
+/// Locks each user's vault after the configured minutes without them
+/// encrypting or decrypting anything. Does nothing when auto-lock is
+/// disabled.
+pub fn spawn_auto_lock(app: AppHandle) {
+    let minutes = app.state::<AppState>().config.encryption.auto_lock_minutes;
+    if minutes == 0 {
+        return;
+    }
+    let idle = Duration::from_secs(minutes * SECONDS_PER_MINUTE);
+
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(AUTO_LOCK_CHECK_INTERVAL).await;
+            for user_id in app.state::<AppState>().vault_lock.lock_if_idle(idle) {
+                info!(
+                    "Locked the vault of user {} after {} idle minutes",
+                    user_id, minutes
+                );
+                emit_lock_changed(&app, &user_id, false);
+            }
+        }
+    });
+}
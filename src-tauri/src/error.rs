@@ -2,19 +2,41 @@
 pub enum AppError {
     #[error("Bcrypt error: {0}")]
     BcryptError(#[from] bcrypt::BcryptError),
+    #[error("Encryption error: {0}")]
+    EncryptionError(String),
     #[error("Figment error: {0}")]
     FigmentError(#[from] figment::Error),
+    #[error("Git error: {0}")]
+    GitError(String),
+    #[error("Highlighting error: {0}")]
+    HighlightError(#[from] syntect::Error),
     #[error("IO error: {0}")]
     IoError(#[from] std::io::Error),
     #[error("JWT error: {0}")]
     JwtError(#[from] jsonwebtoken::errors::Error),
+    #[error("Prisma client error: {0}")]
+    PrismaClientError(#[from] prisma::NewClientError),
     #[error("Prisma query error: {0}")]
     PrismaQueryError(#[from] prisma::QueryError),
     #[error("Serde error: {0}")]
     SerdeError(#[from] serde_json::Error),
     #[error("Tauri error: {0}")]
     TauriError(#[from] tauri::Error),
+    #[error("XML error: {0}")]
+    XmlError(#[from] quick_xml::Error),
 
+    #[error("Database unavailable")]
+    DatabaseUnavailable,
+    #[error("Vault is locked")]
+    VaultLocked,
+    #[error("Unauthorized: {0}")]
+    Unauthorized(String),
+    #[error("Forbidden: {0}")]
+    Forbidden(String),
+    #[error("{0} not found")]
+    NotFound(String),
+    #[error("Possible secrets found: {0}")]
+    SecretsDetected(String),
     #[error("Error: {0}")]
     Other(String),
 }
@@ -25,6 +47,47 @@ impl From<&str> for AppError {
     }
 }
 
+/// Prisma error codes for a database server that can't be reached, timed
+/// out or dropped the connection.
+const CONNECTION_ERROR_CODES: &[&str] = &["P1001", "P1002", "P1008", "P1017", "P2024"];
+
+/// How the MongoDB driver describes a lost server in errors Prisma doesn't
+/// classify.
+const CONNECTION_ERROR_MESSAGES: &[&str] = &["Server selection timeout", "I/O error"];
+
+impl AppError {
+    /// Whether the database couldn't be reached, as opposed to rejecting the
+    /// query.
+    pub fn is_connection_error(&self) -> bool {
+        match self {
+            AppError::DatabaseUnavailable => true,
+            AppError::PrismaQueryError(prisma::QueryError::Execute(error)) => {
+                match error.as_known() {
+                    Some(known) => CONNECTION_ERROR_CODES.contains(&&*known.error_code),
+                    None => CONNECTION_ERROR_MESSAGES
+                        .iter()
+                        .any(|message| error.message().contains(message)),
+                }
+            }
+            _ => false,
+        }
+    }
+
+    /// The HTTP status the local API answers this error with.
+    pub fn http_status(&self) -> u16 {
+        match self {
+            AppError::Unauthorized(_) | AppError::JwtError(_) => 401,
+            AppError::Forbidden(_) => 403,
+            AppError::NotFound(_) => 404,
+            AppError::VaultLocked => 423,
+            AppError::SecretsDetected(_) => 422,
+            AppError::DatabaseUnavailable | AppError::PrismaClientError(_) => 503,
+            AppError::SerdeError(_) | AppError::Other(_) => 400,
+            _ => 500,
+        }
+    }
+}
+
 impl serde::Serialize for AppError {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
@@ -0,0 +1,302 @@
+//! Formatters that ship with the app, so the common data formats can be
+//! tidied without anything installed. The JSON, TOML and YAML formatters only
+//! change whitespace, keeping key order and comments.
+
+use quick_xml::{events::Event, Reader, Writer};
+use sqlformat::{FormatOptions, QueryParams};
+
+use crate::{error::AppError, prelude::AppResult};
+
+const INDENT: &str = "  ";
+/// Continuation lines of multi-line TOML arrays.
+const TOML_ARRAY_INDENT: &str = "    ";
+
+/// Formats `code` if `language` has a built-in formatter.
+pub fn format(code: &str, language: &str) -> AppResult<Option<String>> {
+    let formatted = match language {
+        "json" => format_json(code)?,
+        "toml" => format_toml(code)?,
+        "yaml" => format_yaml(code),
+        "sql" => format_sql(code),
+        "xml" => format_xml(code)?,
+        _ => return Ok(None),
+    };
+    Ok(Some(formatted))
+}
+
+/// Re-indents JSON by two spaces, keeping keys in their original order.
+fn format_json(code: &str) -> AppResult<String> {
+    serde_json::from_str::<serde_json::Value>(code)
+        .map_err(|e| AppError::Other(format!("Invalid JSON: {}", e)))?;
+
+    let mut output = String::with_capacity(code.len());
+    let mut depth = 0;
+    let mut chars = code.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                output.push(c);
+                while let Some(c) = chars.next() {
+                    output.push(c);
+                    match c {
+                        '\\' => output.extend(chars.next()),
+                        '"' => break,
+                        _ => {}
+                    }
+                }
+            }
+            '{' | '[' => {
+                output.push(c);
+                while chars.peek().map_or(false, |c| c.is_whitespace()) {
+                    chars.next();
+                }
+                if matches!(chars.peek(), Some('}') | Some(']')) {
+                    output.extend(chars.next());
+                } else {
+                    depth += 1;
+                    push_line(&mut output, depth);
+                }
+            }
+            '}' | ']' => {
+                depth -= 1;
+                push_line(&mut output, depth);
+                output.push(c);
+            }
+            ',' => {
+                output.push(c);
+                push_line(&mut output, depth);
+            }
+            ':' => output.push_str(": "),
+            c if c.is_whitespace() => {}
+            c => output.push(c),
+        }
+    }
+
+    output.push('\n');
+    Ok(output)
+}
+
+fn push_line(output: &mut String, depth: usize) {
+    output.push('\n');
+    output.push_str(&INDENT.repeat(depth));
+}
+
+/// Normalizes spacing in TOML: `key = value`, no indentation outside
+/// arrays, a blank line before each table and no runs of blank lines.
+fn format_toml(code: &str) -> AppResult<String> {
+    code.parse::<toml::Table>()
+        .map_err(|e| AppError::Other(format!("Invalid TOML: {}", e)))?;
+
+    let mut lines: Vec<String> = vec![];
+    let mut array_depth = 0usize;
+    let mut multiline_string: Option<&str> = None;
+
+    for line in code.lines() {
+        if let Some(delimiter) = multiline_string {
+            lines.push(line.to_string());
+            if line.matches(delimiter).count() % 2 == 1 {
+                multiline_string = None;
+            }
+            continue;
+        }
+
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            if lines.last().map_or(false, |last| !last.is_empty()) {
+                lines.push(String::new());
+            }
+            continue;
+        }
+
+        if array_depth > 0 {
+            let closes = trimmed.starts_with(']');
+            let depth = if closes { array_depth - 1 } else { array_depth };
+            lines.push(format!("{}{}", TOML_ARRAY_INDENT.repeat(depth), trimmed));
+        } else if trimmed.starts_with('[') {
+            if lines.last().map_or(false, |last| !last.is_empty()) {
+                lines.push(String::new());
+            }
+            lines.push(trimmed.to_string());
+            continue;
+        } else if trimmed.starts_with('#') {
+            lines.push(trimmed.to_string());
+            continue;
+        } else {
+            lines.push(match split_toml_key(trimmed) {
+                Some((key, value)) => format!("{} = {}", key.trim(), value.trim()),
+                None => trimmed.to_string(),
+            });
+        }
+
+        array_depth = (array_depth as isize + toml_bracket_balance(trimmed)).max(0) as usize;
+        for delimiter in ["\"\"\"", "'''"] {
+            if trimmed.matches(delimiter).count() % 2 == 1 {
+                multiline_string = Some(delimiter);
+            }
+        }
+    }
+
+    while lines.last().map_or(false, String::is_empty) {
+        lines.pop();
+    }
+    Ok(lines.join("\n") + "\n")
+}
+
+/// Splits a key/value line at the first `=` outside a quoted key.
+fn split_toml_key(line: &str) -> Option<(&str, &str)> {
+    let mut quote: Option<char> = None;
+    for (index, c) in line.char_indices() {
+        match (quote, c) {
+            (None, '"') | (None, '\'') => quote = Some(c),
+            (Some(open), c) if c == open => quote = None,
+            (None, '=') => return Some((&line[..index], &line[index + 1..])),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Opened minus closed brackets outside strings and comments.
+fn toml_bracket_balance(line: &str) -> isize {
+    let mut balance = 0;
+    let mut quote: Option<char> = None;
+    let mut escaped = false;
+    for c in line.chars() {
+        match (quote, c) {
+            (Some('"'), '\\') if !escaped => {
+                escaped = true;
+                continue;
+            }
+            (Some(open), c) if c == open && !escaped => quote = None,
+            (None, '"') | (None, '\'') => quote = Some(c),
+            (None, '#') => break,
+            (None, '[') => balance += 1,
+            (None, ']') => balance -= 1,
+            _ => {}
+        }
+        escaped = false;
+    }
+    balance
+}
+
+/// Re-indents block YAML by two spaces per level and tidies blank lines.
+/// Block scalars (`|`, `>`) keep their content's relative indentation.
+fn format_yaml(code: &str) -> String {
+    let mut lines: Vec<String> = vec![];
+    // Original and new indentation of each open level.
+    let mut levels: Vec<(usize, usize)> = vec![(0, 0)];
+    // Original indentation of the line that opened a block scalar, the new
+    // indentation of its content and the original one of its first line.
+    let mut block: Option<(usize, usize, Option<usize>)> = None;
+
+    for line in code.lines() {
+        let line = line.trim_end();
+        let indent = line.len() - line.trim_start().len();
+
+        if let Some((parent, new_indent, first)) = block {
+            if line.is_empty() {
+                lines.push(String::new());
+                continue;
+            }
+            if indent > parent {
+                let first = first.unwrap_or(indent);
+                block = Some((parent, new_indent, Some(first)));
+                let extra = indent.saturating_sub(first);
+                lines.push(format!(
+                    "{}{}",
+                    " ".repeat(new_indent + extra),
+                    line.trim_start()
+                ));
+                continue;
+            }
+            block = None;
+        }
+
+        if line.is_empty() {
+            if lines.last().map_or(false, |last| !last.is_empty()) {
+                lines.push(String::new());
+            }
+            continue;
+        }
+
+        let content = line.trim_start();
+        if content == "---" || content == "..." {
+            levels.truncate(1);
+            lines.push(content.to_string());
+            continue;
+        }
+
+        while levels.len() > 1 && levels.last().map_or(false, |&(orig, _)| orig > indent) {
+            levels.pop();
+        }
+        let &(orig, new) = levels.last().unwrap_or(&(0, 0));
+        let new_indent = if indent > orig {
+            new + INDENT.len()
+        } else {
+            new
+        };
+        // Comments don't open levels of their own.
+        if indent > orig && !content.starts_with('#') {
+            levels.push((indent, new_indent));
+        }
+
+        let content = normalize_sequence_item(content);
+        if opens_block_scalar(&content) {
+            // Content goes deeper than the key, which for `- key: |` sits
+            // after the dash.
+            let key_indent = new_indent + if content.starts_with("- ") { 2 } else { 0 };
+            block = Some((indent, key_indent + INDENT.len(), None));
+        }
+        lines.push(format!("{}{}", " ".repeat(new_indent), content));
+    }
+
+    while lines.last().map_or(false, String::is_empty) {
+        lines.pop();
+    }
+    lines.join("\n") + "\n"
+}
+
+/// `-   item` becomes `- item`, so that keys continuing the item line up
+/// with it at two spaces.
+fn normalize_sequence_item(content: &str) -> String {
+    match content.strip_prefix('-') {
+        Some(rest) if rest.starts_with(' ') => format!("- {}", rest.trim_start()),
+        _ => content.to_string(),
+    }
+}
+
+fn opens_block_scalar(content: &str) -> bool {
+    let value = content.rsplit(": ").next().unwrap_or(content);
+    let value = value.strip_prefix("- ").unwrap_or(value).trim();
+    let mut chars = value.chars();
+    matches!(chars.next(), Some('|') | Some('>'))
+        && chars.all(|c| c == '-' || c == '+' || c.is_ascii_digit())
+}
+
+fn format_sql(code: &str) -> String {
+    let options = FormatOptions {
+        uppercase: true,
+        lines_between_queries: 2,
+        ..FormatOptions::default()
+    };
+    sqlformat::format(code, &QueryParams::None, options) + "\n"
+}
+
+fn format_xml(code: &str) -> AppResult<String> {
+    let mut reader = Reader::from_str(code);
+    reader.trim_text(true);
+    let mut writer = Writer::new_with_indent(Vec::new(), b' ', INDENT.len());
+
+    loop {
+        match reader.read_event()? {
+            Event::Eof => break,
+            event => writer.write_event(event)?,
+        }
+    }
+
+    let mut formatted = String::from_utf8(writer.into_inner())
+        .map_err(|e| AppError::Other(format!("Invalid XML: {}", e)))?;
+    formatted.push('\n');
+    Ok(formatted)
+}
@@ -0,0 +1,105 @@
+//! Formatters installed on the machine, run with the code on stdin. They
+//! are preferred over the built-in ones, and skipped when not installed.
+
+use std::{io::ErrorKind, process::Stdio, time::Duration};
+
+use tokio::{io::AsyncWriteExt, process::Command};
+
+use crate::{error::AppError, languages::registry, prelude::AppResult};
+
+pub struct ExternalFormatter {
+    pub program: &'static str,
+    /// `{file}` is replaced with a file name carrying the language's
+    /// extension, for formatters that pick a parser by it.
+    args: &'static [&'static str],
+    languages: &'static [&'static str],
+}
+
+const FORMATTERS: &[ExternalFormatter] = &[
+    ExternalFormatter {
+        program: "rustfmt",
+        args: &["--edition", "2021"],
+        languages: &["rust"],
+    },
+    ExternalFormatter {
+        program: "black",
+        args: &["--quiet", "-"],
+        languages: &["python"],
+    },
+    ExternalFormatter {
+        program: "prettier",
+        args: &["--stdin-filepath", "{file}"],
+        languages: &[
+            "css",
+            "html",
+            "javascript",
+            "json",
+            "markdown",
+            "scss",
+            "typescript",
+            "yaml",
+        ],
+    },
+];
+
+pub fn formatter_for(language: &str) -> Option<&'static ExternalFormatter> {
+    FORMATTERS
+        .iter()
+        .find(|formatter| formatter.languages.contains(&language))
+}
+
+impl ExternalFormatter {
+    /// The formatted code, or `None` if the formatter isn't installed.
+    pub async fn run(
+        &self,
+        code: &str,
+        language: &str,
+        timeout: Duration,
+    ) -> AppResult<Option<String>> {
+        let file = format!("snippet.{}", registry::extension_for(language));
+        let args = self.args.iter().map(|arg| arg.replace("{file}", &file));
+
+        let mut child = match Command::new(self.program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+        {
+            Ok(child) => child,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+
+        // Written from a task so a formatter that streams its output can't
+        // block on a full stdout pipe while we are still writing.
+        if let Some(mut stdin) = child.stdin.take() {
+            let code = code.to_string();
+            tokio::spawn(async move {
+                let _ = stdin.write_all(code.as_bytes()).await;
+            });
+        }
+
+        let output = tokio::time::timeout(timeout, child.wait_with_output())
+            .await
+            .map_err(|_| {
+                AppError::Other(format!(
+                    "{} did not finish within {}s",
+                    self.program,
+                    timeout.as_secs()
+                ))
+            })??;
+
+        if !output.status.success() {
+            return Err(AppError::Other(format!(
+                "{} failed: {}",
+                self.program,
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+        String::from_utf8(output.stdout)
+            .map(Some)
+            .map_err(|_| AppError::Other(format!("{} returned invalid UTF-8", self.program)))
+    }
+}
@@ -0,0 +1,88 @@
+//! Reformatting snippet code according to its language, with a locally
+//! installed formatter when there is one and the built-in ones otherwise.
+
+pub mod builtin;
+pub mod external;
+
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use similar::TextDiff;
+
+use crate::{config::FormatterConfig, error::AppError, prelude::AppResult, snippets::SavedSnippet};
+
+const BUILTIN_FORMATTER: &str = "built-in";
+const DIFF_CONTEXT_LINES: usize = 3;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FormatSnippetForm {
+    pub id: String,
+    /// Saves the formatted code. Otherwise the snippet is left unchanged.
+    #[serde(default)]
+    pub save: bool,
+    /// Whether installed formatters may be used. Defaults to the setting.
+    pub use_external: Option<bool>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FormatReport {
+    pub language: String,
+    /// The program that formatted the code, or `built-in`.
+    pub formatter: String,
+    pub formatted: String,
+    /// Unified diff from the stored code to the formatted code; empty when
+    /// nothing changed.
+    pub diff: String,
+    pub changed: bool,
+    /// The updated snippet, when saving was requested and the code changed.
+    pub saved: Option<SavedSnippet>,
+}
+
+pub struct Formatted {
+    pub code: String,
+    pub formatter: &'static str,
+}
+
+/// Formats `code` as `language`, a registry id.
+pub async fn format_code(
+    code: &str,
+    language: &str,
+    use_external: bool,
+    config: &FormatterConfig,
+) -> AppResult<Formatted> {
+    if use_external {
+        if let Some(formatter) = external::formatter_for(language) {
+            let timeout = Duration::from_secs(config.timeout_secs);
+            if let Some(formatted) = formatter.run(code, language, timeout).await? {
+                return Ok(Formatted {
+                    code: formatted,
+                    formatter: formatter.program,
+                });
+            }
+        }
+    }
+
+    match builtin::format(code, language)? {
+        Some(formatted) => Ok(Formatted {
+            code: formatted,
+            formatter: BUILTIN_FORMATTER,
+        }),
+        None => Err(AppError::Other(format!(
+            "No formatter is available for '{}'",
+            language
+        ))),
+    }
+}
+
+pub fn unified_diff(original: &str, formatted: &str) -> String {
+    if original == formatted {
+        return String::new();
+    }
+    TextDiff::from_lines(original, formatted)
+        .unified_diff()
+        .context_radius(DIFF_CONTEXT_LINES)
+        .header("original", "formatted")
+        .to_string()
+}
@@ -0,0 +1,175 @@
+//! A thin wrapper around the `git` command line, so remotes, credentials and
+//! hooks behave exactly as they do for the user's own checkouts.
+
+use std::path::{Path, PathBuf};
+
+use log::debug;
+use tokio::process::Command;
+
+use crate::{error::AppError, prelude::AppResult};
+
+pub const REMOTE_NAME: &str = "origin";
+
+pub struct CommitAuthor {
+    pub name: String,
+    pub email: String,
+}
+
+pub enum MergeOutcome {
+    UpToDate,
+    Merged,
+    /// The merge was aborted; these paths conflicted.
+    Conflicts(Vec<String>),
+}
+
+pub struct GitRepository {
+    dir: PathBuf,
+}
+
+impl GitRepository {
+    /// Opens the working tree at `dir`, initializing a repository on `branch`
+    /// if there is none yet.
+    pub async fn open_or_init(dir: PathBuf, branch: &str) -> AppResult<Self> {
+        tokio::fs::create_dir_all(&dir).await?;
+        let repository = Self { dir };
+
+        if !repository.dir.join(".git").exists() {
+            repository.run(&["init", "--quiet"]).await?;
+            repository
+                .run(&["symbolic-ref", "HEAD", &format!("refs/heads/{}", branch)])
+                .await?;
+        }
+        Ok(repository)
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Directory for files git neither tracks nor shows as untracked.
+    pub fn private_dir(&self) -> PathBuf {
+        self.dir.join(".git")
+    }
+
+    /// Points `origin` at `url`, adding it if needed.
+    pub async fn set_remote(&self, url: &str) -> AppResult<()> {
+        let remotes = self.run(&["remote"]).await?;
+        if remotes.lines().any(|remote| remote == REMOTE_NAME) {
+            self.run(&["remote", "set-url", REMOTE_NAME, url]).await?;
+        } else {
+            self.run(&["remote", "add", REMOTE_NAME, url]).await?;
+        }
+        Ok(())
+    }
+
+    /// Stages everything under `path` and commits it. Returns `false` when
+    /// there was nothing to commit.
+    pub async fn commit_all(
+        &self,
+        path: &str,
+        message: &str,
+        author: &CommitAuthor,
+    ) -> AppResult<bool> {
+        self.run(&["add", "--all", "--", path]).await?;
+        if self.has_no_staged_changes().await? {
+            return Ok(false);
+        }
+
+        self.run_as(&["commit", "--quiet", "--message", message], Some(author))
+            .await?;
+        Ok(true)
+    }
+
+    /// Fetches `branch` from `origin` and merges it into the checked out
+    /// branch. Conflicting merges are aborted so the tree stays usable.
+    pub async fn pull(&self, branch: &str, author: &CommitAuthor) -> AppResult<MergeOutcome> {
+        self.run(&["fetch", "--quiet", REMOTE_NAME, branch]).await?;
+
+        let fetched = self.run(&["rev-parse", "FETCH_HEAD"]).await?;
+        if self
+            .run(&["rev-parse", "--verify", "--quiet", "HEAD"])
+            .await
+            .is_err()
+        {
+            // Nothing committed locally yet, so simply adopt the remote branch.
+            self.run(&["reset", "--hard", "--quiet", fetched.trim()])
+                .await?;
+            return Ok(MergeOutcome::Merged);
+        }
+        if self
+            .run(&["merge-base", "--is-ancestor", fetched.trim(), "HEAD"])
+            .await
+            .is_ok()
+        {
+            return Ok(MergeOutcome::UpToDate);
+        }
+
+        let merge = self
+            .run_as(
+                &["merge", "--no-edit", "--quiet", "FETCH_HEAD"],
+                Some(author),
+            )
+            .await;
+        if merge.is_ok() {
+            return Ok(MergeOutcome::Merged);
+        }
+
+        let conflicts = self
+            .run(&["diff", "--name-only", "--diff-filter=U"])
+            .await?
+            .lines()
+            .map(str::to_owned)
+            .collect::<Vec<_>>();
+        self.run(&["merge", "--abort"]).await?;
+        if conflicts.is_empty() {
+            // Failed for another reason than conflicting changes.
+            merge?;
+        }
+        Ok(MergeOutcome::Conflicts(conflicts))
+    }
+
+    pub async fn push(&self, branch: &str) -> AppResult<()> {
+        self.run(&["push", "--quiet", REMOTE_NAME, &format!("HEAD:{}", branch)])
+            .await?;
+        Ok(())
+    }
+
+    async fn has_no_staged_changes(&self) -> AppResult<bool> {
+        let status = Command::new("git")
+            .args(["diff", "--cached", "--quiet"])
+            .current_dir(&self.dir)
+            .status()
+            .await?;
+        Ok(status.success())
+    }
+
+    async fn run(&self, args: &[&str]) -> AppResult<String> {
+        self.run_as(args, None).await
+    }
+
+    async fn run_as(&self, args: &[&str], author: Option<&CommitAuthor>) -> AppResult<String> {
+        debug!("Running git {}", args.join(" "));
+        let mut command = Command::new("git");
+        command
+            .args(args)
+            .current_dir(&self.dir)
+            .env("GIT_TERMINAL_PROMPT", "0");
+        if let Some(author) = author {
+            command
+                .env("GIT_AUTHOR_NAME", &author.name)
+                .env("GIT_AUTHOR_EMAIL", &author.email)
+                .env("GIT_COMMITTER_NAME", &author.name)
+                .env("GIT_COMMITTER_EMAIL", &author.email);
+        }
+
+        let output = command.output().await?;
+        if !output.status.success() {
+            return Err(AppError::GitError(format!(
+                "git {} failed: {}",
+                args[0],
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+}
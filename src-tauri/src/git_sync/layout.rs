@@ -0,0 +1,162 @@
+//! How snippets are laid out in the repository: each snippet is a code file
+//! named after its id with the extension of its language, next to a
+//! `<id>.meta.json` sidecar holding everything else.
+
+use std::{ffi::OsStr, io::ErrorKind, path::Path};
+
+use chrono::{DateTime, FixedOffset};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    database::models::Snippet, error::AppError, languages::registry::extension_for,
+    prelude::AppResult,
+};
+
+pub const SNIPPETS_DIR: &str = "snippets";
+const METADATA_SUFFIX: &str = ".meta.json";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SnippetMetadata {
+    pub id: String,
+    pub title: String,
+    pub description: String,
+    pub language: String,
+    /// Name of the code file, relative to the sidecar.
+    pub file: String,
+    /// Tag names, for readers; `tag_ids` is what import goes by first.
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub tag_ids: Vec<String>,
+    #[serde(default)]
+    pub is_favorite: bool,
+    pub author_id: Option<String>,
+    /// Absent for snippets of the default workspace.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub workspace_id: Option<String>,
+    pub created_at: DateTime<FixedOffset>,
+    /// Version of the vault record this file was written from.
+    pub updated_at: DateTime<FixedOffset>,
+}
+
+pub struct SnippetFile {
+    pub metadata: SnippetMetadata,
+    pub code: String,
+}
+
+impl From<&Snippet> for SnippetMetadata {
+    fn from(snippet: &Snippet) -> Self {
+        Self {
+            id: snippet.id.clone(),
+            title: snippet.title.clone(),
+            description: snippet.description.clone(),
+            language: snippet.language.clone(),
+            file: format!("{}.{}", snippet.id, extension_for(&snippet.language)),
+            tags: snippet
+                .tags
+                .iter()
+                .flatten()
+                .map(|tag| tag.name.clone())
+                .collect(),
+            tag_ids: snippet.tag_ids.clone(),
+            is_favorite: snippet
+                .state
+                .as_ref()
+                .map_or(false, |state| state.is_favorite),
+            author_id: snippet.author_id.clone(),
+            workspace_id: snippet.workspace_id.clone(),
+            created_at: snippet.created_at,
+            updated_at: snippet.updated_at,
+        }
+    }
+}
+
+/// Writes the snippet's code and sidecar into `dir`, removing its previous
+/// code file if the language, and so the extension, changed.
+pub fn write_snippet(dir: &Path, snippet: &Snippet) -> AppResult<()> {
+    std::fs::create_dir_all(dir)?;
+
+    let metadata = SnippetMetadata::from(snippet);
+    if let Some(previous) = read_metadata(dir, &snippet.id)? {
+        if previous.file != metadata.file {
+            remove_file(&dir.join(checked_file_name(&previous.file)?))?;
+        }
+    }
+
+    std::fs::write(dir.join(&metadata.file), &snippet.code)?;
+    let mut sidecar = serde_json::to_vec_pretty(&metadata)?;
+    sidecar.push(b'\n');
+    std::fs::write(dir.join(metadata_file_name(&snippet.id)), sidecar)?;
+    Ok(())
+}
+
+pub fn remove_snippet(dir: &Path, id: &str) -> AppResult<()> {
+    if let Some(metadata) = read_metadata(dir, id)? {
+        remove_file(&dir.join(checked_file_name(&metadata.file)?))?;
+    }
+    remove_file(&dir.join(metadata_file_name(id)))
+}
+
+/// Ids of the snippets in `dir`, going by the sidecars present.
+pub fn snippet_ids(dir: &Path) -> AppResult<Vec<String>> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(vec![]),
+        Err(e) => return Err(e.into()),
+    };
+
+    let mut ids = vec![];
+    for entry in entries {
+        let name = entry?.file_name();
+        if let Some(id) = name
+            .to_str()
+            .and_then(|name| name.strip_suffix(METADATA_SUFFIX))
+        {
+            ids.push(id.to_owned());
+        }
+    }
+    Ok(ids)
+}
+
+pub fn read_snippets(dir: &Path) -> AppResult<Vec<SnippetFile>> {
+    let mut snippets = vec![];
+    for id in snippet_ids(dir)? {
+        if let Some(metadata) = read_metadata(dir, &id)? {
+            let code = std::fs::read_to_string(dir.join(checked_file_name(&metadata.file)?))?;
+            snippets.push(SnippetFile { metadata, code });
+        }
+    }
+    Ok(snippets)
+}
+
+fn read_metadata(dir: &Path, id: &str) -> AppResult<Option<SnippetMetadata>> {
+    match std::fs::read(dir.join(metadata_file_name(id))) {
+        Ok(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn metadata_file_name(id: &str) -> String {
+    format!("{}{}", id, METADATA_SUFFIX)
+}
+
+/// Sidecars come from the repository, so their `file` must not point outside
+/// the snippets directory.
+fn checked_file_name(name: &str) -> AppResult<&str> {
+    if name.is_empty() || Path::new(name).file_name() != Some(OsStr::new(name)) {
+        return Err(AppError::GitError(format!(
+            "Invalid snippet file name in the repository: {}",
+            name
+        )));
+    }
+    Ok(name)
+}
+
+fn remove_file(path: &Path) -> AppResult<()> {
+    match std::fs::remove_file(path) {
+        Err(e) if e.kind() != ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,524 @@
+pub mod git;
+pub mod layout;
+
+use std::{
+    collections::{HashMap, HashSet},
+    io::ErrorKind,
+    path::PathBuf,
+    sync::Arc,
+};
+
+use chrono::{DateTime, FixedOffset, Utc};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tauri::{AppHandle, Manager};
+use tokio::sync::Mutex;
+
+use self::{
+    git::{CommitAuthor, GitRepository, MergeOutcome},
+    layout::{SnippetFile, SNIPPETS_DIR},
+};
+use crate::{
+    auth::Principal,
+    config::GitSyncConfig,
+    database::models::{Snippet, SnippetForm, SnippetState, Tag, VaultSnapshot},
+    encryption,
+    error::AppError,
+    prelude::AppResult,
+    secrets::SnippetSecrets,
+    snippets::SnippetSaver,
+    state::AppState,
+    storage::{new_object_id, Storage},
+};
+
+const DEFAULT_REPOSITORY_DIR: &str = "snippet-repository";
+const SYNC_STATE_FILE: &str = "snippet-vault-sync.json";
+
+/// Mirrors the vault into a git working tree, one file per snippet, and
+/// brings changes made in that tree back into the vault. The mirror spans
+/// every workspace and ignores sharing, so only callers allowed to manage the
+/// whole vault may export, import, pull or push it.
+pub struct GitSync {
+    repository: GitRepository,
+    branch: String,
+    has_remote: bool,
+    /// Serializes everything touching the working tree.
+    lock: Mutex<()>,
+}
+
+/// The version of each snippet as of the last time the vault and the working
+/// tree agreed on it. This is the base that tells one-sided changes apart from
+/// conflicting ones.
+#[derive(Default, Serialize, Deserialize)]
+struct SyncState {
+    snippets: HashMap<String, DateTime<FixedOffset>>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncConflict {
+    pub snippet_id: String,
+    pub title: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportReport {
+    pub written: usize,
+    pub removed: usize,
+    pub committed: bool,
+}
+
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportReport {
+    pub created: usize,
+    pub updated: usize,
+    pub deleted: usize,
+    /// Snippets left untouched because both sides changed them.
+    pub conflicts: Vec<SyncConflict>,
+    /// Created or updated snippets whose code may contain secrets.
+    pub secrets: Vec<SnippetSecrets>,
+}
+
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PullReport {
+    pub merged: bool,
+    /// Paths that conflicted while merging; the merge was aborted and
+    /// nothing was imported.
+    pub merge_conflicts: Vec<String>,
+    pub import: ImportReport,
+}
+
+impl From<&Principal> for CommitAuthor {
+    fn from(principal: &Principal) -> Self {
+        Self {
+            name: principal.email.clone(),
+            email: principal.email.clone(),
+        }
+    }
+}
+
+impl GitSync {
+    /// Opens the configured repository, or returns `None` when git sync is
+    /// disabled.
+    pub async fn open(app: &AppHandle, config: &GitSyncConfig) -> AppResult<Option<Self>> {
+        if !config.enabled {
+            return Ok(None);
+        }
+
+        let dir = config
+            .repository
+            .clone()
+            .or_else(|| {
+                app.path_resolver()
+                    .app_data_dir()
+                    .map(|dir| dir.join(DEFAULT_REPOSITORY_DIR))
+            })
+            .ok_or("Could not resolve the snippet repository directory")?;
+        let repository = GitRepository::open_or_init(dir, &config.branch).await?;
+        if let Some(remote) = &config.remote {
+            repository.set_remote(remote).await?;
+        }
+
+        info!(
+            "Syncing snippets with the git repository at {}",
+            repository.dir().display()
+        );
+        Ok(Some(Self {
+            repository,
+            branch: config.branch.clone(),
+            has_remote: config.remote.is_some(),
+            lock: Mutex::new(()),
+        }))
+    }
+
+    fn snippets_dir(&self) -> PathBuf {
+        self.repository.dir().join(SNIPPETS_DIR)
+    }
+
+    async fn load_state(&self) -> AppResult<SyncState> {
+        let path = self.repository.private_dir().join(SYNC_STATE_FILE);
+        match tokio::fs::read(&path).await {
+            Ok(bytes) => Ok(serde_json::from_slice(&bytes)?),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(SyncState::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn save_state(&self, state: &SyncState) -> AppResult<()> {
+        let path = self.repository.private_dir().join(SYNC_STATE_FILE);
+        tokio::fs::write(&path, serde_json::to_vec_pretty(state)?).await?;
+        Ok(())
+    }
+
+    pub async fn snippet_saved(
+        &self,
+        snippet: &Snippet,
+        created: bool,
+        author: &CommitAuthor,
+    ) -> AppResult<()> {
+        let _guard = self.lock.lock().await;
+
+        layout::write_snippet(&self.snippets_dir(), snippet)?;
+        let mut state = self.load_state().await?;
+        state
+            .snippets
+            .insert(snippet.id.clone(), snippet.updated_at);
+        self.save_state(&state).await?;
+
+        let verb = if created { "Add" } else { "Update" };
+        self.repository
+            .commit_all(
+                SNIPPETS_DIR,
+                &format!("{} snippet \"{}\"", verb, snippet.title),
+                author,
+            )
+            .await?;
+        Ok(())
+    }
+
+    pub async fn snippet_deleted(&self, snippet: &Snippet, author: &CommitAuthor) -> AppResult<()> {
+        let _guard = self.lock.lock().await;
+
+        layout::remove_snippet(&self.snippets_dir(), &snippet.id)?;
+        let mut state = self.load_state().await?;
+        state.snippets.remove(&snippet.id);
+        self.save_state(&state).await?;
+
+        self.repository
+            .commit_all(
+                SNIPPETS_DIR,
+                &format!("Delete snippet \"{}\"", snippet.title),
+                author,
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Makes the working tree match the vault exactly and commits the result.
+    /// Useful as a first sync, and to settle conflicts in favour of the vault.
+    pub async fn export(
+        &self,
+        storage: &dyn Storage,
+        author: &CommitAuthor,
+    ) -> AppResult<ExportReport> {
+        let _guard = self.lock.lock().await;
+        let dir = self.snippets_dir();
+        let snippets = storage.list_snippets(None).await?;
+        let mut report = ExportReport::default();
+
+        for snippet in &snippets {
+            layout::write_snippet(&dir, snippet)?;
+            report.written += 1;
+        }
+
+        let ids: HashSet<&str> = snippets.iter().map(|snippet| snippet.id.as_str()).collect();
+        for id in layout::snippet_ids(&dir)? {
+            if !ids.contains(id.as_str()) {
+                layout::remove_snippet(&dir, &id)?;
+                report.removed += 1;
+            }
+        }
+
+        let state = SyncState {
+            snippets: snippets
+                .iter()
+                .map(|snippet| (snippet.id.clone(), snippet.updated_at))
+                .collect(),
+        };
+        self.save_state(&state).await?;
+
+        report.committed = self
+            .repository
+            .commit_all(SNIPPETS_DIR, "Export the vault", author)
+            .await?;
+        info!(
+            "Exported {} snippets to the repository, removed {}",
+            report.written, report.removed
+        );
+        Ok(report)
+    }
+
+    /// Applies the working tree to the vault. A snippet changed on one side
+    /// only takes that side's version; one changed on both is reported as a
+    /// conflict and left alone. The saver must leave the mirror alone, as
+    /// the changes come from it.
+    pub async fn import(&self, saver: &SnippetSaver<'_>) -> AppResult<ImportReport> {
+        let _guard = self.lock.lock().await;
+        let storage = saver.storage();
+        let mut state = self.load_state().await?;
+        let files = layout::read_snippets(&self.snippets_dir())?;
+        let vault: HashMap<String, Snippet> = storage
+            .list_snippets(None)
+            .await?
+            .into_iter()
+            .map(|snippet| (snippet.id.clone(), snippet))
+            .collect();
+        let tags = storage.list_tags(None).await?;
+        let mut report = ImportReport::default();
+
+        for file in &files {
+            let id = &file.metadata.id;
+            let base = state.snippets.get(id).copied();
+            let tag_ids = resolve_tags(file, &tags);
+
+            match vault.get(id) {
+                Some(snippet) if has_same_content(snippet, file, &tag_ids) => {
+                    state.snippets.insert(id.clone(), snippet.updated_at);
+                }
+                Some(snippet) => {
+                    if snippet.updated_at > base.unwrap_or(file.metadata.updated_at) {
+                        report.conflicts.push(conflict(
+                            file,
+                            "changed both in the vault and in the repository",
+                        ));
+                        continue;
+                    }
+
+                    let form = SnippetForm {
+                        title: file.metadata.title.clone(),
+                        description: file.metadata.description.clone(),
+                        language: file.metadata.language.clone(),
+                        code: file.code.clone(),
+                        tag_ids,
+                        snippet_state_id: snippet.snippet_state_id.clone(),
+                        // The file holds private code as stored, sealed.
+                        is_private: Some(encryption::is_encrypted(&file.code)),
+                        workspace_id: snippet.workspace_id.clone(),
+                    };
+                    let updated = saver.update(snippet, form).await?;
+                    state
+                        .snippets
+                        .insert(id.clone(), updated.snippet.updated_at);
+                    report.secrets.extend(updated.secrets());
+                    report.updated += 1;
+                }
+                None if base.is_some() => {
+                    report.conflicts.push(conflict(
+                        file,
+                        "deleted from the vault but still in the repository",
+                    ));
+                }
+                None => {
+                    let findings = saver.check(&file.code)?;
+                    let created = create_from_file(storage, file, tag_ids).await?;
+                    state.snippets.insert(id.clone(), created.updated_at);
+                    if !findings.is_empty() {
+                        report.secrets.push(SnippetSecrets {
+                            snippet_id: created.id.clone(),
+                            title: created.title.clone(),
+                            findings,
+                        });
+                    }
+                    report.created += 1;
+                }
+            }
+        }
+
+        let file_ids: HashSet<&str> = files.iter().map(|file| file.metadata.id.as_str()).collect();
+        for snippet in vault.values() {
+            if file_ids.contains(snippet.id.as_str()) {
+                continue;
+            }
+            // Snippets never synced yet are left for the next export.
+            let base = match state.snippets.get(&snippet.id) {
+                Some(base) => *base,
+                None => continue,
+            };
+
+            if snippet.updated_at > base {
+                report.conflicts.push(SyncConflict {
+                    snippet_id: snippet.id.clone(),
+                    title: snippet.title.clone(),
+                    reason: "removed from the repository but changed in the vault".to_string(),
+                });
+                continue;
+            }
+            storage.delete_snippet(snippet.id.clone()).await?;
+            state.snippets.remove(&snippet.id);
+            report.deleted += 1;
+        }
+
+        self.save_state(&state).await?;
+        info!(
+            "Imported the repository: {} created, {} updated, {} deleted, {} conflicts",
+            report.created,
+            report.updated,
+            report.deleted,
+            report.conflicts.len()
+        );
+        Ok(report)
+    }
+
+    /// Merges the remote branch into the working tree and imports the result.
+    pub async fn pull(
+        &self,
+        saver: &SnippetSaver<'_>,
+        author: &CommitAuthor,
+    ) -> AppResult<PullReport> {
+        self.require_remote()?;
+
+        let outcome = {
+            let _guard = self.lock.lock().await;
+            self.repository.pull(&self.branch, author).await?
+        };
+        let merged = match outcome {
+            MergeOutcome::UpToDate => false,
+            MergeOutcome::Merged => true,
+            MergeOutcome::Conflicts(paths) => {
+                warn!("Pulling the snippet repository conflicted on {:?}", paths);
+                return Ok(PullReport {
+                    merge_conflicts: paths,
+                    ..PullReport::default()
+                });
+            }
+        };
+
+        Ok(PullReport {
+            merged,
+            merge_conflicts: vec![],
+            import: self.import(saver).await?,
+        })
+    }
+
+    pub async fn push(&self) -> AppResult<()> {
+        self.require_remote()?;
+        let _guard = self.lock.lock().await;
+        self.repository.push(&self.branch).await
+    }
+
+    fn require_remote(&self) -> AppResult<()> {
+        if !self.has_remote {
+            return Err(AppError::GitError(
+                "No remote is configured for the snippet repository".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+fn conflict(file: &SnippetFile, reason: &str) -> SyncConflict {
+    SyncConflict {
+        snippet_id: file.metadata.id.clone(),
+        title: file.metadata.title.clone(),
+        reason: reason.to_string(),
+    }
+}
+
+/// Tags the file refers to that exist in this vault, by id or else by name.
+fn resolve_tags(file: &SnippetFile, tags: &[Tag]) -> Vec<String> {
+    let mut tag_ids: Vec<String> = file
+        .metadata
+        .tag_ids
+        .iter()
+        .filter(|id| tags.iter().any(|tag| &tag.id == *id))
+        .cloned()
+        .collect();
+
+    for name in &file.metadata.tags {
+        let tag = tags.iter().find(|tag| tag.name.eq_ignore_ascii_case(name));
+        if let Some(tag) = tag {
+            if !tag_ids.contains(&tag.id) {
+                tag_ids.push(tag.id.clone());
+            }
+        }
+    }
+    tag_ids
+}
+
+fn has_same_content(snippet: &Snippet, file: &SnippetFile, tag_ids: &[String]) -> bool {
+    let same_tags = snippet.tag_ids.len() == tag_ids.len()
+        && tag_ids.iter().all(|id| snippet.tag_ids.contains(id));
+
+    same_tags
+        && snippet.title == file.metadata.title
+        && snippet.description == file.metadata.description
+        && snippet.language == file.metadata.language
+        && snippet.code == file.code
+}
+
+/// Adds a snippet that only exists in the repository under its own id, so the
+/// file keeps matching it in every vault synced with the repository.
+async fn create_from_file(
+    storage: &dyn Storage,
+    file: &SnippetFile,
+    tag_ids: Vec<String>,
+) -> AppResult<Snippet> {
+    let metadata = &file.metadata;
+    let author_id = match &metadata.author_id {
+        Some(id) if storage.get_user(id.clone()).await?.is_some() => Some(id.clone()),
+        _ => None,
+    };
+
+    let state: SnippetState = serde_json::from_value(json!({
+        "id": new_object_id(),
+        "isFavorite": metadata.is_favorite,
+        "isDark": false,
+    }))?;
+    let snippet: Snippet = serde_json::from_value(json!({
+        "id": metadata.id,
+        "createdAt": metadata.created_at,
+        "updatedAt": DateTime::<FixedOffset>::from(Utc::now()),
+        "title": metadata.title,
+        "description": metadata.description,
+        "language": metadata.language,
+        "code": file.code,
+        "tagIds": tag_ids,
+        "snippetStateId": state.id,
+        "metadata": null,
+        "authorId": author_id,
+        "workspaceId": metadata.workspace_id,
+    }))?;
+
+    storage
+        .import_snapshot(
+            VaultSnapshot {
+                snippets: vec![snippet],
+                states: vec![state],
+                ..VaultSnapshot::default()
+            },
+            false,
+        )
+        .await?;
+
+    storage
+        .get_snippet(metadata.id.clone())
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Snippet with ID {}", metadata.id)))
+}
+
+fn git_sync(app: &AppHandle) -> Option<Arc<GitSync>> {
+    app.state::<AppState>().git_sync.clone()
+}
+
+/// Commits a created or updated snippet when git sync is enabled. Failing to
+/// sync is logged rather than failing the save itself.
+pub async fn commit_saved_snippet(
+    app: &AppHandle,
+    snippet: &Snippet,
+    created: bool,
+    principal: &Principal,
+) {
+    if let Some(sync) = git_sync(app) {
+        let author = CommitAuthor::from(principal);
+        if let Err(e) = sync.snippet_saved(snippet, created, &author).await {
+            warn!("Failed to commit snippet {} to git: {}", snippet.id, e);
+        }
+    }
+}
+
+pub async fn commit_deleted_snippet(app: &AppHandle, snippet: &Snippet, principal: &Principal) {
+    if let Some(sync) = git_sync(app) {
+        let author = CommitAuthor::from(principal);
+        if let Err(e) = sync.snippet_deleted(snippet, &author).await {
+            warn!(
+                "Failed to commit deletion of snippet {} to git: {}",
+                snippet.id, e
+            );
+        }
+    }
+}
@@ -0,0 +1,173 @@
+//! Syntax highlighting outside the editor, for exports, previews and the
+//! terminal. Code is tokenized with syntect's bundled Sublime grammars and
+//! colored with the same Monaco themes the editor uses.
+
+pub mod themes;
+
+use serde::Deserialize;
+use syntect::{
+    easy::HighlightLines,
+    highlighting::{Color, Style, Theme},
+    html::{styled_line_to_highlighted_html, IncludeBackground},
+    parsing::{SyntaxReference, SyntaxSet},
+    util::{as_24_bit_terminal_escaped, LinesWithEndings},
+};
+
+use crate::{database::models::Snippet, languages::registry, prelude::AppResult};
+
+pub use themes::{list_themes, load_theme, ThemeInfo, DEFAULT_THEME};
+
+const ANSI_RESET: &str = "\x1b[0m";
+const FONT_FAMILY: &str =
+    "ui-monospace, SFMono-Regular, Menlo, Consolas, 'Liberation Mono', monospace";
+
+/// Languages without a bundled grammar, highlighted with a close relative.
+const GRAMMAR_FALLBACKS: &[(&str, &str)] = &[("typescript", "js"), ("scss", "css")];
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RenderSnippetForm {
+    pub id: String,
+    /// Name or id of a theme in `src/assets/themes`.
+    pub theme: Option<String>,
+}
+
+/// Holds the grammars, which are slow to load, for the life of the app.
+pub struct Highlighter {
+    syntaxes: SyntaxSet,
+}
+
+impl Default for Highlighter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Highlighter {
+    pub fn new() -> Self {
+        Self {
+            syntaxes: SyntaxSet::load_defaults_newlines(),
+        }
+    }
+
+    /// The grammar for a registry language id, or plain text.
+    fn syntax_for(&self, language: &str) -> &SyntaxReference {
+        let extensions = registry::find(language)
+            .map(|language| language.extensions)
+            .unwrap_or_default();
+        let fallback = GRAMMAR_FALLBACKS
+            .iter()
+            .find(|(id, _)| *id == language)
+            .map(|(_, extension)| *extension);
+
+        extensions
+            .iter()
+            .copied()
+            .chain(fallback)
+            .find_map(|extension| self.syntaxes.find_syntax_by_extension(extension))
+            .or_else(|| self.syntaxes.find_syntax_by_token(language))
+            .unwrap_or_else(|| self.syntaxes.find_syntax_plain_text())
+    }
+
+    fn highlight<'a>(
+        &self,
+        code: &'a str,
+        language: &str,
+        theme: &Theme,
+    ) -> AppResult<Vec<Vec<(Style, &'a str)>>> {
+        let mut highlighter = HighlightLines::new(self.syntax_for(language), theme);
+        let mut lines = vec![];
+        for line in LinesWithEndings::from(code) {
+            lines.push(highlighter.highlight_line(line, &self.syntaxes)?);
+        }
+        Ok(lines)
+    }
+
+    /// The code as a `<pre>` block with inline styles.
+    pub fn code_to_html(&self, code: &str, language: &str, theme: &Theme) -> AppResult<String> {
+        let foreground = theme.settings.foreground.unwrap_or(Color::BLACK);
+        let background = theme.settings.background.unwrap_or(Color::WHITE);
+
+        let mut html = format!(
+            "<pre style=\"margin:0;padding:16px;overflow:auto;border-radius:8px;\
+             font-family:{};font-size:14px;line-height:1.5;color:{};background-color:{};\"><code>",
+            FONT_FAMILY,
+            css_color(foreground),
+            css_color(background)
+        );
+        for line in self.highlight(code, language, theme)? {
+            html.push_str(&styled_line_to_highlighted_html(
+                &line,
+                IncludeBackground::IfDifferent(background),
+            )?);
+        }
+        html.push_str("</code></pre>");
+        Ok(html)
+    }
+
+    /// A standalone HTML page showing the snippet, with no external styles
+    /// or scripts, so it can be saved, mailed or embedded as is.
+    pub fn snippet_to_html(&self, snippet: &Snippet, theme: &Theme) -> AppResult<String> {
+        let code = self.code_to_html(&snippet.code, &snippet.language, theme)?;
+        let foreground = css_color(theme.settings.foreground.unwrap_or(Color::BLACK));
+        let background = css_color(theme.settings.background.unwrap_or(Color::WHITE));
+        let language = registry::find(&snippet.language)
+            .map_or(snippet.language.as_str(), |language| language.name);
+
+        let mut html = String::from("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+        html.push_str(&format!("<title>{}</title>\n", escape(&snippet.title)));
+        html.push_str("</head>\n");
+        html.push_str(&format!(
+            "<body style=\"margin:0;padding:24px;font-family:system-ui,sans-serif;color:{};background-color:{};\">\n",
+            foreground, background
+        ));
+        html.push_str(&format!(
+            "<h1 style=\"margin:0 0 8px;font-size:20px;\">{}</h1>\n",
+            escape(&snippet.title)
+        ));
+        if !snippet.description.trim().is_empty() {
+            html.push_str(&format!(
+                "<p style=\"margin:0 0 8px;opacity:0.8;\">{}</p>\n",
+                escape(snippet.description.trim())
+            ));
+        }
+        html.push_str(&format!(
+            "<p style=\"margin:0 0 16px;font-size:12px;opacity:0.6;\">{}</p>\n",
+            escape(language)
+        ));
+        html.push_str(&code);
+        html.push_str("\n</body>\n</html>\n");
+        Ok(html)
+    }
+
+    /// The code with 24-bit color escapes, for terminals.
+    pub fn code_to_ansi(&self, code: &str, language: &str, theme: &Theme) -> AppResult<String> {
+        let mut output = String::new();
+        for line in self.highlight(code, language, theme)? {
+            output.push_str(&as_24_bit_terminal_escaped(&line, false));
+        }
+        output.push_str(ANSI_RESET);
+        Ok(output)
+    }
+}
+
+fn css_color(color: Color) -> String {
+    if color.a == 0xff {
+        format!("#{:02x}{:02x}{:02x}", color.r, color.g, color.b)
+    } else {
+        format!(
+            "rgba({},{},{},{:.2})",
+            color.r,
+            color.g,
+            color.b,
+            f32::from(color.a) / 255.0
+        )
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,155 @@
+//! The Monaco editor themes in `src/assets/themes`, turned into syntect
+//! themes. Their rules are keyed by TextMate scopes, which syntect's grammars
+//! produce as well, so the colors match what the editor shows.
+
+use std::{collections::HashMap, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+use syntect::highlighting::{
+    Color, FontStyle, ScopeSelectors, StyleModifier, Theme, ThemeItem, ThemeSettings,
+};
+
+use crate::{error::AppError, prelude::AppResult};
+
+mod embedded {
+    include!(concat!(env!("OUT_DIR"), "/monaco_themes.rs"));
+}
+
+/// The theme the editor uses in dark mode.
+pub const DEFAULT_THEME: &str = "Dracula";
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ThemeInfo {
+    /// Id the frontend's theme list uses, such as `tomorrow-night`.
+    pub id: String,
+    pub name: &'static str,
+    pub dark: bool,
+}
+
+#[derive(Deserialize)]
+struct MonacoTheme {
+    base: String,
+    rules: Vec<MonacoRule>,
+    #[serde(default)]
+    colors: HashMap<String, String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MonacoRule {
+    token: String,
+    foreground: Option<String>,
+    background: Option<String>,
+    font_style: Option<String>,
+}
+
+pub fn list_themes() -> AppResult<Vec<ThemeInfo>> {
+    let ids = theme_ids()?;
+    embedded::THEMES
+        .iter()
+        .map(|&(name, json)| {
+            let theme: MonacoTheme = serde_json::from_str(json)?;
+            Ok(ThemeInfo {
+                id: ids
+                    .get(name)
+                    .cloned()
+                    .unwrap_or_else(|| name.to_lowercase().replace(' ', "-")),
+                name,
+                dark: theme.base != "vs",
+            })
+        })
+        .collect()
+}
+
+/// The theme with this name or id, ignoring case.
+pub fn load_theme(name: &str) -> AppResult<Theme> {
+    let ids = theme_ids()?;
+    let &(theme_name, json) = embedded::THEMES
+        .iter()
+        .find(|&&(theme_name, _)| {
+            theme_name.eq_ignore_ascii_case(name)
+                || ids
+                    .get(theme_name)
+                    .map_or(false, |id| id.eq_ignore_ascii_case(name))
+        })
+        .ok_or_else(|| AppError::NotFound(format!("Theme '{}'", name)))?;
+
+    let monaco: MonacoTheme = serde_json::from_str(json)?;
+    Ok(convert(theme_name, monaco))
+}
+
+/// Maps theme names to the ids in `themelist.json`.
+fn theme_ids() -> AppResult<HashMap<String, String>> {
+    let list: HashMap<String, String> = serde_json::from_str(embedded::THEME_LIST)?;
+    Ok(list.into_iter().map(|(id, name)| (name, id)).collect())
+}
+
+fn convert(name: &str, monaco: MonacoTheme) -> Theme {
+    let mut settings = ThemeSettings {
+        foreground: color_setting(&monaco.colors, "editor.foreground"),
+        background: color_setting(&monaco.colors, "editor.background"),
+        caret: color_setting(&monaco.colors, "editorCursor.foreground"),
+        line_highlight: color_setting(&monaco.colors, "editor.lineHighlightBackground"),
+        selection: color_setting(&monaco.colors, "editor.selectionBackground"),
+        ..ThemeSettings::default()
+    };
+
+    let mut scopes = vec![];
+    for rule in monaco.rules {
+        let style = StyleModifier {
+            foreground: rule.foreground.as_deref().and_then(parse_color),
+            background: rule.background.as_deref().and_then(parse_color),
+            font_style: rule.font_style.as_deref().map(parse_font_style),
+        };
+
+        // The empty token holds the defaults, for themes without `colors`.
+        if rule.token.trim().is_empty() {
+            settings.foreground = settings.foreground.or(style.foreground);
+            settings.background = settings.background.or(style.background);
+            continue;
+        }
+        // A few themes have rules syntect can't parse; they are left out.
+        if let Ok(scope) = ScopeSelectors::from_str(&rule.token) {
+            scopes.push(ThemeItem { scope, style });
+        }
+    }
+
+    Theme {
+        name: Some(name.to_string()),
+        settings,
+        scopes,
+        ..Theme::default()
+    }
+}
+
+fn color_setting(colors: &HashMap<String, String>, key: &str) -> Option<Color> {
+    colors.get(key).and_then(|value| parse_color(value))
+}
+
+/// Parses `rrggbb` or `rrggbbaa`, with or without a leading `#`.
+fn parse_color(value: &str) -> Option<Color> {
+    let hex = value.trim().trim_start_matches('#');
+    let channel = |index: usize| u8::from_str_radix(hex.get(index..index + 2)?, 16).ok();
+
+    match hex.len() {
+        6 | 8 => Some(Color {
+            r: channel(0)?,
+            g: channel(2)?,
+            b: channel(4)?,
+            a: if hex.len() == 8 { channel(6)? } else { 0xff },
+        }),
+        _ => None,
+    }
+}
+
+fn parse_font_style(value: &str) -> FontStyle {
+    value
+        .split_whitespace()
+        .fold(FontStyle::empty(), |style, word| match word {
+            "bold" => style | FontStyle::BOLD,
+            "italic" => style | FontStyle::ITALIC,
+            "underline" => style | FontStyle::UNDERLINE,
+            _ => style,
+        })
+}
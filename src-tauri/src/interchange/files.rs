@@ -0,0 +1,216 @@
+//! Snippets from source files on disk, such as a cloned gist: one snippet
+//! per file, titled after the file name, with a README in the same directory
+//! as the description. Sublime snippets and JetBrains live template files
+//! are read with their importers instead.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::{add_tags, ImportFormat, SnippetDraft};
+use crate::{error::AppError, languages, prelude::AppResult, snippets::SavedSnippet};
+
+/// Files larger than this are unlikely to be snippets.
+const MAX_FILE_SIZE: u64 = 1024 * 1024;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileImportForm {
+    /// Files, and directories to import recursively.
+    pub paths: Vec<PathBuf>,
+    /// Tag names added to every imported snippet.
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Only report what would be imported.
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileDraft {
+    pub path: PathBuf,
+    pub snippet: SnippetDraft,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SkippedFile {
+    pub path: PathBuf,
+    pub reason: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileImportReport {
+    pub dry_run: bool,
+    /// What is, or in a dry run would be, imported from each file.
+    pub files: Vec<FileDraft>,
+    pub skipped: Vec<SkippedFile>,
+    /// Empty in a dry run.
+    pub created: Vec<SavedSnippet>,
+}
+
+/// Reads `paths` into drafts, recording why any file was left out. Runs off
+/// the async runtime, since directories can be large.
+pub async fn read_drafts(
+    paths: Vec<PathBuf>,
+    tags: Vec<String>,
+) -> AppResult<(Vec<FileDraft>, Vec<SkippedFile>)> {
+    tokio::task::spawn_blocking(move || collect_drafts(&paths, &tags))
+        .await
+        .map_err(|e| AppError::Other(format!("File import failed: {}", e)))
+}
+
+fn collect_drafts(paths: &[PathBuf], tags: &[String]) -> (Vec<FileDraft>, Vec<SkippedFile>) {
+    let mut drafts = vec![];
+    let mut skipped = vec![];
+
+    for path in paths {
+        if path.is_dir() {
+            read_directory(path, tags, &mut drafts, &mut skipped);
+        } else {
+            let description = path
+                .parent()
+                .and_then(readme_description)
+                .unwrap_or_default();
+            read_file(path, &description, tags, &mut drafts, &mut skipped);
+        }
+    }
+    (drafts, skipped)
+}
+
+fn read_directory(
+    dir: &Path,
+    tags: &[String],
+    drafts: &mut Vec<FileDraft>,
+    skipped: &mut Vec<SkippedFile>,
+) {
+    let mut entries =
+        match fs::read_dir(dir).and_then(|entries| entries.collect::<Result<Vec<_>, _>>()) {
+            Ok(entries) => entries,
+            Err(e) => {
+                skipped.push(SkippedFile {
+                    path: dir.to_path_buf(),
+                    reason: e.to_string(),
+                });
+                return;
+            }
+        };
+    entries.sort_by_key(|entry| entry.file_name());
+
+    let description = readme_description(dir).unwrap_or_default();
+    for entry in entries {
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy().into_owned();
+        // Skips .git and other hidden files alongside the sources.
+        if name.starts_with('.') {
+            continue;
+        }
+
+        if path.is_dir() {
+            read_directory(&path, tags, drafts, skipped);
+        } else if !is_readme(&name) {
+            read_file(&path, &description, tags, drafts, skipped);
+        }
+    }
+}
+
+fn read_file(
+    path: &Path,
+    description: &str,
+    tags: &[String],
+    drafts: &mut Vec<FileDraft>,
+    skipped: &mut Vec<SkippedFile>,
+) {
+    let code = match read_source(path) {
+        Ok(code) => code,
+        Err(reason) => {
+            skipped.push(SkippedFile {
+                path: path.to_path_buf(),
+                reason,
+            });
+            return;
+        }
+    };
+
+    // Snippet files of other editors hold snippets of their own.
+    if let Some(format) = snippet_file_format(path, &code) {
+        match format.importer(None).parse(&code) {
+            Ok(snippets) => drafts.extend(snippets.into_iter().map(|mut snippet| {
+                add_tags(&mut snippet, tags);
+                FileDraft {
+                    path: path.to_path_buf(),
+                    snippet,
+                }
+            })),
+            Err(e) => skipped.push(SkippedFile {
+                path: path.to_path_buf(),
+                reason: e.to_string(),
+            }),
+        }
+        return;
+    }
+
+    let title = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    drafts.push(FileDraft {
+        path: path.to_path_buf(),
+        snippet: SnippetDraft {
+            language: detect_language(path, &code).to_string(),
+            title,
+            description: description.to_string(),
+            code,
+            tags: tags.to_vec(),
+        },
+    });
+}
+
+/// Whether the file is a Sublime snippet or a set of JetBrains live
+/// templates rather than plain source.
+fn snippet_file_format(path: &Path, code: &str) -> Option<ImportFormat> {
+    match path.extension()?.to_str()? {
+        "sublime-snippet" => Some(ImportFormat::SublimeSnippet),
+        "xml" if code.contains("<templateSet") => Some(ImportFormat::JetbrainsLiveTemplates),
+        _ => None,
+    }
+}
+
+fn read_source(path: &Path) -> Result<String, String> {
+    let size = fs::metadata(path).map_err(|e| e.to_string())?.len();
+    if size > MAX_FILE_SIZE {
+        return Err(format!("larger than {} bytes", MAX_FILE_SIZE));
+    }
+
+    let bytes = fs::read(path).map_err(|e| e.to_string())?;
+    if bytes.contains(&0) {
+        return Err("binary file".to_string());
+    }
+    String::from_utf8(bytes).map_err(|_| "not valid UTF-8 text".to_string())
+}
+
+fn is_readme(name: &str) -> bool {
+    let stem = name.split('.').next().unwrap_or_default();
+    stem.eq_ignore_ascii_case("readme")
+}
+
+/// Contents of the README in `dir`, if there is one.
+fn readme_description(dir: &Path) -> Option<String> {
+    let entries = fs::read_dir(dir).ok()?;
+    let readme = entries
+        .filter_map(Result::ok)
+        .find(|entry| is_readme(&entry.file_name().to_string_lossy()))?;
+    let text = read_source(&readme.path()).ok()?;
+    Some(text.trim().to_string())
+}
+
+/// Detects the language from the file name, then from the code.
+pub fn detect_language(path: &Path, code: &str) -> &'static str {
+    let file_name = path.file_name().and_then(|name| name.to_str());
+    languages::detect_best(code, file_name)
+}
@@ -0,0 +1,337 @@
+//! JetBrains live templates, as exported from the IDE settings or found in
+//! its `templates` directory: a `templateSet` of templates that keep their
+//! code in the `value` attribute, with `$NAME$` variables and the contexts
+//! (languages) they apply in.
+//!
+//! Variables become numbered `${1:default}` placeholders and `$END$` the
+//! final `$0` stop, the snippet syntax Sublime and VS Code use.
+
+use quick_xml::{
+    events::{BytesStart, Event},
+    Reader,
+};
+
+use super::{SnippetDraft, SnippetImporter};
+use crate::prelude::AppResult;
+
+const END_VARIABLE: &str = "END";
+const SELECTION_VARIABLE: &str = "SELECTION";
+
+pub struct JetBrainsImporter {
+    /// Language for templates whose contexts don't name a known one.
+    pub default_language: Option<String>,
+}
+
+#[derive(Default)]
+struct Template {
+    name: String,
+    value: String,
+    description: String,
+    variables: Vec<Variable>,
+    contexts: Vec<String>,
+}
+
+struct Variable {
+    name: String,
+    default_value: Option<String>,
+}
+
+impl SnippetImporter for JetBrainsImporter {
+    fn parse(&self, source: &str) -> AppResult<Vec<SnippetDraft>> {
+        let mut reader = Reader::from_str(source);
+        reader.trim_text(true);
+
+        let mut group: Option<String> = None;
+        let mut current: Option<Template> = None;
+        let mut drafts = vec![];
+
+        loop {
+            let (element, is_empty) = match reader.read_event()? {
+                Event::Start(element) => (element, false),
+                Event::Empty(element) => (element, true),
+                Event::End(element) => {
+                    if element.name().as_ref() == b"template" {
+                        if let Some(template) = current.take() {
+                            drafts.push(self.draft(template, group.as_deref()));
+                        }
+                    }
+                    continue;
+                }
+                Event::Eof => break,
+                _ => continue,
+            };
+
+            match element.name().as_ref() {
+                b"templateSet" => group = attribute(&element, b"group")?,
+                b"template" => {
+                    let template = Template {
+                        name: attribute(&element, b"name")?.unwrap_or_default(),
+                        value: attribute(&element, b"value")?.unwrap_or_default(),
+                        description: attribute(&element, b"description")?.unwrap_or_default(),
+                        ..Template::default()
+                    };
+                    if is_empty {
+                        drafts.push(self.draft(template, group.as_deref()));
+                    } else {
+                        current = Some(template);
+                    }
+                }
+                b"variable" => {
+                    if let (Some(template), Some(name)) =
+                        (current.as_mut(), attribute(&element, b"name")?)
+                    {
+                        template.variables.push(Variable {
+                            name,
+                            default_value: attribute(&element, b"defaultValue")?,
+                        });
+                    }
+                }
+                // Context options sit inside `<context>`; one set to false
+                // excludes a context instead.
+                b"option" => {
+                    if let (Some(template), Some(name)) =
+                        (current.as_mut(), attribute(&element, b"name")?)
+                    {
+                        if attribute(&element, b"value")?.as_deref() == Some("true") {
+                            template.contexts.push(name);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Ok(drafts)
+    }
+}
+
+impl JetBrainsImporter {
+    fn draft(&self, template: Template, group: Option<&str>) -> SnippetDraft {
+        let language = template
+            .contexts
+            .iter()
+            .map(String::as_str)
+            .chain(group)
+            .find_map(language_for_context)
+            .map(str::to_string)
+            .or_else(|| self.default_language.clone())
+            .unwrap_or_default();
+
+        SnippetDraft {
+            title: template.name,
+            description: template.description,
+            language,
+            code: to_snippet_syntax(&template.value, &template.variables),
+            tags: group
+                .map(|group| vec![group.to_string()])
+                .unwrap_or_default(),
+        }
+    }
+}
+
+fn attribute(element: &BytesStart, name: &[u8]) -> AppResult<Option<String>> {
+    for attribute in element.attributes() {
+        let attribute = attribute.map_err(quick_xml::Error::from)?;
+        if attribute.key.as_ref() == name {
+            return Ok(Some(attribute.unescape_value()?.into_owned()));
+        }
+    }
+    Ok(None)
+}
+
+/// Rewrites the template's `$NAME$` variables as placeholders numbered in
+/// the order the variables are declared, which is the order JetBrains tabs
+/// through them. `$$` is an escaped dollar sign.
+fn to_snippet_syntax(value: &str, variables: &[Variable]) -> String {
+    let mut code = String::with_capacity(value.len());
+    let mut rest = value;
+
+    while let Some(start) = rest.find('$') {
+        code.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let end = match after.find('$') {
+            Some(end) => end,
+            None => {
+                code.push_str(&rest[start..]);
+                rest = "";
+                break;
+            }
+        };
+
+        let name = &after[..end];
+        if name.is_empty() {
+            code.push_str("\\$");
+        } else if name == END_VARIABLE {
+            code.push_str("$0");
+        } else if name == SELECTION_VARIABLE {
+            code.push_str("${TM_SELECTED_TEXT}");
+        } else {
+            match variables.iter().position(|variable| variable.name == name) {
+                Some(index) => {
+                    let default = variables[index]
+                        .default_value
+                        .as_deref()
+                        .and_then(literal_default)
+                        .unwrap_or(name);
+                    code.push_str(&format!("${{{}:{}}}", index + 1, default));
+                }
+                // Undeclared variables still get a stop, after the
+                // declared ones.
+                None => code.push_str(&format!("${{{}:{}}}", variables.len() + 1, name)),
+            }
+        }
+        rest = &after[end + 1..];
+    }
+
+    code.push_str(rest);
+    code
+}
+
+/// A default value that is a quoted string literal, without the quotes.
+/// Other defaults are expressions such as `className()` the IDE evaluates.
+fn literal_default(expression: &str) -> Option<&str> {
+    let expression = expression.trim();
+    if expression.len() >= 2 && expression.starts_with('"') && expression.ends_with('"') {
+        Some(&expression[1..expression.len() - 1])
+    } else {
+        None
+    }
+}
+
+/// The language of a context option (`JAVA_SCRIPT`, `KOTLIN_STATEMENT`,
+/// `Python`, ...) or template group name.
+fn language_for_context(context: &str) -> Option<&'static str> {
+    let name: String = context
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_uppercase())
+        .collect();
+
+    let exact = match name.as_str() {
+        "C" | "CPP" | "OC" => Some("cpp"),
+        "GO" => Some("go"),
+        "JS" => Some("javascript"),
+        "TS" | "TSX" => Some("typescript"),
+        _ => None,
+    };
+    if exact.is_some() {
+        return exact;
+    }
+
+    const PREFIXES: &[(&str, &str)] = &[
+        ("JSON", "json"),
+        ("JAVASCRIPT", "javascript"),
+        ("TYPESCRIPT", "typescript"),
+        ("JAVA", "java"),
+        ("KOTLIN", "kotlin"),
+        ("PYTHON", "python"),
+        ("GOLANG", "go"),
+        ("RUST", "rust"),
+        ("PHP", "php"),
+        ("SHELL", "shell"),
+        ("BASH", "shell"),
+        ("SQL", "sql"),
+        ("HTML", "html"),
+        ("XML", "xml"),
+        ("SCSS", "scss"),
+        ("CSS", "css"),
+        ("YAML", "yaml"),
+        ("RUBY", "ruby"),
+        ("CSHARP", "csharp"),
+        ("MARKDOWN", "markdown"),
+    ];
+    PREFIXES
+        .iter()
+        .find(|(prefix, _)| name.starts_with(prefix))
+        .map(|&(_, language)| language)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LIVE_TEMPLATES: &str =
+        include_str!("../../tests/fixtures/interchange/live-templates.xml");
+
+    fn parse(source: &str, default_language: Option<&str>) -> Vec<SnippetDraft> {
+        JetBrainsImporter {
+            default_language: default_language.map(str::to_string),
+        }
+        .parse(source)
+        .unwrap()
+    }
+
+    #[test]
+    fn reads_every_template_of_the_set() {
+        let drafts = parse(LIVE_TEMPLATES, None);
+
+        let titles: Vec<&str> = drafts.iter().map(|draft| draft.title.as_str()).collect();
+        assert_eq!(titles, ["fetchj", "logsel", "todo", "empty"]);
+        assert_eq!(drafts[0].description, "Fetch JSON");
+        assert!(drafts
+            .iter()
+            .all(|draft| draft.tags == ["JavaScript".to_string()]));
+    }
+
+    #[test]
+    fn variables_become_numbered_placeholders() {
+        let drafts = parse(LIVE_TEMPLATES, None);
+
+        assert_eq!(
+            drafts[0].code,
+            "const ${1:data} = await fetch(${2:URL})\
+             .then((response) => response.json());\n$0"
+        );
+        assert_eq!(
+            drafts[1].code,
+            "console.log('${TM_SELECTED_TEXT}', \\$${1:NAME});"
+        );
+        assert_eq!(drafts[2].code, "// TODO(${1:OWNER}): $0");
+        assert_eq!(drafts[3].code, "$");
+    }
+
+    #[test]
+    fn language_comes_from_enabled_contexts_then_the_group() {
+        let drafts = parse(LIVE_TEMPLATES, Some("rust"));
+
+        let languages: Vec<&str> = drafts.iter().map(|draft| draft.language.as_str()).collect();
+        assert_eq!(
+            languages,
+            ["javascript", "typescript", "javascript", "javascript"]
+        );
+    }
+
+    #[test]
+    fn language_falls_back_to_the_default() {
+        let source = r#"<templateSet group="user">
+            <template name="hr" value="----" description="">
+              <context><option name="OTHER" value="true" /></context>
+            </template>
+          </templateSet>"#;
+
+        assert_eq!(parse(source, Some("markdown"))[0].language, "markdown");
+        assert_eq!(parse(source, None)[0].language, "");
+    }
+
+    #[test]
+    fn context_names_map_to_languages() {
+        assert_eq!(language_for_context("JAVA_SCRIPT"), Some("javascript"));
+        assert_eq!(language_for_context("JAVA_CODE"), Some("java"));
+        assert_eq!(language_for_context("KOTLIN_STATEMENT"), Some("kotlin"));
+        assert_eq!(language_for_context("Python"), Some("python"));
+        assert_eq!(language_for_context("GO"), Some("go"));
+        assert_eq!(language_for_context("JSON"), Some("json"));
+        assert_eq!(language_for_context("OTHER"), None);
+    }
+
+    #[test]
+    fn malformed_xml_is_an_error() {
+        let importer = JetBrainsImporter {
+            default_language: None,
+        };
+
+        assert!(importer
+            .parse(r#"<templateSet><template name="a" value="b"></templateSet>"#)
+            .is_err());
+    }
+}
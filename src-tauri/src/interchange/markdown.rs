@@ -0,0 +1,193 @@
+//! Snippets as a Markdown cheat sheet: a heading per snippet, its
+//! description and tags, then the code in a fenced block tagged with its
+//! language.
+
+use serde::Deserialize;
+
+use super::{SnippetDraft, SnippetImporter};
+use crate::{
+    database::models::{Snippet, SnippetFilter},
+    prelude::AppResult,
+};
+
+const DEFAULT_DOCUMENT_TITLE: &str = "Snippets";
+const UNTITLED: &str = "Untitled snippet";
+const TAGS_LABEL: &str = "**Tags:**";
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MarkdownExportOptions {
+    pub filter: Option<SnippetFilter>,
+    /// Top-level heading of the document.
+    pub title: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MarkdownImportForm {
+    pub markdown: String,
+    /// Language for code blocks without one.
+    pub default_language: Option<String>,
+}
+
+pub struct MarkdownImporter {
+    /// Language for code blocks without one.
+    pub default_language: Option<String>,
+}
+
+impl SnippetImporter for MarkdownImporter {
+    fn parse(&self, source: &str) -> AppResult<Vec<SnippetDraft>> {
+        Ok(parse(source, self.default_language.as_deref()))
+    }
+}
+
+pub fn render(title: Option<&str>, snippets: &[Snippet]) -> String {
+    let mut document = format!("# {}\n", title.unwrap_or(DEFAULT_DOCUMENT_TITLE));
+
+    for snippet in snippets {
+        document.push_str(&format!("\n## {}\n\n", snippet.title.trim()));
+
+        if !snippet.description.trim().is_empty() {
+            document.push_str(snippet.description.trim());
+            document.push_str("\n\n");
+        }
+
+        let tags: Vec<String> = snippet
+            .tags
+            .iter()
+            .flatten()
+            .map(|tag| format!("`{}`", tag.name))
+            .collect();
+        if !tags.is_empty() {
+            document.push_str(&format!("{} {}\n\n", TAGS_LABEL, tags.join(", ")));
+        }
+
+        let fence = fence_for(&snippet.code);
+        document.push_str(&format!("{}{}\n", fence, snippet.language.trim()));
+        document.push_str(&snippet.code);
+        if !snippet.code.ends_with('\n') {
+            document.push('\n');
+        }
+        document.push_str(&fence);
+        document.push('\n');
+    }
+
+    document
+}
+
+/// A backtick fence longer than any run of backticks in the code, so the
+/// code can never close it early.
+fn fence_for(code: &str) -> String {
+    let longest_run = code.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    "`".repeat(longest_run.max(2) + 1)
+}
+
+/// Reads every fenced code block as a snippet, titled after the heading
+/// above it and described by the text between the two. Several blocks under
+/// one heading are numbered.
+pub fn parse(markdown: &str, default_language: Option<&str>) -> Vec<SnippetDraft> {
+    let mut drafts = vec![];
+    let mut title: Option<String> = None;
+    let mut blocks_under_title = 0;
+    let mut description: Vec<&str> = vec![];
+    let mut tags: Vec<String> = vec![];
+    let mut lines = markdown.lines();
+
+    while let Some(line) = lines.next() {
+        if let Some(heading) = parse_heading(line) {
+            title = Some(heading);
+            blocks_under_title = 0;
+            description.clear();
+            tags.clear();
+        } else if let Some((fence, info)) = parse_fence_open(line) {
+            let mut code = String::new();
+            for line in lines.by_ref() {
+                if is_fence_close(line, &fence) {
+                    break;
+                }
+                code.push_str(line);
+                code.push('\n');
+            }
+
+            blocks_under_title += 1;
+            let base_title = title.clone().unwrap_or_else(|| UNTITLED.to_string());
+            let language = info
+                .split_whitespace()
+                .next()
+                .map(|language| language.trim_start_matches('{').trim_start_matches('.'))
+                .filter(|language| !language.is_empty())
+                .or(default_language)
+                .unwrap_or_default();
+
+            drafts.push(SnippetDraft {
+                title: if blocks_under_title > 1 {
+                    format!("{} ({})", base_title, blocks_under_title)
+                } else {
+                    base_title
+                },
+                description: description.join("\n").trim().to_string(),
+                language: language.to_string(),
+                code,
+                tags: tags.clone(),
+            });
+            description.clear();
+        } else if let Some(names) = parse_tags(line) {
+            tags = names;
+        } else {
+            description.push(line);
+        }
+    }
+
+    drafts
+}
+
+fn parse_heading(line: &str) -> Option<String> {
+    let trimmed = line.trim_start();
+    let level = trimmed.chars().take_while(|&c| c == '#').count();
+    if level == 0 || level > 6 {
+        return None;
+    }
+
+    let rest = &trimmed[level..];
+    if !rest.is_empty() && !rest.starts_with(char::is_whitespace) {
+        return None;
+    }
+    let text = rest.trim().trim_end_matches('#').trim();
+    Some(text.to_string())
+}
+
+/// The fence (e.g. "```") and info string of an opening fence line.
+fn parse_fence_open(line: &str) -> Option<(String, &str)> {
+    let trimmed = line.trim_start();
+    let marker = trimmed.chars().next().filter(|&c| c == '`' || c == '~')?;
+    let length = trimmed.chars().take_while(|&c| c == marker).count();
+    if length < 3 {
+        return None;
+    }
+
+    let info = trimmed[length..].trim();
+    if marker == '`' && info.contains('`') {
+        return None;
+    }
+    Some((trimmed[..length].to_string(), info))
+}
+
+fn is_fence_close(line: &str, fence: &str) -> bool {
+    let trimmed = line.trim();
+    let marker = fence.chars().next().unwrap_or('`');
+    trimmed.len() >= fence.len() && trimmed.chars().all(|c| c == marker)
+}
+
+fn parse_tags(line: &str) -> Option<Vec<String>> {
+    let trimmed = line.trim();
+    let list = trimmed
+        .strip_prefix(TAGS_LABEL)
+        .or_else(|| trimmed.strip_prefix("Tags:"))?;
+
+    Some(
+        list.split(',')
+            .map(|name| name.trim().trim_matches('`').trim().to_string())
+            .filter(|name| !name.is_empty())
+            .collect(),
+    )
+}
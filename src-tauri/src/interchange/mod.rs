@@ -0,0 +1,156 @@
+//! Moving snippets in and out of the vault in formats other tools use.
+
+pub mod files;
+pub mod jetbrains;
+pub mod markdown;
+pub mod sublime;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    database::models::{SnippetForm, TagFilter, TagForm, WorkspaceScope},
+    prelude::AppResult,
+    snippets::{SavedSnippet, SnippetSaver},
+    storage::Storage,
+};
+
+use self::{jetbrains::JetBrainsImporter, markdown::MarkdownImporter, sublime::SublimeImporter};
+
+/// A snippet read from an outside source, before it is saved.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SnippetDraft {
+    pub title: String,
+    pub description: String,
+    /// Empty when the source doesn't say; detected from the code on import.
+    pub language: String,
+    pub code: String,
+    /// Tag names; missing tags are created on import.
+    pub tags: Vec<String>,
+}
+
+/// Reads snippets out of a document in another tool's format.
+pub trait SnippetImporter: Send + Sync {
+    fn parse(&self, source: &str) -> AppResult<Vec<SnippetDraft>>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ImportFormat {
+    Markdown,
+    /// A `templateSet` XML file of JetBrains live templates.
+    JetbrainsLiveTemplates,
+    /// A `.sublime-snippet` file.
+    SublimeSnippet,
+}
+
+impl ImportFormat {
+    pub fn importer(self, default_language: Option<String>) -> Box<dyn SnippetImporter> {
+        match self {
+            ImportFormat::Markdown => Box::new(MarkdownImporter { default_language }),
+            ImportFormat::JetbrainsLiveTemplates => {
+                Box::new(JetBrainsImporter { default_language })
+            }
+            ImportFormat::SublimeSnippet => Box::new(SublimeImporter { default_language }),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SnippetImportForm {
+    pub format: ImportFormat,
+    pub content: String,
+    /// Language for snippets the source doesn't give one for.
+    pub default_language: Option<String>,
+    /// Tag names added to every imported snippet.
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+/// Adds `tags` to the draft's own tags, skipping ones it already has.
+pub fn add_tags(draft: &mut SnippetDraft, tags: &[String]) {
+    for tag in tags {
+        if !draft
+            .tags
+            .iter()
+            .any(|existing| existing.eq_ignore_ascii_case(tag))
+        {
+            draft.tags.push(tag.clone());
+        }
+    }
+}
+
+/// Saves the drafts as snippets authored by the saver's principal, in their
+/// active workspace. Nothing is saved if any draft is blocked for containing
+/// secrets.
+pub async fn create_snippets(
+    saver: &SnippetSaver<'_>,
+    drafts: Vec<SnippetDraft>,
+) -> AppResult<Vec<SavedSnippet>> {
+    for draft in &drafts {
+        saver.check(&draft.code)?;
+    }
+
+    let mut snippets = Vec::with_capacity(drafts.len());
+    let workspace = saver.principal().workspace();
+    for draft in drafts {
+        let tag_ids = resolve_tag_ids(saver.storage(), &draft.tags, &workspace).await?;
+        let form = SnippetForm {
+            title: draft.title,
+            description: draft.description,
+            language: draft.language,
+            code: draft.code,
+            tag_ids,
+            snippet_state_id: String::new(),
+            is_private: None,
+            workspace_id: workspace.workspace_id(),
+        };
+        snippets.push(saver.create(form).await?);
+    }
+    Ok(snippets)
+}
+
+/// Ids of the workspace's tags with these names, matched case-insensitively,
+/// creating the ones that don't exist yet.
+pub async fn resolve_tag_ids(
+    storage: &dyn Storage,
+    names: &[String],
+    workspace: &WorkspaceScope,
+) -> AppResult<Vec<String>> {
+    if names.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let mut tags = storage
+        .list_tags(Some(TagFilter {
+            name: None,
+            workspace: Some(workspace.clone()),
+        }))
+        .await?;
+    let mut tag_ids = vec![];
+    for name in names {
+        let existing = tags.iter().find(|tag| tag.name.eq_ignore_ascii_case(name));
+        let id = match existing {
+            Some(tag) => tag.id.clone(),
+            None => {
+                let tag = storage
+                    .create_tag(TagForm {
+                        name: name.clone(),
+                        color: None,
+                        emoji: None,
+                        parent_id: None,
+                        workspace_id: workspace.workspace_id(),
+                    })
+                    .await?;
+                let id = tag.id.clone();
+                tags.push(tag);
+                id
+            }
+        };
+        if !tag_ids.contains(&id) {
+            tag_ids.push(id);
+        }
+    }
+    Ok(tag_ids)
+}
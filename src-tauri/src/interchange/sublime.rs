@@ -0,0 +1,203 @@
+//! Sublime Text `.sublime-snippet` files: a `snippet` element holding the
+//! code in `content`, its `tabTrigger`, `description` and the `scope` it
+//! applies in. The code already uses `${1:default}` placeholders and is kept
+//! as is.
+
+use quick_xml::{events::Event, Reader};
+
+use super::{SnippetDraft, SnippetImporter};
+use crate::{error::AppError, prelude::AppResult};
+
+const UNTITLED: &str = "Untitled snippet";
+
+pub struct SublimeImporter {
+    /// Language for snippets without a scope, or with one that isn't known.
+    pub default_language: Option<String>,
+}
+
+#[derive(Default)]
+struct Fields {
+    content: Option<String>,
+    tab_trigger: String,
+    description: String,
+    scope: String,
+}
+
+impl SnippetImporter for SublimeImporter {
+    fn parse(&self, source: &str) -> AppResult<Vec<SnippetDraft>> {
+        let mut reader = Reader::from_str(source);
+        let mut fields = Fields::default();
+        let mut element: Vec<u8> = vec![];
+
+        loop {
+            let text = match reader.read_event()? {
+                Event::Start(start) => {
+                    element = start.name().as_ref().to_vec();
+                    continue;
+                }
+                Event::End(_) => {
+                    element.clear();
+                    continue;
+                }
+                Event::Text(text) => text.unescape()?.into_owned(),
+                Event::CData(data) => String::from_utf8_lossy(&data.into_inner()).into_owned(),
+                Event::Eof => break,
+                _ => continue,
+            };
+
+            // Content can come in several text and CDATA pieces.
+            match element.as_slice() {
+                b"content" => fields
+                    .content
+                    .get_or_insert_with(String::new)
+                    .push_str(&text),
+                b"tabTrigger" => fields.tab_trigger.push_str(&text),
+                b"description" => fields.description.push_str(&text),
+                b"scope" => fields.scope.push_str(&text),
+                _ => {}
+            }
+        }
+
+        let content = fields.content.ok_or_else(|| {
+            AppError::Other("Not a Sublime snippet: it has no <content> element".to_string())
+        })?;
+        let tab_trigger = fields.tab_trigger.trim().to_string();
+        let description = fields.description.trim().to_string();
+
+        let language = language_for_scope(&fields.scope)
+            .map(str::to_string)
+            .or_else(|| self.default_language.clone())
+            .unwrap_or_default();
+        let (title, description) = match (tab_trigger.is_empty(), description.is_empty()) {
+            (false, _) => (tab_trigger, description),
+            (true, false) => (description, String::new()),
+            (true, true) => (UNTITLED.to_string(), String::new()),
+        };
+
+        Ok(vec![SnippetDraft {
+            title,
+            description,
+            language,
+            code: content
+                .trim_start_matches(|c| c == '\r' || c == '\n')
+                .trim_end()
+                .to_string(),
+            tags: vec![],
+        }])
+    }
+}
+
+/// The language of the first scope in a selector such as
+/// `source.js, source.ts` or `text.html.basic - source`.
+fn language_for_scope(selector: &str) -> Option<&'static str> {
+    let scope = selector
+        .split(|c: char| c == ',' || c == '|' || c == '-' || c.is_whitespace())
+        .find(|scope| scope.starts_with("source.") || scope.starts_with("text."))?;
+    let mut parts = scope.split('.').skip(1);
+    let name = parts.next()?;
+
+    let language = match name {
+        "c" | "c++" | "objc" | "objc++" => "cpp",
+        "cs" => "csharp",
+        "css" => "css",
+        "dart" => "dart",
+        "go" => "go",
+        "html" if parts.next() == Some("markdown") => "markdown",
+        "html" => "html",
+        "java" => "java",
+        "js" | "jsx" => "javascript",
+        "json" => "json",
+        "kotlin" => "kotlin",
+        "lua" => "lua",
+        "php" => "php",
+        "python" => "python",
+        "ruby" => "ruby",
+        "rust" => "rust",
+        "scss" => "scss",
+        "shell" => "shell",
+        "sql" => "sql",
+        "swift" => "swift",
+        "toml" => "toml",
+        "ts" | "tsx" => "typescript",
+        "xml" => "xml",
+        "yaml" => "yaml",
+        _ => return None,
+    };
+    Some(language)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FOR_LOOP: &str =
+        include_str!("../../tests/fixtures/interchange/for-loop.sublime-snippet");
+
+    fn parse(source: &str, default_language: Option<&str>) -> AppResult<Vec<SnippetDraft>> {
+        SublimeImporter {
+            default_language: default_language.map(str::to_string),
+        }
+        .parse(source)
+    }
+
+    #[test]
+    fn reads_a_snippet_file() {
+        let drafts = parse(FOR_LOOP, None).unwrap();
+
+        assert_eq!(drafts.len(), 1);
+        let draft = &drafts[0];
+        assert_eq!(draft.title, "for");
+        assert_eq!(draft.description, "For loop & body");
+        assert_eq!(draft.language, "python");
+        assert_eq!(draft.code, "for ${1:item} in ${2:items}:\n    ${0:pass}");
+        assert!(draft.tags.is_empty());
+    }
+
+    #[test]
+    fn title_falls_back_to_the_description() {
+        let described = "<snippet><content>x</content><description>Ex</description></snippet>";
+        let bare = "<snippet><content>x</content></snippet>";
+
+        let draft = &parse(described, None).unwrap()[0];
+        assert_eq!(
+            (draft.title.as_str(), draft.description.as_str()),
+            ("Ex", "")
+        );
+        assert_eq!(parse(bare, None).unwrap()[0].title, UNTITLED);
+    }
+
+    #[test]
+    fn content_may_be_split_into_text_and_cdata() {
+        let source = "<snippet><content>if &lt;<![CDATA[${1:a} && b]]>&gt;</content></snippet>";
+
+        assert_eq!(parse(source, None).unwrap()[0].code, "if <${1:a} && b>");
+    }
+
+    #[test]
+    fn language_falls_back_to_the_default() {
+        let unknown = "<snippet><content>x</content><scope>source.brainfuck</scope></snippet>";
+
+        assert_eq!(parse(unknown, Some("shell")).unwrap()[0].language, "shell");
+        assert_eq!(parse(unknown, None).unwrap()[0].language, "");
+    }
+
+    #[test]
+    fn a_file_without_content_is_rejected() {
+        let source = "<snippet><tabTrigger>for</tabTrigger></snippet>";
+
+        assert!(matches!(parse(source, None), Err(AppError::Other(_))));
+    }
+
+    #[test]
+    fn scopes_map_to_languages() {
+        assert_eq!(
+            language_for_scope("source.js, source.ts"),
+            Some("javascript")
+        );
+        assert_eq!(language_for_scope("text.html.markdown"), Some("markdown"));
+        assert_eq!(language_for_scope("text.html.basic - source"), Some("html"));
+        assert_eq!(language_for_scope("source.c++"), Some("cpp"));
+        assert_eq!(language_for_scope("meta.block"), None);
+        assert_eq!(language_for_scope(""), None);
+    }
+}
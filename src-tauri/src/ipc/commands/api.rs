@@ -0,0 +1,53 @@
+use tauri::{AppHandle, Manager};
+
+use crate::{
+    api::{self, tokens::ApiTokenInfo, ApiToken},
+    database::handle_authenticated_storage_operation,
+    ipc::{params::DeleteParams, responses::IpcResponse},
+    state::AppState,
+};
+
+/// Issues a long-lived token for the local HTTP API, for the caller's
+/// active workspace.
+#[tauri::command]
+pub async fn create_api_token(app: AppHandle, token: Option<String>) -> IpcResponse<ApiToken> {
+    let handle = app.clone();
+    handle_authenticated_storage_operation(app, token, move |_, principal| async move {
+        api::issue_token(&handle, &principal).await
+    })
+    .await
+}
+
+/// The caller's API tokens that are still valid.
+#[tauri::command]
+pub async fn list_api_tokens(
+    app: AppHandle,
+    token: Option<String>,
+) -> IpcResponse<Vec<ApiTokenInfo>> {
+    let handle = app.clone();
+    handle_authenticated_storage_operation(app, token, move |_, principal| async move {
+        Ok(handle
+            .state::<AppState>()
+            .api_tokens
+            .list(&principal.user_id)
+            .await)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn revoke_api_token(
+    app: AppHandle,
+    token: Option<String>,
+    params: DeleteParams,
+) -> IpcResponse<ApiTokenInfo> {
+    let handle = app.clone();
+    handle_authenticated_storage_operation(app, token, move |_, principal| async move {
+        handle
+            .state::<AppState>()
+            .api_tokens
+            .revoke(&principal.user_id, &params.id)
+            .await
+    })
+    .await
+}
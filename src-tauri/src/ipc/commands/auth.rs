@@ -1,73 +1,45 @@
 use crate::{
+    auth::token::TokenType,
     database::{
-        handle_db_operation,
-        models::{AuthPayload, Credentials, UserForm},
+        handle_storage_operation,
+        models::{AuthPayload, Credentials, User, UserForm},
     },
+    error::AppError,
     ipc::{params::PostParams, responses::IpcResponse},
+    prelude::AppResult,
+    state::AppState,
+    storage::Storage,
+    workspaces,
 };
 
 use bcrypt::{hash, verify, DEFAULT_COST};
-use chrono::{Duration, Utc};
-use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
-use serde::{Deserialize, Serialize};
-use tauri::AppHandle;
-
-#[derive(Debug, Serialize, Deserialize)]
-pub struct Claims {
-    sub: String,
-    exp: i64,
-    iat: i64,
-}
-
-const JWT_SECRET: &[u8] = b"SECRET_KEY";
-
-const ACCESS_TOKEN_EXPIRATION_HOURS: i64 = 12; // 12 hours
-const REFRESH_TOKEN_EXPIRATION_HOURS: i64 = 24 * 7; // 1 week
-
-pub fn create_token(
-    email: &str,
-    expiration_hours: i64,
-) -> Result<String, jsonwebtoken::errors::Error> {
-    let claims = Claims {
-        sub: email.to_owned(),
-        exp: Utc::now()
-            .checked_add_signed(Duration::hours(expiration_hours))
-            .expect("valid timestamp")
-            .timestamp(),
-        iat: Utc::now().timestamp(),
-    };
-
-    let token = encode(
-        &Header::default(),
-        &claims,
-        &EncodingKey::from_secret(JWT_SECRET),
-    )?;
-
-    Ok(token)
-}
+use log::{info, warn};
+use prisma::Role;
+use tauri::{AppHandle, Manager};
 
 #[tauri::command]
 pub async fn login(app: AppHandle, params: PostParams<Credentials>) -> IpcResponse<AuthPayload> {
-    handle_db_operation(app, |client| async move {
-        let user = client
-            .user()
-            .find_first(vec![prisma::user::email::equals(params.data.email)])
-            .exec()
-            .await?;
+    let secret = app.state::<AppState>().token_secret.clone();
+    handle_storage_operation(app, |storage| async move {
+        let user = storage.find_user_by_email(params.data.email).await?;
 
         if let Some(user) = user {
+            // The offline cache keeps accounts without their password hash.
+            if user.password_hash.is_empty() {
+                return Err("Signing in needs the database; try again once it is reachable".into());
+            }
             if !verify(params.data.password, &user.password_hash).unwrap_or(false) {
                 return Err("Invalid password".into());
             }
 
-            let access_token = create_token(&user.email, ACCESS_TOKEN_EXPIRATION_HOURS)?;
-            let refresh_token = create_token(&user.email, REFRESH_TOKEN_EXPIRATION_HOURS)?;
-
-            return Ok(AuthPayload {
-                access_token,
-                refresh_token,
+            let user = ensure_admin(storage.as_ref(), user).await;
+            return workspaces::issue_tokens(
+                storage.as_ref(),
+                &secret,
                 user,
-            });
+                params.data.workspace_id,
+            )
+            .await;
         }
 
         Err("Invalid credentials".into())
@@ -75,52 +47,91 @@ pub async fn login(app: AppHandle, params: PostParams<Credentials>) -> IpcRespon
     .await
 }
 
+/// Vaults from before roles have no administrator, since only the first
+/// account registered is made one. The oldest account is promoted the first
+/// time anyone signs in; returns `user` as it stands afterwards.
+async fn ensure_admin(storage: &dyn Storage, user: User) -> User {
+    match promote_oldest_user(storage).await {
+        Ok(Some(admin)) => {
+            info!("Made {} the vault's administrator", admin.email);
+            if admin.id == user.id {
+                admin
+            } else {
+                user
+            }
+        }
+        Ok(None) => user,
+        Err(e) => {
+            warn!("Could not check for a vault administrator: {}", e);
+            user
+        }
+    }
+}
+
+async fn promote_oldest_user(storage: &dyn Storage) -> AppResult<Option<User>> {
+    if storage.count_users(Some(Role::Admin)).await? > 0 {
+        return Ok(None);
+    }
+    let oldest = storage
+        .list_users(None)
+        .await?
+        .into_iter()
+        .min_by_key(|user| user.created_at);
+    match oldest {
+        Some(oldest) => Ok(Some(storage.set_user_role(oldest.id, Role::Admin).await?)),
+        None => Ok(None),
+    }
+}
+
 #[tauri::command]
 pub async fn register(app: AppHandle, params: PostParams<UserForm>) -> IpcResponse<AuthPayload> {
-    handle_db_operation(app, |client| async move {
+    let secret = app.state::<AppState>().token_secret.clone();
+    handle_storage_operation(app, |storage| async move {
         let hashed_password = hash(params.data.password, DEFAULT_COST)?;
 
-        let new_user = client
-            .user()
-            .create(params.data.name, params.data.email, hashed_password, vec![])
-            .exec()
+        // The first account of a fresh vault administers it; everyone else
+        // starts as a regular member until an admin promotes them.
+        let existing_users = storage.count_users(None).await?;
+        let role = if existing_users == 0 {
+            Role::Admin
+        } else {
+            Role::Member
+        };
+
+        let new_user = storage
+            .create_user(params.data.name, params.data.email, hashed_password, role)
             .await?;
 
-        let access_token = create_token(&new_user.email, ACCESS_TOKEN_EXPIRATION_HOURS)?;
-        let refresh_token = create_token(&new_user.email, REFRESH_TOKEN_EXPIRATION_HOURS)?;
-
-        Ok(AuthPayload {
-            access_token,
-            refresh_token,
-            user: new_user,
-        })
+        workspaces::issue_tokens(storage.as_ref(), &secret, new_user, None).await
     })
     .await
 }
 
 #[tauri::command]
 pub async fn refresh_token(app: AppHandle, params: PostParams<String>) -> IpcResponse<AuthPayload> {
-    handle_db_operation(app, |client| async move {
-        let token = decode::<Claims>(
-            &params.data,
-            &DecodingKey::from_secret(JWT_SECRET),
-            &Validation::default(),
-        )?;
-        let user = client
-            .user()
-            .find_unique(prisma::user::email::equals(token.claims.sub))
-            .exec()
-            .await?;
+    let secret = app.state::<AppState>().token_secret.clone();
+    handle_storage_operation(app, |storage| async move {
+        let claims = secret
+            .decode_token(&params.data, TokenType::Refresh)
+            .map_err(|_| AppError::Unauthorized("invalid or expired refresh token".into()))?;
+        let user = storage.get_user(claims.sub).await?;
 
         if let Some(user) = user {
-            let access_token = create_token(&user.email, ACCESS_TOKEN_EXPIRATION_HOURS)?;
-            let refresh_token = create_token(&user.email, REFRESH_TOKEN_EXPIRATION_HOURS)?;
-
-            return Ok(AuthPayload {
-                access_token,
-                refresh_token,
-                user,
-            });
+            // Users removed from their workspace fall back to the default one.
+            let workspace_id = claims.workspace.map(|workspace| workspace.id);
+            return match workspaces::issue_tokens(
+                storage.as_ref(),
+                &secret,
+                user.clone(),
+                workspace_id,
+            )
+            .await
+            {
+                Err(AppError::Forbidden(_)) => {
+                    workspaces::issue_tokens(storage.as_ref(), &secret, user, None).await
+                }
+                result => result,
+            };
         }
 
         Err("Invalid refresh token".into())
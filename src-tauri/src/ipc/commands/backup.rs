@@ -0,0 +1,71 @@
+use log::info;
+use tauri::{AppHandle, Manager};
+
+use crate::{
+    auth::Permission,
+    backup::{self, backup_dir, BackupForm, BackupInfo, BackupKind, RestoreForm, RestoreReport},
+    database::handle_authorized_storage_operation,
+    ipc::{params::PostParams, responses::IpcResponse},
+    state::AppState,
+};
+
+#[tauri::command]
+pub async fn create_backup(
+    app: AppHandle,
+    token: Option<String>,
+    params: PostParams<BackupForm>,
+) -> IpcResponse<BackupInfo> {
+    let handle = app.clone();
+    handle_authorized_storage_operation(
+        app,
+        token,
+        Permission::ManageVault,
+        move |storage, _| async move {
+            let dir = match params.data.directory {
+                Some(dir) => dir,
+                None => backup_dir(&handle, &handle.state::<AppState>().config.backup)?,
+            };
+            backup::create_backup(storage.as_ref(), &dir, BackupKind::Manual).await
+        },
+    )
+    .await
+}
+
+#[tauri::command]
+pub async fn list_backups(app: AppHandle, token: Option<String>) -> IpcResponse<Vec<BackupInfo>> {
+    let handle = app.clone();
+    handle_authorized_storage_operation(
+        app,
+        token,
+        Permission::ManageVault,
+        move |_, _| async move {
+            let dir = backup_dir(&handle, &handle.state::<AppState>().config.backup)?;
+            backup::list_backups(dir).await
+        },
+    )
+    .await
+}
+
+#[tauri::command]
+pub async fn restore_backup(
+    app: AppHandle,
+    token: Option<String>,
+    params: PostParams<RestoreForm>,
+) -> IpcResponse<RestoreReport> {
+    info!(
+        "Restoring backup from {} ({:?})",
+        params.data.path.display(),
+        params.data.mode
+    );
+    let handle = app.clone();
+    handle_authorized_storage_operation(
+        app,
+        token,
+        Permission::ManageVault,
+        move |storage, _| async move {
+            let dir = backup_dir(&handle, &handle.state::<AppState>().config.backup)?;
+            backup::restore_backup(storage.as_ref(), params.data.path, params.data.mode, &dir).await
+        },
+    )
+    .await
+}
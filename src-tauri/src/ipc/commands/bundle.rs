@@ -0,0 +1,92 @@
+use log::info;
+use tauri::{AppHandle, Manager};
+
+use crate::{
+    auth::Permission,
+    bundle::{
+        self, signing::BundleSigner, BundleExportForm, BundleImportForm, BundleImportReport,
+        BundleInfo,
+    },
+    database::{handle_authenticated_storage_operation, handle_authorized_storage_operation},
+    ipc::{params::PostParams, responses::IpcResponse},
+    snippets::SnippetSaver,
+    state::AppState,
+};
+
+/// Writes the chosen snippets and collections to a `.svault` bundle.
+#[tauri::command]
+pub async fn export_bundle(
+    app: AppHandle,
+    token: Option<String>,
+    params: PostParams<BundleExportForm>,
+) -> IpcResponse<BundleInfo> {
+    info!("Exporting bundle to {}", params.data.path.display());
+    let handle = app.clone();
+    handle_authorized_storage_operation(
+        app,
+        token,
+        Permission::ReadVault,
+        move |storage, principal| async move {
+            let signer = if params.data.sign {
+                Some(BundleSigner::for_app(&handle)?)
+            } else {
+                None
+            };
+            let state = handle.state::<AppState>();
+            bundle::export(
+                storage.as_ref(),
+                &principal,
+                &state.vault_lock,
+                signer.as_ref(),
+                params.data,
+            )
+            .await
+        },
+    )
+    .await
+}
+
+/// Imports a bundle into the active workspace, or with `dryRun` only
+/// reports what it would do.
+#[tauri::command]
+pub async fn import_bundle(
+    app: AppHandle,
+    token: Option<String>,
+    params: PostParams<BundleImportForm>,
+) -> IpcResponse<BundleImportReport> {
+    info!(
+        "Importing bundle from {} ({:?})",
+        params.data.path.display(),
+        params.data.on_conflict
+    );
+    let handle = app.clone();
+    handle_authorized_storage_operation(
+        app,
+        token,
+        Permission::WriteVault,
+        move |storage, principal| async move {
+            let signer = BundleSigner::for_app(&handle).ok();
+            let state = handle.state::<AppState>();
+            bundle::import(
+                &SnippetSaver::new(&handle, storage.as_ref(), &principal),
+                &state.vault_lock,
+                &state.config.bundle,
+                signer.as_ref(),
+                params.data,
+            )
+            .await
+        },
+    )
+    .await
+}
+
+/// The public key this installation signs bundles with, for teammates to
+/// add to their trusted keys.
+#[tauri::command]
+pub async fn get_bundle_signing_key(app: AppHandle, token: Option<String>) -> IpcResponse<String> {
+    let handle = app.clone();
+    handle_authenticated_storage_operation(app, token, move |_, _| async move {
+        Ok(BundleSigner::for_app(&handle)?.public_key())
+    })
+    .await
+}
@@ -0,0 +1,47 @@
+use log::info;
+use tauri::AppHandle;
+
+use crate::{
+    auth::Permission,
+    clipboard::{self, CopySnippetForm},
+    database::{handle_authorized_storage_operation, models::SnippetState},
+    interchange::SnippetDraft,
+    ipc::{params::PostParams, responses::IpcResponse},
+};
+
+/// Puts a snippet's code on the clipboard and counts the copy.
+#[tauri::command]
+pub async fn copy_snippet(
+    app: AppHandle,
+    token: Option<String>,
+    params: PostParams<CopySnippetForm>,
+) -> IpcResponse<SnippetState> {
+    info!("Copying snippet {}", params.data.id);
+    let handle = app.clone();
+    handle_authorized_storage_operation(
+        app,
+        token,
+        Permission::ReadVault,
+        move |storage, principal| async move {
+            clipboard::copy_snippet(&handle, storage, &principal, params.data).await
+        },
+    )
+    .await
+}
+
+/// A draft of a new snippet holding the clipboard's text, in the language
+/// detected from it, for the snippet form to open with. Nothing is saved.
+#[tauri::command]
+pub async fn create_snippet_from_clipboard(
+    app: AppHandle,
+    token: Option<String>,
+) -> IpcResponse<SnippetDraft> {
+    let handle = app.clone();
+    handle_authorized_storage_operation(
+        app,
+        token,
+        Permission::WriteVault,
+        move |_, _| async move { Ok(clipboard::draft_from_text(clipboard::read_text(&handle)?)) },
+    )
+    .await
+}
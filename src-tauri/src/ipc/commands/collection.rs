@@ -0,0 +1,212 @@
+use log::info;
+use tauri::AppHandle;
+
+use crate::{
+    auth::Permission,
+    collections::{
+        self, CollectionSnippetsForm, MoveCollectionForm, RenameCollectionForm,
+        ReorderCollectionForm,
+    },
+    database::{
+        handle_authorized_storage_operation,
+        models::{Collection, CollectionForm},
+    },
+    ipc::{
+        params::{DeleteParams, GetParams, PostParams, PutParams},
+        responses::IpcResponse,
+    },
+};
+
+#[tauri::command]
+pub async fn create_collection(
+    app: AppHandle,
+    token: Option<String>,
+    params: PostParams<CollectionForm>,
+) -> IpcResponse<Collection> {
+    info!("Creating collection with name: {}", params.data.name);
+    handle_authorized_storage_operation(
+        app,
+        token,
+        Permission::WriteVault,
+        move |storage, principal| async move {
+            collections::create(storage.as_ref(), &principal, params.data).await
+        },
+    )
+    .await
+}
+
+#[tauri::command]
+pub async fn get_collection(
+    app: AppHandle,
+    token: Option<String>,
+    params: GetParams,
+) -> IpcResponse<Option<Collection>> {
+    info!("Fetching collection with ID: {}", params.id);
+    handle_authorized_storage_operation(
+        app,
+        token,
+        Permission::ReadVault,
+        |storage, principal| async move {
+            let collection = storage.get_collection(params.id).await?;
+            if let Some(collection) = &collection {
+                principal.require_workspace(collection.workspace_id.as_deref())?;
+            }
+            Ok(collection)
+        },
+    )
+    .await
+}
+
+/// Every collection of the active workspace, ordered by position; the
+/// frontend builds the tree from their parent ids.
+#[tauri::command]
+pub async fn list_collections(
+    app: AppHandle,
+    token: Option<String>,
+) -> IpcResponse<Vec<Collection>> {
+    info!("Listing collections");
+    handle_authorized_storage_operation(
+        app,
+        token,
+        Permission::ReadVault,
+        |storage, principal| async move {
+            storage
+                .list_collections(Some(principal.workspace()))
+                .await
+        },
+    )
+    .await
+}
+
+#[tauri::command]
+pub async fn rename_collection(
+    app: AppHandle,
+    token: Option<String>,
+    params: PutParams<RenameCollectionForm>,
+) -> IpcResponse<Collection> {
+    info!("Renaming collection with ID: {}", params.id);
+    handle_authorized_storage_operation(
+        app,
+        token,
+        Permission::WriteVault,
+        move |storage, principal| async move {
+            collections::rename(storage.as_ref(), &principal, params.id, params.data).await
+        },
+    )
+    .await
+}
+
+#[tauri::command]
+pub async fn move_collection(
+    app: AppHandle,
+    token: Option<String>,
+    params: PutParams<MoveCollectionForm>,
+) -> IpcResponse<Collection> {
+    info!(
+        "Moving collection {} under {:?}",
+        params.id, params.data.parent_id
+    );
+    handle_authorized_storage_operation(
+        app,
+        token,
+        Permission::WriteVault,
+        move |storage, principal| async move {
+            collections::move_to(storage.as_ref(), &principal, params.id, params.data).await
+        },
+    )
+    .await
+}
+
+/// Deletes a collection but not its snippets; child collections move up a
+/// level.
+#[tauri::command]
+pub async fn delete_collection(
+    app: AppHandle,
+    token: Option<String>,
+    params: DeleteParams,
+) -> IpcResponse<Collection> {
+    info!("Deleting collection with ID: {}", params.id);
+    handle_authorized_storage_operation(
+        app,
+        token,
+        Permission::WriteVault,
+        move |storage, principal| async move {
+            collections::delete(storage.as_ref(), &principal, params.id).await
+        },
+    )
+    .await
+}
+
+#[tauri::command]
+pub async fn add_snippets_to_collection(
+    app: AppHandle,
+    token: Option<String>,
+    params: PutParams<CollectionSnippetsForm>,
+) -> IpcResponse<Collection> {
+    info!(
+        "Adding {} snippet(s) to collection {}",
+        params.data.snippet_ids.len(),
+        params.id
+    );
+    handle_authorized_storage_operation(
+        app,
+        token,
+        Permission::WriteVault,
+        move |storage, principal| async move {
+            collections::add_snippets(storage.as_ref(), &principal, params.id, params.data).await
+        },
+    )
+    .await
+}
+
+#[tauri::command]
+pub async fn remove_snippets_from_collection(
+    app: AppHandle,
+    token: Option<String>,
+    params: PutParams<CollectionSnippetsForm>,
+) -> IpcResponse<Collection> {
+    info!(
+        "Removing {} snippet(s) from collection {}",
+        params.data.snippet_ids.len(),
+        params.id
+    );
+    handle_authorized_storage_operation(
+        app,
+        token,
+        Permission::WriteVault,
+        move |storage, principal| async move {
+            collections::remove_snippets(
+                storage.as_ref(),
+                &principal,
+                params.id,
+                params.data.snippet_ids,
+            )
+            .await
+        },
+    )
+    .await
+}
+
+#[tauri::command]
+pub async fn reorder_collection_snippets(
+    app: AppHandle,
+    token: Option<String>,
+    params: PutParams<ReorderCollectionForm>,
+) -> IpcResponse<Collection> {
+    info!("Reordering the snippets of collection {}", params.id);
+    handle_authorized_storage_operation(
+        app,
+        token,
+        Permission::WriteVault,
+        move |storage, principal| async move {
+            collections::reorder_snippets(
+                storage.as_ref(),
+                &principal,
+                params.id,
+                params.data.snippet_ids,
+            )
+            .await
+        },
+    )
+    .await
+}
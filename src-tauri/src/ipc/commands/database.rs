@@ -0,0 +1,11 @@
+use tauri::{AppHandle, Manager};
+
+use crate::{
+    database::status::DbStatus, ipc::responses::IpcResponse, prelude::AppResult, state::AppState,
+};
+
+#[tauri::command]
+pub async fn get_db_status(app: AppHandle) -> IpcResponse<DbStatus> {
+    let status: AppResult<DbStatus> = Ok(app.state::<AppState>().db_status.current());
+    status.into()
+}
@@ -0,0 +1,77 @@
+use log::info;
+use tauri::{AppHandle, Manager};
+
+use crate::{
+    auth::Permission,
+    database::handle_authorized_storage_operation,
+    dedup::{self, DuplicateCluster, DuplicateSearchForm, MergeResult, MergeSnippetsForm},
+    error::AppError,
+    git_sync::{commit_deleted_snippet, commit_saved_snippet},
+    ipc::{params::PostParams, responses::IpcResponse},
+    languages,
+    sharing::{Access, Grants},
+    state::AppState,
+};
+
+/// Groups of snippets whose code is the same or nearly so, ignoring
+/// whitespace and comments.
+#[tauri::command]
+pub async fn find_duplicate_snippets(
+    app: AppHandle,
+    token: Option<String>,
+    params: PostParams<DuplicateSearchForm>,
+) -> IpcResponse<Vec<DuplicateCluster>> {
+    info!("Finding duplicate snippets");
+    let threshold = params
+        .data
+        .threshold
+        .unwrap_or(app.state::<AppState>().config.dedup.threshold)
+        .clamp(0.0, 1.0);
+    handle_authorized_storage_operation(
+        app,
+        token,
+        Permission::ReadVault,
+        move |storage, principal| async move {
+            let mut filter = params.data.filter.unwrap_or_default();
+            filter.language = filter.language.as_deref().map(languages::normalize);
+            filter.workspace = Some(principal.workspace());
+            let mut snippets = storage.list_snippets(Some(filter)).await?;
+            let grants = Grants::load(storage.as_ref(), &principal).await?;
+            snippets.retain(|snippet| grants.access(&principal, snippet) >= Access::Read);
+            tokio::task::spawn_blocking(move || dedup::find_clusters(&snippets, threshold))
+                .await
+                .map_err(|e| AppError::Other(format!("Duplicate search failed: {}", e)))
+        },
+    )
+    .await
+}
+
+/// Merges duplicates into one snippet and deletes them.
+#[tauri::command]
+pub async fn merge_duplicate_snippets(
+    app: AppHandle,
+    token: Option<String>,
+    params: PostParams<MergeSnippetsForm>,
+) -> IpcResponse<MergeResult> {
+    info!(
+        "Merging snippets {:?} into {}",
+        params.data.merge_ids, params.data.keep_id
+    );
+    let handle = app.clone();
+    handle_authorized_storage_operation(
+        app,
+        token,
+        Permission::WriteVault,
+        move |storage, principal| async move {
+            let form = params.data;
+            let result =
+                dedup::merge(storage.as_ref(), &principal, form.keep_id, form.merge_ids).await?;
+            commit_saved_snippet(&handle, &result.snippet, false, &principal).await;
+            for snippet in &result.deleted {
+                commit_deleted_snippet(&handle, snippet, &principal).await;
+            }
+            Ok(result)
+        },
+    )
+    .await
+}
@@ -0,0 +1,150 @@
+use log::info;
+use tauri::{AppHandle, Manager};
+
+use crate::{
+    auth::Permission,
+    database::{handle_authorized_storage_operation, models::SnippetForm},
+    encryption::{
+        self, emit_lock_changed, SetSnippetPrivateForm, UnlockVaultForm, VaultLockStatus,
+    },
+    error::AppError,
+    ipc::{params::PostParams, responses::IpcResponse},
+    sharing::{self, Access},
+    snippets::{SavedSnippet, SnippetSaver},
+    state::AppState,
+};
+
+#[tauri::command]
+pub async fn get_vault_lock_status(
+    app: AppHandle,
+    token: Option<String>,
+) -> IpcResponse<VaultLockStatus> {
+    let handle = app.clone();
+    handle_authorized_storage_operation(
+        app,
+        token,
+        Permission::ReadVault,
+        move |storage, principal| async move {
+            let private = encryption::own_private_snippets(storage.as_ref(), &principal).await?;
+            let state = handle.state::<AppState>();
+            Ok(VaultLockStatus {
+                unlocked: state.vault_lock.is_unlocked(&principal),
+                has_private_snippets: !private.is_empty(),
+                auto_lock_minutes: state.config.encryption.auto_lock_minutes,
+            })
+        },
+    )
+    .await
+}
+
+/// Unlocks private snippets with the passphrase. The passphrase is checked
+/// against one of the user's own private snippets; with none yet, it becomes
+/// the passphrase for those encrypted from now on.
+#[tauri::command]
+pub async fn unlock_vault(
+    app: AppHandle,
+    token: Option<String>,
+    params: PostParams<UnlockVaultForm>,
+) -> IpcResponse<()> {
+    let handle = app.clone();
+    handle_authorized_storage_operation(
+        app,
+        token,
+        Permission::ReadVault,
+        move |storage, principal| async move {
+            let sample = encryption::own_private_snippets(storage.as_ref(), &principal)
+                .await?
+                .into_iter()
+                .next()
+                .map(|snippet| snippet.code);
+
+            // Key derivation is deliberately slow.
+            let passphrase = params.data.passphrase;
+            let lock_handle = handle.clone();
+            let lock_principal = principal.clone();
+            tokio::task::spawn_blocking(move || {
+                lock_handle.state::<AppState>().vault_lock.unlock(
+                    &lock_principal,
+                    passphrase,
+                    sample.as_deref(),
+                )
+            })
+            .await
+            .map_err(|e| AppError::Other(format!("Unlocking failed: {}", e)))??;
+
+            info!("Vault unlocked by user {}", principal.user_id);
+            emit_lock_changed(&handle, &principal.user_id, true);
+            Ok(())
+        },
+    )
+    .await
+}
+
+#[tauri::command]
+pub async fn lock_vault(app: AppHandle, token: Option<String>) -> IpcResponse<()> {
+    let handle = app.clone();
+    handle_authorized_storage_operation(
+        app,
+        token,
+        Permission::ReadVault,
+        move |_, principal| async move {
+            handle.state::<AppState>().vault_lock.lock(&principal);
+            info!("Vault locked by user {}", principal.user_id);
+            emit_lock_changed(&handle, &principal.user_id, false);
+            Ok(())
+        },
+    )
+    .await
+}
+
+/// Encrypts a snippet or decrypts it for good. Needs the vault unlocked.
+#[tauri::command]
+pub async fn set_snippet_private(
+    app: AppHandle,
+    token: Option<String>,
+    params: PostParams<SetSnippetPrivateForm>,
+) -> IpcResponse<SavedSnippet> {
+    info!(
+        "Setting snippet {} private: {}",
+        params.data.id, params.data.private
+    );
+    let handle = app.clone();
+    handle_authorized_storage_operation(
+        app,
+        token,
+        Permission::WriteVault,
+        move |storage, principal| async move {
+            let form = params.data;
+            let state = handle.state::<AppState>();
+            if !state.vault_lock.is_unlocked(&principal) {
+                return Err(AppError::VaultLocked);
+            }
+
+            let stored = storage
+                .get_snippet(form.id.clone())
+                .await?
+                .ok_or_else(|| AppError::NotFound("Snippet".into()))?;
+            sharing::access_to(storage.as_ref(), &principal, &stored)
+                .await?
+                .require(Access::Edit, &stored.id)?;
+            if encryption::is_private(&stored) == form.private {
+                return Ok(SavedSnippet {
+                    snippet: state.vault_lock.reveal(&principal, stored),
+                    secret_findings: vec![],
+                });
+            }
+
+            let snippet = state
+                .vault_lock
+                .require_revealed(&principal, stored.clone())?;
+            let update = SnippetForm {
+                is_private: Some(form.private),
+                ..SnippetForm::from(&snippet)
+            };
+            SnippetSaver::new(&handle, storage.as_ref(), &principal)
+                .update(&stored, update)
+                .await
+        },
+    )
+    .await
+}
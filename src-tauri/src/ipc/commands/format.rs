@@ -0,0 +1,88 @@
+use log::info;
+use tauri::{AppHandle, Manager};
+
+use crate::{
+    auth::Permission,
+    database::{handle_authorized_storage_operation, models::SnippetForm},
+    error::AppError,
+    format::{self, FormatReport, FormatSnippetForm},
+    ipc::{params::PostParams, responses::IpcResponse},
+    sharing::{self, Access},
+    snippets::SnippetSaver,
+    state::AppState,
+};
+
+/// Reformats a snippet's code, returning the result and a diff. The snippet
+/// is only updated when `save` is set.
+#[tauri::command]
+pub async fn format_snippet(
+    app: AppHandle,
+    token: Option<String>,
+    params: PostParams<FormatSnippetForm>,
+) -> IpcResponse<FormatReport> {
+    info!(
+        "Formatting snippet {} (save: {})",
+        params.data.id, params.data.save
+    );
+    let handle = app.clone();
+    let permission = if params.data.save {
+        Permission::WriteVault
+    } else {
+        Permission::ReadVault
+    };
+    handle_authorized_storage_operation(
+        app,
+        token,
+        permission,
+        move |storage, principal| async move {
+            let form = params.data;
+            let stored = storage
+                .get_snippet(form.id.clone())
+                .await?
+                .ok_or_else(|| AppError::NotFound("Snippet".into()))?;
+            let needed = if form.save {
+                Access::Edit
+            } else {
+                Access::Read
+            };
+            sharing::access_to(storage.as_ref(), &principal, &stored)
+                .await?
+                .require(needed, &stored.id)?;
+            let state = handle.state::<AppState>();
+            let snippet = state
+                .vault_lock
+                .require_revealed(&principal, stored.clone())?;
+
+            let config = state.config.formatter.clone();
+            let use_external = form.use_external.unwrap_or(config.use_external);
+            let formatted =
+                format::format_code(&snippet.code, &snippet.language, use_external, &config)
+                    .await?;
+
+            let diff = format::unified_diff(&snippet.code, &formatted.code);
+            let changed = !diff.is_empty();
+            let saved = if form.save && changed {
+                let update = SnippetForm {
+                    code: formatted.code.clone(),
+                    ..SnippetForm::from(&snippet)
+                };
+                let saved = SnippetSaver::new(&handle, storage.as_ref(), &principal)
+                    .update(&stored, update)
+                    .await?;
+                Some(saved)
+            } else {
+                None
+            };
+
+            Ok(FormatReport {
+                language: snippet.language,
+                formatter: formatted.formatter.to_string(),
+                formatted: formatted.code,
+                diff,
+                changed,
+                saved,
+            })
+        },
+    )
+    .await
+}
@@ -0,0 +1,95 @@
+use std::sync::Arc;
+
+use log::info;
+use tauri::{AppHandle, Manager};
+
+use crate::{
+    auth::Permission,
+    database::handle_authorized_storage_operation,
+    error::AppError,
+    git_sync::{git::CommitAuthor, ExportReport, GitSync, ImportReport, PullReport},
+    ipc::responses::IpcResponse,
+    prelude::AppResult,
+    snippets::SnippetSaver,
+    state::AppState,
+};
+
+fn git_sync(app: &AppHandle) -> AppResult<Arc<GitSync>> {
+    app.state::<AppState>()
+        .git_sync
+        .clone()
+        .ok_or_else(|| AppError::Other("Git sync is not enabled in the settings".to_string()))
+}
+
+#[tauri::command]
+pub async fn export_to_snippet_repository(
+    app: AppHandle,
+    token: Option<String>,
+) -> IpcResponse<ExportReport> {
+    info!("Exporting the vault to the snippet repository");
+    let handle = app.clone();
+    handle_authorized_storage_operation(
+        app,
+        token,
+        Permission::ManageVault,
+        move |storage, principal| async move {
+            git_sync(&handle)?
+                .export(storage.as_ref(), &CommitAuthor::from(&principal))
+                .await
+        },
+    )
+    .await
+}
+
+#[tauri::command]
+pub async fn import_snippet_repository(
+    app: AppHandle,
+    token: Option<String>,
+) -> IpcResponse<ImportReport> {
+    info!("Importing the snippet repository into the vault");
+    let handle = app.clone();
+    handle_authorized_storage_operation(
+        app,
+        token,
+        Permission::ManageVault,
+        move |storage, principal| async move {
+            let saver = SnippetSaver::new(&handle, storage.as_ref(), &principal).without_mirror();
+            git_sync(&handle)?.import(&saver).await
+        },
+    )
+    .await
+}
+
+#[tauri::command]
+pub async fn pull_snippet_repository(
+    app: AppHandle,
+    token: Option<String>,
+) -> IpcResponse<PullReport> {
+    info!("Pulling the snippet repository");
+    let handle = app.clone();
+    handle_authorized_storage_operation(
+        app,
+        token,
+        Permission::ManageVault,
+        move |storage, principal| async move {
+            let saver = SnippetSaver::new(&handle, storage.as_ref(), &principal).without_mirror();
+            git_sync(&handle)?
+                .pull(&saver, &CommitAuthor::from(&principal))
+                .await
+        },
+    )
+    .await
+}
+
+#[tauri::command]
+pub async fn push_snippet_repository(app: AppHandle, token: Option<String>) -> IpcResponse<()> {
+    info!("Pushing the snippet repository");
+    let handle = app.clone();
+    handle_authorized_storage_operation(
+        app,
+        token,
+        Permission::ManageVault,
+        move |_, _| async move { git_sync(&handle)?.push().await },
+    )
+    .await
+}
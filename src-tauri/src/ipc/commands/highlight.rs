@@ -0,0 +1,53 @@
+use log::info;
+use tauri::{AppHandle, Manager};
+
+use crate::{
+    auth::Permission,
+    database::handle_authorized_storage_operation,
+    error::AppError,
+    highlight::{self, RenderSnippetForm, ThemeInfo, DEFAULT_THEME},
+    ipc::{params::PostParams, responses::IpcResponse},
+    sharing::{self, Access},
+    state::AppState,
+};
+
+/// The themes snippets can be rendered with.
+#[tauri::command]
+pub async fn list_highlight_themes() -> IpcResponse<Vec<ThemeInfo>> {
+    highlight::list_themes().into()
+}
+
+/// Renders a snippet as a standalone, highlighted HTML page.
+#[tauri::command]
+pub async fn render_snippet_html(
+    app: AppHandle,
+    token: Option<String>,
+    params: PostParams<RenderSnippetForm>,
+) -> IpcResponse<String> {
+    info!("Rendering snippet {} as HTML", params.data.id);
+    let handle = app.clone();
+    handle_authorized_storage_operation(
+        app,
+        token,
+        Permission::ReadVault,
+        move |storage, principal| async move {
+            let form = params.data;
+            let snippet = storage
+                .get_snippet(form.id)
+                .await?
+                .ok_or_else(|| AppError::NotFound("Snippet".into()))?;
+            sharing::access_to(storage.as_ref(), &principal, &snippet)
+                .await?
+                .require(Access::Read, &snippet.id)?;
+            let theme = highlight::load_theme(form.theme.as_deref().unwrap_or(DEFAULT_THEME))?;
+
+            let state = handle.state::<AppState>();
+            let snippet = state.vault_lock.require_revealed(&principal, snippet)?;
+            let highlighter = state.highlighter.clone();
+            tokio::task::spawn_blocking(move || highlighter.snippet_to_html(&snippet, &theme))
+                .await
+                .map_err(|e| AppError::Other(format!("Rendering failed: {}", e)))?
+        },
+    )
+    .await
+}
@@ -0,0 +1,142 @@
+use log::info;
+use tauri::{AppHandle, Manager};
+
+use crate::{
+    auth::Permission,
+    database::{handle_authorized_storage_operation, models::Snippet},
+    encryption,
+    interchange::{
+        self, add_tags,
+        files::{self, FileImportForm, FileImportReport},
+        markdown::{self, MarkdownExportOptions, MarkdownImportForm},
+        ImportFormat, SnippetImportForm,
+    },
+    ipc::{params::PostParams, responses::IpcResponse},
+    sharing::{Access, Grants},
+    snippets::{SavedSnippet, SnippetSaver},
+    state::AppState,
+};
+
+#[tauri::command]
+pub async fn export_markdown(
+    app: AppHandle,
+    token: Option<String>,
+    params: PostParams<MarkdownExportOptions>,
+) -> IpcResponse<String> {
+    info!("Exporting snippets as Markdown");
+    let handle = app.clone();
+    handle_authorized_storage_operation(
+        app,
+        token,
+        Permission::ReadVault,
+        move |storage, principal| async move {
+            let mut filter = params.data.filter.unwrap_or_default();
+            filter.workspace = Some(principal.workspace());
+            let mut snippets = encryption::list_matching(storage.as_ref(), &filter).await?;
+            let grants = Grants::load(storage.as_ref(), &principal).await?;
+            snippets.retain(|snippet| grants.access(&principal, snippet) >= Access::Read);
+            // Private snippets are exported only if they can be decrypted.
+            let snippets: Vec<Snippet> = handle
+                .state::<AppState>()
+                .vault_lock
+                .reveal_all(&principal, snippets)
+                .into_iter()
+                .filter(|snippet| !encryption::is_private(snippet))
+                .collect();
+            Ok(markdown::render(params.data.title.as_deref(), &snippets))
+        },
+    )
+    .await
+}
+
+#[tauri::command]
+pub async fn import_markdown(
+    app: AppHandle,
+    token: Option<String>,
+    params: PostParams<MarkdownImportForm>,
+) -> IpcResponse<Vec<SavedSnippet>> {
+    let handle = app.clone();
+    handle_authorized_storage_operation(
+        app,
+        token,
+        Permission::WriteVault,
+        move |storage, principal| async move {
+            let form = params.data;
+            let drafts = ImportFormat::Markdown
+                .importer(form.default_language)
+                .parse(&form.markdown)?;
+            info!("Importing {} snippets from Markdown", drafts.len());
+            let saver = SnippetSaver::new(&handle, storage.as_ref(), &principal);
+            interchange::create_snippets(&saver, drafts).await
+        },
+    )
+    .await
+}
+
+/// Imports snippets from a document in any of the supported formats.
+#[tauri::command]
+pub async fn import_snippets(
+    app: AppHandle,
+    token: Option<String>,
+    params: PostParams<SnippetImportForm>,
+) -> IpcResponse<Vec<SavedSnippet>> {
+    let handle = app.clone();
+    handle_authorized_storage_operation(
+        app,
+        token,
+        Permission::WriteVault,
+        move |storage, principal| async move {
+            let form = params.data;
+            let mut drafts = form
+                .format
+                .importer(form.default_language)
+                .parse(&form.content)?;
+            for draft in &mut drafts {
+                add_tags(draft, &form.tags);
+            }
+            info!("Importing {} snippets ({:?})", drafts.len(), form.format);
+            let saver = SnippetSaver::new(&handle, storage.as_ref(), &principal);
+            interchange::create_snippets(&saver, drafts).await
+        },
+    )
+    .await
+}
+
+#[tauri::command]
+pub async fn import_files(
+    app: AppHandle,
+    token: Option<String>,
+    params: PostParams<FileImportForm>,
+) -> IpcResponse<FileImportReport> {
+    info!(
+        "Importing snippets from {} paths (dry run: {})",
+        params.data.paths.len(),
+        params.data.dry_run
+    );
+    let handle = app.clone();
+    handle_authorized_storage_operation(
+        app,
+        token,
+        Permission::WriteVault,
+        move |storage, principal| async move {
+            let form = params.data;
+            let (files, skipped) = files::read_drafts(form.paths, form.tags).await?;
+
+            let created = if form.dry_run {
+                vec![]
+            } else {
+                let drafts = files.iter().map(|file| file.snippet.clone()).collect();
+                let saver = SnippetSaver::new(&handle, storage.as_ref(), &principal);
+                interchange::create_snippets(&saver, drafts).await?
+            };
+
+            Ok(FileImportReport {
+                dry_run: form.dry_run,
+                files,
+                skipped,
+                created,
+            })
+        },
+    )
+    .await
+}
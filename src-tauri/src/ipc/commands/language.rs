@@ -0,0 +1,23 @@
+use crate::{
+    ipc::{params::PostParams, responses::IpcResponse},
+    languages::{self, DetectLanguageForm, Language, LanguageCandidate},
+    prelude::AppResult,
+};
+
+/// The languages snippets are stored as, with the aliases each is entered as.
+#[tauri::command]
+pub async fn list_languages() -> IpcResponse<&'static [Language]> {
+    let languages: AppResult<&'static [Language]> = Ok(languages::LANGUAGES);
+    languages.into()
+}
+
+/// Ranks the languages the code could be in, most likely first.
+#[tauri::command]
+pub async fn detect_language(
+    params: PostParams<DetectLanguageForm>,
+) -> IpcResponse<Vec<LanguageCandidate>> {
+    let form = params.data;
+    let candidates: AppResult<Vec<LanguageCandidate>> =
+        Ok(languages::detect(&form.code, form.file_name.as_deref()));
+    candidates.into()
+}
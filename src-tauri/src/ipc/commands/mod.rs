@@ -1,4 +1,22 @@
+pub mod api;
 pub mod auth;
+pub mod backup;
+pub mod bundle;
+pub mod clipboard;
+pub mod collection;
+pub mod database;
+pub mod dedup;
+pub mod encryption;
+pub mod format;
+pub mod git_sync;
+pub mod highlight;
+pub mod interchange;
+pub mod language;
+pub mod offline;
+pub mod palette;
+pub mod secrets;
+pub mod sharing;
 pub mod snippet;
 pub mod tag;
 pub mod user;
+pub mod workspace;
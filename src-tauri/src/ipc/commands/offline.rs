@@ -0,0 +1,38 @@
+use crate::{
+    auth::Permission,
+    database::handle_authorized_storage_operation,
+    ipc::{params::DeleteParams, responses::IpcResponse},
+    storage::PendingChange,
+};
+use log::info;
+use tauri::AppHandle;
+
+#[tauri::command]
+pub async fn list_pending_changes(
+    app: AppHandle,
+    token: Option<String>,
+) -> IpcResponse<Vec<PendingChange>> {
+    handle_authorized_storage_operation(
+        app,
+        token,
+        Permission::ReadVault,
+        |storage, _| async move { storage.pending_changes().await },
+    )
+    .await
+}
+
+#[tauri::command]
+pub async fn discard_pending_change(
+    app: AppHandle,
+    token: Option<String>,
+    params: DeleteParams,
+) -> IpcResponse<PendingChange> {
+    info!("Discarding pending change with ID: {}", params.id);
+    handle_authorized_storage_operation(
+        app,
+        token,
+        Permission::WriteVault,
+        |storage, _| async move { storage.discard_pending_change(params.id).await },
+    )
+    .await
+}
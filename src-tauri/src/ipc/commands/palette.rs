@@ -0,0 +1,42 @@
+use log::info;
+use tauri::AppHandle;
+
+use crate::{
+    auth::Permission,
+    clipboard::{self, CopySnippetForm},
+    database::handle_authorized_storage_operation,
+    ipc::{params::GetParams, responses::IpcResponse},
+    palette,
+};
+
+/// Copies a snippet chosen in the quick-search palette, with its
+/// placeholders rendered, and hides the palette.
+#[tauri::command]
+pub async fn copy_palette_snippet(
+    app: AppHandle,
+    token: Option<String>,
+    params: GetParams,
+) -> IpcResponse<()> {
+    info!("Copying snippet {} from the palette", params.id);
+    let handle = app.clone();
+    handle_authorized_storage_operation(
+        app,
+        token,
+        Permission::ReadVault,
+        move |storage, principal| async move {
+            let form = CopySnippetForm {
+                id: params.id,
+                render_placeholders: true,
+            };
+            clipboard::copy_snippet(&handle, storage, &principal, form).await?;
+            palette::hide(&handle)
+        },
+    )
+    .await
+}
+
+/// Hides the palette without copying anything, as on Escape.
+#[tauri::command]
+pub async fn hide_palette(app: AppHandle) -> IpcResponse<()> {
+    palette::hide(&app).into()
+}
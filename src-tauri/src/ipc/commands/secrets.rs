@@ -0,0 +1,57 @@
+use log::info;
+use tauri::{AppHandle, Manager};
+
+use crate::{
+    auth::Permission,
+    database::{handle_authorized_storage_operation, models::SnippetFilter},
+    error::AppError,
+    ipc::responses::IpcResponse,
+    secrets::SnippetSecrets,
+    sharing::{Access, Grants},
+    state::AppState,
+};
+
+/// Scans every snippet the caller can read in the active workspace for secrets, returning those with
+/// findings. Runs regardless of whether scanning on save is enabled.
+#[tauri::command]
+pub async fn scan_vault_for_secrets(
+    app: AppHandle,
+    token: Option<String>,
+) -> IpcResponse<Vec<SnippetSecrets>> {
+    info!("Scanning the vault for secrets");
+    let handle = app.clone();
+    handle_authorized_storage_operation(
+        app,
+        token,
+        Permission::ReadVault,
+        move |storage, principal| async move {
+            let filter = SnippetFilter {
+                workspace: Some(principal.workspace()),
+                ..Default::default()
+            };
+            let mut snippets = storage.list_snippets(Some(filter)).await?;
+            let grants = Grants::load(storage.as_ref(), &principal).await?;
+            snippets.retain(|snippet| grants.access(&principal, snippet) >= Access::Read);
+            let (scanner, config) = {
+                let state = handle.state::<AppState>();
+                (
+                    state.secret_scanner.clone(),
+                    state.config.secret_scan.clone(),
+                )
+            };
+
+            let results = tokio::task::spawn_blocking(move || {
+                snippets
+                    .iter()
+                    .map(|snippet| scanner.scan_snippet(snippet, &config))
+                    .filter(|result| !result.findings.is_empty())
+                    .collect::<Vec<_>>()
+            })
+            .await
+            .map_err(|e| AppError::Other(format!("Secret scan failed: {}", e)))?;
+            info!("Found possible secrets in {} snippet(s)", results.len());
+            Ok(results)
+        },
+    )
+    .await
+}
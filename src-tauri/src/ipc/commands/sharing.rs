@@ -0,0 +1,172 @@
+use log::info;
+use tauri::{AppHandle, Manager};
+
+use crate::{
+    auth::Permission,
+    database::{
+        handle_authorized_storage_operation,
+        models::{Share, UserGroup},
+    },
+    ipc::{
+        params::{DeleteParams, ListParams, PostParams, PutParams},
+        responses::IpcResponse,
+    },
+    sharing::{self, ShareFilter, ShareForm, SharedWithMe, UserGroupForm},
+    state::AppState,
+};
+
+/// Grants a user or group read or edit access to a snippet or collection,
+/// and notifies them.
+#[tauri::command]
+pub async fn share_snippet(
+    app: AppHandle,
+    token: Option<String>,
+    params: PostParams<ShareForm>,
+) -> IpcResponse<Share> {
+    info!(
+        "Sharing snippet {:?} / collection {:?}",
+        params.data.snippet_id, params.data.collection_id
+    );
+    handle_authorized_storage_operation(
+        app,
+        token,
+        Permission::WriteVault,
+        move |storage, principal| async move {
+            sharing::share(storage.as_ref(), &principal, params.data).await
+        },
+    )
+    .await
+}
+
+#[tauri::command]
+pub async fn list_shares(
+    app: AppHandle,
+    token: Option<String>,
+    params: ListParams<ShareFilter>,
+) -> IpcResponse<Vec<Share>> {
+    handle_authorized_storage_operation(
+        app,
+        token,
+        Permission::ReadVault,
+        move |storage, principal| async move {
+            sharing::list_shares(storage.as_ref(), &principal, params.filter).await
+        },
+    )
+    .await
+}
+
+#[tauri::command]
+pub async fn list_shared_with_me(
+    app: AppHandle,
+    token: Option<String>,
+) -> IpcResponse<SharedWithMe> {
+    let handle = app.clone();
+    handle_authorized_storage_operation(
+        app,
+        token,
+        Permission::ReadVault,
+        move |storage, principal| async move {
+            let mut shared = sharing::shared_with_me(storage.as_ref(), &principal).await?;
+            let state = handle.state::<AppState>();
+            let vault_lock = &state.vault_lock;
+            shared.snippets = shared
+                .snippets
+                .into_iter()
+                .map(|mut entry| {
+                    entry.snippet = vault_lock.reveal(&principal, entry.snippet);
+                    entry
+                })
+                .collect();
+            Ok(shared)
+        },
+    )
+    .await
+}
+
+#[tauri::command]
+pub async fn revoke_share(
+    app: AppHandle,
+    token: Option<String>,
+    params: DeleteParams,
+) -> IpcResponse<Share> {
+    info!("Revoking share with ID: {}", params.id);
+    handle_authorized_storage_operation(
+        app,
+        token,
+        Permission::ReadVault,
+        move |storage, principal| async move {
+            sharing::revoke(storage.as_ref(), &principal, params.id).await
+        },
+    )
+    .await
+}
+
+#[tauri::command]
+pub async fn create_user_group(
+    app: AppHandle,
+    token: Option<String>,
+    params: PostParams<UserGroupForm>,
+) -> IpcResponse<UserGroup> {
+    info!("Creating group with name: {}", params.data.name);
+    handle_authorized_storage_operation(
+        app,
+        token,
+        Permission::WriteVault,
+        move |storage, principal| async move {
+            sharing::create_group(storage.as_ref(), &principal, params.data).await
+        },
+    )
+    .await
+}
+
+#[tauri::command]
+pub async fn list_user_groups(
+    app: AppHandle,
+    token: Option<String>,
+) -> IpcResponse<Vec<UserGroup>> {
+    handle_authorized_storage_operation(
+        app,
+        token,
+        Permission::ReadVault,
+        move |storage, principal| async move {
+            sharing::list_groups(storage.as_ref(), &principal).await
+        },
+    )
+    .await
+}
+
+#[tauri::command]
+pub async fn update_user_group(
+    app: AppHandle,
+    token: Option<String>,
+    params: PutParams<UserGroupForm>,
+) -> IpcResponse<UserGroup> {
+    info!("Updating group with ID: {}", params.id);
+    handle_authorized_storage_operation(
+        app,
+        token,
+        Permission::WriteVault,
+        move |storage, principal| async move {
+            sharing::update_group(storage.as_ref(), &principal, params.id, params.data).await
+        },
+    )
+    .await
+}
+
+#[tauri::command]
+pub async fn delete_user_group(
+    app: AppHandle,
+    token: Option<String>,
+    params: DeleteParams,
+) -> IpcResponse<UserGroup> {
+    info!("Deleting group with ID: {}", params.id);
+    handle_authorized_storage_operation(
+        app,
+        token,
+        Permission::WriteVault,
+        move |storage, principal| async move {
+            sharing::delete_group(storage.as_ref(), &principal, params.id).await
+        },
+    )
+    .await
+}
@@ -1,155 +1,131 @@
 use crate::{
+    auth::{Permission, Principal},
     database::{
-        handle_db_operation,
+        handle_authorized_storage_operation,
         models::{Tag, TagFilter, TagForm},
     },
     ipc::{
         params::{DeleteParams, GetParams, ListParams, PostParams, PutParams},
         responses::IpcResponse,
     },
+    prelude::AppResult,
+    storage::Storage,
 };
-use log::{error, info};
+use log::info;
 use tauri::AppHandle;
 
+/// Refuses tags of workspaces other than the caller's active one.
+async fn require_tag_in_workspace(
+    storage: &dyn Storage,
+    principal: &Principal,
+    id: &str,
+) -> AppResult<()> {
+    if let Some(tag) = storage.get_tag(id.to_owned()).await? {
+        principal.require_workspace(tag.workspace_id.as_deref())?;
+    }
+    Ok(())
+}
+
 #[tauri::command]
-pub async fn create_tag(app: AppHandle, params: PostParams<TagForm>) -> IpcResponse<Tag> {
+pub async fn create_tag(
+    app: AppHandle,
+    token: Option<String>,
+    params: PostParams<TagForm>,
+) -> IpcResponse<Tag> {
     info!("Creating tag with name: {}", params.data.name);
-    handle_db_operation(app, move |client| async move {
-        let data = params.data;
-
-        let tag = match client
-            .tag()
-            .create(data.name, vec![prisma::tag::color::set(data.color)])
-            .exec()
-            .await
-        {
-            Ok(tag) => {
-                info!("Successfully created tag with ID: {}", tag.id);
-                tag
-            }
-            Err(e) => {
-                error!("Failed to create tag: {}", e);
-                return Err(e.into());
+    handle_authorized_storage_operation(
+        app,
+        token,
+        Permission::WriteVault,
+        move |storage, principal| async move {
+            let mut form = params.data;
+            form.workspace_id = principal.workspace_id.clone();
+            if let Some(parent_id) = &form.parent_id {
+                require_tag_in_workspace(storage.as_ref(), &principal, parent_id).await?;
             }
-        };
-
-        Ok(tag)
-    })
+            storage.create_tag(form).await
+        },
+    )
     .await
 }
 
 #[tauri::command]
-pub async fn get_tag(app: AppHandle, params: GetParams) -> IpcResponse<Option<Tag>> {
+pub async fn get_tag(
+    app: AppHandle,
+    token: Option<String>,
+    params: GetParams,
+) -> IpcResponse<Option<Tag>> {
     info!("Fetching tag with ID: {}", params.id);
-    handle_db_operation(app, |client| async move {
-        let tag: Option<Tag> = client
-            .tag()
-            .find_unique(prisma::tag::id::equals(params.id.to_owned()))
-            .with(prisma::tag::snippets::fetch(vec![]))
-            .exec()
-            .await?;
-
-        if let Some(tag) = tag.as_ref() {
-            info!("Successfully fetched tag with ID: {}", tag.id);
-        } else {
-            error!("Tag with ID: {} not found", params.id);
-        }
-
-        Ok(tag)
-    })
+    handle_authorized_storage_operation(
+        app,
+        token,
+        Permission::ReadVault,
+        |storage, principal| async move {
+            let tag = storage.get_tag(params.id).await?;
+            if let Some(tag) = &tag {
+                principal.require_workspace(tag.workspace_id.as_deref())?;
+            }
+            Ok(tag)
+        },
+    )
     .await
 }
 
 #[tauri::command]
-pub async fn list_tags(app: AppHandle, params: ListParams<TagFilter>) -> IpcResponse<Vec<Tag>> {
+pub async fn list_tags(
+    app: AppHandle,
+    token: Option<String>,
+    params: ListParams<TagFilter>,
+) -> IpcResponse<Vec<Tag>> {
     info!("Listing tags with filter: {:?}", params.filter);
-    let where_params = build_tag_filters(params.filter);
-    handle_db_operation(app, |client| async move {
-        let tags = match client
-            .tag()
-            .find_many(where_params)
-            .with(prisma::tag::snippets::fetch(vec![]))
-            .exec()
-            .await
-        {
-            Ok(tags) => {
-                info!("Found {} tags", tags.len());
-                tags
-            }
-            Err(e) => {
-                error!("Failed to list tags: {}", e);
-                return Err(e.into());
-            }
-        };
-
-        Ok(tags)
-    })
+    handle_authorized_storage_operation(
+        app,
+        token,
+        Permission::ReadVault,
+        |storage, principal| async move {
+            let mut filter = params.filter.unwrap_or_default();
+            filter.workspace = Some(principal.workspace());
+            storage.list_tags(Some(filter)).await
+        },
+    )
     .await
 }
 
 #[tauri::command]
-pub async fn update_tag(app: AppHandle, params: PutParams<TagForm>) -> IpcResponse<Tag> {
+pub async fn update_tag(
+    app: AppHandle,
+    token: Option<String>,
+    params: PutParams<TagForm>,
+) -> IpcResponse<Tag> {
     info!("Updating tag with ID: {}", params.id);
-    handle_db_operation(app, move |client| async move {
-        let data = params.data;
-
-        let update_params = vec![
-            prisma::tag::name::set(data.name),
-            prisma::tag::color::set(data.color),
-        ];
-
-        let updated_tag = match client
-            .tag()
-            .update(prisma::tag::id::equals(params.id.to_owned()), update_params)
-            .exec()
-            .await
-        {
-            Ok(tag) => {
-                info!("Successfully updated tag with ID: {}", tag.id);
-                tag
-            }
-            Err(e) => {
-                error!("Failed to update tag with ID: {}: {}", params.id, e);
-                return Err(e.into());
-            }
-        };
-
-        Ok(updated_tag)
-    })
+    handle_authorized_storage_operation(
+        app,
+        token,
+        Permission::WriteVault,
+        move |storage, principal| async move {
+            require_tag_in_workspace(storage.as_ref(), &principal, &params.id).await?;
+            storage.update_tag(params.id, params.data).await
+        },
+    )
     .await
 }
 
 #[tauri::command]
-pub async fn delete_tag(app: AppHandle, params: DeleteParams) -> IpcResponse<()> {
+pub async fn delete_tag(
+    app: AppHandle,
+    token: Option<String>,
+    params: DeleteParams,
+) -> IpcResponse<()> {
     info!("Deleting tag with ID: {}", params.id);
-    handle_db_operation(app, |client| async move {
-        let _tag = match client
-            .tag()
-            .delete(prisma::tag::id::equals(params.id.to_owned()))
-            .exec()
-            .await
-        {
-            Ok(tag) => {
-                info!("Successfully deleted tag with ID: {}", tag.id);
-                tag
-            }
-            Err(e) => {
-                error!("Failed to delete tag with ID: {}: {}", params.id, e);
-                return Err(e.into());
-            }
-        };
-
-        Ok(())
-    })
+    handle_authorized_storage_operation(
+        app,
+        token,
+        Permission::WriteVault,
+        |storage, principal| async move {
+            require_tag_in_workspace(storage.as_ref(), &principal, &params.id).await?;
+            storage.delete_tag(params.id).await?;
+            Ok(())
+        },
+    )
     .await
 }
-
-fn build_tag_filters(filter: Option<TagFilter>) -> Vec<prisma::tag::WhereParam> {
-    let mut where_params = vec![];
-    if let Some(filter) = filter {
-        if let Some(name) = filter.name {
-            where_params.push(prisma::tag::name::contains(name));
-        }
-    }
-    where_params
-}
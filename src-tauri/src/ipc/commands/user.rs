@@ -1,10 +1,13 @@
+use prisma::Role;
 use tauri::AppHandle;
 
 use crate::{
+    auth::{effective_role, Permission},
     database::{
-        handle_db_operation,
-        models::{User, UserFilter, UserForm},
+        handle_authenticated_storage_operation, handle_authorized_storage_operation,
+        models::{RoleUpdate, User, UserDataExport, UserDeletionOptions, UserFilter, UserForm},
     },
+    error::AppError,
     ipc::{
         params::{GetParams, ListParams, PutParams},
         responses::IpcResponse,
@@ -12,66 +15,122 @@ use crate::{
 };
 
 #[tauri::command]
-pub async fn get_user(app: AppHandle, params: GetParams) -> IpcResponse<Option<User>> {
-    handle_db_operation(app, |client| async move {
-        let user = client
-            .user()
-            .find_unique(prisma::user::id::equals(params.id))
-            .exec()
-            .await?;
-        Ok(user)
+pub async fn get_user(
+    app: AppHandle,
+    token: Option<String>,
+    params: GetParams,
+) -> IpcResponse<Option<User>> {
+    handle_authenticated_storage_operation(app, token, |storage, principal| async move {
+        principal.require_self_or(&params.id, Permission::ManageUsers)?;
+        storage.get_user(params.id).await
     })
     .await
 }
 
-fn build_user_filters(filter: Option<UserFilter>) -> Vec<prisma::user::WhereParam> {
-    let mut where_params = vec![];
-    if let Some(filter) = filter {
-        if let Some(email) = filter.email {
-            where_params.push(prisma::user::email::contains(email));
-        }
-    }
-    where_params
+#[tauri::command]
+pub async fn list_users(
+    app: AppHandle,
+    token: Option<String>,
+    params: ListParams<UserFilter>,
+) -> IpcResponse<Vec<User>> {
+    handle_authorized_storage_operation(
+        app,
+        token,
+        Permission::ManageUsers,
+        |storage, _| async move { storage.list_users(params.filter).await },
+    )
+    .await
 }
 
 #[tauri::command]
-pub async fn list_users(app: AppHandle, params: ListParams<UserFilter>) -> IpcResponse<Vec<User>> {
-    handle_db_operation(app, |client| async move {
-        let where_params = build_user_filters(params.filter);
-        let users = client.user().find_many(where_params).exec().await?;
-        Ok(users)
+pub async fn update_user(
+    app: AppHandle,
+    token: Option<String>,
+    params: PutParams<UserForm>,
+) -> IpcResponse<User> {
+    handle_authenticated_storage_operation(app, token, |storage, principal| async move {
+        principal.require_self_or(&params.id, Permission::ManageUsers)?;
+        storage
+            .update_user(params.id, params.data.name, params.data.email)
+            .await
     })
     .await
 }
 
 #[tauri::command]
-pub async fn update_user(app: AppHandle, params: PutParams<UserForm>) -> IpcResponse<User> {
-    handle_db_operation(app, |client| async move {
-        let user = client
-            .user()
-            .update(
-                prisma::user::id::equals(params.id),
-                vec![
-                    prisma::user::name::set(params.data.name),
-                    prisma::user::email::set(params.data.email),
-                ],
-            )
-            .exec()
-            .await?;
-        Ok(user)
+pub async fn set_user_role(
+    app: AppHandle,
+    token: Option<String>,
+    params: PutParams<RoleUpdate>,
+) -> IpcResponse<User> {
+    handle_authorized_storage_operation(
+        app,
+        token,
+        Permission::ManageUsers,
+        |storage, principal| async move {
+            // Never leave the vault without an administrator.
+            if principal.is(&params.id) && params.data.role != Role::Admin {
+                let admins = storage.count_users(Some(Role::Admin)).await?;
+                if admins <= 1 {
+                    return Err("Cannot demote the last administrator".into());
+                }
+            }
+
+            storage.set_user_role(params.id, params.data.role).await
+        },
+    )
+    .await
+}
+
+/// Deletes an account together with everything that references it.
+///
+/// Ratings, notifications, rate limits and activity are removed, while the
+/// account's snippets are either handed over to another user or kept without
+/// an author.
+#[tauri::command]
+pub async fn delete_user(
+    app: AppHandle,
+    token: Option<String>,
+    user_id: String,
+    options: Option<UserDeletionOptions>,
+) -> IpcResponse<User> {
+    handle_authenticated_storage_operation(app, token, |storage, principal| async move {
+        principal.require_self_or(&user_id, Permission::ManageUsers)?;
+        let options = options.unwrap_or_default();
+
+        let user = storage
+            .get_user(user_id.clone())
+            .await?
+            .ok_or_else(|| AppError::NotFound("User".into()))?;
+
+        if effective_role(user.role) == Role::Admin {
+            let admins = storage.count_users(Some(Role::Admin)).await?;
+            if admins <= 1 {
+                return Err("Cannot delete the last administrator".into());
+            }
+        }
+
+        if let Some(new_author_id) = &options.transfer_snippets_to {
+            if *new_author_id == user_id {
+                return Err("Cannot transfer snippets to the account being deleted".into());
+            }
+            storage
+                .get_user(new_author_id.clone())
+                .await?
+                .ok_or_else(|| AppError::NotFound("Snippet transfer target".into()))?;
+        }
+
+        storage.delete_user(user_id, options).await
     })
     .await
 }
 
+/// Collects everything tied to the caller's account into a single archive,
+/// so it can be saved before the account is deleted.
 #[tauri::command]
-pub async fn delete_user(app: AppHandle, user_id: String) -> IpcResponse<User> {
-    handle_db_operation(app, |client| async move {
-        let user = client
-            .user()
-            .delete(prisma::user::id::equals(user_id))
-            .exec()
-            .await?;
-        Ok(user)
+pub async fn export_my_data(app: AppHandle, token: Option<String>) -> IpcResponse<UserDataExport> {
+    handle_authenticated_storage_operation(app, token, |storage, principal| async move {
+        storage.export_user_data(principal.user_id).await
     })
     .await
 }
@@ -0,0 +1,130 @@
+use log::info;
+use tauri::{AppHandle, Manager};
+
+use crate::{
+    database::{
+        handle_authenticated_storage_operation,
+        models::{AuthPayload, Workspace, WorkspaceInfo, WorkspaceMembership},
+    },
+    error::AppError,
+    ipc::{
+        params::{DeleteParams, GetParams, PostParams, PutParams},
+        responses::IpcResponse,
+    },
+    state::AppState,
+    workspaces::{self, SwitchWorkspaceForm, WorkspaceForm, WorkspaceMemberForm},
+};
+
+#[tauri::command]
+pub async fn create_workspace(
+    app: AppHandle,
+    token: Option<String>,
+    params: PostParams<WorkspaceForm>,
+) -> IpcResponse<WorkspaceInfo> {
+    info!("Creating workspace with name: {}", params.data.name);
+    handle_authenticated_storage_operation(app, token, move |storage, principal| async move {
+        workspaces::create(storage.as_ref(), &principal, params.data).await
+    })
+    .await
+}
+
+/// The workspaces the caller belongs to, with their role in each.
+#[tauri::command]
+pub async fn list_workspaces(
+    app: AppHandle,
+    token: Option<String>,
+) -> IpcResponse<Vec<WorkspaceInfo>> {
+    handle_authenticated_storage_operation(app, token, |storage, principal| async move {
+        workspaces::workspaces_of(storage.as_ref(), &principal.user_id).await
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn rename_workspace(
+    app: AppHandle,
+    token: Option<String>,
+    params: PutParams<WorkspaceForm>,
+) -> IpcResponse<Workspace> {
+    info!("Renaming workspace with ID: {}", params.id);
+    handle_authenticated_storage_operation(app, token, move |storage, principal| async move {
+        workspaces::rename(storage.as_ref(), &principal, params.id, params.data).await
+    })
+    .await
+}
+
+/// Deletes a workspace that no longer holds any snippets, tags or
+/// collections.
+#[tauri::command]
+pub async fn delete_workspace(
+    app: AppHandle,
+    token: Option<String>,
+    params: DeleteParams,
+) -> IpcResponse<Workspace> {
+    info!("Deleting workspace with ID: {}", params.id);
+    handle_authenticated_storage_operation(app, token, move |storage, principal| async move {
+        workspaces::delete(storage.as_ref(), &principal, params.id).await
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn list_workspace_members(
+    app: AppHandle,
+    token: Option<String>,
+    params: GetParams,
+) -> IpcResponse<Vec<WorkspaceMembership>> {
+    handle_authenticated_storage_operation(app, token, move |storage, principal| async move {
+        workspaces::list_members(storage.as_ref(), &principal, params.id).await
+    })
+    .await
+}
+
+/// Adds a member to a workspace or changes their role.
+#[tauri::command]
+pub async fn set_workspace_member(
+    app: AppHandle,
+    token: Option<String>,
+    params: PutParams<WorkspaceMemberForm>,
+) -> IpcResponse<WorkspaceMembership> {
+    info!(
+        "Setting user {} as {:?} of workspace {}",
+        params.data.user_id, params.data.role, params.id
+    );
+    handle_authenticated_storage_operation(app, token, move |storage, principal| async move {
+        workspaces::set_member(storage.as_ref(), &principal, params.id, params.data).await
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn remove_workspace_member(
+    app: AppHandle,
+    token: Option<String>,
+    params: PutParams<String>,
+) -> IpcResponse<WorkspaceMembership> {
+    info!("Removing user {} from workspace {}", params.data, params.id);
+    handle_authenticated_storage_operation(app, token, move |storage, principal| async move {
+        workspaces::remove_member(storage.as_ref(), &principal, params.id, params.data).await
+    })
+    .await
+}
+
+/// Issues new tokens for another workspace; every later command works in it.
+#[tauri::command]
+pub async fn switch_workspace(
+    app: AppHandle,
+    token: Option<String>,
+    params: PostParams<SwitchWorkspaceForm>,
+) -> IpcResponse<AuthPayload> {
+    info!("Switching to workspace {:?}", params.data.workspace_id);
+    let secret = app.state::<AppState>().token_secret.clone();
+    handle_authenticated_storage_operation(app, token, move |storage, principal| async move {
+        let user = storage
+            .get_user(principal.user_id.clone())
+            .await?
+            .ok_or_else(|| AppError::Unauthorized("unknown user".into()))?;
+        workspaces::issue_tokens(storage.as_ref(), &secret, user, params.data.workspace_id).await
+    })
+    .await
+}
@@ -5,6 +5,10 @@ use serde::Serialize;
 #[derive(Serialize)]
 pub struct IpcError {
     pub message: String,
+    /// The HTTP status the local API answers with; not part of the IPC
+    /// payload.
+    #[serde(skip)]
+    pub http_status: u16,
 }
 
 /// Represents a simple result for IPC communication, containing data.
@@ -66,6 +70,7 @@ where
                 response: Response::Error {
                     error: IpcError {
                         message: format!("{}", err),
+                        http_status: err.http_status(),
                     },
                 },
             },
@@ -10,6 +10,8 @@
 use chrono::Local;
 use rodio::{Decoder, OutputStream, Sink};
 use serde::{Deserialize, Serialize};
+// The language registry is shared with the command-line client.
+use snippet_vault_shared::languages;
 use std::io::Cursor;
 use std::sync::{
     atomic::{AtomicUsize, Ordering},
@@ -20,16 +22,38 @@ use tauri::{
     SystemTrayMenuItem, SystemTraySubmenu,
 };
 
+mod api;
+mod auth;
+mod backup;
+mod bundle;
+mod clipboard;
+mod collections;
+mod config;
 mod database;
+mod dedup;
+mod encryption;
 mod error;
+mod format;
+mod git_sync;
+mod highlight;
+mod interchange;
 mod ipc;
+mod palette;
 mod prelude;
+mod secrets;
+mod sharing;
+mod snippets;
 mod state;
+mod storage;
+mod workspaces;
 
 use ipc::commands;
+use palette::PALETTE_WINDOW;
 use prelude::AppResult;
 use state::init_state;
 
+const MAIN_WINDOW: &str = "main";
+
 // Include the sound file as bytes
 const NOTIFICATION_SOUND: &[u8] = include_bytes!("../assets/notification_sound.wav");
 
@@ -77,7 +101,12 @@ fn main() -> AppResult<()> {
         MenuItemType::Normal(MenuItem {
             id: "toggle_window".into(),
             title: "Toggle Window".into(),
-            handler: Arc::new(|app, _| toggle_window(app)),
+            handler: Arc::new(|app, _| toggle_window(app, MAIN_WINDOW)),
+        }),
+        MenuItemType::Normal(MenuItem {
+            id: "toggle_palette".into(),
+            title: "Quick Search".into(),
+            handler: Arc::new(|app, _| toggle_window(app, PALETTE_WINDOW)),
         }),
         MenuItemType::Separator,
         MenuItemType::Normal(MenuItem {
@@ -114,7 +143,7 @@ fn main() -> AppResult<()> {
         .manage(app_state.clone())
         .system_tray(SystemTray::new().with_menu(tray_menu))
         .on_system_tray_event(move |app, event| match event {
-            SystemTrayEvent::LeftClick { .. } => toggle_window(app),
+            SystemTrayEvent::LeftClick { .. } => toggle_window(app, MAIN_WINDOW),
             SystemTrayEvent::MenuItemClick { id, .. } => {
                 handle_menu_item_click(app, &id, &menu_items, &app_state)
             }
@@ -125,14 +154,20 @@ fn main() -> AppResult<()> {
                 .set_tooltip("Welcome to the Snippet Vault")
                 .expect("Failed to set default tooltip");
 
-            tauri::async_runtime::block_on(async {
-                init_state(app.handle())
-                    .await
-                    .expect("Failed to initialize state");
-            });
+            tauri::async_runtime::block_on(init_state(app.handle()));
+
+            let handle = app.handle();
+            if let Err(e) = palette::register(&app.handle(), move || {
+                toggle_window(&handle, PALETTE_WINDOW)
+            }) {
+                log::error!("The quick search palette is disabled: {}", e);
+            }
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
+            commands::api::create_api_token,
+            commands::api::list_api_tokens,
+            commands::api::revoke_api_token,
             commands::auth::login,
             commands::auth::register,
             commands::auth::refresh_token,
@@ -140,6 +175,58 @@ fn main() -> AppResult<()> {
             commands::user::list_users,
             commands::user::update_user,
             commands::user::delete_user,
+            commands::user::set_user_role,
+            commands::user::export_my_data,
+            commands::collection::create_collection,
+            commands::collection::get_collection,
+            commands::collection::list_collections,
+            commands::collection::rename_collection,
+            commands::collection::move_collection,
+            commands::collection::delete_collection,
+            commands::collection::add_snippets_to_collection,
+            commands::collection::remove_snippets_from_collection,
+            commands::collection::reorder_collection_snippets,
+            commands::database::get_db_status,
+            commands::backup::create_backup,
+            commands::backup::list_backups,
+            commands::backup::restore_backup,
+            commands::bundle::export_bundle,
+            commands::bundle::import_bundle,
+            commands::bundle::get_bundle_signing_key,
+            commands::clipboard::copy_snippet,
+            commands::clipboard::create_snippet_from_clipboard,
+            commands::git_sync::export_to_snippet_repository,
+            commands::git_sync::import_snippet_repository,
+            commands::git_sync::pull_snippet_repository,
+            commands::git_sync::push_snippet_repository,
+            commands::dedup::find_duplicate_snippets,
+            commands::dedup::merge_duplicate_snippets,
+            commands::encryption::get_vault_lock_status,
+            commands::encryption::unlock_vault,
+            commands::encryption::lock_vault,
+            commands::encryption::set_snippet_private,
+            commands::format::format_snippet,
+            commands::highlight::list_highlight_themes,
+            commands::highlight::render_snippet_html,
+            commands::interchange::export_markdown,
+            commands::interchange::import_markdown,
+            commands::interchange::import_snippets,
+            commands::interchange::import_files,
+            commands::language::list_languages,
+            commands::language::detect_language,
+            commands::offline::list_pending_changes,
+            commands::offline::discard_pending_change,
+            commands::palette::copy_palette_snippet,
+            commands::palette::hide_palette,
+            commands::secrets::scan_vault_for_secrets,
+            commands::sharing::share_snippet,
+            commands::sharing::list_shares,
+            commands::sharing::list_shared_with_me,
+            commands::sharing::revoke_share,
+            commands::sharing::create_user_group,
+            commands::sharing::list_user_groups,
+            commands::sharing::update_user_group,
+            commands::sharing::delete_user_group,
             commands::snippet::create_snippet,
             commands::snippet::get_snippet,
             commands::snippet::list_snippets,
@@ -150,7 +237,15 @@ fn main() -> AppResult<()> {
             commands::tag::get_tag,
             commands::tag::list_tags,
             commands::tag::update_tag,
-            commands::tag::delete_tag
+            commands::tag::delete_tag,
+            commands::workspace::create_workspace,
+            commands::workspace::list_workspaces,
+            commands::workspace::rename_workspace,
+            commands::workspace::delete_workspace,
+            commands::workspace::list_workspace_members,
+            commands::workspace::set_workspace_member,
+            commands::workspace::remove_workspace_member,
+            commands::workspace::switch_workspace
         ])
         .build(tauri::generate_context!())
         .expect("error while building tauri application");
@@ -224,8 +319,10 @@ fn handle_menu_item_click(
     }
 }
 
-fn toggle_window(app: &AppHandle) {
-    if let Some(window) = app.get_window("main") {
+/// Shows and focuses the window with this label, or hides it when it is
+/// visible: the main window from the tray, the palette from its shortcut.
+fn toggle_window(app: &AppHandle, label: &str) {
+    if let Some(window) = app.get_window(label) {
         if window.is_visible().unwrap_or(false) {
             window.hide().unwrap();
         } else {
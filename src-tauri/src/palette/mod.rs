@@ -0,0 +1,76 @@
+//! The quick-search palette: a small window above every other, opened with
+//! a global shortcut, that finds a snippet as you type and copies it.
+//!
+//! The window is created hidden at startup so that it opens at once, and
+//! hides itself again when it loses focus.
+
+use log::{info, warn};
+use tauri::{AppHandle, GlobalShortcutManager, Manager, WindowBuilder, WindowEvent, WindowUrl};
+
+use crate::{error::AppError, prelude::AppResult, state::AppState};
+
+pub const PALETTE_WINDOW: &str = "palette";
+
+/// Emitted to the palette whenever it gains focus, so it starts from an
+/// empty query.
+pub const PALETTE_OPENED_EVENT: &str = "palette-opened";
+
+const PALETTE_URL: &str = "palette";
+const PALETTE_WIDTH: f64 = 640.0;
+const PALETTE_HEIGHT: f64 = 400.0;
+
+/// Creates the palette window and registers its shortcut, which calls
+/// `toggle`. Does nothing when the palette is disabled in settings.
+pub fn register<F>(app: &AppHandle, toggle: F) -> AppResult<()>
+where
+    F: Fn() + Send + 'static,
+{
+    let config = app.state::<AppState>().config.palette.clone();
+    if !config.enabled {
+        return Ok(());
+    }
+
+    let window = WindowBuilder::new(app, PALETTE_WINDOW, WindowUrl::App(PALETTE_URL.into()))
+        .title("Quick Search")
+        .inner_size(PALETTE_WIDTH, PALETTE_HEIGHT)
+        .resizable(false)
+        .decorations(false)
+        .always_on_top(true)
+        .skip_taskbar(true)
+        .center()
+        .visible(false)
+        .build()?;
+
+    let handle = window.clone();
+    window.on_window_event(move |event| match event {
+        WindowEvent::Focused(true) => {
+            if let Err(e) = handle.emit(PALETTE_OPENED_EVENT, ()) {
+                warn!("Failed to emit the palette opening: {}", e);
+            }
+        }
+        WindowEvent::Focused(false) => {
+            if let Err(e) = handle.hide() {
+                warn!("Failed to hide the palette: {}", e);
+            }
+        }
+        _ => {}
+    });
+
+    app.global_shortcut_manager()
+        .register(&config.shortcut, toggle)
+        .map_err(|e| {
+            AppError::Other(format!(
+                "Could not register the palette shortcut {}: {}",
+                config.shortcut, e
+            ))
+        })?;
+    info!("Quick search palette opens with {}", config.shortcut);
+    Ok(())
+}
+
+pub fn hide(app: &AppHandle) -> AppResult<()> {
+    if let Some(window) = app.get_window(PALETTE_WINDOW) {
+        window.hide()?;
+    }
+    Ok(())
+}
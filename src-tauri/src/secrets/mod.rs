@@ -0,0 +1,215 @@
+//! Scanning snippet code for credentials pasted into it: well-known key and
+//! token formats, literals assigned to password-like names and long
+//! random-looking strings.
+
+pub mod rules;
+
+use regex::Regex;
+use schemars::JsonSchema;
+use serde::Serialize;
+use tauri::{AppHandle, Manager};
+
+use crate::{
+    config::SecretScanConfig, database::models::Snippet, encryption, error::AppError,
+    prelude::AppResult, state::AppState,
+};
+
+pub use rules::SecretKind;
+
+/// Lines containing this are not scanned, for keys that are meant to be
+/// there, like test fixtures.
+const IGNORE_MARKER: &str = "secret-scan:ignore";
+/// Characters visible at the start of a finding's preview.
+const PREVIEW_LENGTH: usize = 4;
+const HEX_MIN_LENGTH: usize = 32;
+/// Hex has 16 symbols, so its strings top out at 4 bits of entropy per
+/// character and are held to a lower bar.
+const HEX_ENTROPY_THRESHOLD: f64 = 3.0;
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SecretFinding {
+    pub kind: SecretKind,
+    pub description: String,
+    /// 1-based line and column of the secret's first character.
+    pub line: usize,
+    pub column: usize,
+    /// Length of the secret in characters.
+    pub length: usize,
+    /// The start of the secret, the rest masked.
+    pub preview: String,
+}
+
+/// Findings in one snippet.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SnippetSecrets {
+    pub snippet_id: String,
+    pub title: String,
+    pub findings: Vec<SecretFinding>,
+}
+
+/// Holds the compiled patterns for the life of the app.
+pub struct SecretScanner {
+    rules: Vec<rules::Rule>,
+    words: Regex,
+}
+
+impl Default for SecretScanner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SecretScanner {
+    pub fn new() -> Self {
+        Self {
+            rules: rules::compile(),
+            // Slashes are left out so paths and URLs split into their
+            // segments.
+            words: Regex::new(r"[A-Za-z0-9+=_\-]+").expect("word pattern is valid"),
+        }
+    }
+
+    /// Possible secrets in `code`, in order of position.
+    pub fn scan(&self, code: &str, config: &SecretScanConfig) -> Vec<SecretFinding> {
+        let mut spans: Vec<(usize, usize, SecretKind)> = vec![];
+
+        for rule in &self.rules {
+            for captures in rule.pattern.captures_iter(code) {
+                if let Some(secret) = captures.get(rule.group) {
+                    push_span(&mut spans, secret.start(), secret.end(), rule.kind);
+                }
+            }
+        }
+
+        for word in self.words.find_iter(code) {
+            let text = word.as_str();
+            if text.len() >= config.min_entropy_length
+                && looks_random(text, config.entropy_threshold)
+            {
+                push_span(
+                    &mut spans,
+                    word.start(),
+                    word.end(),
+                    SecretKind::HighEntropyString,
+                );
+            }
+        }
+
+        spans.sort_by_key(|&(start, _, _)| start);
+        spans
+            .into_iter()
+            .filter_map(|(start, end, kind)| {
+                let line_start = code[..start].rfind('\n').map_or(0, |index| index + 1);
+                let line_end = code[start..]
+                    .find('\n')
+                    .map_or(code.len(), |index| start + index);
+                if code[line_start..line_end].contains(IGNORE_MARKER) {
+                    return None;
+                }
+
+                let secret = &code[start..end];
+                let length = secret.chars().count();
+                let visible: String = secret
+                    .chars()
+                    .take(PREVIEW_LENGTH.min(length / 4))
+                    .collect();
+                Some(SecretFinding {
+                    kind,
+                    description: format!("Possible {}", kind.label()),
+                    line: code[..start].matches('\n').count() + 1,
+                    column: code[line_start..start].chars().count() + 1,
+                    length,
+                    preview: format!(
+                        "{}{}",
+                        visible,
+                        "*".repeat(length - visible.chars().count())
+                    ),
+                })
+            })
+            .collect()
+    }
+
+    /// Private snippets have no findings: their code is encrypted.
+    pub fn scan_snippet(&self, snippet: &Snippet, config: &SecretScanConfig) -> SnippetSecrets {
+        let findings = if encryption::is_private(snippet) {
+            vec![]
+        } else {
+            self.scan(&snippet.code, config)
+        };
+        SnippetSecrets {
+            snippet_id: snippet.id.clone(),
+            title: snippet.title.clone(),
+            findings,
+        }
+    }
+}
+
+/// Scans code about to be saved. Fails if secrets are found and the
+/// settings block saving them; otherwise returns the findings for the caller
+/// to warn about once saved.
+pub fn check_before_save(app: &AppHandle, code: &str) -> AppResult<Vec<SecretFinding>> {
+    let state = app.state::<AppState>();
+    let config = &state.config.secret_scan;
+    if !config.enabled || encryption::is_encrypted(code) {
+        return Ok(vec![]);
+    }
+
+    let findings = state.secret_scanner.scan(code, config);
+    if config.block_on_save && !findings.is_empty() {
+        return Err(AppError::SecretsDetected(summarize(&findings)));
+    }
+    Ok(findings)
+}
+
+/// A one-line summary of the findings, for errors and logs.
+pub fn summarize(findings: &[SecretFinding]) -> String {
+    findings
+        .iter()
+        .map(|finding| format!("{} on line {}", finding.kind.label(), finding.line))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Adds a span unless it overlaps one found before, so a key matched by a
+/// specific rule isn't reported again as a high-entropy string.
+fn push_span(
+    spans: &mut Vec<(usize, usize, SecretKind)>,
+    start: usize,
+    end: usize,
+    kind: SecretKind,
+) {
+    if !spans.iter().any(|&(s, e, _)| start < e && s < end) {
+        spans.push((start, end, kind));
+    }
+}
+
+fn looks_random(text: &str, threshold: f64) -> bool {
+    // Identifiers and paths rarely mix in digits; random strings nearly
+    // always do.
+    if !text.bytes().any(|b| b.is_ascii_digit()) || !text.bytes().any(|b| b.is_ascii_alphabetic()) {
+        return false;
+    }
+    if text.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return text.len() >= HEX_MIN_LENGTH && shannon_entropy(text) >= HEX_ENTROPY_THRESHOLD;
+    }
+    shannon_entropy(text) >= threshold
+}
+
+/// Bits of entropy per character.
+fn shannon_entropy(text: &str) -> f64 {
+    let mut counts = [0usize; 256];
+    for byte in text.bytes() {
+        counts[byte as usize] += 1;
+    }
+    let length = text.len() as f64;
+    counts
+        .iter()
+        .filter(|&&count| count > 0)
+        .map(|&count| {
+            let p = count as f64 / length;
+            -p * p.log2()
+        })
+        .sum()
+}
@@ -0,0 +1,93 @@
+//! Patterns of well-known credential formats.
+
+use regex::Regex;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum SecretKind {
+    AwsAccessKeyId,
+    AwsSecretAccessKey,
+    PrivateKey,
+    Jwt,
+    GithubToken,
+    SlackToken,
+    /// A literal assigned to something named like a password or key.
+    Credential,
+    /// A long random-looking string, which may be a key or token.
+    HighEntropyString,
+}
+
+impl SecretKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            SecretKind::AwsAccessKeyId => "AWS access key ID",
+            SecretKind::AwsSecretAccessKey => "AWS secret access key",
+            SecretKind::PrivateKey => "private key",
+            SecretKind::Jwt => "JSON Web Token",
+            SecretKind::GithubToken => "GitHub token",
+            SecretKind::SlackToken => "Slack token",
+            SecretKind::Credential => "hard-coded credential",
+            SecretKind::HighEntropyString => "high-entropy string",
+        }
+    }
+}
+
+pub struct Rule {
+    pub kind: SecretKind,
+    pub pattern: Regex,
+    /// Capture group holding the secret itself; 0 for the whole match.
+    pub group: usize,
+}
+
+const PATTERNS: &[(SecretKind, &str, usize)] = &[
+    (
+        SecretKind::PrivateKey,
+        r"-----BEGIN (?:[A-Z0-9]+ )*PRIVATE KEY(?: BLOCK)?-----",
+        0,
+    ),
+    (
+        SecretKind::AwsAccessKeyId,
+        r"\b(?:AKIA|ASIA|AGPA|AIDA|AROA|ANPA|ANVA|AIPA)[A-Z0-9]{16}\b",
+        0,
+    ),
+    (
+        SecretKind::AwsSecretAccessKey,
+        r#"(?i)aws.{0,20}?(?:secret|private).{0,20}?["'`:=\s]([A-Za-z0-9/+]{40})(?:[^A-Za-z0-9/+]|$)"#,
+        1,
+    ),
+    (
+        SecretKind::Jwt,
+        r"\beyJ[A-Za-z0-9_-]{10,}\.eyJ[A-Za-z0-9_-]{10,}\.[A-Za-z0-9_-]{10,}",
+        0,
+    ),
+    (
+        SecretKind::GithubToken,
+        r"\b(?:gh[pousr]_[A-Za-z0-9]{36,}|github_pat_[A-Za-z0-9_]{60,})\b",
+        0,
+    ),
+    (
+        SecretKind::SlackToken,
+        r"\bxox[abposr]-[A-Za-z0-9-]{10,}",
+        0,
+    ),
+    // Values starting like a placeholder, such as `${VAR}`, `<token>`,
+    // `{{ secret }}` or `%s`, are left alone.
+    (
+        SecretKind::Credential,
+        r#"(?i)(?:password|passwd|pwd|secret|api_?key|access_?token|auth_?token)["']?\s*(?::|=|:=|=>)\s*["']([^"'\s$<{%][^"'\s]{5,})["']"#,
+        1,
+    ),
+];
+
+pub fn compile() -> Vec<Rule> {
+    PATTERNS
+        .iter()
+        .map(|&(kind, pattern, group)| Rule {
+            kind,
+            pattern: Regex::new(pattern).expect("secret patterns are valid"),
+            group,
+        })
+        .collect()
+}
@@ -0,0 +1,669 @@
+//! Sharing snippets with other users and groups.
+//!
+//! A snippet belongs to its author, who can share it for reading or editing
+//! with a user or a group. Sharing a collection shares every snippet in it and
+//! in the collections nested under it that the sharer wrote; snippets of
+//! other authors in the collection stay as shared as they were. Snippets
+//! without an author predate sharing and stay open to everyone, and admins
+//! reach every snippet.
+//!
+//! Ownership only counts inside the caller's active workspace; snippets of
+//! other workspaces are reached through shares alone.
+//!
+//! Shares and groups are only stored in MongoDB. With the local backend, or
+//! while offline, users only reach their own snippets and unowned ones.
+
+use std::collections::{HashMap, HashSet};
+
+use log::{info, warn};
+use prisma::{PrismaClient, ShareAccess};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    auth::{Permission, Principal},
+    database::models::{Collection, Share, Snippet, UserGroup},
+    error::AppError,
+    prelude::AppResult,
+    storage::Storage,
+};
+
+/// How far a user may go with a snippet, weakest first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Access {
+    None,
+    Read,
+    Edit,
+    /// May also delete the snippet and share it.
+    Owner,
+}
+
+impl From<ShareAccess> for Access {
+    fn from(access: ShareAccess) -> Self {
+        match access {
+            ShareAccess::Read => Access::Read,
+            ShareAccess::Edit => Access::Edit,
+        }
+    }
+}
+
+impl Access {
+    pub fn require(self, needed: Access, snippet_id: &str) -> AppResult<()> {
+        if self >= needed {
+            return Ok(());
+        }
+        Err(AppError::Forbidden(match self {
+            Access::None => format!("snippet {} isn't shared with you", snippet_id),
+            Access::Read if needed == Access::Edit => {
+                format!("snippet {} is shared with you read-only", snippet_id)
+            }
+            _ => format!("only the owner of snippet {} can do this", snippet_id),
+        }))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShareForm {
+    /// The snippet to share; set either this or `collection_id`.
+    pub snippet_id: Option<String>,
+    pub collection_id: Option<String>,
+    /// The recipient; set either this or `group_id`.
+    pub user_id: Option<String>,
+    pub group_id: Option<String>,
+    pub access: ShareAccess,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShareFilter {
+    pub snippet_id: Option<String>,
+    pub collection_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserGroupForm {
+    pub name: String,
+    #[serde(default)]
+    pub member_ids: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SharedSnippet {
+    pub share_id: String,
+    pub access: ShareAccess,
+    pub granted_by_id: String,
+    pub snippet: Snippet,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SharedCollection {
+    pub share_id: String,
+    pub access: ShareAccess,
+    pub granted_by_id: String,
+    pub collection: Collection,
+}
+
+/// Everything other users shared with the caller.
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SharedWithMe {
+    pub snippets: Vec<SharedSnippet>,
+    pub collections: Vec<SharedCollection>,
+}
+
+/// Whether the caller owns the snippet outright.
+fn owns(principal: &Principal, snippet: &Snippet) -> bool {
+    if !principal
+        .workspace()
+        .contains(snippet.workspace_id.as_deref())
+    {
+        return false;
+    }
+    principal.can(Permission::ManageVault)
+        || snippet
+            .author_id
+            .as_deref()
+            .map_or(true, |author_id| principal.is(author_id))
+}
+
+/// The client holding shares, or none when they aren't available.
+fn shares_client(storage: &dyn Storage) -> Option<std::sync::Arc<PrismaClient>> {
+    storage.prisma().ok()
+}
+
+fn forbidden(message: &str) -> AppError {
+    AppError::Forbidden(message.to_owned())
+}
+
+/// Shares whose recipient is the user or one of the user's groups, leaving
+/// out those the user granted to a group they belong to.
+async fn shares_with(client: &PrismaClient, user_id: &str) -> AppResult<Vec<Share>> {
+    let group_ids: Vec<String> = client
+        .user_group()
+        .find_many(vec![prisma::user_group::member_ids::has(
+            user_id.to_owned(),
+        )])
+        .exec()
+        .await?
+        .into_iter()
+        .map(|group| group.id)
+        .collect();
+
+    let shares = client
+        .share()
+        .find_many(vec![prisma::or![
+            prisma::share::user_id::equals(Some(user_id.to_owned())),
+            prisma::share::group_id::in_vec(group_ids),
+        ]])
+        .exec()
+        .await?
+        .into_iter()
+        .filter(|share| share.granted_by_id != user_id)
+        .collect();
+    Ok(shares)
+}
+
+/// The snippets shared with a user, directly or through collections, with
+/// the strongest access granted to each.
+#[derive(Default)]
+pub struct Grants {
+    snippets: HashMap<String, Access>,
+    /// Access through shared collections, by snippet, along with who shared
+    /// the collection. It only counts for snippets that user wrote, so that
+    /// putting someone else's snippet in a collection never shares it.
+    through_collections: HashMap<String, Vec<(String, Access)>>,
+}
+
+impl Grants {
+    pub async fn load(storage: &dyn Storage, principal: &Principal) -> AppResult<Self> {
+        let mut grants = Self::default();
+        let client = match shares_client(storage) {
+            Some(client) => client,
+            None => return Ok(grants),
+        };
+
+        let shares = shares_with(&client, &principal.user_id).await?;
+        let mut shared_collections: HashMap<String, Vec<(String, Access)>> = HashMap::new();
+        for share in shares {
+            let access = Access::from(share.access);
+            if let Some(snippet_id) = share.snippet_id {
+                grants.grant(snippet_id, access);
+            }
+            if let Some(collection_id) = share.collection_id {
+                shared_collections
+                    .entry(collection_id)
+                    .or_default()
+                    .push((share.granted_by_id, access));
+            }
+        }
+        if shared_collections.is_empty() {
+            return Ok(grants);
+        }
+
+        let collections = storage.list_collections(None).await?;
+        for collection in &collections {
+            // The shares on the collection and every collection above it.
+            let mut shared = vec![];
+            let mut visited = HashSet::new();
+            let mut current = Some(collection);
+            while let Some(ancestor) = current {
+                if !visited.insert(ancestor.id.as_str()) {
+                    break;
+                }
+                if let Some(shares) = shared_collections.get(&ancestor.id) {
+                    shared.extend(shares.iter().cloned());
+                }
+                current = ancestor.parent_id.as_ref().and_then(|parent_id| {
+                    collections
+                        .iter()
+                        .find(|collection| collection.id == *parent_id)
+                });
+            }
+
+            if !shared.is_empty() {
+                for snippet_id in &collection.snippet_ids {
+                    grants
+                        .through_collections
+                        .entry(snippet_id.clone())
+                        .or_default()
+                        .extend(shared.iter().cloned());
+                }
+            }
+        }
+        Ok(grants)
+    }
+
+    fn grant(&mut self, snippet_id: String, access: Access) {
+        let entry = self.snippets.entry(snippet_id).or_insert(access);
+        *entry = (*entry).max(access);
+    }
+
+    pub fn access(&self, principal: &Principal, snippet: &Snippet) -> Access {
+        if owns(principal, snippet) {
+            return Access::Owner;
+        }
+        let shared = self
+            .snippets
+            .get(&snippet.id)
+            .copied()
+            .unwrap_or(Access::None);
+        let through_collections = self
+            .through_collections
+            .get(&snippet.id)
+            .into_iter()
+            .flatten()
+            .filter(|(granted_by_id, _)| {
+                snippet
+                    .author_id
+                    .as_deref()
+                    .map_or(false, |author_id| author_id == granted_by_id)
+            })
+            .map(|(_, access)| *access)
+            .max()
+            .unwrap_or(Access::None);
+        shared.max(through_collections)
+    }
+}
+
+/// The caller's access to a single snippet.
+pub async fn access_to(
+    storage: &dyn Storage,
+    principal: &Principal,
+    snippet: &Snippet,
+) -> AppResult<Access> {
+    if owns(principal, snippet) {
+        return Ok(Access::Owner);
+    }
+    Ok(Grants::load(storage, principal)
+        .await?
+        .access(principal, snippet))
+}
+
+/// Grants access to a snippet or collection and notifies the recipients.
+/// Sharing the same thing with the same recipient again changes the access
+/// of the existing share.
+pub async fn share(
+    storage: &dyn Storage,
+    principal: &Principal,
+    form: ShareForm,
+) -> AppResult<Share> {
+    let client = storage.prisma()?;
+
+    let subject = match (&form.snippet_id, &form.collection_id) {
+        (Some(snippet_id), None) => {
+            let snippet = storage
+                .get_snippet(snippet_id.clone())
+                .await?
+                .ok_or_else(|| AppError::NotFound(format!("Snippet with ID {}", snippet_id)))?;
+            if !owns(principal, &snippet) {
+                return Err(forbidden("only the owner of a snippet can share it"));
+            }
+            format!("the snippet \"{}\"", snippet.title)
+        }
+        (None, Some(collection_id)) => {
+            let collection = storage
+                .get_collection(collection_id.clone())
+                .await?
+                .ok_or_else(|| {
+                    AppError::NotFound(format!("Collection with ID {}", collection_id))
+                })?;
+            principal.require_workspace(collection.workspace_id.as_deref())?;
+            format!("the collection \"{}\"", collection.name)
+        }
+        _ => {
+            return Err(AppError::Other(
+                "Share either a snippet or a collection".into(),
+            ))
+        }
+    };
+
+    let recipients = match (&form.user_id, &form.group_id) {
+        (Some(user_id), None) => {
+            if principal.is(user_id) {
+                return Err(AppError::Other("You can't share with yourself".into()));
+            }
+            storage
+                .get_user(user_id.clone())
+                .await?
+                .ok_or_else(|| AppError::NotFound(format!("User with ID {}", user_id)))?;
+            vec![user_id.clone()]
+        }
+        (None, Some(group_id)) => {
+            client
+                .user_group()
+                .find_unique(prisma::user_group::id::equals(group_id.clone()))
+                .exec()
+                .await?
+                .ok_or_else(|| AppError::NotFound(format!("Group with ID {}", group_id)))?
+                .member_ids
+        }
+        _ => {
+            return Err(AppError::Other(
+                "Share with either a user or a group".into(),
+            ))
+        }
+    };
+
+    let existing = client
+        .share()
+        .find_first(vec![
+            prisma::share::snippet_id::equals(form.snippet_id.clone()),
+            prisma::share::collection_id::equals(form.collection_id.clone()),
+            prisma::share::user_id::equals(form.user_id.clone()),
+            prisma::share::group_id::equals(form.group_id.clone()),
+        ])
+        .exec()
+        .await?;
+    let share = match existing {
+        Some(existing) => {
+            client
+                .share()
+                .update(
+                    prisma::share::id::equals(existing.id),
+                    vec![prisma::share::access::set(form.access)],
+                )
+                .exec()
+                .await?
+        }
+        None => {
+            client
+                .share()
+                .create(
+                    principal.user_id.clone(),
+                    vec![
+                        prisma::share::access::set(form.access),
+                        prisma::share::snippet_id::set(form.snippet_id),
+                        prisma::share::collection_id::set(form.collection_id),
+                        prisma::share::user_id::set(form.user_id),
+                        prisma::share::group_id::set(form.group_id),
+                    ],
+                )
+                .exec()
+                .await?
+        }
+    };
+    info!(
+        "{} shared {} ({:?})",
+        principal.email, subject, share.access
+    );
+
+    let access = match share.access {
+        ShareAccess::Read => "read",
+        ShareAccess::Edit => "edit",
+    };
+    let message = format!(
+        "{} shared {} with you ({} access)",
+        principal.email, subject, access
+    );
+    for recipient in recipients.into_iter().filter(|id| !principal.is(id)) {
+        if let Err(e) = client
+            .notification()
+            .create(
+                message.clone(),
+                prisma::user::id::equals(recipient.clone()),
+                vec![],
+            )
+            .exec()
+            .await
+        {
+            warn!("Failed to notify user {} of a share: {}", recipient, e);
+        }
+    }
+
+    Ok(share)
+}
+
+/// Shares of a snippet or collection, or those the caller granted when no
+/// filter is given.
+pub async fn list_shares(
+    storage: &dyn Storage,
+    principal: &Principal,
+    filter: Option<ShareFilter>,
+) -> AppResult<Vec<Share>> {
+    let client = storage.prisma()?;
+    let filter = filter.unwrap_or(ShareFilter {
+        snippet_id: None,
+        collection_id: None,
+    });
+
+    let mut where_params = vec![];
+    if let Some(snippet_id) = filter.snippet_id {
+        let snippet = storage
+            .get_snippet(snippet_id.clone())
+            .await?
+            .ok_or_else(|| AppError::NotFound(format!("Snippet with ID {}", snippet_id)))?;
+        if !owns(principal, &snippet) {
+            return Err(forbidden("only the owner of a snippet can see its shares"));
+        }
+        where_params.push(prisma::share::snippet_id::equals(Some(snippet_id)));
+    }
+    if let Some(collection_id) = filter.collection_id {
+        let collection = storage
+            .get_collection(collection_id.clone())
+            .await?
+            .ok_or_else(|| AppError::NotFound(format!("Collection with ID {}", collection_id)))?;
+        principal.require_workspace(collection.workspace_id.as_deref())?;
+        // Collections have no owner, so besides admins everyone only sees
+        // the shares they granted themselves.
+        if !principal.can(Permission::ManageVault) {
+            where_params.push(prisma::share::granted_by_id::equals(
+                principal.user_id.clone(),
+            ));
+        }
+        where_params.push(prisma::share::collection_id::equals(Some(collection_id)));
+    }
+    if where_params.is_empty() {
+        where_params.push(prisma::share::granted_by_id::equals(
+            principal.user_id.clone(),
+        ));
+    }
+
+    let shares = client.share().find_many(where_params).exec().await?;
+    Ok(shares)
+}
+
+pub async fn shared_with_me(
+    storage: &dyn Storage,
+    principal: &Principal,
+) -> AppResult<SharedWithMe> {
+    let client = storage.prisma()?;
+    let mut shared = SharedWithMe::default();
+
+    for share in shares_with(&client, &principal.user_id).await? {
+        if let Some(snippet_id) = share.snippet_id {
+            // Shares of deleted snippets are skipped.
+            if let Some(snippet) = storage.get_snippet(snippet_id).await? {
+                shared.snippets.push(SharedSnippet {
+                    share_id: share.id,
+                    access: share.access,
+                    granted_by_id: share.granted_by_id,
+                    snippet,
+                });
+            }
+        } else if let Some(collection_id) = share.collection_id {
+            if let Some(collection) = storage.get_collection(collection_id).await? {
+                shared.collections.push(SharedCollection {
+                    share_id: share.id,
+                    access: share.access,
+                    granted_by_id: share.granted_by_id,
+                    collection,
+                });
+            }
+        }
+    }
+    Ok(shared)
+}
+
+/// Removes a share. Whoever granted it, the owner of the shared snippet and
+/// an admin may revoke it, and a user may leave a share made to them.
+pub async fn revoke(storage: &dyn Storage, principal: &Principal, id: String) -> AppResult<Share> {
+    let client = storage.prisma()?;
+    let share = client
+        .share()
+        .find_unique(prisma::share::id::equals(id.clone()))
+        .exec()
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Share with ID {}", id)))?;
+
+    let mut allowed = principal.can(Permission::ManageVault)
+        || principal.is(&share.granted_by_id)
+        || share
+            .user_id
+            .as_deref()
+            .map_or(false, |user_id| principal.is(user_id));
+    if !allowed {
+        if let Some(snippet_id) = &share.snippet_id {
+            if let Some(snippet) = storage.get_snippet(snippet_id.clone()).await? {
+                allowed = owns(principal, &snippet);
+            }
+        }
+    }
+    if !allowed {
+        return Err(forbidden("you can't revoke this share"));
+    }
+
+    let share = client
+        .share()
+        .delete(prisma::share::id::equals(id))
+        .exec()
+        .await?;
+    info!("Revoked share {}", share.id);
+    Ok(share)
+}
+
+/// Drops the shares of a deleted snippet.
+pub async fn forget_snippet(storage: &dyn Storage, snippet_id: &str) {
+    let client = match shares_client(storage) {
+        Some(client) => client,
+        None => return,
+    };
+    if let Err(e) = client
+        .share()
+        .delete_many(vec![prisma::share::snippet_id::equals(Some(
+            snippet_id.to_owned(),
+        ))])
+        .exec()
+        .await
+    {
+        warn!(
+            "Failed to remove the shares of snippet {}: {}",
+            snippet_id, e
+        );
+    }
+}
+
+fn group_form(form: UserGroupForm) -> AppResult<(String, Vec<String>)> {
+    let name = form.name.trim().to_owned();
+    if name.is_empty() {
+        return Err(AppError::Other("Group names can't be empty".into()));
+    }
+    let mut member_ids: Vec<String> = vec![];
+    for id in form.member_ids {
+        if !member_ids.contains(&id) {
+            member_ids.push(id);
+        }
+    }
+    Ok((name, member_ids))
+}
+
+async fn owned_group(
+    client: &PrismaClient,
+    principal: &Principal,
+    id: &str,
+) -> AppResult<UserGroup> {
+    let group = client
+        .user_group()
+        .find_unique(prisma::user_group::id::equals(id.to_owned()))
+        .exec()
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Group with ID {}", id)))?;
+    if !principal.is(&group.owner_id) && !principal.can(Permission::ManageUsers) {
+        return Err(forbidden("only the owner of a group can change it"));
+    }
+    Ok(group)
+}
+
+pub async fn create_group(
+    storage: &dyn Storage,
+    principal: &Principal,
+    form: UserGroupForm,
+) -> AppResult<UserGroup> {
+    let client = storage.prisma()?;
+    let (name, member_ids) = group_form(form)?;
+    let group = client
+        .user_group()
+        .create(
+            name,
+            principal.user_id.clone(),
+            vec![prisma::user_group::member_ids::set(member_ids)],
+        )
+        .exec()
+        .await?;
+    info!("Created group with ID: {}", group.id);
+    Ok(group)
+}
+
+/// Groups the caller owns or belongs to.
+pub async fn list_groups(
+    storage: &dyn Storage,
+    principal: &Principal,
+) -> AppResult<Vec<UserGroup>> {
+    let client = storage.prisma()?;
+    let groups = client
+        .user_group()
+        .find_many(vec![prisma::or![
+            prisma::user_group::owner_id::equals(principal.user_id.clone()),
+            prisma::user_group::member_ids::has(principal.user_id.clone()),
+        ]])
+        .exec()
+        .await?;
+    Ok(groups)
+}
+
+pub async fn update_group(
+    storage: &dyn Storage,
+    principal: &Principal,
+    id: String,
+    form: UserGroupForm,
+) -> AppResult<UserGroup> {
+    let client = storage.prisma()?;
+    owned_group(&client, principal, &id).await?;
+    let (name, member_ids) = group_form(form)?;
+    let group = client
+        .user_group()
+        .update(
+            prisma::user_group::id::equals(id),
+            vec![
+                prisma::user_group::name::set(name),
+                prisma::user_group::member_ids::set(member_ids),
+            ],
+        )
+        .exec()
+        .await?;
+    Ok(group)
+}
+
+/// Deletes a group along with everything shared with it.
+pub async fn delete_group(
+    storage: &dyn Storage,
+    principal: &Principal,
+    id: String,
+) -> AppResult<UserGroup> {
+    let client = storage.prisma()?;
+    owned_group(&client, principal, &id).await?;
+    client
+        .share()
+        .delete_many(vec![prisma::share::group_id::equals(Some(id.clone()))])
+        .exec()
+        .await?;
+    let group = client
+        .user_group()
+        .delete(prisma::user_group::id::equals(id))
+        .exec()
+        .await?;
+    info!("Deleted group with ID: {}", group.id);
+    Ok(group)
+}
@@ -0,0 +1,155 @@
+//! The one way snippets are saved. Every creation and update, whether from
+//! the snippet form, an import, the formatter or a git pull, goes through
+//! [`SnippetSaver`], which resolves the language, scans the code for secrets,
+//! seals private snippets and mirrors the result to git.
+
+use log::warn;
+use serde::Serialize;
+use tauri::{AppHandle, Manager};
+
+use crate::{
+    auth::Principal,
+    database::models::{Snippet, SnippetForm},
+    encryption,
+    git_sync::commit_saved_snippet,
+    languages,
+    prelude::AppResult,
+    secrets::{self, SecretFinding, SnippetSecrets},
+    state::AppState,
+    storage::Storage,
+};
+
+/// A saved snippet, as the caller may read it, with the possible secrets
+/// found in its code for the caller to warn about.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SavedSnippet {
+    #[serde(flatten)]
+    pub snippet: Snippet,
+    pub secret_findings: Vec<SecretFinding>,
+}
+
+impl SavedSnippet {
+    /// The findings for a report covering many snippets; none when the code
+    /// looked clean.
+    pub fn secrets(&self) -> Option<SnippetSecrets> {
+        if self.secret_findings.is_empty() {
+            return None;
+        }
+        Some(SnippetSecrets {
+            snippet_id: self.snippet.id.clone(),
+            title: self.snippet.title.clone(),
+            findings: self.secret_findings.clone(),
+        })
+    }
+}
+
+pub struct SnippetSaver<'a> {
+    app: &'a AppHandle,
+    storage: &'a dyn Storage,
+    principal: &'a Principal,
+    /// Whether saves are committed to the git mirror.
+    mirror: bool,
+}
+
+impl<'a> SnippetSaver<'a> {
+    /// Saves as `principal`, who the caller has checked may write.
+    pub fn new(app: &'a AppHandle, storage: &'a dyn Storage, principal: &'a Principal) -> Self {
+        Self {
+            app,
+            storage,
+            principal,
+            mirror: true,
+        }
+    }
+
+    /// Leaves the git mirror alone, for saves that come from it.
+    pub fn without_mirror(mut self) -> Self {
+        self.mirror = false;
+        self
+    }
+
+    pub fn storage(&self) -> &'a dyn Storage {
+        self.storage
+    }
+
+    pub fn principal(&self) -> &'a Principal {
+        self.principal
+    }
+
+    /// Scans code about to be saved, failing when the settings block saving
+    /// secrets. Batches check every snippet up front with this, so a blocked
+    /// one doesn't leave them half saved.
+    pub fn check(&self, code: &str) -> AppResult<Vec<SecretFinding>> {
+        secrets::check_before_save(self.app, code)
+    }
+
+    /// Creates a snippet authored by the principal, in their active
+    /// workspace.
+    pub async fn create(&self, mut form: SnippetForm) -> AppResult<SavedSnippet> {
+        form.language = languages::resolve(&form.language, &form.code);
+        form.workspace_id = self.principal.workspace_id.clone();
+        let findings = self.check(&form.code)?;
+        let state = self.app.state::<AppState>();
+        state.vault_lock.seal_form(
+            self.principal,
+            &mut form,
+            encryption::wants_private(&form, None),
+            None,
+        )?;
+
+        let snippet = self
+            .storage
+            .create_snippet(form, Some(self.principal.user_id.clone()))
+            .await?;
+        if self.mirror {
+            commit_saved_snippet(self.app, &snippet, true, self.principal).await;
+        }
+        Ok(self.saved(snippet, findings))
+    }
+
+    /// Updates `existing`, the stored snippet the caller has checked the
+    /// principal may edit.
+    pub async fn update(
+        &self,
+        existing: &Snippet,
+        mut form: SnippetForm,
+    ) -> AppResult<SavedSnippet> {
+        form.language = languages::resolve(&form.language, &form.code);
+        let findings = self.check(&form.code)?;
+        let state = self.app.state::<AppState>();
+        state.vault_lock.seal_form(
+            self.principal,
+            &mut form,
+            encryption::wants_private(&form, Some(existing)),
+            Some(existing),
+        )?;
+
+        let snippet = self
+            .storage
+            .update_snippet(existing.id.clone(), form)
+            .await?;
+        if self.mirror {
+            commit_saved_snippet(self.app, &snippet, false, self.principal).await;
+        }
+        Ok(self.saved(snippet, findings))
+    }
+
+    fn saved(&self, snippet: Snippet, findings: Vec<SecretFinding>) -> SavedSnippet {
+        if !findings.is_empty() {
+            warn!(
+                "Snippet {} may contain secrets: {}",
+                snippet.id,
+                secrets::summarize(&findings)
+            );
+        }
+        SavedSnippet {
+            snippet: self
+                .app
+                .state::<AppState>()
+                .vault_lock
+                .reveal(self.principal, snippet),
+            secret_findings: findings,
+        }
+    }
+}
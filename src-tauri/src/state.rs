@@ -1,17 +1,62 @@
 use async_trait::async_trait;
-use prisma::PrismaClient;
-use std::{future::Future, sync::Arc};
+use log::{error, info};
+use std::{future::Future, path::PathBuf, sync::Arc};
 use tauri::{AppHandle, Manager};
 
-use crate::{database::init_db, error::AppError};
+use crate::{
+    api::{spawn_api_server, tokens::ApiTokens},
+    auth::TokenSecret,
+    backup::schedule::spawn_backup_scheduler,
+    clipboard::{spawn_clipboard_watcher, ClipboardTracker},
+    config::{AppConfig, StorageBackend},
+    database::{
+        monitor::spawn_connection_monitor,
+        status::{ConnectionState, DbStatusTracker},
+    },
+    encryption::{spawn_auto_lock, VaultLock},
+    error::AppError,
+    git_sync::GitSync,
+    highlight::Highlighter,
+    secrets::SecretScanner,
+    storage::{LocalStorage, MongoStorage, OfflineStorage, Storage},
+};
+
+const OFFLINE_CACHE_DIR: &str = "offline-cache";
 
 pub struct AppState {
-    pub db: Arc<PrismaClient>,
+    pub config: AppConfig,
+    pub storage: Arc<dyn Storage>,
+    pub db_status: DbStatusTracker,
+    pub token_secret: Arc<TokenSecret>,
+    pub api_tokens: ApiTokens,
+    pub git_sync: Option<Arc<GitSync>>,
+    pub highlighter: Arc<Highlighter>,
+    pub secret_scanner: Arc<SecretScanner>,
+    pub vault_lock: VaultLock,
+    pub clipboard: ClipboardTracker,
 }
 
 impl AppState {
-    pub fn new(db: PrismaClient) -> Self {
-        Self { db: db.into() }
+    pub fn new(
+        config: AppConfig,
+        storage: Arc<dyn Storage>,
+        db_status: DbStatusTracker,
+        token_secret: Arc<TokenSecret>,
+        api_tokens: ApiTokens,
+        git_sync: Option<Arc<GitSync>>,
+    ) -> Self {
+        Self {
+            config,
+            storage,
+            db_status,
+            token_secret,
+            api_tokens,
+            git_sync,
+            highlighter: Arc::new(Highlighter::new()),
+            secret_scanner: Arc::new(SecretScanner::new()),
+            vault_lock: VaultLock::default(),
+            clipboard: ClipboardTracker::default(),
+        }
     }
 }
 
@@ -22,6 +67,12 @@ pub trait ServiceAccess {
         F: FnOnce(Arc<prisma::PrismaClient>) -> Fut + Send + Sync + 'static,
         Fut: Future<Output = Result<TResult, AppError>> + Send + 'static,
         TResult: Send + 'static;
+
+    async fn storage<F, Fut, TResult>(&self, operation: F) -> Result<TResult, AppError>
+    where
+        F: FnOnce(Arc<dyn Storage>) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<TResult, AppError>> + Send + 'static,
+        TResult: Send + 'static;
 }
 
 #[async_trait]
@@ -33,15 +84,188 @@ impl ServiceAccess for AppHandle {
         TResult: Send + 'static,
     {
         let app_state: tauri::State<AppState> = self.state();
-        let db_client = Arc::clone(&app_state.db);
+        let db_client = app_state.storage.prisma()?;
 
         operation(db_client).await
     }
+
+    async fn storage<F, Fut, TResult>(&self, operation: F) -> Result<TResult, AppError>
+    where
+        F: FnOnce(Arc<dyn Storage>) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<TResult, AppError>> + Send + 'static,
+        TResult: Send + 'static,
+    {
+        let app_state: tauri::State<AppState> = self.state();
+        let storage = Arc::clone(&app_state.storage);
+
+        operation(storage).await
+    }
 }
 
-pub async fn init_state(app: AppHandle) -> Result<(), AppError> {
-    let client = init_db().await;
-    let app_state = AppState::new(client);
-    app.manage(app_state);
-    Ok(())
+/// Directory that holds the local vault, or the offline cache of the MongoDB
+/// backend.
+fn data_dir(app: &AppHandle, config: &AppConfig) -> Result<PathBuf, AppError> {
+    config
+        .storage
+        .local_path
+        .clone()
+        .or_else(|| app.path_resolver().app_data_dir())
+        .ok_or_else(|| "Could not resolve the app data directory".into())
+}
+
+/// Where the offline cache goes when it can't be opened in the data
+/// directory. Changes made while offline are then only kept until the app
+/// quits.
+fn fallback_cache_dir() -> PathBuf {
+    std::env::temp_dir()
+        .join("snippet-vault")
+        .join(OFFLINE_CACHE_DIR)
+}
+
+/// This installation's token signing key. Without a usable app data
+/// directory a key is made up for the session, so everyone signs in again
+/// after a restart.
+fn load_token_secret(app: &AppHandle) -> TokenSecret {
+    let loaded = app
+        .path_resolver()
+        .app_data_dir()
+        .ok_or_else(|| AppError::from("Could not resolve the app data directory"))
+        .and_then(|dir| TokenSecret::load_or_create(&dir));
+    loaded.unwrap_or_else(|e| {
+        error!("Sessions won't survive a restart: {}", e);
+        TokenSecret::generate()
+    })
+}
+
+/// The API tokens issued on this installation. Without a usable app data
+/// directory they only last for the session.
+async fn load_api_tokens(app: &AppHandle) -> ApiTokens {
+    let dir = match app.path_resolver().app_data_dir() {
+        Some(dir) => dir,
+        None => {
+            error!("API tokens won't survive a restart: no app data directory");
+            return ApiTokens::in_memory();
+        }
+    };
+    ApiTokens::open(&dir).await.unwrap_or_else(|e| {
+        error!("API tokens won't survive a restart: {}", e);
+        ApiTokens::in_memory()
+    })
+}
+
+/// Opens the offline cache of the MongoDB backend, falling back to a
+/// temporary one so that the app still starts when the data directory is
+/// unusable.
+async fn open_offline_storage(
+    remote: Arc<MongoStorage>,
+    dir: Result<PathBuf, AppError>,
+) -> Result<OfflineStorage, AppError> {
+    let error = match dir {
+        Ok(dir) => match OfflineStorage::open(remote.clone(), dir.join(OFFLINE_CACHE_DIR)).await {
+            Ok(storage) => return Ok(storage),
+            Err(e) => e,
+        },
+        Err(e) => e,
+    };
+    let fallback = fallback_cache_dir();
+    error!(
+        "Could not open the offline cache, using {} for this session: {}",
+        fallback.display(),
+        error
+    );
+    OfflineStorage::open(remote, fallback).await
+}
+
+/// Sets up the managed [`AppState`]. Never fails: the MongoDB backend
+/// connects in the background, and a vault that can't be opened leaves the
+/// app running with the error reported as its database status.
+pub async fn init_state(app: AppHandle) {
+    let config = AppConfig::load(&app).unwrap_or_else(|e| {
+        error!("Failed to load settings, using defaults: {}", e);
+        AppConfig::default()
+    });
+    let dir = data_dir(&app, &config);
+    let token_secret = Arc::new(load_token_secret(&app));
+    let api_tokens = load_api_tokens(&app).await;
+    let git_sync = match GitSync::open(&app, &config.git_sync).await {
+        Ok(git_sync) => git_sync.map(Arc::new),
+        Err(e) => {
+            error!("Git sync is disabled: {}", e);
+            None
+        }
+    };
+
+    let backend = config.storage.backend;
+    let opened: Result<(Arc<dyn Storage>, Option<Arc<OfflineStorage>>), AppError> = match backend {
+        StorageBackend::Mongodb => {
+            let remote = Arc::new(MongoStorage::default());
+            open_offline_storage(remote, dir).await.map(|storage| {
+                let storage = Arc::new(storage);
+                (storage.clone() as Arc<dyn Storage>, Some(storage))
+            })
+        }
+        StorageBackend::Local => match dir {
+            Ok(dir) => LocalStorage::open(dir)
+                .await
+                .map(|storage| (Arc::new(storage) as Arc<dyn Storage>, None)),
+            Err(e) => Err(e),
+        },
+    };
+
+    match opened {
+        Ok((storage, Some(offline))) => {
+            info!("Using the {} storage backend", storage.backend_name());
+            let db_status =
+                DbStatusTracker::new(storage.backend_name(), ConnectionState::Connecting);
+            app.manage(AppState::new(
+                config,
+                storage,
+                db_status,
+                token_secret,
+                api_tokens,
+                git_sync,
+            ));
+            spawn_connection_monitor(app.clone(), offline);
+        }
+        Ok((storage, None)) => {
+            info!("Using the {} storage backend", storage.backend_name());
+            let db_status =
+                DbStatusTracker::new(storage.backend_name(), ConnectionState::Connected);
+            app.manage(AppState::new(
+                config,
+                storage,
+                db_status,
+                token_secret,
+                api_tokens,
+                git_sync,
+            ));
+        }
+        Err(e) => {
+            // Every storage command fails with `DatabaseUnavailable` until
+            // the problem is fixed and the app restarted.
+            error!("Failed to open the vault: {}", e);
+            let storage: Arc<dyn Storage> = Arc::new(MongoStorage::default());
+            let db_status = DbStatusTracker::new(backend.name(), ConnectionState::Disconnected);
+            app.manage(AppState::new(
+                config,
+                storage,
+                db_status,
+                token_secret,
+                api_tokens,
+                git_sync,
+            ));
+            app.state::<AppState>().db_status.update(
+                &app,
+                ConnectionState::Disconnected,
+                Some(e.to_string()),
+                None,
+            );
+            return;
+        }
+    }
+
+    spawn_auto_lock(app.clone());
+    spawn_api_server(app.clone());
+    spawn_clipboard_watcher(app.clone());
+    spawn_backup_scheduler(app);
 }
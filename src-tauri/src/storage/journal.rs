@@ -0,0 +1,325 @@
+use std::{io::ErrorKind, path::PathBuf};
+
+use chrono::{DateTime, FixedOffset, Utc};
+use log::debug;
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+
+use super::new_object_id;
+use crate::{
+    database::models::{Snippet, Tag},
+    prelude::AppResult,
+};
+
+const JOURNAL_FILE_NAME: &str = "journal.json";
+
+/// The fields of a snippet needed to recreate or update it remotely.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SnippetChange {
+    pub id: String,
+    pub title: String,
+    pub description: String,
+    pub language: String,
+    pub code: String,
+    pub tag_ids: Vec<String>,
+    pub snippet_state_id: String,
+    pub author_id: Option<String>,
+    #[serde(default)]
+    pub workspace_id: Option<String>,
+}
+
+impl From<&Snippet> for SnippetChange {
+    fn from(snippet: &Snippet) -> Self {
+        Self {
+            id: snippet.id.clone(),
+            title: snippet.title.clone(),
+            description: snippet.description.clone(),
+            language: snippet.language.clone(),
+            code: snippet.code.clone(),
+            tag_ids: snippet.tag_ids.clone(),
+            snippet_state_id: snippet.snippet_state_id.clone(),
+            author_id: snippet.author_id.clone(),
+            workspace_id: snippet.workspace_id.clone(),
+        }
+    }
+}
+
+/// The fields of a tag needed to recreate or update it remotely.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TagChange {
+    pub id: String,
+    pub name: String,
+    pub color: Option<String>,
+    #[serde(default)]
+    pub emoji: Option<String>,
+    #[serde(default)]
+    pub parent_id: Option<String>,
+    #[serde(default)]
+    pub workspace_id: Option<String>,
+}
+
+impl From<&Tag> for TagChange {
+    fn from(tag: &Tag) -> Self {
+        Self {
+            id: tag.id.clone(),
+            name: tag.name.clone(),
+            color: tag.color.clone(),
+            emoji: tag.emoji.clone(),
+            parent_id: tag.parent_id.clone(),
+            workspace_id: tag.workspace_id.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum ChangeOperation {
+    CreateSnippet {
+        snippet: SnippetChange,
+    },
+    UpdateSnippet {
+        snippet: SnippetChange,
+    },
+    #[serde(rename_all = "camelCase")]
+    DeleteSnippet {
+        id: String,
+    },
+    #[serde(rename_all = "camelCase")]
+    UpdateSnippetState {
+        id: String,
+        is_favorite: bool,
+        is_dark: bool,
+    },
+    /// Copies counted offline, added to the server's count on replay.
+    #[serde(rename_all = "camelCase")]
+    RecordSnippetCopies {
+        id: String,
+        copies: i32,
+        last_copied_at: DateTime<FixedOffset>,
+    },
+    CreateTag {
+        tag: TagChange,
+    },
+    UpdateTag {
+        tag: TagChange,
+    },
+    DeleteTag {
+        id: String,
+    },
+}
+
+impl ChangeOperation {
+    /// The id of the record this operation touches.
+    pub fn target_id(&self) -> &str {
+        match self {
+            ChangeOperation::CreateSnippet { snippet }
+            | ChangeOperation::UpdateSnippet { snippet } => &snippet.id,
+            ChangeOperation::CreateTag { tag } | ChangeOperation::UpdateTag { tag } => &tag.id,
+            ChangeOperation::DeleteSnippet { id }
+            | ChangeOperation::UpdateSnippetState { id, .. }
+            | ChangeOperation::RecordSnippetCopies { id, .. }
+            | ChangeOperation::DeleteTag { id } => id,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ChangeStatus {
+    /// Waiting to be replayed once the database is reachable.
+    Pending,
+    /// The record changed on the server since it was edited offline; the
+    /// change is kept for review but no longer replayed.
+    Conflict,
+}
+
+/// A mutation made while the database was unreachable.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PendingChange {
+    pub id: String,
+    pub operation: ChangeOperation,
+    /// `updatedAt` of the record when it was first edited offline, used to
+    /// detect concurrent edits on the server.
+    pub base_updated_at: Option<DateTime<FixedOffset>>,
+    pub recorded_at: DateTime<Utc>,
+    pub status: ChangeStatus,
+    pub conflict: Option<String>,
+}
+
+/// A durable, ordered log of offline mutations, stored as JSON next to the
+/// offline cache.
+pub struct Journal {
+    path: PathBuf,
+    changes: Mutex<Vec<PendingChange>>,
+}
+
+impl Journal {
+    pub async fn open(dir: PathBuf) -> AppResult<Self> {
+        tokio::fs::create_dir_all(&dir).await?;
+        let path = dir.join(JOURNAL_FILE_NAME);
+
+        let changes = match tokio::fs::read(&path).await {
+            Ok(bytes) => serde_json::from_slice(&bytes)?,
+            Err(e) if e.kind() == ErrorKind::NotFound => vec![],
+            Err(e) => return Err(e.into()),
+        };
+
+        Ok(Self {
+            path,
+            changes: Mutex::new(changes),
+        })
+    }
+
+    async fn persist(&self, changes: &[PendingChange]) -> AppResult<()> {
+        let tmp_path = self.path.with_extension("json.tmp");
+        tokio::fs::write(&tmp_path, serde_json::to_vec_pretty(changes)?).await?;
+        tokio::fs::rename(&tmp_path, &self.path).await?;
+        Ok(())
+    }
+
+    pub async fn list(&self) -> Vec<PendingChange> {
+        self.changes.lock().await.clone()
+    }
+
+    pub async fn pending(&self) -> Vec<PendingChange> {
+        self.changes
+            .lock()
+            .await
+            .iter()
+            .filter(|change| change.status == ChangeStatus::Pending)
+            .cloned()
+            .collect()
+    }
+
+    /// Appends an operation, folding it into an earlier pending change for
+    /// the same record where possible so that replay never trips over its own
+    /// edits: updates of a record created offline become part of the create,
+    /// repeated updates collapse into one, and deleting a record created
+    /// offline drops it from the journal altogether.
+    pub async fn record(
+        &self,
+        operation: ChangeOperation,
+        base_updated_at: Option<DateTime<FixedOffset>>,
+    ) -> AppResult<()> {
+        let mut changes = self.changes.lock().await;
+
+        let earlier = changes.iter().position(|change| {
+            change.status == ChangeStatus::Pending
+                && change.operation.target_id() == operation.target_id()
+                && same_entity(&change.operation, &operation)
+        });
+
+        let folded = match earlier {
+            Some(index) => fold(&mut changes, index, operation.clone()),
+            None => false,
+        };
+
+        if !folded {
+            changes.push(PendingChange {
+                id: new_object_id(),
+                operation,
+                base_updated_at,
+                recorded_at: Utc::now(),
+                status: ChangeStatus::Pending,
+                conflict: None,
+            });
+        }
+
+        debug!("Offline journal holds {} changes", changes.len());
+        self.persist(&changes).await
+    }
+
+    pub async fn remove(&self, id: &str) -> AppResult<Option<PendingChange>> {
+        let mut changes = self.changes.lock().await;
+        let removed = changes
+            .iter()
+            .position(|change| change.id == id)
+            .map(|index| changes.remove(index));
+        self.persist(&changes).await?;
+        Ok(removed)
+    }
+
+    pub async fn mark_conflict(&self, id: &str, reason: String) -> AppResult<()> {
+        let mut changes = self.changes.lock().await;
+        if let Some(change) = changes.iter_mut().find(|change| change.id == id) {
+            change.status = ChangeStatus::Conflict;
+            change.conflict = Some(reason);
+        }
+        self.persist(&changes).await
+    }
+}
+
+fn is_snippet_operation(operation: &ChangeOperation) -> bool {
+    matches!(
+        operation,
+        ChangeOperation::CreateSnippet { .. }
+            | ChangeOperation::UpdateSnippet { .. }
+            | ChangeOperation::DeleteSnippet { .. }
+    )
+}
+
+fn is_tag_operation(operation: &ChangeOperation) -> bool {
+    matches!(
+        operation,
+        ChangeOperation::CreateTag { .. }
+            | ChangeOperation::UpdateTag { .. }
+            | ChangeOperation::DeleteTag { .. }
+    )
+}
+
+fn same_entity(a: &ChangeOperation, b: &ChangeOperation) -> bool {
+    (is_snippet_operation(a) && is_snippet_operation(b))
+        || (is_tag_operation(a) && is_tag_operation(b))
+        || matches!(
+            (a, b),
+            (
+                ChangeOperation::UpdateSnippetState { .. },
+                ChangeOperation::UpdateSnippetState { .. }
+            ) | (
+                ChangeOperation::RecordSnippetCopies { .. },
+                ChangeOperation::RecordSnippetCopies { .. }
+            )
+        )
+}
+
+/// Merges `operation` into the change at `index`. Returns `false` when the
+/// two can't be combined and `operation` must be appended instead.
+fn fold(changes: &mut Vec<PendingChange>, index: usize, operation: ChangeOperation) -> bool {
+    use ChangeOperation::*;
+
+    let earlier = &mut changes[index].operation;
+    match (earlier, operation) {
+        (CreateSnippet { snippet }, UpdateSnippet { snippet: updated }) => *snippet = updated,
+        (UpdateSnippet { snippet }, UpdateSnippet { snippet: updated }) => *snippet = updated,
+        (CreateTag { tag }, UpdateTag { tag: updated }) => *tag = updated,
+        (UpdateTag { tag }, UpdateTag { tag: updated }) => *tag = updated,
+        (earlier @ UpdateSnippetState { .. }, later @ UpdateSnippetState { .. }) => {
+            *earlier = later
+        }
+        (
+            RecordSnippetCopies {
+                copies,
+                last_copied_at,
+                ..
+            },
+            RecordSnippetCopies {
+                copies: more,
+                last_copied_at: later,
+                ..
+            },
+        ) => {
+            *copies += more;
+            *last_copied_at = later;
+        }
+        (CreateSnippet { .. }, DeleteSnippet { .. }) | (CreateTag { .. }, DeleteTag { .. }) => {
+            changes.remove(index);
+        }
+        (earlier @ UpdateSnippet { .. }, later @ DeleteSnippet { .. })
+        | (earlier @ UpdateTag { .. }, later @ DeleteTag { .. }) => *earlier = later,
+        _ => return false,
+    }
+    true
+}
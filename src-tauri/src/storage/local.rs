@@ -0,0 +1,1072 @@
+use std::{io::ErrorKind, path::PathBuf};
+
+use async_trait::async_trait;
+use chrono::{DateTime, FixedOffset, Utc};
+use log::{debug, info};
+use prisma::Role;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+use tokio::sync::RwLock;
+
+use super::{
+    new_object_id, sort_by_usage, sort_in_collection_order, BackupStore, CollectionStore,
+    SnippetStore, Storage, TagStore, UserStore, USER_EXPORT_FORMAT_VERSION,
+};
+use crate::{
+    auth::effective_role,
+    database::models::{
+        Collection, CollectionForm, Snippet, SnippetFilter, SnippetForm, SnippetSort, SnippetState,
+        SnippetStateUpdate, Tag, TagFilter, TagForm, User, UserDataExport, UserDeletionOptions,
+        UserFilter, VaultSnapshot, WorkspaceScope,
+    },
+    error::AppError,
+    prelude::AppResult,
+};
+
+const STORE_FILE_NAME: &str = "vault.json";
+
+/// Embedded storage that keeps the whole vault in a JSON file, so the app
+/// works without a database server.
+///
+/// Records are kept in their own shapes and converted into the Prisma models
+/// on the way out, so commands see the same types whichever backend is active.
+pub struct LocalStorage {
+    path: PathBuf,
+    data: RwLock<LocalData>,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LocalData {
+    #[serde(default)]
+    users: Vec<UserRecord>,
+    #[serde(default)]
+    snippets: Vec<SnippetRecord>,
+    #[serde(default)]
+    states: Vec<StateRecord>,
+    #[serde(default)]
+    tags: Vec<TagRecord>,
+    #[serde(default)]
+    collections: Vec<CollectionRecord>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UserRecord {
+    id: String,
+    name: String,
+    email: String,
+    #[serde(default)]
+    email_verified: bool,
+    phone: Option<String>,
+    #[serde(default)]
+    phone_verified: bool,
+    password_hash: String,
+    #[serde(default)]
+    role: Option<Role>,
+    created_at: DateTime<FixedOffset>,
+    updated_at: DateTime<FixedOffset>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SnippetRecord {
+    id: String,
+    created_at: DateTime<FixedOffset>,
+    updated_at: DateTime<FixedOffset>,
+    title: String,
+    description: String,
+    language: String,
+    code: String,
+    #[serde(default)]
+    tag_ids: Vec<String>,
+    snippet_state_id: String,
+    metadata: Option<Value>,
+    author_id: Option<String>,
+    workspace_id: Option<String>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StateRecord {
+    id: String,
+    #[serde(default)]
+    is_favorite: bool,
+    #[serde(default)]
+    is_dark: bool,
+    #[serde(default)]
+    copy_count: Option<i32>,
+    #[serde(default)]
+    last_copied_at: Option<DateTime<FixedOffset>>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TagRecord {
+    id: String,
+    created_at: DateTime<FixedOffset>,
+    updated_at: DateTime<FixedOffset>,
+    name: String,
+    color: Option<String>,
+    emoji: Option<String>,
+    #[serde(default)]
+    count: i32,
+    parent_id: Option<String>,
+    #[serde(default)]
+    snippet_ids: Vec<String>,
+    workspace_id: Option<String>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CollectionRecord {
+    id: String,
+    created_at: DateTime<FixedOffset>,
+    updated_at: DateTime<FixedOffset>,
+    name: String,
+    #[serde(default)]
+    description: String,
+    #[serde(default)]
+    position: i32,
+    parent_id: Option<String>,
+    #[serde(default)]
+    snippet_ids: Vec<String>,
+    workspace_id: Option<String>,
+}
+
+impl LocalStorage {
+    /// Opens the vault stored in `dir`, creating an empty one if needed.
+    pub async fn open(dir: PathBuf) -> AppResult<Self> {
+        tokio::fs::create_dir_all(&dir).await?;
+        let path = dir.join(STORE_FILE_NAME);
+
+        let data = match tokio::fs::read(&path).await {
+            Ok(bytes) => serde_json::from_slice(&bytes)?,
+            Err(e) if e.kind() == ErrorKind::NotFound => LocalData::default(),
+            Err(e) => return Err(e.into()),
+        };
+
+        info!("Opened local vault at {}", path.display());
+        Ok(Self {
+            path,
+            data: RwLock::new(data),
+        })
+    }
+
+    /// Writes the vault next to the store file and swaps it in, so a crash
+    /// mid-write never leaves a truncated store behind.
+    async fn persist(&self, data: &LocalData) -> AppResult<()> {
+        let tmp_path = self.path.with_extension("json.tmp");
+        tokio::fs::write(&tmp_path, serde_json::to_vec_pretty(data)?).await?;
+        tokio::fs::rename(&tmp_path, &self.path).await?;
+        debug!("Persisted local vault to {}", self.path.display());
+        Ok(())
+    }
+}
+
+/// Mirroring of records fetched from another backend, used when this store
+/// acts as the offline cache of the MongoDB backend.
+impl LocalStorage {
+    /// Replaces the whole store with a snapshot of another backend.
+    pub async fn replace_all(
+        &self,
+        users: &[User],
+        snippets: &[Snippet],
+        tags: &[Tag],
+        collections: &[Collection],
+    ) -> AppResult<()> {
+        let mut store = self.data.write().await;
+
+        store.users = users.iter().map(from_model).collect::<AppResult<_>>()?;
+        store.snippets = snippets.iter().map(from_model).collect::<AppResult<_>>()?;
+        store.states = snippets
+            .iter()
+            .filter_map(|snippet| snippet.state.as_deref())
+            .map(from_model)
+            .collect::<AppResult<_>>()?;
+        store.tags = tags.iter().map(from_model).collect::<AppResult<_>>()?;
+        store.collections = collections
+            .iter()
+            .map(from_model)
+            .collect::<AppResult<_>>()?;
+
+        self.persist(&store).await
+    }
+
+    pub async fn upsert_snippet(&self, snippet: &Snippet) -> AppResult<()> {
+        let mut store = self.data.write().await;
+
+        let record: SnippetRecord = from_model(snippet)?;
+        store.link_tags(&record.id, &record.tag_ids);
+        upsert(&mut store.snippets, record, |a, b| a.id == b.id);
+        if let Some(state) = snippet.state.as_deref() {
+            upsert(&mut store.states, from_model(state)?, |a, b| a.id == b.id);
+        }
+
+        self.persist(&store).await
+    }
+
+    pub async fn upsert_snippet_state(&self, state: &SnippetState) -> AppResult<()> {
+        let mut store = self.data.write().await;
+        upsert(&mut store.states, from_model(state)?, |a, b| a.id == b.id);
+        self.persist(&store).await
+    }
+
+    pub async fn upsert_tag(&self, tag: &Tag) -> AppResult<()> {
+        let mut store = self.data.write().await;
+        upsert(&mut store.tags, from_model(tag)?, |a, b| a.id == b.id);
+        self.persist(&store).await
+    }
+
+    pub async fn upsert_collection(&self, collection: &Collection) -> AppResult<()> {
+        let mut store = self.data.write().await;
+        upsert(&mut store.collections, from_model(collection)?, |a, b| {
+            a.id == b.id
+        });
+        self.persist(&store).await
+    }
+
+    pub async fn remove_collection(&self, id: &str) -> AppResult<()> {
+        let mut store = self.data.write().await;
+        store.collections.retain(|collection| collection.id != id);
+        self.persist(&store).await
+    }
+
+    pub async fn upsert_user(&self, user: &User) -> AppResult<()> {
+        let mut store = self.data.write().await;
+        upsert(&mut store.users, from_model(user)?, |a, b| a.id == b.id);
+        self.persist(&store).await
+    }
+
+    pub async fn remove_user(&self, id: &str) -> AppResult<()> {
+        let mut store = self.data.write().await;
+        store.users.retain(|user| user.id != id);
+        self.persist(&store).await
+    }
+}
+
+impl Storage for LocalStorage {
+    fn backend_name(&self) -> &'static str {
+        "local"
+    }
+}
+
+fn now() -> DateTime<FixedOffset> {
+    Utc::now().into()
+}
+
+/// Converts a stored record (plus any relation fields set on the JSON value)
+/// into the matching Prisma model.
+fn into_model<T: DeserializeOwned>(value: Value) -> AppResult<T> {
+    Ok(serde_json::from_value(value)?)
+}
+
+/// The inverse of [`into_model`]: reads a record out of a Prisma model,
+/// ignoring relation fields the record doesn't store.
+fn from_model<T: DeserializeOwned>(model: &impl Serialize) -> AppResult<T> {
+    Ok(serde_json::from_value(serde_json::to_value(model)?)?)
+}
+
+fn upsert<T>(records: &mut Vec<T>, record: T, same: impl Fn(&T, &T) -> bool) {
+    match records.iter_mut().find(|existing| same(existing, &record)) {
+        Some(existing) => *existing = record,
+        None => records.push(record),
+    }
+}
+
+fn not_found(kind: &str, id: &str) -> AppError {
+    AppError::NotFound(format!("{} with ID {}", kind, id))
+}
+
+impl LocalData {
+    fn snippet_to_model(&self, record: &SnippetRecord) -> AppResult<Snippet> {
+        let mut value = serde_json::to_value(record)?;
+
+        let tags = self
+            .tags
+            .iter()
+            .filter(|tag| record.tag_ids.contains(&tag.id))
+            .map(serde_json::to_value)
+            .collect::<Result<Vec<_>, _>>()?;
+        value["tags"] = Value::Array(tags);
+
+        if let Some(state) = self
+            .states
+            .iter()
+            .find(|state| state.id == record.snippet_state_id)
+        {
+            value["state"] = serde_json::to_value(state)?;
+        }
+
+        into_model(value)
+    }
+
+    fn tag_to_model(&self, record: &TagRecord) -> AppResult<Tag> {
+        let mut value = serde_json::to_value(record)?;
+
+        let snippets = self
+            .snippets
+            .iter()
+            .filter(|snippet| snippet.tag_ids.contains(&record.id))
+            .map(serde_json::to_value)
+            .collect::<Result<Vec<_>, _>>()?;
+        value["snippets"] = Value::Array(snippets);
+
+        into_model(value)
+    }
+
+    fn user_to_model(record: &UserRecord) -> AppResult<User> {
+        into_model(serde_json::to_value(record)?)
+    }
+
+    /// Keeps `Tag.snippetIds` in line with `Snippet.tagIds`, mirroring both
+    /// sides of the many-to-many relation the way MongoDB stores it.
+    fn link_tags(&mut self, snippet_id: &str, tag_ids: &[String]) {
+        for tag in self.tags.iter_mut() {
+            let linked = tag_ids.contains(&tag.id);
+            let position = tag.snippet_ids.iter().position(|id| id == snippet_id);
+            match (linked, position) {
+                (true, None) => tag.snippet_ids.push(snippet_id.to_owned()),
+                (false, Some(index)) => {
+                    tag.snippet_ids.remove(index);
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+fn snippet_matches(data: &LocalData, snippet: &SnippetRecord, filter: &SnippetFilter) -> bool {
+    if let Some(workspace) = &filter.workspace {
+        if !workspace.contains(snippet.workspace_id.as_deref()) {
+            return false;
+        }
+    }
+
+    if let Some(search) = &filter.search {
+        let fields = [
+            &snippet.title,
+            &snippet.description,
+            &snippet.code,
+            &snippet.language,
+        ];
+        if !fields.iter().any(|field| field.contains(search.as_str())) {
+            return false;
+        }
+    }
+
+    if let Some(title) = &filter.title {
+        if !snippet.title.contains(title.as_str()) {
+            return false;
+        }
+    }
+
+    if let Some(description) = &filter.description {
+        if !snippet.description.contains(description.as_str()) {
+            return false;
+        }
+    }
+
+    if let Some(language) = &filter.language {
+        if snippet.language != *language {
+            return false;
+        }
+    }
+
+    if let Some(code) = &filter.code {
+        if !snippet.code.contains(code.as_str()) {
+            return false;
+        }
+    }
+
+    if let Some(state_filter) = &filter.state {
+        let state = data
+            .states
+            .iter()
+            .find(|state| state.id == snippet.snippet_state_id);
+        let state_matches = state.map_or(false, |state| {
+            state_filter
+                .is_favorite
+                .map_or(true, |is_favorite| state.is_favorite == is_favorite)
+                && state_filter
+                    .is_dark
+                    .map_or(true, |is_dark| state.is_dark == is_dark)
+        });
+        if !state_matches {
+            return false;
+        }
+    }
+
+    if let Some(collection_id) = &filter.collection_id {
+        let in_collection = data
+            .collections
+            .iter()
+            .find(|collection| collection.id == *collection_id)
+            .map_or(false, |collection| {
+                collection.snippet_ids.contains(&snippet.id)
+            });
+        if !in_collection {
+            return false;
+        }
+    }
+
+    if let Some(tags) = &filter.tags {
+        if !tags.is_empty() {
+            let has_tag = data
+                .tags
+                .iter()
+                .filter(|tag| snippet.tag_ids.contains(&tag.id))
+                .any(|tag| tags.contains(&tag.name));
+            if !has_tag {
+                return false;
+            }
+        }
+    }
+
+    true
+}
+
+#[async_trait]
+impl SnippetStore for LocalStorage {
+    async fn create_snippet(
+        &self,
+        data: SnippetForm,
+        author_id: Option<String>,
+    ) -> AppResult<Snippet> {
+        let mut store = self.data.write().await;
+
+        let state = StateRecord {
+            id: new_object_id(),
+            is_favorite: false,
+            is_dark: false,
+            copy_count: None,
+            last_copied_at: None,
+        };
+        let timestamp = now();
+        let snippet = SnippetRecord {
+            id: new_object_id(),
+            created_at: timestamp,
+            updated_at: timestamp,
+            title: data.title,
+            description: data.description,
+            language: data.language,
+            code: data.code,
+            tag_ids: data.tag_ids,
+            snippet_state_id: state.id.clone(),
+            metadata: None,
+            author_id,
+            workspace_id: data.workspace_id,
+        };
+
+        store.link_tags(&snippet.id, &snippet.tag_ids);
+        store.states.push(state);
+        store.snippets.push(snippet.clone());
+        self.persist(&store).await?;
+
+        info!("Successfully created snippet with ID: {}", snippet.id);
+        store.snippet_to_model(&snippet)
+    }
+
+    async fn get_snippet(&self, id: String) -> AppResult<Option<Snippet>> {
+        let store = self.data.read().await;
+        store
+            .snippets
+            .iter()
+            .find(|snippet| snippet.id == id)
+            .map(|snippet| store.snippet_to_model(snippet))
+            .transpose()
+    }
+
+    async fn list_snippets(&self, filter: Option<SnippetFilter>) -> AppResult<Vec<Snippet>> {
+        let store = self.data.read().await;
+
+        let collection = match filter.as_ref().and_then(|f| f.collection_id.as_ref()) {
+            Some(collection_id) => Some(
+                store
+                    .collections
+                    .iter()
+                    .find(|collection| collection.id == *collection_id)
+                    .ok_or_else(|| not_found("Collection", collection_id))?,
+            ),
+            None => None,
+        };
+
+        let mut records: Vec<&SnippetRecord> = store
+            .snippets
+            .iter()
+            .filter(|snippet| {
+                filter
+                    .as_ref()
+                    .map_or(true, |filter| snippet_matches(&store, snippet, filter))
+            })
+            .collect();
+        records.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+        if let Some(collection) = collection {
+            sort_in_collection_order(&mut records, &collection.snippet_ids, |snippet| {
+                snippet.id.as_str()
+            });
+        }
+
+        let mut snippets = records
+            .into_iter()
+            .map(|snippet| store.snippet_to_model(snippet))
+            .collect::<AppResult<Vec<_>>>()?;
+        if filter.as_ref().and_then(|f| f.sort) == Some(SnippetSort::MostUsed) {
+            sort_by_usage(&mut snippets);
+        }
+
+        info!("Found {} snippets", snippets.len());
+        Ok(snippets)
+    }
+
+    async fn update_snippet(&self, id: String, data: SnippetForm) -> AppResult<Snippet> {
+        let mut store = self.data.write().await;
+
+        let snippet = store
+            .snippets
+            .iter_mut()
+            .find(|snippet| snippet.id == id)
+            .ok_or_else(|| not_found("Snippet", &id))?;
+        snippet.title = data.title;
+        snippet.description = data.description;
+        snippet.language = data.language;
+        snippet.code = data.code;
+        snippet.tag_ids = data.tag_ids;
+        snippet.snippet_state_id = data.snippet_state_id;
+        snippet.updated_at = now();
+        let snippet = snippet.clone();
+
+        store.link_tags(&snippet.id, &snippet.tag_ids);
+        self.persist(&store).await?;
+
+        info!("Successfully updated snippet with ID: {}", snippet.id);
+        store.snippet_to_model(&snippet)
+    }
+
+    async fn delete_snippet(&self, id: String) -> AppResult<Snippet> {
+        let mut store = self.data.write().await;
+
+        let index = store
+            .snippets
+            .iter()
+            .position(|snippet| snippet.id == id)
+            .ok_or_else(|| not_found("Snippet", &id))?;
+        let deleted = store.snippet_to_model(&store.snippets[index])?;
+
+        let snippet = store.snippets.remove(index);
+        store.link_tags(&snippet.id, &[]);
+        store
+            .states
+            .retain(|state| state.id != snippet.snippet_state_id);
+        for collection in store.collections.iter_mut() {
+            collection
+                .snippet_ids
+                .retain(|snippet_id| *snippet_id != id);
+        }
+        self.persist(&store).await?;
+
+        info!("Successfully deleted snippet with ID: {}", id);
+        Ok(deleted)
+    }
+
+    async fn update_snippet_state(
+        &self,
+        id: String,
+        data: SnippetStateUpdate,
+    ) -> AppResult<SnippetState> {
+        let mut store = self.data.write().await;
+
+        let state = store
+            .states
+            .iter_mut()
+            .find(|state| state.id == id)
+            .ok_or_else(|| not_found("Snippet state", &id))?;
+        if let Some(is_dark) = data.is_dark {
+            state.is_dark = is_dark;
+        }
+        if let Some(is_favorite) = data.is_favorite {
+            state.is_favorite = is_favorite;
+        }
+        let state = state.clone();
+
+        self.persist(&store).await?;
+        into_model(serde_json::to_value(state)?)
+    }
+
+    async fn record_snippet_copy(&self, id: String) -> AppResult<SnippetState> {
+        let mut store = self.data.write().await;
+
+        let state = store
+            .states
+            .iter_mut()
+            .find(|state| state.id == id)
+            .ok_or_else(|| not_found("Snippet state", &id))?;
+        state.copy_count = Some(state.copy_count.unwrap_or(0) + 1);
+        state.last_copied_at = Some(now());
+        let state = state.clone();
+
+        self.persist(&store).await?;
+        into_model(serde_json::to_value(state)?)
+    }
+}
+
+#[async_trait]
+impl TagStore for LocalStorage {
+    async fn create_tag(&self, data: TagForm) -> AppResult<Tag> {
+        let mut store = self.data.write().await;
+
+        if let Some(parent_id) = &data.parent_id {
+            if !store.tags.iter().any(|tag| tag.id == *parent_id) {
+                return Err(not_found("Tag", parent_id));
+            }
+        }
+
+        let timestamp = now();
+        let tag = TagRecord {
+            id: new_object_id(),
+            created_at: timestamp,
+            updated_at: timestamp,
+            name: data.name,
+            color: data.color,
+            emoji: data.emoji,
+            count: 0,
+            parent_id: data.parent_id,
+            snippet_ids: vec![],
+            workspace_id: data.workspace_id,
+        };
+        store.tags.push(tag.clone());
+        self.persist(&store).await?;
+
+        info!("Successfully created tag with ID: {}", tag.id);
+        store.tag_to_model(&tag)
+    }
+
+    async fn get_tag(&self, id: String) -> AppResult<Option<Tag>> {
+        let store = self.data.read().await;
+        store
+            .tags
+            .iter()
+            .find(|tag| tag.id == id)
+            .map(|tag| store.tag_to_model(tag))
+            .transpose()
+    }
+
+    async fn list_tags(&self, filter: Option<TagFilter>) -> AppResult<Vec<Tag>> {
+        let store = self.data.read().await;
+        let (name, workspace) = filter
+            .map(|filter| (filter.name, filter.workspace))
+            .unwrap_or((None, None));
+
+        let tags = store
+            .tags
+            .iter()
+            .filter(|tag| {
+                name.as_ref()
+                    .map_or(true, |name| tag.name.contains(name.as_str()))
+                    && workspace.as_ref().map_or(true, |workspace| {
+                        workspace.contains(tag.workspace_id.as_deref())
+                    })
+            })
+            .map(|tag| store.tag_to_model(tag))
+            .collect::<AppResult<Vec<_>>>()?;
+
+        info!("Found {} tags", tags.len());
+        Ok(tags)
+    }
+
+    async fn update_tag(&self, id: String, data: TagForm) -> AppResult<Tag> {
+        let mut store = self.data.write().await;
+
+        let tag = store
+            .tags
+            .iter_mut()
+            .find(|tag| tag.id == id)
+            .ok_or_else(|| not_found("Tag", &id))?;
+        tag.name = data.name;
+        tag.color = data.color;
+        tag.updated_at = now();
+        let tag = tag.clone();
+
+        self.persist(&store).await?;
+
+        info!("Successfully updated tag with ID: {}", tag.id);
+        store.tag_to_model(&tag)
+    }
+
+    async fn delete_tag(&self, id: String) -> AppResult<Tag> {
+        let mut store = self.data.write().await;
+
+        let index = store
+            .tags
+            .iter()
+            .position(|tag| tag.id == id)
+            .ok_or_else(|| not_found("Tag", &id))?;
+        let deleted = store.tag_to_model(&store.tags[index])?;
+
+        store.tags.remove(index);
+        for snippet in store.snippets.iter_mut() {
+            snippet.tag_ids.retain(|tag_id| *tag_id != id);
+        }
+        self.persist(&store).await?;
+
+        info!("Successfully deleted tag with ID: {}", id);
+        Ok(deleted)
+    }
+}
+
+#[async_trait]
+impl CollectionStore for LocalStorage {
+    async fn create_collection(&self, data: CollectionForm) -> AppResult<Collection> {
+        let mut store = self.data.write().await;
+
+        if let Some(parent_id) = &data.parent_id {
+            if !store
+                .collections
+                .iter()
+                .any(|collection| collection.id == *parent_id)
+            {
+                return Err(not_found("Collection", parent_id));
+            }
+        }
+
+        let timestamp = now();
+        let collection = CollectionRecord {
+            id: new_object_id(),
+            created_at: timestamp,
+            updated_at: timestamp,
+            name: data.name,
+            description: data.description,
+            position: data.position,
+            parent_id: data.parent_id,
+            snippet_ids: data.snippet_ids,
+            workspace_id: data.workspace_id,
+        };
+        store.collections.push(collection.clone());
+        self.persist(&store).await?;
+
+        info!("Successfully created collection with ID: {}", collection.id);
+        into_model(serde_json::to_value(collection)?)
+    }
+
+    async fn get_collection(&self, id: String) -> AppResult<Option<Collection>> {
+        let store = self.data.read().await;
+        store
+            .collections
+            .iter()
+            .find(|collection| collection.id == id)
+            .map(|collection| into_model(serde_json::to_value(collection)?))
+            .transpose()
+    }
+
+    async fn list_collections(
+        &self,
+        workspace: Option<WorkspaceScope>,
+    ) -> AppResult<Vec<Collection>> {
+        let store = self.data.read().await;
+
+        let mut records: Vec<&CollectionRecord> = store
+            .collections
+            .iter()
+            .filter(|collection| {
+                workspace.as_ref().map_or(true, |workspace| {
+                    workspace.contains(collection.workspace_id.as_deref())
+                })
+            })
+            .collect();
+        records.sort_by_key(|collection| collection.position);
+        let collections = records
+            .into_iter()
+            .map(|collection| into_model(serde_json::to_value(collection)?))
+            .collect::<AppResult<Vec<_>>>()?;
+
+        info!("Found {} collections", collections.len());
+        Ok(collections)
+    }
+
+    async fn update_collection(&self, id: String, data: CollectionForm) -> AppResult<Collection> {
+        let mut store = self.data.write().await;
+
+        let collection = store
+            .collections
+            .iter_mut()
+            .find(|collection| collection.id == id)
+            .ok_or_else(|| not_found("Collection", &id))?;
+        collection.name = data.name;
+        collection.description = data.description;
+        collection.parent_id = data.parent_id;
+        collection.position = data.position;
+        collection.snippet_ids = data.snippet_ids;
+        collection.updated_at = now();
+        let collection = collection.clone();
+
+        self.persist(&store).await?;
+
+        info!("Successfully updated collection with ID: {}", collection.id);
+        into_model(serde_json::to_value(collection)?)
+    }
+
+    async fn delete_collection(&self, id: String) -> AppResult<Collection> {
+        let mut store = self.data.write().await;
+
+        let index = store
+            .collections
+            .iter()
+            .position(|collection| collection.id == id)
+            .ok_or_else(|| not_found("Collection", &id))?;
+        let collection = store.collections.remove(index);
+        self.persist(&store).await?;
+
+        info!("Successfully deleted collection with ID: {}", id);
+        into_model(serde_json::to_value(collection)?)
+    }
+}
+
+#[async_trait]
+impl UserStore for LocalStorage {
+    async fn create_user(
+        &self,
+        name: String,
+        email: String,
+        password_hash: String,
+        role: Role,
+    ) -> AppResult<User> {
+        let mut store = self.data.write().await;
+
+        if store.users.iter().any(|user| user.email == email) {
+            return Err(AppError::Other(format!(
+                "A user with email {} already exists",
+                email
+            )));
+        }
+
+        let timestamp = now();
+        let user = UserRecord {
+            id: new_object_id(),
+            name,
+            email,
+            email_verified: false,
+            phone: None,
+            phone_verified: false,
+            password_hash,
+            role: Some(role),
+            created_at: timestamp,
+            updated_at: timestamp,
+        };
+        store.users.push(user.clone());
+        self.persist(&store).await?;
+
+        LocalData::user_to_model(&user)
+    }
+
+    async fn get_user(&self, id: String) -> AppResult<Option<User>> {
+        let store = self.data.read().await;
+        store
+            .users
+            .iter()
+            .find(|user| user.id == id)
+            .map(LocalData::user_to_model)
+            .transpose()
+    }
+
+    async fn find_user_by_email(&self, email: String) -> AppResult<Option<User>> {
+        let store = self.data.read().await;
+        store
+            .users
+            .iter()
+            .find(|user| user.email == email)
+            .map(LocalData::user_to_model)
+            .transpose()
+    }
+
+    async fn list_users(&self, filter: Option<UserFilter>) -> AppResult<Vec<User>> {
+        let store = self.data.read().await;
+        let (email, role) = filter
+            .map(|filter| (filter.email, filter.role))
+            .unwrap_or((None, None));
+
+        store
+            .users
+            .iter()
+            .filter(|user| {
+                email
+                    .as_ref()
+                    .map_or(true, |email| user.email.contains(email.as_str()))
+                    && role.map_or(true, |role| effective_role(user.role) == role)
+            })
+            .map(LocalData::user_to_model)
+            .collect()
+    }
+
+    async fn count_users(&self, role: Option<Role>) -> AppResult<i64> {
+        let store = self.data.read().await;
+        let count = store
+            .users
+            .iter()
+            .filter(|user| role.map_or(true, |role| effective_role(user.role) == role))
+            .count();
+        Ok(count as i64)
+    }
+
+    async fn update_user(&self, id: String, name: String, email: String) -> AppResult<User> {
+        let mut store = self.data.write().await;
+
+        if store
+            .users
+            .iter()
+            .any(|user| user.email == email && user.id != id)
+        {
+            return Err(AppError::Other(format!(
+                "A user with email {} already exists",
+                email
+            )));
+        }
+
+        let user = store
+            .users
+            .iter_mut()
+            .find(|user| user.id == id)
+            .ok_or_else(|| not_found("User", &id))?;
+        user.name = name;
+        user.email = email;
+        user.updated_at = now();
+        let user = user.clone();
+
+        self.persist(&store).await?;
+        LocalData::user_to_model(&user)
+    }
+
+    async fn set_user_role(&self, id: String, role: Role) -> AppResult<User> {
+        let mut store = self.data.write().await;
+
+        let user = store
+            .users
+            .iter_mut()
+            .find(|user| user.id == id)
+            .ok_or_else(|| not_found("User", &id))?;
+        user.role = Some(role);
+        user.updated_at = now();
+        let user = user.clone();
+
+        self.persist(&store).await?;
+        LocalData::user_to_model(&user)
+    }
+
+    async fn delete_user(&self, id: String, options: UserDeletionOptions) -> AppResult<User> {
+        let mut store = self.data.write().await;
+
+        let index = store
+            .users
+            .iter()
+            .position(|user| user.id == id)
+            .ok_or_else(|| not_found("User", &id))?;
+
+        for snippet in store.snippets.iter_mut() {
+            if snippet.author_id.as_deref() == Some(id.as_str()) {
+                snippet.author_id = options.transfer_snippets_to.clone();
+            }
+        }
+        let user = store.users.remove(index);
+        self.persist(&store).await?;
+
+        info!("Deleted user with ID: {}", user.id);
+        LocalData::user_to_model(&user)
+    }
+
+    async fn export_user_data(&self, id: String) -> AppResult<UserDataExport> {
+        let store = self.data.read().await;
+
+        let user = store
+            .users
+            .iter()
+            .find(|user| user.id == id)
+            .ok_or_else(|| not_found("User", &id))?;
+        let snippets = store
+            .snippets
+            .iter()
+            .filter(|snippet| snippet.author_id.as_deref() == Some(id.as_str()))
+            .map(|snippet| store.snippet_to_model(snippet))
+            .collect::<AppResult<Vec<_>>>()?;
+
+        // Ratings, notifications, rate limits and activity are only recorded
+        // by the MongoDB backend.
+        Ok(UserDataExport {
+            format_version: USER_EXPORT_FORMAT_VERSION,
+            exported_at: Utc::now(),
+            user: LocalData::user_to_model(user)?.into(),
+            snippets,
+            snippet_ratings: vec![],
+            notifications: vec![],
+            api_rate_limits: vec![],
+            activity_logs: vec![],
+        })
+    }
+}
+
+/// Ratings, tag usages and activity logs aren't kept by this store, so they
+/// are absent from its snapshots and dropped on import.
+#[async_trait]
+impl BackupStore for LocalStorage {
+    async fn export_snapshot(&self) -> AppResult<VaultSnapshot> {
+        let store = self.data.read().await;
+
+        Ok(VaultSnapshot {
+            snippets: store
+                .snippets
+                .iter()
+                .map(|record| into_model(serde_json::to_value(record)?))
+                .collect::<AppResult<_>>()?,
+            states: store
+                .states
+                .iter()
+                .map(|record| into_model(serde_json::to_value(record)?))
+                .collect::<AppResult<_>>()?,
+            tags: store
+                .tags
+                .iter()
+                .map(|record| into_model(serde_json::to_value(record)?))
+                .collect::<AppResult<_>>()?,
+            collections: store
+                .collections
+                .iter()
+                .map(|record| into_model(serde_json::to_value(record)?))
+                .collect::<AppResult<_>>()?,
+            ..VaultSnapshot::default()
+        })
+    }
+
+    async fn import_snapshot(&self, snapshot: VaultSnapshot, replace: bool) -> AppResult<()> {
+        let mut store = self.data.write().await;
+
+        if replace {
+            store.snippets.clear();
+            store.states.clear();
+            store.tags.clear();
+            store.collections.clear();
+        }
+
+        for state in &snapshot.states {
+            store.states.push(from_model(state)?);
+        }
+        for tag in &snapshot.tags {
+            store.tags.push(from_model(tag)?);
+        }
+        for snippet in &snapshot.snippets {
+            store.snippets.push(from_model(snippet)?);
+        }
+        for collection in &snapshot.collections {
+            store.collections.push(from_model(collection)?);
+        }
+
+        let skipped = snapshot.snippet_ratings.len()
+            + snapshot.tag_usages.len()
+            + snapshot.activity_logs.len();
+        if skipped > 0 {
+            info!(
+                "Skipped {} ratings, tag usages and activity logs the local vault doesn't store",
+                skipped
+            );
+        }
+
+        self.persist(&store).await
+    }
+}
@@ -0,0 +1,170 @@
+pub mod journal;
+pub mod local;
+pub mod mongo;
+pub mod offline;
+
+use std::{
+    cmp::Reverse,
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc,
+    },
+};
+
+use async_trait::async_trait;
+use chrono::Utc;
+use prisma::{PrismaClient, Role};
+use rand::RngCore;
+
+use crate::{
+    database::models::{
+        Collection, CollectionForm, Snippet, SnippetFilter, SnippetForm, SnippetState,
+        SnippetStateUpdate, Tag, TagFilter, TagForm, User, UserDataExport, UserDeletionOptions,
+        UserFilter, VaultSnapshot, WorkspaceScope,
+    },
+    error::AppError,
+    prelude::AppResult,
+};
+
+pub use journal::PendingChange;
+pub use local::LocalStorage;
+pub use mongo::MongoStorage;
+pub use offline::OfflineStorage;
+
+pub const USER_EXPORT_FORMAT_VERSION: u32 = 1;
+
+#[async_trait]
+pub trait SnippetStore: Send + Sync {
+    async fn create_snippet(
+        &self,
+        data: SnippetForm,
+        author_id: Option<String>,
+    ) -> AppResult<Snippet>;
+    async fn get_snippet(&self, id: String) -> AppResult<Option<Snippet>>;
+    async fn list_snippets(&self, filter: Option<SnippetFilter>) -> AppResult<Vec<Snippet>>;
+    async fn update_snippet(&self, id: String, data: SnippetForm) -> AppResult<Snippet>;
+    async fn delete_snippet(&self, id: String) -> AppResult<Snippet>;
+    async fn update_snippet_state(
+        &self,
+        id: String,
+        data: SnippetStateUpdate,
+    ) -> AppResult<SnippetState>;
+    /// Counts a copy of the snippet with this state id.
+    async fn record_snippet_copy(&self, id: String) -> AppResult<SnippetState>;
+}
+
+#[async_trait]
+pub trait TagStore: Send + Sync {
+    async fn create_tag(&self, data: TagForm) -> AppResult<Tag>;
+    async fn get_tag(&self, id: String) -> AppResult<Option<Tag>>;
+    async fn list_tags(&self, filter: Option<TagFilter>) -> AppResult<Vec<Tag>>;
+    async fn update_tag(&self, id: String, data: TagForm) -> AppResult<Tag>;
+    async fn delete_tag(&self, id: String) -> AppResult<Tag>;
+}
+
+#[async_trait]
+pub trait CollectionStore: Send + Sync {
+    async fn create_collection(&self, data: CollectionForm) -> AppResult<Collection>;
+    async fn get_collection(&self, id: String) -> AppResult<Option<Collection>>;
+    /// The collections in `workspace`, or all of them, ordered by position.
+    async fn list_collections(
+        &self,
+        workspace: Option<WorkspaceScope>,
+    ) -> AppResult<Vec<Collection>>;
+    async fn update_collection(&self, id: String, data: CollectionForm) -> AppResult<Collection>;
+    async fn delete_collection(&self, id: String) -> AppResult<Collection>;
+}
+
+#[async_trait]
+pub trait UserStore: Send + Sync {
+    async fn create_user(
+        &self,
+        name: String,
+        email: String,
+        password_hash: String,
+        role: Role,
+    ) -> AppResult<User>;
+    async fn get_user(&self, id: String) -> AppResult<Option<User>>;
+    async fn find_user_by_email(&self, email: String) -> AppResult<Option<User>>;
+    async fn list_users(&self, filter: Option<UserFilter>) -> AppResult<Vec<User>>;
+    async fn count_users(&self, role: Option<Role>) -> AppResult<i64>;
+    async fn update_user(&self, id: String, name: String, email: String) -> AppResult<User>;
+    async fn set_user_role(&self, id: String, role: Role) -> AppResult<User>;
+    /// Deletes the user and everything referencing it in one operation.
+    async fn delete_user(&self, id: String, options: UserDeletionOptions) -> AppResult<User>;
+    async fn export_user_data(&self, id: String) -> AppResult<UserDataExport>;
+}
+
+#[async_trait]
+pub trait BackupStore: Send + Sync {
+    /// Reads everything a backup covers.
+    async fn export_snapshot(&self) -> AppResult<VaultSnapshot>;
+    /// Writes the snapshot's records under their own ids. With `replace`, all
+    /// snippets, tags and their related records are removed first; accounts
+    /// are always kept.
+    async fn import_snapshot(&self, snapshot: VaultSnapshot, replace: bool) -> AppResult<()>;
+}
+
+/// A complete backend the IPC commands can run against.
+#[async_trait]
+pub trait Storage: SnippetStore + TagStore + CollectionStore + UserStore + BackupStore {
+    fn backend_name(&self) -> &'static str;
+
+    /// The Prisma client behind this storage, for features that only the
+    /// MongoDB backend supports.
+    fn prisma(&self) -> AppResult<Arc<PrismaClient>> {
+        Err(AppError::Other(format!(
+            "This operation requires the MongoDB storage backend, but '{}' is active",
+            self.backend_name()
+        )))
+    }
+
+    /// Mutations made offline that haven't reached the database yet.
+    async fn pending_changes(&self) -> AppResult<Vec<PendingChange>> {
+        Ok(vec![])
+    }
+
+    async fn discard_pending_change(&self, id: String) -> AppResult<PendingChange> {
+        Err(AppError::NotFound(format!("Pending change with ID {}", id)))
+    }
+
+    /// Told the user each command runs for, so that a backend keeping a copy
+    /// of the vault can keep it to what that user may see.
+    async fn signed_in(&self, _user: &User) {}
+}
+
+/// Sorts records filtered by a collection into the collection's order.
+fn sort_in_collection_order<T>(records: &mut [T], snippet_ids: &[String], id: impl Fn(&T) -> &str) {
+    records.sort_by_key(|record| {
+        snippet_ids
+            .iter()
+            .position(|snippet_id| snippet_id == id(record))
+    });
+}
+
+/// Sorts snippets most copied first, keeping the order they are in among
+/// those copied equally often.
+fn sort_by_usage(snippets: &mut [Snippet]) {
+    snippets.sort_by_key(|snippet| {
+        let state = snippet.state.as_deref();
+        (
+            Reverse(state.and_then(|state| state.copy_count).unwrap_or(0)),
+            Reverse(state.and_then(|state| state.last_copied_at)),
+        )
+    });
+}
+
+/// Generates a MongoDB-compatible ObjectId (timestamp, random bytes and a
+/// counter, hex encoded), so records created by other backends can later be
+/// written to MongoDB unchanged.
+pub fn new_object_id() -> String {
+    static COUNTER: AtomicU32 = AtomicU32::new(0);
+
+    let mut bytes = [0u8; 12];
+    bytes[..4].copy_from_slice(&(Utc::now().timestamp() as u32).to_be_bytes());
+    rand::thread_rng().fill_bytes(&mut bytes[4..9]);
+    let counter = COUNTER.fetch_add(1, Ordering::Relaxed).to_be_bytes();
+    bytes[9..].copy_from_slice(&counter[1..]);
+
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
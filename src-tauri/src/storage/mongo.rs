@@ -0,0 +1,1153 @@
+use std::sync::{Arc, RwLock};
+
+use async_trait::async_trait;
+use chrono::Utc;
+use log::{debug, error, info, warn};
+use prisma::{Direction, PrismaClient, QueryError, Role};
+
+use super::{
+    sort_by_usage, sort_in_collection_order, BackupStore, CollectionStore, SnippetStore, Storage,
+    TagStore, UserStore, USER_EXPORT_FORMAT_VERSION,
+};
+use crate::{
+    auth::effective_role,
+    database::{
+        init_db,
+        models::{
+            Collection, CollectionForm, Snippet, SnippetFilter, SnippetForm, SnippetSort,
+            SnippetState, SnippetStateUpdate, Tag, TagFilter, TagForm, User, UserDataExport,
+            UserDeletionOptions, UserFilter, VaultSnapshot, WorkspaceScope,
+        },
+    },
+    error::AppError,
+    prelude::AppResult,
+};
+
+/// Storage backed by the Prisma client against MongoDB.
+///
+/// The client is absent until the first successful connection and is dropped
+/// again when the server stops answering; every operation fails with
+/// [`AppError::DatabaseUnavailable`] in the meantime.
+#[derive(Default)]
+pub struct MongoStorage {
+    client: RwLock<Option<Arc<PrismaClient>>>,
+}
+
+impl MongoStorage {
+    fn client(&self) -> AppResult<Arc<PrismaClient>> {
+        self.client
+            .read()
+            .unwrap()
+            .clone()
+            .ok_or(AppError::DatabaseUnavailable)
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.client.read().unwrap().is_some()
+    }
+
+    /// Builds a new client and only keeps it once the server has answered.
+    pub async fn connect(&self) -> AppResult<()> {
+        let client = init_db().await?;
+        ping(&client).await?;
+        *self.client.write().unwrap() = Some(Arc::new(client));
+        info!("Connected to MongoDB");
+        Ok(())
+    }
+
+    pub fn disconnect(&self) {
+        *self.client.write().unwrap() = None;
+    }
+
+    pub async fn ping(&self) -> AppResult<()> {
+        ping(&self.client()?).await
+    }
+}
+
+async fn ping(client: &PrismaClient) -> AppResult<()> {
+    client.user().count(vec![]).exec().await?;
+    Ok(())
+}
+
+impl Storage for MongoStorage {
+    fn backend_name(&self) -> &'static str {
+        "mongodb"
+    }
+
+    fn prisma(&self) -> AppResult<Arc<PrismaClient>> {
+        self.client()
+    }
+}
+
+#[async_trait]
+impl SnippetStore for MongoStorage {
+    async fn create_snippet(
+        &self,
+        data: SnippetForm,
+        author_id: Option<String>,
+    ) -> AppResult<Snippet> {
+        let client = self.client()?;
+
+        let state = client.snippet_state().create(vec![]).exec().await?;
+
+        let mut create_params = vec![
+            prisma::snippet::tag_ids::set(data.tag_ids),
+            prisma::snippet::workspace_id::set(data.workspace_id),
+        ];
+        if let Some(author_id) = author_id {
+            create_params.push(prisma::snippet::author::connect(prisma::user::id::equals(
+                author_id,
+            )));
+        }
+
+        let snippet = match client
+            .snippet()
+            .create(
+                data.title,
+                data.description,
+                data.language,
+                data.code,
+                prisma::snippet_state::id::equals(state.id),
+                create_params,
+            )
+            .exec()
+            .await
+        {
+            Ok(snippet) => snippet,
+            Err(e) => {
+                error!("Failed to create snippet: {}", e);
+                return Err(e.into());
+            }
+        };
+
+        let snippet_with_relations: Option<Snippet> = client
+            .snippet()
+            .find_unique(prisma::snippet::id::equals(snippet.id))
+            .with(prisma::snippet::state::fetch())
+            .with(prisma::snippet::tags::fetch(vec![]))
+            .exec()
+            .await?;
+
+        if let Some(snippet) = snippet_with_relations {
+            info!("Successfully created snippet with ID: {}", snippet.id);
+            return Ok(snippet);
+        }
+
+        error!("Snippet not found after creation");
+        Err("Snippet not found".into())
+    }
+
+    async fn get_snippet(&self, id: String) -> AppResult<Option<Snippet>> {
+        let snippet_with_relations: Option<Snippet> = self
+            .client()?
+            .snippet()
+            .find_unique(prisma::snippet::id::equals(id.to_owned()))
+            .with(prisma::snippet::state::fetch())
+            .with(prisma::snippet::tags::fetch(vec![]))
+            .exec()
+            .await?;
+
+        if snippet_with_relations.is_some() {
+            info!("Successfully fetched snippet with ID: {}", id);
+        } else {
+            debug!("Snippet with ID: {} not found", id);
+        }
+
+        Ok(snippet_with_relations)
+    }
+
+    async fn list_snippets(&self, filter: Option<SnippetFilter>) -> AppResult<Vec<Snippet>> {
+        let client = self.client()?;
+
+        let collection_order = match filter.as_ref().and_then(|f| f.collection_id.clone()) {
+            Some(collection_id) => Some(
+                client
+                    .collection()
+                    .find_unique(prisma::collection::id::equals(collection_id.clone()))
+                    .exec()
+                    .await?
+                    .ok_or_else(|| {
+                        AppError::NotFound(format!("Collection with ID {}", collection_id))
+                    })?
+                    .snippet_ids,
+            ),
+            None => None,
+        };
+
+        let workspace = filter.as_ref().and_then(|f| f.workspace.clone());
+        let sort = filter.as_ref().and_then(|f| f.sort);
+        let mut where_params = build_snippet_filters(filter);
+        if let Some(snippet_ids) = &collection_order {
+            where_params.push(prisma::snippet::id::in_vec(snippet_ids.clone()));
+        }
+
+        let mut snippets = client
+            .snippet()
+            .find_many(where_params)
+            .with(prisma::snippet::state::fetch())
+            .with(prisma::snippet::tags::fetch(vec![]))
+            .order_by(prisma::snippet::created_at::order(Direction::Desc))
+            .exec()
+            .await?;
+        if let Some(snippet_ids) = &collection_order {
+            sort_in_collection_order(&mut snippets, snippet_ids, |snippet| snippet.id.as_str());
+        }
+        if let Some(workspace) = &workspace {
+            snippets.retain(|snippet| workspace.contains(snippet.workspace_id.as_deref()));
+        }
+        if sort == Some(SnippetSort::MostUsed) {
+            sort_by_usage(&mut snippets);
+        }
+
+        info!("Found {} snippets", snippets.len());
+        Ok(snippets)
+    }
+
+    async fn update_snippet(&self, id: String, data: SnippetForm) -> AppResult<Snippet> {
+        let client = self.client()?;
+
+        let update_params = vec![
+            prisma::snippet::title::set(data.title),
+            prisma::snippet::description::set(data.description),
+            prisma::snippet::language::set(data.language),
+            prisma::snippet::code::set(data.code),
+            prisma::snippet::tag_ids::set(data.tag_ids),
+            prisma::snippet::snippet_state_id::set(data.snippet_state_id),
+        ];
+
+        let updated_snippet = match client
+            .snippet()
+            .update(prisma::snippet::id::equals(id.to_owned()), update_params)
+            .exec()
+            .await
+        {
+            Ok(snippet) => snippet,
+            Err(e) => {
+                error!("Failed to update snippet with ID: {}: {}", id, e);
+                return Err(e.into());
+            }
+        };
+
+        let snippet_with_relations: Option<Snippet> = client
+            .snippet()
+            .find_unique(prisma::snippet::id::equals(updated_snippet.id))
+            .with(prisma::snippet::state::fetch())
+            .with(prisma::snippet::tags::fetch(vec![]))
+            .exec()
+            .await?;
+
+        if let Some(snippet) = snippet_with_relations {
+            info!("Successfully updated snippet with ID: {}", snippet.id);
+            return Ok(snippet);
+        }
+
+        error!("Updated snippet with ID: {} not found", id);
+        Err("Updated snippet not found".into())
+    }
+
+    async fn delete_snippet(&self, id: String) -> AppResult<Snippet> {
+        let client = self.client()?;
+        match client
+            .snippet()
+            .delete(prisma::snippet::id::equals(id.to_owned()))
+            .exec()
+            .await
+        {
+            Ok(snippet) => {
+                info!("Successfully deleted snippet with ID: {}", id);
+                remove_from_collections(&client, &id).await?;
+                Ok(snippet)
+            }
+            Err(e) => {
+                error!("Failed to delete snippet with ID: {}: {}", id, e);
+                Err(e.into())
+            }
+        }
+    }
+
+    async fn update_snippet_state(
+        &self,
+        id: String,
+        data: SnippetStateUpdate,
+    ) -> AppResult<SnippetState> {
+        let mut updated_params = vec![];
+
+        if let Some(is_dark) = data.is_dark {
+            updated_params.push(prisma::snippet_state::is_dark::set(is_dark));
+        }
+
+        if let Some(is_favorite) = data.is_favorite {
+            updated_params.push(prisma::snippet_state::is_favorite::set(is_favorite));
+        }
+
+        let updated_state: SnippetState = self
+            .client()?
+            .snippet_state()
+            .update(prisma::snippet_state::id::equals(id), updated_params)
+            .exec()
+            .await?;
+
+        Ok(updated_state)
+    }
+
+    async fn record_snippet_copy(&self, id: String) -> AppResult<SnippetState> {
+        let client = self.client()?;
+        let copied_at = Utc::now();
+
+        // A snippet never copied has no count to increment, so the first copy
+        // sets it. Each update only matches while the count is in the state it
+        // expects, so concurrent copies are never lost.
+        let started = client
+            .snippet_state()
+            .update_many(
+                vec![
+                    prisma::snippet_state::id::equals(id.clone()),
+                    prisma::not![prisma::snippet_state::copy_count::gte(0)],
+                ],
+                vec![
+                    prisma::snippet_state::copy_count::set(Some(1)),
+                    prisma::snippet_state::last_copied_at::set(Some(copied_at.into())),
+                ],
+            )
+            .exec()
+            .await?;
+        if started == 0 {
+            client
+                .snippet_state()
+                .update_many(
+                    vec![prisma::snippet_state::id::equals(id.clone())],
+                    vec![
+                        prisma::snippet_state::copy_count::increment(1),
+                        prisma::snippet_state::last_copied_at::set(Some(copied_at.into())),
+                    ],
+                )
+                .exec()
+                .await?;
+        }
+
+        client
+            .snippet_state()
+            .find_unique(prisma::snippet_state::id::equals(id.clone()))
+            .exec()
+            .await?
+            .ok_or_else(|| AppError::NotFound(format!("Snippet state with ID {}", id)))
+    }
+}
+
+/// Takes a deleted snippet out of the collections it was in.
+async fn remove_from_collections(client: &PrismaClient, snippet_id: &str) -> AppResult<()> {
+    let collections = client
+        .collection()
+        .find_many(vec![prisma::collection::snippet_ids::has(
+            snippet_id.to_owned(),
+        )])
+        .exec()
+        .await?;
+
+    for collection in collections {
+        let snippet_ids: Vec<String> = collection
+            .snippet_ids
+            .into_iter()
+            .filter(|id| id != snippet_id)
+            .collect();
+        client
+            .collection()
+            .update(
+                prisma::collection::id::equals(collection.id),
+                vec![prisma::collection::snippet_ids::set(snippet_ids)],
+            )
+            .exec()
+            .await?;
+    }
+    Ok(())
+}
+
+fn build_snippet_filters(filter: Option<SnippetFilter>) -> Vec<prisma::snippet::WhereParam> {
+    let mut where_params = vec![];
+
+    info!("Building snippet filters");
+
+    if let Some(filter) = filter {
+        info!("Processing filter: {:?}", filter);
+
+        // Records from before workspaces have no workspaceId at all, which
+        // an equality filter on null doesn't match, so the default workspace
+        // is filtered after the query.
+        if let Some(WorkspaceScope::Workspace(id)) = &filter.workspace {
+            debug!("Adding workspace filter: {}", id);
+            where_params.push(prisma::snippet::workspace_id::equals(Some(id.clone())));
+        }
+
+        if let Some(search) = filter.search {
+            debug!("Adding search filter: {}", search);
+            where_params.push(prisma::or![
+                prisma::snippet::title::contains(search.clone()),
+                prisma::snippet::description::contains(search.clone()),
+                prisma::snippet::code::contains(search.clone()),
+                prisma::snippet::language::contains(search),
+            ]);
+        }
+
+        if let Some(title) = filter.title {
+            debug!("Adding title filter: {}", title);
+            where_params.push(prisma::snippet::title::contains(title));
+        }
+
+        if let Some(description) = filter.description {
+            debug!("Adding description filter: {}", description);
+            where_params.push(prisma::snippet::description::contains(description));
+        }
+
+        if let Some(language) = filter.language {
+            debug!("Adding language filter: {}", language);
+            where_params.push(prisma::snippet::language::equals(language));
+        }
+
+        if let Some(code) = filter.code {
+            debug!("Adding code filter: {}", code);
+            where_params.push(prisma::snippet::code::contains(code));
+        }
+
+        if let Some(state) = filter.state {
+            let mut state_params = vec![];
+            if let Some(is_favorite) = state.is_favorite {
+                debug!("Adding is_favorite filter: {}", is_favorite);
+                state_params.push(prisma::snippet_state::is_favorite::equals(is_favorite));
+            }
+            if let Some(is_dark) = state.is_dark {
+                debug!("Adding is_dark filter: {}", is_dark);
+                state_params.push(prisma::snippet_state::is_dark::equals(is_dark));
+            }
+            if !state_params.is_empty() {
+                info!("Adding state filter");
+                where_params.push(prisma::snippet::state::is(state_params));
+            }
+        }
+
+        if let Some(tags) = filter.tags {
+            if !tags.is_empty() {
+                debug!("Adding tags filter: {:?}", tags);
+                where_params.push(prisma::snippet::tags::some(vec![
+                    prisma::tag::name::in_vec(tags),
+                ]));
+            }
+        }
+    } else {
+        warn!("No filter provided");
+    }
+
+    where_params
+}
+
+#[async_trait]
+impl TagStore for MongoStorage {
+    async fn create_tag(&self, data: TagForm) -> AppResult<Tag> {
+        let mut create_params = vec![
+            prisma::tag::color::set(data.color),
+            prisma::tag::emoji::set(data.emoji),
+            prisma::tag::workspace_id::set(data.workspace_id),
+        ];
+        if let Some(parent_id) = data.parent_id {
+            create_params.push(prisma::tag::parent::connect(prisma::tag::id::equals(
+                parent_id,
+            )));
+        }
+
+        match self
+            .client()?
+            .tag()
+            .create(data.name, create_params)
+            .exec()
+            .await
+        {
+            Ok(tag) => {
+                info!("Successfully created tag with ID: {}", tag.id);
+                Ok(tag)
+            }
+            Err(e) => {
+                error!("Failed to create tag: {}", e);
+                Err(e.into())
+            }
+        }
+    }
+
+    async fn get_tag(&self, id: String) -> AppResult<Option<Tag>> {
+        let tag: Option<Tag> = self
+            .client()?
+            .tag()
+            .find_unique(prisma::tag::id::equals(id.to_owned()))
+            .with(prisma::tag::snippets::fetch(vec![]))
+            .exec()
+            .await?;
+
+        if let Some(tag) = tag.as_ref() {
+            info!("Successfully fetched tag with ID: {}", tag.id);
+        } else {
+            error!("Tag with ID: {} not found", id);
+        }
+
+        Ok(tag)
+    }
+
+    async fn list_tags(&self, filter: Option<TagFilter>) -> AppResult<Vec<Tag>> {
+        let workspace = filter.as_ref().and_then(|f| f.workspace.clone());
+        let where_params = build_tag_filters(filter);
+        match self
+            .client()?
+            .tag()
+            .find_many(where_params)
+            .with(prisma::tag::snippets::fetch(vec![]))
+            .exec()
+            .await
+        {
+            Ok(mut tags) => {
+                if let Some(workspace) = &workspace {
+                    tags.retain(|tag| workspace.contains(tag.workspace_id.as_deref()));
+                }
+                info!("Found {} tags", tags.len());
+                Ok(tags)
+            }
+            Err(e) => {
+                error!("Failed to list tags: {}", e);
+                Err(e.into())
+            }
+        }
+    }
+
+    async fn update_tag(&self, id: String, data: TagForm) -> AppResult<Tag> {
+        let update_params = vec![
+            prisma::tag::name::set(data.name),
+            prisma::tag::color::set(data.color),
+        ];
+
+        match self
+            .client()?
+            .tag()
+            .update(prisma::tag::id::equals(id.to_owned()), update_params)
+            .exec()
+            .await
+        {
+            Ok(tag) => {
+                info!("Successfully updated tag with ID: {}", tag.id);
+                Ok(tag)
+            }
+            Err(e) => {
+                error!("Failed to update tag with ID: {}: {}", id, e);
+                Err(e.into())
+            }
+        }
+    }
+
+    async fn delete_tag(&self, id: String) -> AppResult<Tag> {
+        match self
+            .client()?
+            .tag()
+            .delete(prisma::tag::id::equals(id.to_owned()))
+            .exec()
+            .await
+        {
+            Ok(tag) => {
+                info!("Successfully deleted tag with ID: {}", tag.id);
+                Ok(tag)
+            }
+            Err(e) => {
+                error!("Failed to delete tag with ID: {}: {}", id, e);
+                Err(e.into())
+            }
+        }
+    }
+}
+
+fn build_tag_filters(filter: Option<TagFilter>) -> Vec<prisma::tag::WhereParam> {
+    let mut where_params = vec![];
+    if let Some(filter) = filter {
+        if let Some(name) = filter.name {
+            where_params.push(prisma::tag::name::contains(name));
+        }
+        if let Some(WorkspaceScope::Workspace(id)) = filter.workspace {
+            where_params.push(prisma::tag::workspace_id::equals(Some(id)));
+        }
+    }
+    where_params
+}
+
+#[async_trait]
+impl CollectionStore for MongoStorage {
+    async fn create_collection(&self, data: CollectionForm) -> AppResult<Collection> {
+        let mut create_params = vec![
+            prisma::collection::description::set(data.description),
+            prisma::collection::position::set(data.position),
+            prisma::collection::snippet_ids::set(data.snippet_ids),
+            prisma::collection::workspace_id::set(data.workspace_id),
+        ];
+        if let Some(parent_id) = data.parent_id {
+            create_params.push(prisma::collection::parent::connect(
+                prisma::collection::id::equals(parent_id),
+            ));
+        }
+
+        let collection = self
+            .client()?
+            .collection()
+            .create(data.name, create_params)
+            .exec()
+            .await?;
+        info!("Successfully created collection with ID: {}", collection.id);
+        Ok(collection)
+    }
+
+    async fn get_collection(&self, id: String) -> AppResult<Option<Collection>> {
+        let collection = self
+            .client()?
+            .collection()
+            .find_unique(prisma::collection::id::equals(id))
+            .exec()
+            .await?;
+        Ok(collection)
+    }
+
+    async fn list_collections(
+        &self,
+        workspace: Option<WorkspaceScope>,
+    ) -> AppResult<Vec<Collection>> {
+        let mut where_params = vec![];
+        if let Some(WorkspaceScope::Workspace(id)) = &workspace {
+            where_params.push(prisma::collection::workspace_id::equals(Some(id.clone())));
+        }
+        let mut collections = self
+            .client()?
+            .collection()
+            .find_many(where_params)
+            .order_by(prisma::collection::position::order(Direction::Asc))
+            .exec()
+            .await?;
+        if let Some(workspace) = &workspace {
+            collections.retain(|collection| workspace.contains(collection.workspace_id.as_deref()));
+        }
+        info!("Found {} collections", collections.len());
+        Ok(collections)
+    }
+
+    async fn update_collection(&self, id: String, data: CollectionForm) -> AppResult<Collection> {
+        let collection = self
+            .client()?
+            .collection()
+            .update(
+                prisma::collection::id::equals(id),
+                vec![
+                    prisma::collection::name::set(data.name),
+                    prisma::collection::description::set(data.description),
+                    prisma::collection::parent_id::set(data.parent_id),
+                    prisma::collection::position::set(data.position),
+                    prisma::collection::snippet_ids::set(data.snippet_ids),
+                ],
+            )
+            .exec()
+            .await?;
+        info!("Successfully updated collection with ID: {}", collection.id);
+        Ok(collection)
+    }
+
+    async fn delete_collection(&self, id: String) -> AppResult<Collection> {
+        let collection = self
+            .client()?
+            .collection()
+            .delete(prisma::collection::id::equals(id))
+            .exec()
+            .await?;
+        info!("Successfully deleted collection with ID: {}", collection.id);
+        Ok(collection)
+    }
+}
+
+#[async_trait]
+impl UserStore for MongoStorage {
+    async fn create_user(
+        &self,
+        name: String,
+        email: String,
+        password_hash: String,
+        role: Role,
+    ) -> AppResult<User> {
+        let user = self
+            .client()?
+            .user()
+            .create(
+                name,
+                email,
+                password_hash,
+                vec![prisma::user::role::set(Some(role))],
+            )
+            .exec()
+            .await?;
+        Ok(user)
+    }
+
+    async fn get_user(&self, id: String) -> AppResult<Option<User>> {
+        let user = self
+            .client()?
+            .user()
+            .find_unique(prisma::user::id::equals(id))
+            .exec()
+            .await?;
+        Ok(user)
+    }
+
+    async fn find_user_by_email(&self, email: String) -> AppResult<Option<User>> {
+        let user = self
+            .client()?
+            .user()
+            .find_unique(prisma::user::email::equals(email))
+            .exec()
+            .await?;
+        Ok(user)
+    }
+
+    async fn list_users(&self, filter: Option<UserFilter>) -> AppResult<Vec<User>> {
+        let role = filter.as_ref().and_then(|filter| filter.role);
+        let where_params = build_user_filters(filter);
+        let mut users = self.client()?.user().find_many(where_params).exec().await?;
+        if let Some(role) = role {
+            users.retain(|user| effective_role(user.role) == role);
+        }
+        Ok(users)
+    }
+
+    async fn count_users(&self, role: Option<Role>) -> AppResult<i64> {
+        match role {
+            // Members include the accounts with no role stored.
+            Some(Role::Member) => {
+                let filter = UserFilter {
+                    email: None,
+                    role: Some(Role::Member),
+                };
+                Ok(self.list_users(Some(filter)).await?.len() as i64)
+            }
+            role => {
+                let where_params = role
+                    .map(|role| vec![prisma::user::role::equals(Some(role))])
+                    .unwrap_or_default();
+                let count = self.client()?.user().count(where_params).exec().await?;
+                Ok(count)
+            }
+        }
+    }
+
+    async fn update_user(&self, id: String, name: String, email: String) -> AppResult<User> {
+        let user = self
+            .client()?
+            .user()
+            .update(
+                prisma::user::id::equals(id),
+                vec![
+                    prisma::user::name::set(name),
+                    prisma::user::email::set(email),
+                ],
+            )
+            .exec()
+            .await?;
+        Ok(user)
+    }
+
+    async fn set_user_role(&self, id: String, role: Role) -> AppResult<User> {
+        let user = self
+            .client()?
+            .user()
+            .update(
+                prisma::user::id::equals(id),
+                vec![prisma::user::role::set(Some(role))],
+            )
+            .exec()
+            .await?;
+        Ok(user)
+    }
+
+    async fn delete_user(&self, id: String, options: UserDeletionOptions) -> AppResult<User> {
+        let deleted = self
+            .client()?
+            ._transaction()
+            .run(|tx| async move {
+                let new_owner = options.transfer_snippets_to;
+                tx.snippet()
+                    .update_many(
+                        vec![prisma::snippet::author_id::equals(Some(id.clone()))],
+                        vec![prisma::snippet::author_id::set(new_owner.clone())],
+                    )
+                    .exec()
+                    .await?;
+                tx.snippet_rating()
+                    .delete_many(vec![prisma::snippet_rating::user_id::equals(id.clone())])
+                    .exec()
+                    .await?;
+                tx.notification()
+                    .delete_many(vec![prisma::notification::user_id::equals(id.clone())])
+                    .exec()
+                    .await?;
+                tx.share()
+                    .delete_many(vec![prisma::share::user_id::equals(Some(id.clone()))])
+                    .exec()
+                    .await?;
+                // Groups and shares the user made go to whoever takes over
+                // their snippets; without one, they are dropped.
+                match &new_owner {
+                    Some(new_owner) => {
+                        tx.share()
+                            .update_many(
+                                vec![prisma::share::granted_by_id::equals(id.clone())],
+                                vec![prisma::share::granted_by_id::set(new_owner.clone())],
+                            )
+                            .exec()
+                            .await?;
+                        tx.user_group()
+                            .update_many(
+                                vec![prisma::user_group::owner_id::equals(id.clone())],
+                                vec![prisma::user_group::owner_id::set(new_owner.clone())],
+                            )
+                            .exec()
+                            .await?;
+                    }
+                    None => {
+                        let owned_groups: Vec<String> = tx
+                            .user_group()
+                            .find_many(vec![prisma::user_group::owner_id::equals(id.clone())])
+                            .exec()
+                            .await?
+                            .into_iter()
+                            .map(|group| group.id)
+                            .collect();
+                        tx.share()
+                            .delete_many(vec![prisma::or![
+                                prisma::share::granted_by_id::equals(id.clone()),
+                                prisma::share::group_id::in_vec(owned_groups.clone()),
+                            ]])
+                            .exec()
+                            .await?;
+                        tx.user_group()
+                            .delete_many(vec![prisma::user_group::id::in_vec(owned_groups)])
+                            .exec()
+                            .await?;
+                    }
+                }
+                let groups = tx
+                    .user_group()
+                    .find_many(vec![prisma::user_group::member_ids::has(id.clone())])
+                    .exec()
+                    .await?;
+                for group in groups {
+                    let member_ids = group
+                        .member_ids
+                        .into_iter()
+                        .filter(|member_id| *member_id != id)
+                        .collect();
+                    tx.user_group()
+                        .update(
+                            prisma::user_group::id::equals(group.id),
+                            vec![prisma::user_group::member_ids::set(member_ids)],
+                        )
+                        .exec()
+                        .await?;
+                }
+                tx.workspace_membership()
+                    .delete_many(vec![prisma::workspace_membership::user_id::equals(
+                        id.clone(),
+                    )])
+                    .exec()
+                    .await?;
+                tx.api_rate_limit()
+                    .delete_many(vec![prisma::api_rate_limit::user_id::equals(id.clone())])
+                    .exec()
+                    .await?;
+                tx.activity_log()
+                    .delete_many(vec![prisma::activity_log::user_id::equals(id.clone())])
+                    .exec()
+                    .await?;
+
+                tx.user().delete(prisma::user::id::equals(id)).exec().await
+            })
+            .await?;
+
+        info!("Deleted user with ID: {}", deleted.id);
+        Ok(deleted)
+    }
+
+    async fn export_user_data(&self, id: String) -> AppResult<UserDataExport> {
+        let client = self.client()?;
+
+        let user = client
+            .user()
+            .find_unique(prisma::user::id::equals(id.clone()))
+            .exec()
+            .await?
+            .ok_or_else(|| AppError::NotFound("User".into()))?;
+
+        let snippets = client
+            .snippet()
+            .find_many(vec![prisma::snippet::author_id::equals(Some(id.clone()))])
+            .with(prisma::snippet::state::fetch())
+            .with(prisma::snippet::tags::fetch(vec![]))
+            .exec()
+            .await?;
+        let snippet_ratings = client
+            .snippet_rating()
+            .find_many(vec![prisma::snippet_rating::user_id::equals(id.clone())])
+            .exec()
+            .await?;
+        let notifications = client
+            .notification()
+            .find_many(vec![prisma::notification::user_id::equals(id.clone())])
+            .exec()
+            .await?;
+        let api_rate_limits = client
+            .api_rate_limit()
+            .find_many(vec![prisma::api_rate_limit::user_id::equals(id.clone())])
+            .exec()
+            .await?;
+        let activity_logs = client
+            .activity_log()
+            .find_many(vec![prisma::activity_log::user_id::equals(id)])
+            .exec()
+            .await?;
+
+        info!(
+            "Exported {} snippets for user with ID: {}",
+            snippets.len(),
+            user.id
+        );
+
+        Ok(UserDataExport {
+            format_version: USER_EXPORT_FORMAT_VERSION,
+            exported_at: Utc::now(),
+            user: user.into(),
+            snippets,
+            snippet_ratings,
+            notifications,
+            api_rate_limits,
+            activity_logs,
+        })
+    }
+}
+
+#[async_trait]
+impl BackupStore for MongoStorage {
+    async fn export_snapshot(&self) -> AppResult<VaultSnapshot> {
+        let client = self.client()?;
+
+        let snapshot = VaultSnapshot {
+            snippets: client.snippet().find_many(vec![]).exec().await?,
+            states: client.snippet_state().find_many(vec![]).exec().await?,
+            tags: client.tag().find_many(vec![]).exec().await?,
+            snippet_ratings: client.snippet_rating().find_many(vec![]).exec().await?,
+            tag_usages: client.tag_usage().find_many(vec![]).exec().await?,
+            activity_logs: client.activity_log().find_many(vec![]).exec().await?,
+            collections: client.collection().find_many(vec![]).exec().await?,
+        };
+
+        info!(
+            "Read {} snippets and {} tags for a snapshot",
+            snapshot.snippets.len(),
+            snapshot.tags.len()
+        );
+        Ok(snapshot)
+    }
+
+    async fn import_snapshot(&self, snapshot: VaultSnapshot, replace: bool) -> AppResult<()> {
+        let client = self.client()?;
+
+        client
+            ._transaction()
+            .run(|tx| async move {
+                if replace {
+                    tx.collection().delete_many(vec![]).exec().await?;
+                    tx.activity_log().delete_many(vec![]).exec().await?;
+                    tx.tag_usage().delete_many(vec![]).exec().await?;
+                    tx.snippet_rating().delete_many(vec![]).exec().await?;
+                    tx.snippet().delete_many(vec![]).exec().await?;
+                    tx.snippet_state().delete_many(vec![]).exec().await?;
+                    tx.tag().delete_many(vec![]).exec().await?;
+                }
+
+                write_snapshot(&tx, snapshot).await
+            })
+            .await?;
+
+        info!("Imported snapshot (replace: {})", replace);
+        Ok(())
+    }
+}
+
+/// Creates every record of the snapshot, parents before the records that
+/// reference them.
+async fn write_snapshot(client: &PrismaClient, snapshot: VaultSnapshot) -> Result<(), QueryError> {
+    for state in snapshot.states {
+        client
+            .snippet_state()
+            .create(vec![
+                prisma::snippet_state::id::set(state.id),
+                prisma::snippet_state::is_favorite::set(state.is_favorite),
+                prisma::snippet_state::is_dark::set(state.is_dark),
+                prisma::snippet_state::copy_count::set(state.copy_count),
+                prisma::snippet_state::last_copied_at::set(state.last_copied_at),
+            ])
+            .exec()
+            .await?;
+    }
+
+    for tag in &snapshot.tags {
+        client
+            .tag()
+            .create(
+                tag.name.clone(),
+                vec![
+                    prisma::tag::id::set(tag.id.clone()),
+                    prisma::tag::created_at::set(tag.created_at),
+                    prisma::tag::color::set(tag.color.clone()),
+                    prisma::tag::emoji::set(tag.emoji.clone()),
+                    prisma::tag::count::set(tag.count),
+                    prisma::tag::snippet_ids::set(tag.snippet_ids.clone()),
+                    prisma::tag::workspace_id::set(tag.workspace_id.clone()),
+                ],
+            )
+            .exec()
+            .await?;
+    }
+    // Parents may come after their children, so the hierarchy is linked once
+    // every tag exists.
+    for tag in &snapshot.tags {
+        if let Some(parent_id) = &tag.parent_id {
+            client
+                .tag()
+                .update(
+                    prisma::tag::id::equals(tag.id.clone()),
+                    vec![prisma::tag::parent::connect(prisma::tag::id::equals(
+                        parent_id.clone(),
+                    ))],
+                )
+                .exec()
+                .await?;
+        }
+    }
+
+    for snippet in snapshot.snippets {
+        let mut create_params = vec![
+            prisma::snippet::id::set(snippet.id),
+            prisma::snippet::created_at::set(snippet.created_at),
+            prisma::snippet::tag_ids::set(snippet.tag_ids),
+            prisma::snippet::metadata::set(snippet.metadata),
+            prisma::snippet::workspace_id::set(snippet.workspace_id),
+        ];
+        if let Some(author_id) = snippet.author_id {
+            create_params.push(prisma::snippet::author::connect(prisma::user::id::equals(
+                author_id,
+            )));
+        }
+
+        client
+            .snippet()
+            .create(
+                snippet.title,
+                snippet.description,
+                snippet.language,
+                snippet.code,
+                prisma::snippet_state::id::equals(snippet.snippet_state_id),
+                create_params,
+            )
+            .exec()
+            .await?;
+    }
+
+    for rating in snapshot.snippet_ratings {
+        client
+            .snippet_rating()
+            .create(
+                rating.rating,
+                prisma::snippet::id::equals(rating.snippet_id),
+                prisma::user::id::equals(rating.user_id),
+                vec![
+                    prisma::snippet_rating::id::set(rating.id),
+                    prisma::snippet_rating::created_at::set(rating.created_at),
+                ],
+            )
+            .exec()
+            .await?;
+    }
+
+    for usage in snapshot.tag_usages {
+        client
+            .tag_usage()
+            .create(
+                prisma::tag::id::equals(usage.tag_id),
+                prisma::snippet::id::equals(usage.snippet_id),
+                vec![
+                    prisma::tag_usage::id::set(usage.id),
+                    prisma::tag_usage::count::set(usage.count),
+                ],
+            )
+            .exec()
+            .await?;
+    }
+
+    for collection in &snapshot.collections {
+        client
+            .collection()
+            .create(
+                collection.name.clone(),
+                vec![
+                    prisma::collection::id::set(collection.id.clone()),
+                    prisma::collection::created_at::set(collection.created_at),
+                    prisma::collection::description::set(collection.description.clone()),
+                    prisma::collection::position::set(collection.position),
+                    prisma::collection::snippet_ids::set(collection.snippet_ids.clone()),
+                    prisma::collection::workspace_id::set(collection.workspace_id.clone()),
+                ],
+            )
+            .exec()
+            .await?;
+    }
+    for collection in &snapshot.collections {
+        if let Some(parent_id) = &collection.parent_id {
+            client
+                .collection()
+                .update(
+                    prisma::collection::id::equals(collection.id.clone()),
+                    vec![prisma::collection::parent::connect(
+                        prisma::collection::id::equals(parent_id.clone()),
+                    )],
+                )
+                .exec()
+                .await?;
+        }
+    }
+
+    for activity in snapshot.activity_logs {
+        client
+            .activity_log()
+            .create(
+                activity.user_id,
+                activity.action,
+                vec![
+                    prisma::activity_log::id::set(activity.id),
+                    prisma::activity_log::timestamp::set(activity.timestamp),
+                    prisma::activity_log::snippet_id::set(activity.snippet_id),
+                ],
+            )
+            .exec()
+            .await?;
+    }
+
+    Ok(())
+}
+
+fn build_user_filters(filter: Option<UserFilter>) -> Vec<prisma::user::WhereParam> {
+    let mut where_params = vec![];
+    if let Some(filter) = filter {
+        if let Some(email) = filter.email {
+            where_params.push(prisma::user::email::contains(email));
+        }
+        // Members are matched in memory, since accounts from before roles
+        // have none stored.
+        if let Some(role) = filter.role.filter(|role| *role != Role::Member) {
+            where_params.push(prisma::user::role::equals(Some(role)));
+        }
+    }
+    where_params
+}
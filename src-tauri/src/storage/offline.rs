@@ -0,0 +1,932 @@
+use std::{path::PathBuf, sync::Arc};
+
+use async_trait::async_trait;
+use chrono::{DateTime, FixedOffset, Utc};
+use log::{info, warn};
+use prisma::{PrismaClient, Role};
+use serde::Serialize;
+use tokio::sync::RwLock;
+
+use super::{
+    journal::{ChangeOperation, Journal, PendingChange, SnippetChange, TagChange},
+    BackupStore, CollectionStore, LocalStorage, MongoStorage, SnippetStore, Storage, TagStore,
+    UserStore,
+};
+use crate::{
+    auth::{effective_role, Principal},
+    database::models::{
+        Collection, CollectionForm, Snippet, SnippetFilter, SnippetForm, SnippetState,
+        SnippetStateUpdate, Tag, TagFilter, TagForm, User, UserDataExport, UserDeletionOptions,
+        UserFilter, VaultSnapshot, WorkspaceScope,
+    },
+    error::AppError,
+    prelude::AppResult,
+    sharing::{Access, Grants},
+};
+
+pub const PENDING_CHANGES_SYNCED_EVENT: &str = "pending-changes-synced";
+
+/// The MongoDB backend with an offline fallback.
+///
+/// While connected, every call goes to MongoDB and the results are mirrored
+/// into a local cache. While disconnected, reads are served from that cache
+/// and snippet/tag mutations are applied to it optimistically and recorded in
+/// a [`Journal`], which [`OfflineStorage::on_reconnect`] replays against
+/// MongoDB once it is reachable again. A mutation that fails because MongoDB
+/// stopped answering takes the storage offline and is journaled the same way.
+///
+/// The cache is kept for the user the app was last used by and only holds
+/// what they can reach, so the vault file on disk never carries other users'
+/// snippets or any password hash.
+pub struct OfflineStorage {
+    remote: Arc<MongoStorage>,
+    cache: LocalStorage,
+    journal: Journal,
+    /// The user the cache is kept for; none until someone signs in.
+    cache_owner: RwLock<Option<String>>,
+}
+
+/// The outcome of replaying the journal after reconnecting.
+#[derive(Debug, Default, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncReport {
+    pub applied: usize,
+    pub conflicts: usize,
+    pub remaining: usize,
+}
+
+enum ReplayOutcome {
+    Applied,
+    Conflict(String),
+}
+
+impl OfflineStorage {
+    pub async fn open(remote: Arc<MongoStorage>, dir: PathBuf) -> AppResult<Self> {
+        let cache = LocalStorage::open(dir.clone()).await?;
+        let journal = Journal::open(dir).await?;
+        // The cache holds no account but its owner's. One holding several is
+        // from before caches were kept per user and is emptied on the next
+        // refresh.
+        let cache_owner = match cache.list_users(None).await?.as_slice() {
+            [user] => Some(user.id.clone()),
+            _ => None,
+        };
+        Ok(Self {
+            remote,
+            cache,
+            journal,
+            cache_owner: RwLock::new(cache_owner),
+        })
+    }
+
+    pub fn remote(&self) -> &MongoStorage {
+        &self.remote
+    }
+
+    fn is_online(&self) -> bool {
+        self.remote.is_connected()
+    }
+
+    /// Whether a write sent to MongoDB failed because the server stopped
+    /// answering. The storage then goes offline at once, instead of at the
+    /// monitor's next health check, and the write falls back to the journal.
+    fn lost_connection(&self, error: &AppError) -> bool {
+        if !error.is_connection_error() {
+            return false;
+        }
+        warn!(
+            "Lost connection to the database, recording the change offline: {}",
+            error
+        );
+        self.remote.disconnect();
+        true
+    }
+
+    /// Replays pending changes in the order they were made, then refreshes
+    /// the cache from MongoDB once nothing is left to replay.
+    ///
+    /// Replay stops at the first failing change so that later changes never
+    /// overtake it; it is retried on the next reconnect.
+    pub async fn on_reconnect(&self) -> AppResult<SyncReport> {
+        let client = self.remote.prisma()?;
+        let mut report = SyncReport::default();
+
+        for change in self.journal.pending().await {
+            match replay(&client, &change).await? {
+                ReplayOutcome::Applied => {
+                    self.journal.remove(&change.id).await?;
+                    report.applied += 1;
+                }
+                ReplayOutcome::Conflict(reason) => {
+                    warn!(
+                        "Offline change to {} conflicts with the server: {}",
+                        change.operation.target_id(),
+                        reason
+                    );
+                    self.journal.mark_conflict(&change.id, reason).await?;
+                    report.conflicts += 1;
+                }
+            }
+        }
+
+        report.remaining = self.journal.pending().await.len();
+        if report.remaining == 0 {
+            self.refresh_cache(&client).await?;
+        }
+
+        info!(
+            "Synchronized offline changes: {} applied, {} conflicts",
+            report.applied, report.conflicts
+        );
+        Ok(report)
+    }
+
+    /// Rebuilds the cache from MongoDB with what its owner can reach: their
+    /// account, the snippets they may read, and the tags and collections of
+    /// the workspaces they belong to.
+    async fn refresh_cache(&self, client: &PrismaClient) -> AppResult<()> {
+        let owner = match self.cache_owner.read().await.clone() {
+            Some(id) => {
+                client
+                    .user()
+                    .find_unique(prisma::user::id::equals(id))
+                    .exec()
+                    .await?
+            }
+            None => None,
+        };
+        let owner = match owner {
+            Some(owner) => owner,
+            None => return self.cache.replace_all(&[], &[], &[], &[]).await,
+        };
+
+        let principals = workspace_principals(client, &owner).await?;
+        let in_reach = |workspace_id: Option<&str>| {
+            principals
+                .iter()
+                .any(|principal| principal.workspace().contains(workspace_id))
+        };
+        let grants = Grants::load(self.remote.as_ref(), &principals[0]).await?;
+
+        let mut snippets = client
+            .snippet()
+            .find_many(vec![])
+            .with(prisma::snippet::state::fetch())
+            .exec()
+            .await?;
+        snippets.retain(|snippet| {
+            principals
+                .iter()
+                .any(|principal| grants.access(principal, snippet) >= Access::Read)
+        });
+        let mut tags = client.tag().find_many(vec![]).exec().await?;
+        tags.retain(|tag| in_reach(tag.workspace_id.as_deref()));
+        let mut collections = client.collection().find_many(vec![]).exec().await?;
+        collections.retain(|collection| in_reach(collection.workspace_id.as_deref()));
+
+        self.cache
+            .replace_all(&[without_password(&owner)], &snippets, &tags, &collections)
+            .await
+    }
+
+    /// Mirrors a changed account into the cache when it is the owner's.
+    async fn cache_user(&self, user: &User) {
+        if self.cache_owner.read().await.as_deref() == Some(user.id.as_str()) {
+            log_cache_error(self.cache.upsert_user(&without_password(user)).await);
+        }
+    }
+
+    async fn cached_snippet_version(&self, id: &str) -> AppResult<DateTime<FixedOffset>> {
+        let snippet = self
+            .cache
+            .get_snippet(id.to_owned())
+            .await?
+            .ok_or_else(|| not_available_offline("Snippet", id))?;
+        Ok(snippet.updated_at)
+    }
+
+    async fn cached_tag_version(&self, id: &str) -> AppResult<DateTime<FixedOffset>> {
+        let tag = self
+            .cache
+            .get_tag(id.to_owned())
+            .await?
+            .ok_or_else(|| not_available_offline("Tag", id))?;
+        Ok(tag.updated_at)
+    }
+}
+
+/// The user as a principal in the default workspace, first, and in each
+/// workspace they are a member of.
+async fn workspace_principals(client: &PrismaClient, user: &User) -> AppResult<Vec<Principal>> {
+    let memberships = client
+        .workspace_membership()
+        .find_many(vec![prisma::workspace_membership::user_id::equals(
+            user.id.clone(),
+        )])
+        .exec()
+        .await?;
+    let role = effective_role(user.role);
+    Ok(std::iter::once((None, None))
+        .chain(
+            memberships
+                .into_iter()
+                .map(|membership| (Some(membership.workspace_id), Some(membership.role))),
+        )
+        .map(|(workspace_id, workspace_role)| Principal {
+            user_id: user.id.clone(),
+            email: user.email.clone(),
+            role,
+            workspace_id,
+            workspace_role,
+        })
+        .collect())
+}
+
+/// The account as the cache keeps it. Signing in with a password needs
+/// MongoDB; sessions started before going offline keep working.
+fn without_password(user: &User) -> User {
+    User {
+        password_hash: String::new(),
+        ..user.clone()
+    }
+}
+
+fn not_available_offline(kind: &str, id: &str) -> AppError {
+    AppError::Other(format!("{} with ID {} is not available offline", kind, id))
+}
+
+/// Failing to update the cache never fails the call itself; the cache is
+/// rebuilt from MongoDB on the next reconnect anyway.
+fn log_cache_error(result: AppResult<()>) {
+    if let Err(e) = result {
+        warn!("Failed to update the offline cache: {}", e);
+    }
+}
+
+fn changed_since(
+    remote_updated_at: DateTime<FixedOffset>,
+    base_updated_at: Option<DateTime<FixedOffset>>,
+) -> Option<String> {
+    match base_updated_at {
+        Some(base) if remote_updated_at > base => Some(format!(
+            "changed on the server at {} after the offline edit of the version from {}",
+            remote_updated_at, base
+        )),
+        _ => None,
+    }
+}
+
+async fn replay(client: &PrismaClient, change: &PendingChange) -> AppResult<ReplayOutcome> {
+    match &change.operation {
+        ChangeOperation::CreateSnippet { snippet } => create_remote_snippet(client, snippet).await,
+        ChangeOperation::UpdateSnippet { snippet } => {
+            let remote = client
+                .snippet()
+                .find_unique(prisma::snippet::id::equals(snippet.id.clone()))
+                .exec()
+                .await?;
+            let remote = match remote {
+                Some(remote) => remote,
+                None => return Ok(ReplayOutcome::Conflict("deleted on the server".to_string())),
+            };
+            if let Some(reason) = changed_since(remote.updated_at, change.base_updated_at) {
+                return Ok(ReplayOutcome::Conflict(reason));
+            }
+
+            client
+                .snippet()
+                .update(
+                    prisma::snippet::id::equals(snippet.id.clone()),
+                    vec![
+                        prisma::snippet::title::set(snippet.title.clone()),
+                        prisma::snippet::description::set(snippet.description.clone()),
+                        prisma::snippet::language::set(snippet.language.clone()),
+                        prisma::snippet::code::set(snippet.code.clone()),
+                        prisma::snippet::tag_ids::set(snippet.tag_ids.clone()),
+                        prisma::snippet::snippet_state_id::set(snippet.snippet_state_id.clone()),
+                    ],
+                )
+                .exec()
+                .await?;
+            Ok(ReplayOutcome::Applied)
+        }
+        ChangeOperation::DeleteSnippet { id } => {
+            let remote = client
+                .snippet()
+                .find_unique(prisma::snippet::id::equals(id.clone()))
+                .exec()
+                .await?;
+            if let Some(remote) = remote {
+                if let Some(reason) = changed_since(remote.updated_at, change.base_updated_at) {
+                    return Ok(ReplayOutcome::Conflict(reason));
+                }
+                client
+                    .snippet()
+                    .delete(prisma::snippet::id::equals(id.clone()))
+                    .exec()
+                    .await?;
+            }
+            Ok(ReplayOutcome::Applied)
+        }
+        ChangeOperation::UpdateSnippetState {
+            id,
+            is_favorite,
+            is_dark,
+        } => {
+            let remote = client
+                .snippet_state()
+                .find_unique(prisma::snippet_state::id::equals(id.clone()))
+                .exec()
+                .await?;
+            // The state only exists alongside its snippet, so there is nothing
+            // left to update once that is gone.
+            if remote.is_none() {
+                return Ok(ReplayOutcome::Applied);
+            }
+
+            client
+                .snippet_state()
+                .update(
+                    prisma::snippet_state::id::equals(id.clone()),
+                    vec![
+                        prisma::snippet_state::is_favorite::set(*is_favorite),
+                        prisma::snippet_state::is_dark::set(*is_dark),
+                    ],
+                )
+                .exec()
+                .await?;
+            Ok(ReplayOutcome::Applied)
+        }
+        ChangeOperation::RecordSnippetCopies {
+            id,
+            copies,
+            last_copied_at,
+        } => {
+            let remote = match client
+                .snippet_state()
+                .find_unique(prisma::snippet_state::id::equals(id.clone()))
+                .exec()
+                .await?
+            {
+                Some(remote) => remote,
+                None => return Ok(ReplayOutcome::Applied),
+            };
+
+            client
+                .snippet_state()
+                .update(
+                    prisma::snippet_state::id::equals(id.clone()),
+                    vec![
+                        prisma::snippet_state::copy_count::set(Some(
+                            remote.copy_count.unwrap_or(0) + copies,
+                        )),
+                        prisma::snippet_state::last_copied_at::set(
+                            remote.last_copied_at.max(Some(*last_copied_at)),
+                        ),
+                    ],
+                )
+                .exec()
+                .await?;
+            Ok(ReplayOutcome::Applied)
+        }
+        ChangeOperation::CreateTag { tag } => {
+            let existing = client
+                .tag()
+                .find_unique(prisma::tag::id::equals(tag.id.clone()))
+                .exec()
+                .await?;
+            if existing.is_none() {
+                let mut create_params = vec![
+                    prisma::tag::id::set(tag.id.clone()),
+                    prisma::tag::color::set(tag.color.clone()),
+                    prisma::tag::emoji::set(tag.emoji.clone()),
+                    prisma::tag::workspace_id::set(tag.workspace_id.clone()),
+                ];
+                if let Some(parent_id) = &tag.parent_id {
+                    create_params.push(prisma::tag::parent::connect(prisma::tag::id::equals(
+                        parent_id.clone(),
+                    )));
+                }
+                client
+                    .tag()
+                    .create(tag.name.clone(), create_params)
+                    .exec()
+                    .await?;
+            }
+            Ok(ReplayOutcome::Applied)
+        }
+        ChangeOperation::UpdateTag { tag } => {
+            let remote = client
+                .tag()
+                .find_unique(prisma::tag::id::equals(tag.id.clone()))
+                .exec()
+                .await?;
+            let remote = match remote {
+                Some(remote) => remote,
+                None => return Ok(ReplayOutcome::Conflict("deleted on the server".to_string())),
+            };
+            if let Some(reason) = changed_since(remote.updated_at, change.base_updated_at) {
+                return Ok(ReplayOutcome::Conflict(reason));
+            }
+
+            client
+                .tag()
+                .update(
+                    prisma::tag::id::equals(tag.id.clone()),
+                    vec![
+                        prisma::tag::name::set(tag.name.clone()),
+                        prisma::tag::color::set(tag.color.clone()),
+                    ],
+                )
+                .exec()
+                .await?;
+            Ok(ReplayOutcome::Applied)
+        }
+        ChangeOperation::DeleteTag { id } => {
+            let remote = client
+                .tag()
+                .find_unique(prisma::tag::id::equals(id.clone()))
+                .exec()
+                .await?;
+            if let Some(remote) = remote {
+                if let Some(reason) = changed_since(remote.updated_at, change.base_updated_at) {
+                    return Ok(ReplayOutcome::Conflict(reason));
+                }
+                client
+                    .tag()
+                    .delete(prisma::tag::id::equals(id.clone()))
+                    .exec()
+                    .await?;
+            }
+            Ok(ReplayOutcome::Applied)
+        }
+    }
+}
+
+/// Creates a snippet made offline under the ids it was given locally, so
+/// later journal entries and the frontend keep referring to the same records.
+/// Parts that already exist from an interrupted replay are skipped.
+async fn create_remote_snippet(
+    client: &PrismaClient,
+    snippet: &SnippetChange,
+) -> AppResult<ReplayOutcome> {
+    let existing = client
+        .snippet()
+        .find_unique(prisma::snippet::id::equals(snippet.id.clone()))
+        .exec()
+        .await?;
+    if existing.is_some() {
+        return Ok(ReplayOutcome::Applied);
+    }
+
+    let state = client
+        .snippet_state()
+        .find_unique(prisma::snippet_state::id::equals(
+            snippet.snippet_state_id.clone(),
+        ))
+        .exec()
+        .await?;
+    if state.is_none() {
+        client
+            .snippet_state()
+            .create(vec![prisma::snippet_state::id::set(
+                snippet.snippet_state_id.clone(),
+            )])
+            .exec()
+            .await?;
+    }
+
+    let mut create_params = vec![
+        prisma::snippet::id::set(snippet.id.clone()),
+        prisma::snippet::tag_ids::set(snippet.tag_ids.clone()),
+        prisma::snippet::workspace_id::set(snippet.workspace_id.clone()),
+    ];
+    if let Some(author_id) = &snippet.author_id {
+        create_params.push(prisma::snippet::author::connect(prisma::user::id::equals(
+            author_id.clone(),
+        )));
+    }
+
+    client
+        .snippet()
+        .create(
+            snippet.title.clone(),
+            snippet.description.clone(),
+            snippet.language.clone(),
+            snippet.code.clone(),
+            prisma::snippet_state::id::equals(snippet.snippet_state_id.clone()),
+            create_params,
+        )
+        .exec()
+        .await?;
+    Ok(ReplayOutcome::Applied)
+}
+
+#[async_trait]
+impl Storage for OfflineStorage {
+    fn backend_name(&self) -> &'static str {
+        self.remote.backend_name()
+    }
+
+    fn prisma(&self) -> AppResult<Arc<PrismaClient>> {
+        self.remote.prisma()
+    }
+
+    async fn pending_changes(&self) -> AppResult<Vec<PendingChange>> {
+        Ok(self.journal.list().await)
+    }
+
+    async fn discard_pending_change(&self, id: String) -> AppResult<PendingChange> {
+        self.journal
+            .remove(&id)
+            .await?
+            .ok_or_else(|| AppError::NotFound(format!("Pending change with ID {}", id)))
+    }
+
+    /// Hands the cache over to a user other than its owner, rebuilding it for
+    /// them unless offline changes are still waiting to be replayed.
+    async fn signed_in(&self, user: &User) {
+        if self.cache_owner.read().await.as_deref() == Some(user.id.as_str()) {
+            return;
+        }
+        *self.cache_owner.write().await = Some(user.id.clone());
+        info!("Keeping the offline cache for user {}", user.id);
+
+        if !self.is_online() || !self.journal.pending().await.is_empty() {
+            return;
+        }
+        match self.remote.prisma() {
+            Ok(client) => log_cache_error(self.refresh_cache(&client).await),
+            Err(e) => warn!("Failed to update the offline cache: {}", e),
+        }
+    }
+}
+
+#[async_trait]
+impl SnippetStore for OfflineStorage {
+    async fn create_snippet(
+        &self,
+        data: SnippetForm,
+        author_id: Option<String>,
+    ) -> AppResult<Snippet> {
+        if self.is_online() {
+            match self
+                .remote
+                .create_snippet(data.clone(), author_id.clone())
+                .await
+            {
+                Ok(snippet) => {
+                    log_cache_error(self.cache.upsert_snippet(&snippet).await);
+                    return Ok(snippet);
+                }
+                Err(e) if !self.lost_connection(&e) => return Err(e),
+                Err(_) => {}
+            }
+        }
+
+        let snippet = self.cache.create_snippet(data, author_id).await?;
+        self.journal
+            .record(
+                ChangeOperation::CreateSnippet {
+                    snippet: SnippetChange::from(&snippet),
+                },
+                None,
+            )
+            .await?;
+        Ok(snippet)
+    }
+
+    async fn get_snippet(&self, id: String) -> AppResult<Option<Snippet>> {
+        if self.is_online() {
+            return self.remote.get_snippet(id).await;
+        }
+        self.cache.get_snippet(id).await
+    }
+
+    async fn list_snippets(&self, filter: Option<SnippetFilter>) -> AppResult<Vec<Snippet>> {
+        if self.is_online() {
+            return self.remote.list_snippets(filter).await;
+        }
+        self.cache.list_snippets(filter).await
+    }
+
+    async fn update_snippet(&self, id: String, data: SnippetForm) -> AppResult<Snippet> {
+        if self.is_online() {
+            match self.remote.update_snippet(id.clone(), data.clone()).await {
+                Ok(snippet) => {
+                    log_cache_error(self.cache.upsert_snippet(&snippet).await);
+                    return Ok(snippet);
+                }
+                Err(e) if !self.lost_connection(&e) => return Err(e),
+                Err(_) => {}
+            }
+        }
+
+        let base_updated_at = self.cached_snippet_version(&id).await?;
+        let snippet = self.cache.update_snippet(id, data).await?;
+        self.journal
+            .record(
+                ChangeOperation::UpdateSnippet {
+                    snippet: SnippetChange::from(&snippet),
+                },
+                Some(base_updated_at),
+            )
+            .await?;
+        Ok(snippet)
+    }
+
+    async fn delete_snippet(&self, id: String) -> AppResult<Snippet> {
+        if self.is_online() {
+            match self.remote.delete_snippet(id.clone()).await {
+                Ok(snippet) => {
+                    log_cache_error(self.cache.delete_snippet(id).await.map(|_| ()));
+                    return Ok(snippet);
+                }
+                Err(e) if !self.lost_connection(&e) => return Err(e),
+                Err(_) => {}
+            }
+        }
+
+        let base_updated_at = self.cached_snippet_version(&id).await?;
+        let snippet = self.cache.delete_snippet(id.clone()).await?;
+        self.journal
+            .record(ChangeOperation::DeleteSnippet { id }, Some(base_updated_at))
+            .await?;
+        Ok(snippet)
+    }
+
+    async fn update_snippet_state(
+        &self,
+        id: String,
+        data: SnippetStateUpdate,
+    ) -> AppResult<SnippetState> {
+        if self.is_online() {
+            match self
+                .remote
+                .update_snippet_state(id.clone(), data.clone())
+                .await
+            {
+                Ok(state) => {
+                    log_cache_error(self.cache.upsert_snippet_state(&state).await);
+                    return Ok(state);
+                }
+                Err(e) if !self.lost_connection(&e) => return Err(e),
+                Err(_) => {}
+            }
+        }
+
+        let state = self.cache.update_snippet_state(id, data).await?;
+        self.journal
+            .record(
+                ChangeOperation::UpdateSnippetState {
+                    id: state.id.clone(),
+                    is_favorite: state.is_favorite,
+                    is_dark: state.is_dark,
+                },
+                None,
+            )
+            .await?;
+        Ok(state)
+    }
+
+    async fn record_snippet_copy(&self, id: String) -> AppResult<SnippetState> {
+        if self.is_online() {
+            match self.remote.record_snippet_copy(id.clone()).await {
+                Ok(state) => {
+                    log_cache_error(self.cache.upsert_snippet_state(&state).await);
+                    return Ok(state);
+                }
+                Err(e) if !self.lost_connection(&e) => return Err(e),
+                Err(_) => {}
+            }
+        }
+
+        let state = self.cache.record_snippet_copy(id).await?;
+        self.journal
+            .record(
+                ChangeOperation::RecordSnippetCopies {
+                    id: state.id.clone(),
+                    copies: 1,
+                    last_copied_at: state.last_copied_at.unwrap_or_else(|| Utc::now().into()),
+                },
+                None,
+            )
+            .await?;
+        Ok(state)
+    }
+}
+
+#[async_trait]
+impl TagStore for OfflineStorage {
+    async fn create_tag(&self, data: TagForm) -> AppResult<Tag> {
+        if self.is_online() {
+            match self.remote.create_tag(data.clone()).await {
+                Ok(tag) => {
+                    log_cache_error(self.cache.upsert_tag(&tag).await);
+                    return Ok(tag);
+                }
+                Err(e) if !self.lost_connection(&e) => return Err(e),
+                Err(_) => {}
+            }
+        }
+
+        let tag = self.cache.create_tag(data).await?;
+        self.journal
+            .record(
+                ChangeOperation::CreateTag {
+                    tag: TagChange::from(&tag),
+                },
+                None,
+            )
+            .await?;
+        Ok(tag)
+    }
+
+    async fn get_tag(&self, id: String) -> AppResult<Option<Tag>> {
+        if self.is_online() {
+            return self.remote.get_tag(id).await;
+        }
+        self.cache.get_tag(id).await
+    }
+
+    async fn list_tags(&self, filter: Option<TagFilter>) -> AppResult<Vec<Tag>> {
+        if self.is_online() {
+            return self.remote.list_tags(filter).await;
+        }
+        self.cache.list_tags(filter).await
+    }
+
+    async fn update_tag(&self, id: String, data: TagForm) -> AppResult<Tag> {
+        if self.is_online() {
+            match self.remote.update_tag(id.clone(), data.clone()).await {
+                Ok(tag) => {
+                    log_cache_error(self.cache.upsert_tag(&tag).await);
+                    return Ok(tag);
+                }
+                Err(e) if !self.lost_connection(&e) => return Err(e),
+                Err(_) => {}
+            }
+        }
+
+        let base_updated_at = self.cached_tag_version(&id).await?;
+        let tag = self.cache.update_tag(id, data).await?;
+        self.journal
+            .record(
+                ChangeOperation::UpdateTag {
+                    tag: TagChange::from(&tag),
+                },
+                Some(base_updated_at),
+            )
+            .await?;
+        Ok(tag)
+    }
+
+    async fn delete_tag(&self, id: String) -> AppResult<Tag> {
+        if self.is_online() {
+            match self.remote.delete_tag(id.clone()).await {
+                Ok(tag) => {
+                    log_cache_error(self.cache.delete_tag(id).await.map(|_| ()));
+                    return Ok(tag);
+                }
+                Err(e) if !self.lost_connection(&e) => return Err(e),
+                Err(_) => {}
+            }
+        }
+
+        let base_updated_at = self.cached_tag_version(&id).await?;
+        let tag = self.cache.delete_tag(id.clone()).await?;
+        self.journal
+            .record(ChangeOperation::DeleteTag { id }, Some(base_updated_at))
+            .await?;
+        Ok(tag)
+    }
+}
+
+/// Collections can only be changed while connected; reads fall back to the
+/// cache so the sidebar keeps working offline.
+#[async_trait]
+impl CollectionStore for OfflineStorage {
+    async fn create_collection(&self, data: CollectionForm) -> AppResult<Collection> {
+        let collection = self.remote.create_collection(data).await?;
+        log_cache_error(self.cache.upsert_collection(&collection).await);
+        Ok(collection)
+    }
+
+    async fn get_collection(&self, id: String) -> AppResult<Option<Collection>> {
+        if self.is_online() {
+            return self.remote.get_collection(id).await;
+        }
+        self.cache.get_collection(id).await
+    }
+
+    async fn list_collections(
+        &self,
+        workspace: Option<WorkspaceScope>,
+    ) -> AppResult<Vec<Collection>> {
+        if self.is_online() {
+            return self.remote.list_collections(workspace).await;
+        }
+        self.cache.list_collections(workspace).await
+    }
+
+    async fn update_collection(&self, id: String, data: CollectionForm) -> AppResult<Collection> {
+        let collection = self.remote.update_collection(id, data).await?;
+        log_cache_error(self.cache.upsert_collection(&collection).await);
+        Ok(collection)
+    }
+
+    async fn delete_collection(&self, id: String) -> AppResult<Collection> {
+        let collection = self.remote.delete_collection(id.clone()).await?;
+        log_cache_error(self.cache.remove_collection(&id).await);
+        Ok(collection)
+    }
+}
+
+/// Accounts can only be changed while connected, but reads fall back to the
+/// cache so that sessions keep working offline. The cache only has its
+/// owner's account, without the password hash.
+#[async_trait]
+impl UserStore for OfflineStorage {
+    async fn create_user(
+        &self,
+        name: String,
+        email: String,
+        password_hash: String,
+        role: Role,
+    ) -> AppResult<User> {
+        let user = self
+            .remote
+            .create_user(name, email, password_hash, role)
+            .await?;
+        self.cache_user(&user).await;
+        Ok(user)
+    }
+
+    async fn get_user(&self, id: String) -> AppResult<Option<User>> {
+        if self.is_online() {
+            return self.remote.get_user(id).await;
+        }
+        self.cache.get_user(id).await
+    }
+
+    async fn find_user_by_email(&self, email: String) -> AppResult<Option<User>> {
+        if self.is_online() {
+            return self.remote.find_user_by_email(email).await;
+        }
+        self.cache.find_user_by_email(email).await
+    }
+
+    async fn list_users(&self, filter: Option<UserFilter>) -> AppResult<Vec<User>> {
+        if self.is_online() {
+            return self.remote.list_users(filter).await;
+        }
+        self.cache.list_users(filter).await
+    }
+
+    async fn count_users(&self, role: Option<Role>) -> AppResult<i64> {
+        if self.is_online() {
+            return self.remote.count_users(role).await;
+        }
+        self.cache.count_users(role).await
+    }
+
+    async fn update_user(&self, id: String, name: String, email: String) -> AppResult<User> {
+        let user = self.remote.update_user(id, name, email).await?;
+        self.cache_user(&user).await;
+        Ok(user)
+    }
+
+    async fn set_user_role(&self, id: String, role: Role) -> AppResult<User> {
+        let user = self.remote.set_user_role(id, role).await?;
+        self.cache_user(&user).await;
+        Ok(user)
+    }
+
+    async fn delete_user(&self, id: String, options: UserDeletionOptions) -> AppResult<User> {
+        let user = self.remote.delete_user(id, options).await?;
+        log_cache_error(self.cache.remove_user(&user.id).await);
+        Ok(user)
+    }
+
+    async fn export_user_data(&self, id: String) -> AppResult<UserDataExport> {
+        if self.is_online() {
+            return self.remote.export_user_data(id).await;
+        }
+        self.cache.export_user_data(id).await
+    }
+}
+
+/// Backups always go to MongoDB itself: the cache lacks ratings, tag usages
+/// and activity logs, so snapshotting it would silently lose data.
+#[async_trait]
+impl BackupStore for OfflineStorage {
+    async fn export_snapshot(&self) -> AppResult<VaultSnapshot> {
+        self.remote.export_snapshot().await
+    }
+
+    async fn import_snapshot(&self, snapshot: VaultSnapshot, replace: bool) -> AppResult<()> {
+        self.remote.import_snapshot(snapshot, replace).await?;
+        log_cache_error(self.refresh_cache(&self.remote.prisma()?).await);
+        Ok(())
+    }
+}
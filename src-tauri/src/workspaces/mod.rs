@@ -0,0 +1,478 @@
+//! Workspaces split the vault between teams.
+//!
+//! Every snippet, tag and collection belongs to one workspace, and list and
+//! search commands only cover the caller's active workspace, which travels in
+//! the access token. Records created before workspaces existed, or outside
+//! any, form the default workspace that every user can reach.
+//!
+//! Workspaces and memberships are only stored in MongoDB. With the local
+//! backend everyone works in the default workspace.
+
+use log::info;
+use prisma::{PrismaClient, WorkspaceRole};
+use serde::Deserialize;
+
+use crate::{
+    auth::{
+        token::{
+            TokenType, WorkspaceClaim, ACCESS_TOKEN_EXPIRATION_HOURS,
+            REFRESH_TOKEN_EXPIRATION_HOURS,
+        },
+        Permission, Principal, TokenSecret,
+    },
+    database::models::{
+        AuthPayload, SnippetFilter, TagFilter, User, Workspace, WorkspaceInfo, WorkspaceMembership,
+        WorkspaceScope,
+    },
+    error::AppError,
+    prelude::AppResult,
+    storage::Storage,
+};
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkspaceForm {
+    pub name: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SwitchWorkspaceForm {
+    /// The workspace to switch to, or none for the default workspace.
+    pub workspace_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkspaceMemberForm {
+    pub user_id: String,
+    pub role: WorkspaceRole,
+}
+
+fn forbidden(message: &str) -> AppError {
+    AppError::Forbidden(message.to_owned())
+}
+
+fn validate_name(name: &str) -> AppResult<String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(AppError::Other("Workspace names can't be empty".into()));
+    }
+    Ok(name.to_owned())
+}
+
+async fn membership(
+    client: &PrismaClient,
+    user_id: &str,
+    workspace_id: &str,
+) -> AppResult<Option<WorkspaceMembership>> {
+    let membership = client
+        .workspace_membership()
+        .find_first(vec![
+            prisma::workspace_membership::workspace_id::equals(workspace_id.to_owned()),
+            prisma::workspace_membership::user_id::equals(user_id.to_owned()),
+        ])
+        .exec()
+        .await?;
+    Ok(membership)
+}
+
+/// The user's role in the workspace, or none when they aren't a member.
+pub async fn role_in(
+    client: &PrismaClient,
+    user_id: &str,
+    workspace_id: &str,
+) -> AppResult<Option<WorkspaceRole>> {
+    Ok(membership(client, user_id, workspace_id)
+        .await?
+        .map(|membership| membership.role))
+}
+
+async fn get(client: &PrismaClient, id: &str) -> AppResult<Workspace> {
+    client
+        .workspace()
+        .find_unique(prisma::workspace::id::equals(id.to_owned()))
+        .exec()
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Workspace with ID {}", id)))
+}
+
+/// The caller's role in the workspace, failing unless it is one of `roles`.
+/// Users who manage accounts may act as owners of any workspace.
+async fn require_role(
+    client: &PrismaClient,
+    principal: &Principal,
+    workspace_id: &str,
+    roles: &[WorkspaceRole],
+    message: &str,
+) -> AppResult<WorkspaceRole> {
+    if principal.can(Permission::ManageUsers) {
+        return Ok(WorkspaceRole::Owner);
+    }
+    match role_in(client, &principal.user_id, workspace_id).await? {
+        Some(role) if roles.contains(&role) => Ok(role),
+        _ => Err(forbidden(message)),
+    }
+}
+
+async fn count_owners(client: &PrismaClient, workspace_id: &str) -> AppResult<i64> {
+    let owners = client
+        .workspace_membership()
+        .count(vec![
+            prisma::workspace_membership::workspace_id::equals(workspace_id.to_owned()),
+            prisma::workspace_membership::role::equals(WorkspaceRole::Owner),
+        ])
+        .exec()
+        .await?;
+    Ok(owners)
+}
+
+/// Every workspace the user is a member of, with their role in it. Empty
+/// when workspaces aren't available.
+pub async fn workspaces_of(storage: &dyn Storage, user_id: &str) -> AppResult<Vec<WorkspaceInfo>> {
+    let client = match storage.prisma() {
+        Ok(client) => client,
+        Err(_) => return Ok(vec![]),
+    };
+
+    let memberships = client
+        .workspace_membership()
+        .find_many(vec![prisma::workspace_membership::user_id::equals(
+            user_id.to_owned(),
+        )])
+        .exec()
+        .await?;
+    let workspaces = client
+        .workspace()
+        .find_many(vec![prisma::workspace::id::in_vec(
+            memberships
+                .iter()
+                .map(|membership| membership.workspace_id.clone())
+                .collect(),
+        )])
+        .exec()
+        .await?;
+
+    let mut infos: Vec<WorkspaceInfo> = workspaces
+        .into_iter()
+        .filter_map(|workspace| {
+            let membership = memberships
+                .iter()
+                .find(|membership| membership.workspace_id == workspace.id)?;
+            Some(WorkspaceInfo {
+                id: workspace.id,
+                name: workspace.name,
+                role: membership.role,
+            })
+        })
+        .collect();
+    infos.sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
+    Ok(infos)
+}
+
+/// Signs in `user` to a workspace, or to the default workspace when
+/// `workspace_id` is none.
+pub async fn issue_tokens(
+    storage: &dyn Storage,
+    secret: &TokenSecret,
+    user: User,
+    workspace_id: Option<String>,
+) -> AppResult<AuthPayload> {
+    let workspaces = workspaces_of(storage, &user.id).await?;
+    let workspace = match workspace_id {
+        Some(id) => Some(
+            workspaces
+                .iter()
+                .find(|workspace| workspace.id == id)
+                .cloned()
+                .ok_or_else(|| forbidden("you aren't a member of this workspace"))?,
+        ),
+        None => None,
+    };
+
+    let claim = workspace.as_ref().map(|workspace| WorkspaceClaim {
+        id: workspace.id.clone(),
+        role: workspace.role,
+    });
+    let access_token = secret.create_token(
+        &user.id,
+        TokenType::Access,
+        claim.as_ref(),
+        ACCESS_TOKEN_EXPIRATION_HOURS,
+    )?;
+    let refresh_token = secret.create_token(
+        &user.id,
+        TokenType::Refresh,
+        claim.as_ref(),
+        REFRESH_TOKEN_EXPIRATION_HOURS,
+    )?;
+
+    Ok(AuthPayload {
+        access_token,
+        refresh_token,
+        user,
+        workspace,
+        workspaces,
+    })
+}
+
+/// Creates a workspace owned by the caller.
+pub async fn create(
+    storage: &dyn Storage,
+    principal: &Principal,
+    form: WorkspaceForm,
+) -> AppResult<WorkspaceInfo> {
+    let client = storage.prisma()?;
+    let name = validate_name(&form.name)?;
+    let user_id = principal.user_id.clone();
+
+    let (workspace, membership) = client
+        ._transaction()
+        .run(|tx| async move {
+            let workspace = tx.workspace().create(name, vec![]).exec().await?;
+            let membership = tx
+                .workspace_membership()
+                .create(
+                    workspace.id.clone(),
+                    user_id,
+                    vec![prisma::workspace_membership::role::set(
+                        WorkspaceRole::Owner,
+                    )],
+                )
+                .exec()
+                .await?;
+            Ok::<_, prisma::QueryError>((workspace, membership))
+        })
+        .await?;
+
+    info!("Created workspace with ID: {}", workspace.id);
+    Ok(WorkspaceInfo {
+        id: workspace.id,
+        name: workspace.name,
+        role: membership.role,
+    })
+}
+
+pub async fn rename(
+    storage: &dyn Storage,
+    principal: &Principal,
+    id: String,
+    form: WorkspaceForm,
+) -> AppResult<Workspace> {
+    let client = storage.prisma()?;
+    get(&client, &id).await?;
+    require_role(
+        &client,
+        principal,
+        &id,
+        &[WorkspaceRole::Owner, WorkspaceRole::Admin],
+        "only owners and admins can rename a workspace",
+    )
+    .await?;
+
+    let workspace = client
+        .workspace()
+        .update(
+            prisma::workspace::id::equals(id),
+            vec![prisma::workspace::name::set(validate_name(&form.name)?)],
+        )
+        .exec()
+        .await?;
+    Ok(workspace)
+}
+
+/// Deletes an empty workspace along with its memberships.
+pub async fn delete(
+    storage: &dyn Storage,
+    principal: &Principal,
+    id: String,
+) -> AppResult<Workspace> {
+    let client = storage.prisma()?;
+    get(&client, &id).await?;
+    require_role(
+        &client,
+        principal,
+        &id,
+        &[WorkspaceRole::Owner],
+        "only owners can delete a workspace",
+    )
+    .await?;
+
+    let scope = WorkspaceScope::Workspace(id.clone());
+    let snippets = storage
+        .list_snippets(Some(SnippetFilter {
+            workspace: Some(scope.clone()),
+            ..Default::default()
+        }))
+        .await?;
+    let tags = storage
+        .list_tags(Some(TagFilter {
+            workspace: Some(scope.clone()),
+            ..Default::default()
+        }))
+        .await?;
+    let collections = storage.list_collections(Some(scope)).await?;
+    if !snippets.is_empty() || !tags.is_empty() || !collections.is_empty() {
+        return Err(AppError::Other(
+            "Move or delete the snippets, tags and collections of a workspace before deleting it"
+                .into(),
+        ));
+    }
+
+    let workspace = client
+        ._transaction()
+        .run(|tx| async move {
+            tx.workspace_membership()
+                .delete_many(vec![prisma::workspace_membership::workspace_id::equals(
+                    id.clone(),
+                )])
+                .exec()
+                .await?;
+            tx.workspace()
+                .delete(prisma::workspace::id::equals(id))
+                .exec()
+                .await
+        })
+        .await?;
+    info!("Deleted workspace with ID: {}", workspace.id);
+    Ok(workspace)
+}
+
+pub async fn list_members(
+    storage: &dyn Storage,
+    principal: &Principal,
+    id: String,
+) -> AppResult<Vec<WorkspaceMembership>> {
+    let client = storage.prisma()?;
+    require_role(
+        &client,
+        principal,
+        &id,
+        &[
+            WorkspaceRole::Owner,
+            WorkspaceRole::Admin,
+            WorkspaceRole::Member,
+            WorkspaceRole::Viewer,
+        ],
+        "you aren't a member of this workspace",
+    )
+    .await?;
+
+    let members = client
+        .workspace_membership()
+        .find_many(vec![prisma::workspace_membership::workspace_id::equals(id)])
+        .exec()
+        .await?;
+    Ok(members)
+}
+
+/// Adds a user to the workspace or changes their role. Owners and admins
+/// manage members, but only owners hand out or take away ownership, and the
+/// last owner keeps it.
+pub async fn set_member(
+    storage: &dyn Storage,
+    principal: &Principal,
+    id: String,
+    form: WorkspaceMemberForm,
+) -> AppResult<WorkspaceMembership> {
+    let client = storage.prisma()?;
+    get(&client, &id).await?;
+    let caller_role = require_role(
+        &client,
+        principal,
+        &id,
+        &[WorkspaceRole::Owner, WorkspaceRole::Admin],
+        "only owners and admins can manage the members of a workspace",
+    )
+    .await?;
+    storage
+        .get_user(form.user_id.clone())
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("User with ID {}", form.user_id)))?;
+
+    let existing = membership(&client, &form.user_id, &id).await?;
+    let was_owner = existing
+        .as_ref()
+        .map_or(false, |membership| membership.role == WorkspaceRole::Owner);
+    if (form.role == WorkspaceRole::Owner || was_owner) && caller_role != WorkspaceRole::Owner {
+        return Err(forbidden("only owners can change who owns a workspace"));
+    }
+    if was_owner && form.role != WorkspaceRole::Owner && count_owners(&client, &id).await? <= 1 {
+        return Err(AppError::Other(
+            "A workspace must keep at least one owner".into(),
+        ));
+    }
+
+    let membership = match existing {
+        Some(existing) => {
+            client
+                .workspace_membership()
+                .update(
+                    prisma::workspace_membership::id::equals(existing.id),
+                    vec![prisma::workspace_membership::role::set(form.role)],
+                )
+                .exec()
+                .await?
+        }
+        None => {
+            client
+                .workspace_membership()
+                .create(
+                    id,
+                    form.user_id,
+                    vec![prisma::workspace_membership::role::set(form.role)],
+                )
+                .exec()
+                .await?
+        }
+    };
+    info!(
+        "User {} is now {:?} of workspace {}",
+        membership.user_id, membership.role, membership.workspace_id
+    );
+    Ok(membership)
+}
+
+/// Removes a user from the workspace. Members may also leave on their own,
+/// unless they are its last owner.
+pub async fn remove_member(
+    storage: &dyn Storage,
+    principal: &Principal,
+    id: String,
+    user_id: String,
+) -> AppResult<WorkspaceMembership> {
+    let client = storage.prisma()?;
+    let existing = membership(&client, &user_id, &id).await?.ok_or_else(|| {
+        AppError::Other(format!(
+            "User {} isn't a member of workspace {}",
+            user_id, id
+        ))
+    })?;
+
+    if !principal.is(&user_id) {
+        let caller_role = require_role(
+            &client,
+            principal,
+            &id,
+            &[WorkspaceRole::Owner, WorkspaceRole::Admin],
+            "only owners and admins can remove members of a workspace",
+        )
+        .await?;
+        if existing.role == WorkspaceRole::Owner && caller_role != WorkspaceRole::Owner {
+            return Err(forbidden("only owners can remove an owner"));
+        }
+    }
+    if existing.role == WorkspaceRole::Owner && count_owners(&client, &id).await? <= 1 {
+        return Err(AppError::Other(
+            "A workspace must keep at least one owner".into(),
+        ));
+    }
+
+    let removed = client
+        .workspace_membership()
+        .delete(prisma::workspace_membership::id::equals(existing.id))
+        .exec()
+        .await?;
+    info!("Removed user {} from workspace {}", user_id, id);
+    Ok(removed)
+}
@@ -1,5 +1,9 @@
 import { CardFooter } from '@/components/ui/card';
-import { useDeleteSnippet, useUpdateSnippetState } from '@/hooks/useSnippets';
+import {
+  useCopySnippet,
+  useDeleteSnippet,
+  useUpdateSnippetState,
+} from '@/hooks/useSnippets';
 import useSnippetStore from '@/hooks/useSnippetStore';
 import { Snippet } from '@/lib/schemas/snippet';
 import { cn } from '@/lib/utils';
@@ -8,7 +12,6 @@ import { motion } from 'framer-motion';
 import { Check, Copy, Edit, Moon, Sun, Trash2 } from 'lucide-react';
 import { useCallback, useState } from 'react';
 import { IconType } from 'react-icons';
-import { toast } from 'sonner';
 import ActionButton from './ActionButton';
 
 interface SnippetCardFooterProps {
@@ -23,24 +26,17 @@ const SnippetCardFooter: React.FC<SnippetCardFooterProps> = ({
   const [isCopied, setIsCopied] = useState(false);
   const updateSnippetStateMutation = useUpdateSnippetState();
   const deleteSnippetMutation = useDeleteSnippet();
+  const copySnippetMutation = useCopySnippet();
   const { setSnippetDialog } = useSnippetStore();
 
   const handleCopySnippet = useCallback(() => {
-    navigator.clipboard
-      .writeText(snippet.code)
-      .then(() => {
-        toast.success('Copied to Clipboard', {
-          description: 'The snippet code has been copied to your clipboard.',
-        });
+    copySnippetMutation.mutate(snippet.id, {
+      onSuccess: () => {
         setIsCopied(true);
         setTimeout(() => setIsCopied(false), 1000);
-      })
-      .catch(() => {
-        toast.error('Failed to copy', {
-          description: 'An error occurred while copying the snippet.',
-        });
-      });
-  }, [snippet.code]);
+      },
+    });
+  }, [copySnippetMutation, snippet.id]);
 
   const handleEdit = useCallback(() => {
     setSnippetDialog(snippet);
@@ -43,6 +43,7 @@ const SnippetDialog: React.FC<SnippetDialogProps> = ({
           </DialogHeader>
           <SnippetForm
             snippet={initialData ?? undefined}
+            isEditMode={isEditMode}
             onSubmit={handleSubmit}
           />
         </motion.div>
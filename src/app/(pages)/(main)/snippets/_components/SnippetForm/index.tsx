@@ -32,6 +32,7 @@ import TagInput from './TagInput';
 
 interface SnippetFormProps {
   snippet?: Snippet;
+  isEditMode?: boolean;
   onSubmit: (snippet: Snippet) => Promise<void>;
 }
 
@@ -61,7 +62,11 @@ const useDarkMode = ({
   return isDarkMode;
 };
 
-const SnippetForm: React.FC<SnippetFormProps> = ({ snippet, onSubmit }) => {
+const SnippetForm: React.FC<SnippetFormProps> = ({
+  snippet,
+  isEditMode = !!snippet,
+  onSubmit,
+}) => {
   const form = useForm<Snippet>({
     resolver: zodResolver(SnippetSchema),
     defaultValues: snippet || {
@@ -275,7 +280,7 @@ const SnippetForm: React.FC<SnippetFormProps> = ({ snippet, onSubmit }) => {
                         : 'bg-purple-600 hover:bg-purple-700 focus:ring-purple-500',
                     )}
                   >
-                    {snippet ? (
+                    {isEditMode ? (
                       <>
                         <FiSave className="mr-2" />
                         <span>Update Snippet</span>
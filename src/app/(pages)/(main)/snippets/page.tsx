@@ -1,7 +1,10 @@
 'use client';
 
 import { useAuth } from '@/hooks/useAuth';
+import useClipboardOffers from '@/hooks/useClipboardOffers';
 import useCurrentTheme from '@/hooks/useCurrentTheme';
+import useDuplicateWarnings from '@/hooks/useDuplicateWarnings';
+import useFileDropImport from '@/hooks/useFileDropImport';
 import {
   useCreateSnippet,
   useSnippetsQuery,
@@ -70,6 +73,9 @@ export default function SnippetsPage({ searchParams }: SnippetsPageProps) {
   const createTagMutation = useCreateTag();
 
   const { isLoading, error, refetch } = useSnippetsQuery();
+  useFileDropImport(!!auth?.user);
+  useDuplicateWarnings(!!auth?.user);
+  useClipboardOffers(!!auth?.user);
   const filteredSnippets = useSnippetsFilter();
 
   const {
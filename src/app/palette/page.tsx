@@ -0,0 +1,97 @@
+'use client';
+
+import {
+  Command,
+  CommandEmpty,
+  CommandInput,
+  CommandItem,
+  CommandList,
+} from '@/components/ui/command';
+import { Snippet } from '@/lib/schemas/snippet';
+import {
+  copyPaletteSnippet,
+  hidePalette,
+  onPaletteOpened,
+} from '@/lib/tauri/api/palette';
+import { listSnippets } from '@/lib/tauri/api/snippet';
+import { useEffect, useState } from 'react';
+
+const MAX_RESULTS = 20;
+const SEARCH_DELAY_MS = 150;
+
+/**
+ * The quick-search palette, shown in its own window by the global shortcut.
+ * Enter copies the selected snippet; Escape closes the palette.
+ */
+const PalettePage = () => {
+  const [query, setQuery] = useState('');
+  const [snippets, setSnippets] = useState<Snippet[]>([]);
+  const [error, setError] = useState<string | null>(null);
+
+  useEffect(() => {
+    const unlisten = onPaletteOpened(() => {
+      setQuery('');
+      setError(null);
+    });
+    return () => {
+      unlisten.then(fn => fn());
+    };
+  }, []);
+
+  useEffect(() => {
+    const timeout = setTimeout(() => {
+      const search = query.trim();
+      listSnippets({ filter: search ? { search } : {} })
+        .then(results => {
+          setSnippets(results.slice(0, MAX_RESULTS));
+          setError(null);
+        })
+        .catch(e => setError(e instanceof Error ? e.message : String(e)));
+    }, SEARCH_DELAY_MS);
+    return () => clearTimeout(timeout);
+  }, [query]);
+
+  const copy = async (snippet: Snippet) => {
+    try {
+      await copyPaletteSnippet({ id: snippet.id });
+    } catch (e) {
+      setError(e instanceof Error ? e.message : String(e));
+    }
+  };
+
+  return (
+    <Command
+      shouldFilter={false}
+      className="h-screen rounded-none border"
+      onKeyDown={event => {
+        if (event.key === 'Escape') hidePalette();
+      }}
+    >
+      <CommandInput
+        autoFocus
+        value={query}
+        onValueChange={setQuery}
+        placeholder="Search snippets..."
+      />
+      {error && <p className="px-3 py-2 text-sm text-destructive">{error}</p>}
+      <CommandList className="max-h-none flex-1">
+        <CommandEmpty>No snippets found.</CommandEmpty>
+        {snippets.map(snippet => (
+          <CommandItem
+            key={snippet.id}
+            value={snippet.id}
+            onSelect={() => copy(snippet)}
+            className="flex items-center justify-between gap-4"
+          >
+            <span className="truncate">{snippet.title}</span>
+            <span className="shrink-0 text-xs text-muted-foreground">
+              {snippet.language}
+            </span>
+          </CommandItem>
+        ))}
+      </CommandList>
+    </Command>
+  );
+};
+
+export default PalettePage;
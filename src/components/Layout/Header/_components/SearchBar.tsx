@@ -1,15 +1,17 @@
 import { Tooltip } from '@/components/Common/Tooltip';
 import { Button } from '@/components/ui/button';
+import { useSnippetFromClipboard } from '@/hooks/useSnippets';
 import useSnippetStore from '@/hooks/useSnippetStore';
 import { cn } from '@/lib/utils';
 import { AnimatePresence, motion } from 'framer-motion';
-import { PlusCircle, Search } from 'lucide-react';
+import { ClipboardPaste, PlusCircle, Search } from 'lucide-react';
 import { useRouter, useSearchParams } from 'next/navigation';
 import { useEffect, useState } from 'react';
 
 const SearchBar = () => {
   const [isSearchFocused, setIsSearchFocused] = useState(false);
   const { setSnippetDialog } = useSnippetStore();
+  const snippetFromClipboard = useSnippetFromClipboard();
   const router = useRouter();
   const searchParams = useSearchParams();
 
@@ -87,8 +89,19 @@ const SearchBar = () => {
             animate={{ opacity: 1, x: 0 }}
             exit={{ opacity: 0, x: 20 }}
             transition={{ duration: 0.3, ease: 'easeInOut' }}
-            className="absolute right-2 flex items-center justify-center"
+            className="absolute right-2 flex items-center justify-center gap-1"
           >
+            <Button
+              onClick={() => snippetFromClipboard.mutate()}
+              size="sm"
+              variant="ghost"
+              disabled={snippetFromClipboard.isPending}
+              className="rounded-full px-3 py-2 text-gray-500 hover:text-purple-600 dark:text-gray-400 dark:hover:text-purple-400"
+            >
+              <Tooltip content="New Snippet From Clipboard">
+                <ClipboardPaste className="h-4 w-4" />
+              </Tooltip>
+            </Button>
             <Button
               onClick={handleNewSnippet}
               size="sm"
@@ -0,0 +1,30 @@
+import useSnippetStore from '@/hooks/useSnippetStore';
+import { onClipboardCodeDetected } from '@/lib/tauri/api/clipboard';
+import { useEffect } from 'react';
+import { toast } from 'sonner';
+
+/**
+ * Offers to save code copied in other applications, when clipboard
+ * watching is enabled in settings.
+ */
+export default function useClipboardOffers(enabled = true) {
+  const { openSnippetDraft } = useSnippetStore();
+
+  useEffect(() => {
+    if (!enabled) return;
+
+    const unlisten = onClipboardCodeDetected(draft => {
+      toast.info('Save Copied Code?', {
+        description: `"${draft.title}" looks like ${draft.language} code.`,
+        action: {
+          label: 'Save',
+          onClick: () => openSnippetDraft(draft),
+        },
+      });
+    });
+
+    return () => {
+      unlisten.then(fn => fn());
+    };
+  }, [enabled, openSnippetDraft]);
+}
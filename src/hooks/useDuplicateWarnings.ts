@@ -0,0 +1,26 @@
+import { onDuplicateSnippetWarning } from '@/lib/tauri/api/dedup';
+import { useEffect } from 'react';
+import { toast } from 'sonner';
+
+/**
+ * Warns when a newly created snippet resembles existing ones.
+ */
+export default function useDuplicateWarnings(enabled = true) {
+  useEffect(() => {
+    if (!enabled) return;
+
+    const unlisten = onDuplicateSnippetWarning(warning => {
+      const [closest] = warning.duplicates;
+      const similarity = Math.round(closest.similarity * 100);
+      toast.warning('Possible Duplicate', {
+        description: closest.exact
+          ? `"${warning.title}" has the same code as "${closest.title}".`
+          : `"${warning.title}" is ${similarity}% similar to "${closest.title}".`,
+      });
+    });
+
+    return () => {
+      unlisten.then(fn => fn());
+    };
+  }, [enabled]);
+}
@@ -0,0 +1,24 @@
+import { useImportFiles } from '@/hooks/useSnippets';
+import { listen } from '@tauri-apps/api/event';
+import { useEffect } from 'react';
+
+/**
+ * Imports files and folders dropped onto the window as snippets.
+ */
+export default function useFileDropImport(enabled = true) {
+  const { mutate: importFiles } = useImportFiles();
+
+  useEffect(() => {
+    if (!enabled) return;
+
+    const unlisten = listen<string[]>('tauri://file-drop', event => {
+      if (event.payload.length > 0) {
+        importFiles(event.payload);
+      }
+    });
+
+    return () => {
+      unlisten.then(fn => fn());
+    };
+  }, [enabled, importFiles]);
+}
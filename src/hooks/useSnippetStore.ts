@@ -1,6 +1,7 @@
 'use client';
 
 import { Snippet } from '@/lib/schemas/snippet';
+import { SnippetDraft } from '@/lib/tauri/api/clipboard';
 import { create } from 'zustand';
 import { immer } from 'zustand/middleware/immer';
 
@@ -9,6 +10,7 @@ interface SnippetStore {
   isEditMode: boolean;
   editingSnippet: Snippet | null;
   setSnippetDialog: (snippet: Snippet | null) => void;
+  openSnippetDraft: (draft: SnippetDraft) => void;
   resetSnippetDialog: () => void;
 }
 
@@ -27,6 +29,25 @@ const useSnippetStore = create<SnippetStore>()(
         }
       }),
 
+    // Opens the dialog pre-filled for a new snippet, as from the clipboard
+    openSnippetDraft: (draft: SnippetDraft) =>
+      set(state => {
+        state.isSnippetDialogOpen = true;
+        state.isEditMode = false;
+        state.editingSnippet = {
+          title: draft.title,
+          description: draft.description,
+          language: draft.language,
+          code: draft.code,
+          tags: [],
+          tagIds: [],
+          snippetStateId: '',
+          id: Date.now().toString(),
+          createdAt: new Date(),
+          updatedAt: new Date(),
+        } as Snippet;
+      }),
+
     resetSnippetDialog: () =>
       set(state => {
         state.isSnippetDialogOpen = false;
@@ -1,8 +1,23 @@
+import useSnippetStore from '@/hooks/useSnippetStore';
 import { Snippet, SnippetState } from '@/lib/schemas/snippet';
+import * as clipboardApi from '@/lib/tauri/api/clipboard';
+import * as interchangeApi from '@/lib/tauri/api/interchange';
 import * as snippetsApi from '@/lib/tauri/api/snippet';
 import { useMutation, useQuery, useQueryClient } from '@tanstack/react-query';
 import { toast } from 'sonner';
 
+// Warns when a saved snippet looks like it contains credentials
+const warnAboutSecrets = ({
+  title,
+  secretFindings,
+}: snippetsApi.SavedSnippet) => {
+  if (!secretFindings.length) return;
+  const lines = Array.from(new Set(secretFindings.map(f => f.line)));
+  toast.warning('Possible Secrets', {
+    description: `"${title}" may contain credentials on line ${lines.join(', ')}.`,
+  });
+};
+
 export const useSnippetsQuery = (filter: snippetsApi.SnippetFilter = {}) => {
   return useQuery<Snippet[]>({
     queryKey: ['snippets', filter],
@@ -17,10 +32,11 @@ export const useCreateSnippet = () => {
     mutationKey: ['createSnippet'],
     mutationFn: (newSnippet: Snippet) =>
       snippetsApi.createSnippet({ data: newSnippet }),
-    onSuccess: () => {
+    onSuccess: snippet => {
       toast.success('Snippet Created', {
         description: 'Your snippet has been successfully created.',
       });
+      warnAboutSecrets(snippet);
       queryClient.invalidateQueries({ queryKey: ['snippets'] });
     },
     onError: error => {
@@ -42,10 +58,11 @@ export const useUpdateSnippet = () => {
         id: updatedSnippet.id,
         data: updatedSnippet,
       }),
-    onSuccess: () => {
+    onSuccess: snippet => {
       toast.success('Snippet Updated', {
         description: 'Your snippet has been successfully updated.',
       });
+      warnAboutSecrets(snippet);
       queryClient.invalidateQueries({ queryKey: ['snippets'] });
     },
     onError: error => {
@@ -98,3 +115,66 @@ export const useUpdateSnippetState = () => {
     },
   });
 };
+
+export const useImportFiles = () => {
+  const queryClient = useQueryClient();
+
+  return useMutation({
+    mutationKey: ['importFiles'],
+    mutationFn: async (paths: string[]) =>
+      interchangeApi.importFiles({ data: { paths } }),
+    onSuccess: report => {
+      const skipped = report.skipped.length
+        ? ` ${report.skipped.length} file(s) were skipped.`
+        : '';
+      toast.success('Files Imported', {
+        description: `Imported ${report.created.length} snippet(s).${skipped}`,
+      });
+      report.created.forEach(warnAboutSecrets);
+      queryClient.invalidateQueries({ queryKey: ['snippets'] });
+    },
+    onError: error => {
+      toast.error('Error', {
+        description: 'Failed to import files.',
+      });
+      console.error('Import Files Error:', error);
+    },
+  });
+};
+
+export const useCopySnippet = () => {
+  const queryClient = useQueryClient();
+
+  return useMutation({
+    mutationKey: ['copySnippet'],
+    mutationFn: (id: string) => clipboardApi.copySnippet({ data: { id } }),
+    onSuccess: () => {
+      toast.success('Copied to Clipboard', {
+        description: 'The snippet code has been copied to your clipboard.',
+      });
+      queryClient.invalidateQueries({ queryKey: ['snippets'] });
+    },
+    onError: error => {
+      toast.error('Failed to copy', {
+        description: 'An error occurred while copying the snippet.',
+      });
+      console.error('Copy Snippet Error:', error);
+    },
+  });
+};
+
+export const useSnippetFromClipboard = () => {
+  const { openSnippetDraft } = useSnippetStore();
+
+  return useMutation({
+    mutationKey: ['snippetFromClipboard'],
+    mutationFn: () => clipboardApi.createSnippetFromClipboard(),
+    onSuccess: openSnippetDraft,
+    onError: error => {
+      toast.error('Error', {
+        description: 'The clipboard holds no text to save.',
+      });
+      console.error('Snippet From Clipboard Error:', error);
+    },
+  });
+};
@@ -0,0 +1,15 @@
+import { z } from 'zod';
+import { commonSchema } from './common';
+
+const baseCollectionSchema = z.object({
+  ...commonSchema.shape,
+  name: z.string().min(1, 'Collection name is required'),
+  description: z.string(),
+  position: z.number().int(),
+  parentId: z.string().nullable().optional(),
+  snippetIds: z.array(z.string()),
+  workspaceId: z.string().nullable().optional(),
+});
+
+export const CollectionSchema = baseCollectionSchema.brand<'Collection'>();
+export type Collection = z.infer<typeof CollectionSchema>;
@@ -0,0 +1,30 @@
+import { z } from 'zod';
+
+export const ShareAccessSchema = z.enum(['READ', 'EDIT']);
+export type ShareAccess = z.infer<typeof ShareAccessSchema>;
+
+const baseShareSchema = z.object({
+  id: z.string(),
+  createdAt: z.coerce.date(),
+  access: ShareAccessSchema,
+  snippetId: z.string().nullable().optional(),
+  collectionId: z.string().nullable().optional(),
+  userId: z.string().nullable().optional(),
+  groupId: z.string().nullable().optional(),
+  grantedById: z.string(),
+});
+
+export const ShareSchema = baseShareSchema.brand<'Share'>();
+export type Share = z.infer<typeof ShareSchema>;
+
+const baseUserGroupSchema = z.object({
+  id: z.string(),
+  createdAt: z.coerce.date(),
+  updatedAt: z.coerce.date(),
+  name: z.string().min(1, 'Group name is required'),
+  ownerId: z.string(),
+  memberIds: z.array(z.string()),
+});
+
+export const UserGroupSchema = baseUserGroupSchema.brand<'UserGroup'>();
+export type UserGroup = z.infer<typeof UserGroupSchema>;
@@ -6,6 +6,8 @@ const baseSnippetStateSchema = z.object({
   id: z.string(),
   isFavorite: z.boolean(),
   isDark: z.boolean(),
+  copyCount: z.number().nullable().optional(),
+  lastCopiedAt: z.string().nullable().optional(),
 });
 
 export const SnippetStateSchema =
@@ -20,6 +22,7 @@ const baseSnippetSchema = z.object({
   code: z.string().min(1, 'Code is required'),
   tagIds: z.array(z.string()),
   snippetStateId: z.string(),
+  workspaceId: z.string().nullable().optional(),
 });
 
 // Brand the base schema
@@ -9,6 +9,7 @@ const baseTagSchema = z.object({
     .regex(/^#[0-9A-Fa-f]{6}$/)
     .optional(),
   snippetIds: z.array(z.string()),
+  workspaceId: z.string().nullable().optional(),
 });
 
 export const TagSchema = baseTagSchema.brand<'Tag'>();
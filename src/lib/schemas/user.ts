@@ -7,6 +7,11 @@ export const userSchema = z.object({
   name: z.string().min(2, 'Name must be at least 2 characters'),
   email: z.string().email('Invalid email address'),
   passwordHash: z.string(),
+  // Accounts from before roles have none stored and are members
+  role: z
+    .enum(['ADMIN', 'MEMBER', 'READ_ONLY'])
+    .nullish()
+    .transform(role => role ?? 'MEMBER'),
   initials: z
     .string()
     .default('SA')
@@ -38,6 +43,7 @@ export const registerSchema = userSchema
     createdAt: true,
     updatedAt: true,
     passwordHash: true,
+    role: true,
     initials: true,
   })
   .extend({
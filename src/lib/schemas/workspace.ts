@@ -0,0 +1,39 @@
+import { z } from 'zod';
+
+export const WorkspaceRoleSchema = z.enum([
+  'OWNER',
+  'ADMIN',
+  'MEMBER',
+  'VIEWER',
+]);
+export type WorkspaceRole = z.infer<typeof WorkspaceRoleSchema>;
+
+const baseWorkspaceSchema = z.object({
+  id: z.string(),
+  createdAt: z.coerce.date(),
+  updatedAt: z.coerce.date(),
+  name: z.string().min(1, 'Workspace name is required'),
+});
+
+export const WorkspaceSchema = baseWorkspaceSchema.brand<'Workspace'>();
+export type Workspace = z.infer<typeof WorkspaceSchema>;
+
+// A workspace along with the current user's role in it
+export const WorkspaceInfoSchema = z.object({
+  id: z.string(),
+  name: z.string(),
+  role: WorkspaceRoleSchema,
+});
+export type WorkspaceInfo = z.infer<typeof WorkspaceInfoSchema>;
+
+const baseWorkspaceMembershipSchema = z.object({
+  id: z.string(),
+  createdAt: z.coerce.date(),
+  workspaceId: z.string(),
+  userId: z.string(),
+  role: WorkspaceRoleSchema,
+});
+
+export const WorkspaceMembershipSchema =
+  baseWorkspaceMembershipSchema.brand<'WorkspaceMembership'>();
+export type WorkspaceMembership = z.infer<typeof WorkspaceMembershipSchema>;
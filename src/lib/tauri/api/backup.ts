@@ -0,0 +1,54 @@
+import { invokeCommand } from '@/lib/tauri/invoke';
+import { PostParams } from '@/lib/tauri/types';
+
+export type BackupKind = 'manual' | 'automatic' | 'preRestore';
+export type RestoreMode = 'merge' | 'replace';
+
+export interface BackupCounts {
+  snippets: number;
+  states: number;
+  tags: number;
+  snippetRatings: number;
+  tagUsages: number;
+  activityLogs: number;
+  collections?: number;
+}
+
+export interface BackupInfo {
+  path: string;
+  sizeBytes: number;
+  manifest: {
+    formatVersion: number;
+    appVersion: string;
+    createdAt: string;
+    kind: BackupKind;
+    backend: string;
+    counts: BackupCounts;
+    checksum: string;
+  };
+}
+
+export interface RestoreReport {
+  mode: RestoreMode;
+  restored: BackupCounts;
+  remappedIds: number;
+  droppedRecords: number;
+  detachedAuthors: number;
+  safetyBackup: BackupInfo | null;
+}
+
+export async function createBackup(
+  params: PostParams<{ directory?: string }> = { data: {} },
+): Promise<BackupInfo> {
+  return await invokeCommand('create_backup', params);
+}
+
+export async function listBackups(): Promise<BackupInfo[]> {
+  return await invokeCommand('list_backups', {});
+}
+
+export async function restoreBackup(
+  params: PostParams<{ path: string; mode: RestoreMode }>,
+): Promise<RestoreReport> {
+  return await invokeCommand('restore_backup', params);
+}
@@ -0,0 +1,91 @@
+import { SnippetSecrets } from '@/lib/tauri/api/secrets';
+import { invokeCommand } from '@/lib/tauri/invoke';
+import { PostParams } from '@/lib/tauri/types';
+
+export type BundleEncoding = 'json' | 'cbor';
+export type ConflictResolution = 'skip' | 'overwrite' | 'duplicate';
+
+export interface BundleManifest {
+  formatVersion: number;
+  appVersion: string;
+  createdAt: string;
+  createdBy: string;
+  name: string;
+  description: string;
+  counts: {
+    snippets: number;
+    tags: number;
+    collections: number;
+  };
+  contentHash: string;
+}
+
+export interface BundleSignature {
+  publicKey: string;
+  signature: string;
+}
+
+export interface BundleInfo {
+  path: string;
+  sizeBytes: number;
+  encoding: BundleEncoding;
+  manifest: BundleManifest;
+  signature: BundleSignature | null;
+}
+
+export interface BundleExportForm {
+  path: string;
+  name: string;
+  description?: string;
+  snippetIds?: string[];
+  collectionIds?: string[];
+  encoding?: BundleEncoding;
+  sign?: boolean;
+}
+
+export interface BundleImportForm {
+  path: string;
+  onConflict: ConflictResolution;
+  dryRun?: boolean;
+}
+
+export interface BundleImportReport {
+  dryRun: boolean;
+  manifest: BundleManifest;
+  trust: {
+    signedBy: string | null;
+    trusted: boolean;
+  };
+  counts: {
+    snippetsCreated: number;
+    snippetsOverwritten: number;
+    snippetsSkipped: number;
+    tagsCreated: number;
+    collectionsCreated: number;
+    collectionsOverwritten: number;
+    collectionsSkipped: number;
+  };
+  conflicts: {
+    kind: 'snippet' | 'collection';
+    title: string;
+    existingId: string;
+    resolution: ConflictResolution;
+  }[];
+  secrets: SnippetSecrets[];
+}
+
+export async function exportBundle(
+  params: PostParams<BundleExportForm>,
+): Promise<BundleInfo> {
+  return await invokeCommand('export_bundle', params);
+}
+
+export async function importBundle(
+  params: PostParams<BundleImportForm>,
+): Promise<BundleImportReport> {
+  return await invokeCommand('import_bundle', params);
+}
+
+export async function getBundleSigningKey(): Promise<string> {
+  return await invokeCommand('get_bundle_signing_key', {});
+}
@@ -0,0 +1,38 @@
+import { SnippetState, SnippetStateSchema } from '@/lib/schemas/snippet';
+import { invokeCommand } from '@/lib/tauri/invoke';
+import { PostParams } from '@/lib/tauri/types';
+import { listen, UnlistenFn } from '@tauri-apps/api/event';
+
+export interface CopySnippetForm {
+  id: string;
+  renderPlaceholders?: boolean;
+}
+
+export interface SnippetDraft {
+  title: string;
+  description: string;
+  language: string;
+  code: string;
+  tags: string[];
+}
+
+// Copies the snippet's code to the clipboard and counts the copy
+export async function copySnippet(
+  params: PostParams<CopySnippetForm>,
+): Promise<SnippetState> {
+  const response = await invokeCommand('copy_snippet', params);
+  return await SnippetStateSchema.parseAsync(response);
+}
+
+// Reads the clipboard into a draft; nothing is saved
+export async function createSnippetFromClipboard(): Promise<SnippetDraft> {
+  return await invokeCommand('create_snippet_from_clipboard', {});
+}
+
+export async function onClipboardCodeDetected(
+  handler: (draft: SnippetDraft) => void,
+): Promise<UnlistenFn> {
+  return await listen<SnippetDraft>('clipboard-code-detected', event =>
+    handler(event.payload),
+  );
+}
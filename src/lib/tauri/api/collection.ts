@@ -0,0 +1,97 @@
+import { Collection, CollectionSchema } from '@/lib/schemas/collection';
+import { invokeCommand } from '@/lib/tauri/invoke';
+import {
+  DeleteParams,
+  GetParams,
+  PostParams,
+  PutParams,
+} from '@/lib/tauri/types';
+
+export interface CollectionForm {
+  name: string;
+  description?: string;
+  parentId?: string | null;
+  snippetIds?: string[];
+}
+
+export interface RenameCollectionForm {
+  name: string;
+  description?: string;
+}
+
+export interface MoveCollectionForm {
+  parentId?: string | null;
+  position?: number;
+}
+
+export interface CollectionSnippetsForm {
+  snippetIds: string[];
+  position?: number;
+}
+
+export interface ReorderCollectionForm {
+  snippetIds: string[];
+}
+
+export async function createCollection(
+  params: PostParams<CollectionForm>,
+): Promise<Collection> {
+  const response = await invokeCommand('create_collection', params);
+  return await CollectionSchema.parseAsync(response);
+}
+
+export async function getCollection(params: GetParams): Promise<Collection> {
+  const response = await invokeCommand('get_collection', params);
+  return await CollectionSchema.parseAsync(response);
+}
+
+/** Every collection ordered by position; nest them by `parentId`. */
+export async function listCollections(): Promise<Collection[]> {
+  const response = await invokeCommand('list_collections', {});
+  return await CollectionSchema.array().parseAsync(response);
+}
+
+export async function renameCollection(
+  params: PutParams<RenameCollectionForm>,
+): Promise<Collection> {
+  const response = await invokeCommand('rename_collection', params);
+  return await CollectionSchema.parseAsync(response);
+}
+
+export async function moveCollection(
+  params: PutParams<MoveCollectionForm>,
+): Promise<Collection> {
+  const response = await invokeCommand('move_collection', params);
+  return await CollectionSchema.parseAsync(response);
+}
+
+export async function deleteCollection(
+  params: DeleteParams,
+): Promise<Collection> {
+  const response = await invokeCommand('delete_collection', params);
+  return await CollectionSchema.parseAsync(response);
+}
+
+export async function addSnippetsToCollection(
+  params: PutParams<CollectionSnippetsForm>,
+): Promise<Collection> {
+  const response = await invokeCommand('add_snippets_to_collection', params);
+  return await CollectionSchema.parseAsync(response);
+}
+
+export async function removeSnippetsFromCollection(
+  params: PutParams<Omit<CollectionSnippetsForm, 'position'>>,
+): Promise<Collection> {
+  const response = await invokeCommand(
+    'remove_snippets_from_collection',
+    params,
+  );
+  return await CollectionSchema.parseAsync(response);
+}
+
+export async function reorderCollectionSnippets(
+  params: PutParams<ReorderCollectionForm>,
+): Promise<Collection> {
+  const response = await invokeCommand('reorder_collection_snippets', params);
+  return await CollectionSchema.parseAsync(response);
+}
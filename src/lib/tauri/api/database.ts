@@ -0,0 +1,67 @@
+import { invokeCommand } from '@/lib/tauri/invoke';
+import { listen, UnlistenFn } from '@tauri-apps/api/event';
+
+export type ConnectionState = 'connecting' | 'connected' | 'disconnected';
+
+export interface DbStatus {
+  backend: string;
+  state: ConnectionState;
+  lastError: string | null;
+  nextRetryInSecs: number | null;
+  changedAt: string;
+}
+
+export async function getDbStatus(): Promise<DbStatus> {
+  return await invokeCommand('get_db_status', {});
+}
+
+export async function onDbStatusChanged(
+  handler: (status: DbStatus) => void,
+): Promise<UnlistenFn> {
+  return await listen<DbStatus>('db-status-changed', event =>
+    handler(event.payload),
+  );
+}
+
+export type PendingChangeOperation =
+  | { kind: 'createSnippet' | 'updateSnippet'; snippet: { id: string } }
+  | { kind: 'deleteSnippet' | 'deleteTag'; id: string }
+  | { kind: 'updateSnippetState'; id: string; isFavorite: boolean; isDark: boolean }
+  | {
+      kind: 'recordSnippetCopies';
+      id: string;
+      copies: number;
+      lastCopiedAt: string;
+    }
+  | { kind: 'createTag' | 'updateTag'; tag: { id: string; name: string } };
+
+export interface PendingChange {
+  id: string;
+  operation: PendingChangeOperation;
+  baseUpdatedAt: string | null;
+  recordedAt: string;
+  status: 'pending' | 'conflict';
+  conflict: string | null;
+}
+
+export interface SyncReport {
+  applied: number;
+  conflicts: number;
+  remaining: number;
+}
+
+export async function listPendingChanges(): Promise<PendingChange[]> {
+  return await invokeCommand('list_pending_changes', {});
+}
+
+export async function discardPendingChange(id: string): Promise<PendingChange> {
+  return await invokeCommand('discard_pending_change', { id });
+}
+
+export async function onPendingChangesSynced(
+  handler: (report: SyncReport) => void,
+): Promise<UnlistenFn> {
+  return await listen<SyncReport>('pending-changes-synced', event =>
+    handler(event.payload),
+  );
+}
@@ -0,0 +1,66 @@
+import { Snippet, SnippetSchema } from '@/lib/schemas/snippet';
+import { invokeCommand } from '@/lib/tauri/invoke';
+import { SnippetFilter } from '@/lib/tauri/api/snippet';
+import { PostParams } from '@/lib/tauri/types';
+import { listen, UnlistenFn } from '@tauri-apps/api/event';
+
+export interface DuplicateSearchForm {
+  threshold?: number;
+  filter?: SnippetFilter;
+}
+
+export interface DuplicateMatch {
+  snippetId: string;
+  title: string;
+  language: string;
+  similarity: number;
+  exact: boolean;
+}
+
+export interface DuplicateCluster {
+  snippets: DuplicateMatch[];
+  similarity: number;
+}
+
+export interface MergeSnippetsForm {
+  keepId: string;
+  mergeIds: string[];
+}
+
+export interface MergeResult {
+  snippet: Snippet;
+  deleted: Snippet[];
+}
+
+export interface DuplicateWarning {
+  snippetId: string;
+  title: string;
+  duplicates: DuplicateMatch[];
+}
+
+export async function findDuplicateSnippets(
+  params: PostParams<DuplicateSearchForm> = { data: {} },
+): Promise<DuplicateCluster[]> {
+  return await invokeCommand('find_duplicate_snippets', params);
+}
+
+export async function mergeDuplicateSnippets(
+  params: PostParams<MergeSnippetsForm>,
+): Promise<MergeResult> {
+  const response: MergeResult = await invokeCommand(
+    'merge_duplicate_snippets',
+    params,
+  );
+  return {
+    snippet: await SnippetSchema.parseAsync(response.snippet),
+    deleted: await SnippetSchema.array().parseAsync(response.deleted),
+  };
+}
+
+export async function onDuplicateSnippetWarning(
+  handler: (warning: DuplicateWarning) => void,
+): Promise<UnlistenFn> {
+  return await listen<DuplicateWarning>('duplicate-snippet-warning', event =>
+    handler(event.payload),
+  );
+}
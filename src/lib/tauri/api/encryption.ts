@@ -0,0 +1,65 @@
+import { Snippet } from '@/lib/schemas/snippet';
+import { parseSavedSnippet, SavedSnippet } from '@/lib/tauri/api/snippet';
+import { invokeCommand } from '@/lib/tauri/invoke';
+import { PostParams } from '@/lib/tauri/types';
+import { listen, UnlistenFn } from '@tauri-apps/api/event';
+
+const ENCRYPTED_PREFIX = '$svault$';
+
+export interface VaultLockStatus {
+  unlocked: boolean;
+  hasPrivateSnippets: boolean;
+  autoLockMinutes: number;
+}
+
+export interface UnlockVaultForm {
+  passphrase: string;
+}
+
+export interface SetSnippetPrivateForm {
+  id: string;
+  private: boolean;
+}
+
+/**
+ * Whether a snippet is private and was fetched while the vault was locked,
+ * so its code and description are still encrypted.
+ */
+export function isLocked(snippet: Pick<Snippet, 'code'>): boolean {
+  return snippet.code.startsWith(ENCRYPTED_PREFIX);
+}
+
+export async function getVaultLockStatus(): Promise<VaultLockStatus> {
+  return await invokeCommand('get_vault_lock_status', {});
+}
+
+export async function unlockVault(
+  params: PostParams<UnlockVaultForm>,
+): Promise<void> {
+  await invokeCommand('unlock_vault', params);
+}
+
+export async function lockVault(): Promise<void> {
+  await invokeCommand('lock_vault', {});
+}
+
+export async function setSnippetPrivate(
+  params: PostParams<SetSnippetPrivateForm>,
+): Promise<SavedSnippet> {
+  const response = await invokeCommand('set_snippet_private', params);
+  return await parseSavedSnippet(response);
+}
+
+// Each user locks and unlocks their own vault
+export interface VaultLockChange {
+  userId: string;
+  unlocked: boolean;
+}
+
+export async function onVaultLockChanged(
+  handler: (change: VaultLockChange) => void,
+): Promise<UnlistenFn> {
+  return await listen<VaultLockChange>('vault-lock-changed', event =>
+    handler(event.payload),
+  );
+}
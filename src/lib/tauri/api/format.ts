@@ -0,0 +1,24 @@
+import { SavedSnippet } from '@/lib/tauri/api/snippet';
+import { invokeCommand } from '@/lib/tauri/invoke';
+import { PostParams } from '@/lib/tauri/types';
+
+export interface FormatSnippetForm {
+  id: string;
+  save?: boolean;
+  useExternal?: boolean;
+}
+
+export interface FormatReport {
+  language: string;
+  formatter: string;
+  formatted: string;
+  diff: string;
+  changed: boolean;
+  saved: SavedSnippet | null;
+}
+
+export async function formatSnippet(
+  params: PostParams<FormatSnippetForm>,
+): Promise<FormatReport> {
+  return await invokeCommand('format_snippet', params);
+}
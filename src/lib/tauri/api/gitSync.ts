@@ -0,0 +1,44 @@
+import { SnippetSecrets } from '@/lib/tauri/api/secrets';
+import { invokeCommand } from '@/lib/tauri/invoke';
+
+export interface SyncConflict {
+  snippetId: string;
+  title: string;
+  reason: string;
+}
+
+export interface ExportReport {
+  written: number;
+  removed: number;
+  committed: boolean;
+}
+
+export interface ImportReport {
+  created: number;
+  updated: number;
+  deleted: number;
+  conflicts: SyncConflict[];
+  secrets: SnippetSecrets[];
+}
+
+export interface PullReport {
+  merged: boolean;
+  mergeConflicts: string[];
+  import: ImportReport;
+}
+
+export async function exportToSnippetRepository(): Promise<ExportReport> {
+  return await invokeCommand('export_to_snippet_repository', {});
+}
+
+export async function importSnippetRepository(): Promise<ImportReport> {
+  return await invokeCommand('import_snippet_repository', {});
+}
+
+export async function pullSnippetRepository(): Promise<PullReport> {
+  return await invokeCommand('pull_snippet_repository', {});
+}
+
+export async function pushSnippetRepository(): Promise<void> {
+  await invokeCommand('push_snippet_repository', {});
+}
@@ -0,0 +1,23 @@
+import { invokeCommand } from '@/lib/tauri/invoke';
+import { PostParams } from '@/lib/tauri/types';
+
+export interface ThemeInfo {
+  id: string;
+  name: string;
+  dark: boolean;
+}
+
+export interface RenderSnippetForm {
+  id: string;
+  theme?: string;
+}
+
+export async function listHighlightThemes(): Promise<ThemeInfo[]> {
+  return await invokeCommand('list_highlight_themes', {});
+}
+
+export async function renderSnippetHtml(
+  params: PostParams<RenderSnippetForm>,
+): Promise<string> {
+  return await invokeCommand('render_snippet_html', params);
+}
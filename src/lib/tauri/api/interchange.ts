@@ -0,0 +1,77 @@
+import {
+  parseSavedSnippet,
+  SavedSnippet,
+  SnippetFilter,
+} from '@/lib/tauri/api/snippet';
+import { invokeCommand } from '@/lib/tauri/invoke';
+import { PostParams } from '@/lib/tauri/types';
+
+export interface MarkdownExportOptions {
+  filter?: SnippetFilter;
+  title?: string;
+}
+
+export interface MarkdownImportForm {
+  markdown: string;
+  defaultLanguage?: string;
+}
+
+export async function exportMarkdown(
+  params: PostParams<MarkdownExportOptions> = { data: {} },
+): Promise<string> {
+  return await invokeCommand('export_markdown', params);
+}
+
+export async function importMarkdown(
+  params: PostParams<MarkdownImportForm>,
+): Promise<SavedSnippet[]> {
+  const response: unknown[] = await invokeCommand('import_markdown', params);
+  return await Promise.all(response.map(parseSavedSnippet));
+}
+
+export interface FileImportForm {
+  paths: string[];
+  tags?: string[];
+  dryRun?: boolean;
+}
+
+export interface FileImportReport {
+  dryRun: boolean;
+  files: {
+    path: string;
+    snippet: {
+      title: string;
+      description: string;
+      language: string;
+      code: string;
+      tags: string[];
+    };
+  }[];
+  skipped: { path: string; reason: string }[];
+  created: SavedSnippet[];
+}
+
+export async function importFiles(
+  params: PostParams<FileImportForm>,
+): Promise<FileImportReport> {
+  return await invokeCommand('import_files', params);
+}
+
+export type ImportFormat =
+  | 'markdown'
+  | 'jetbrainsLiveTemplates'
+  | 'sublimeSnippet';
+
+export interface SnippetImportForm {
+  format: ImportFormat;
+  content: string;
+  defaultLanguage?: string;
+  tags?: string[];
+}
+
+export async function importSnippets(
+  params: PostParams<SnippetImportForm>,
+): Promise<SavedSnippet[]> {
+  const response: unknown[] = await invokeCommand('import_snippets', params);
+  return await Promise.all(response.map(parseSavedSnippet));
+}
@@ -0,0 +1,32 @@
+import { invokeCommand } from '@/lib/tauri/invoke';
+import { PostParams } from '@/lib/tauri/types';
+
+export interface Language {
+  id: string;
+  name: string;
+  aliases: string[];
+  extensions: string[];
+  fileNames: string[];
+  interpreters: string[];
+}
+
+export interface LanguageCandidate {
+  language: string;
+  name: string;
+  confidence: number;
+}
+
+export interface DetectLanguageForm {
+  code: string;
+  fileName?: string;
+}
+
+export async function listLanguages(): Promise<Language[]> {
+  return await invokeCommand('list_languages', {});
+}
+
+export async function detectLanguage(
+  params: PostParams<DetectLanguageForm>,
+): Promise<LanguageCandidate[]> {
+  return await invokeCommand('detect_language', params);
+}
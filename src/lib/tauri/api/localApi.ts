@@ -0,0 +1,32 @@
+import { invokeCommand } from '@/lib/tauri/invoke';
+import { DeleteParams } from '@/lib/tauri/types';
+
+export interface ApiToken {
+  // Only shown once; the app keeps a hash of it
+  token: string;
+  id: string;
+  expiresAt: string;
+  // Absent while the local API is disabled in settings
+  baseUrl: string | null;
+}
+
+export interface ApiTokenInfo {
+  id: string;
+  workspaceId: string | null;
+  createdAt: string;
+  expiresAt: string;
+}
+
+export async function createApiToken(): Promise<ApiToken> {
+  return await invokeCommand('create_api_token', {});
+}
+
+export async function listApiTokens(): Promise<ApiTokenInfo[]> {
+  return await invokeCommand('list_api_tokens', {});
+}
+
+export async function revokeApiToken(
+  params: DeleteParams,
+): Promise<ApiTokenInfo> {
+  return await invokeCommand('revoke_api_token', params);
+}
@@ -0,0 +1,18 @@
+import { invokeCommand } from '@/lib/tauri/invoke';
+import { GetParams } from '@/lib/tauri/types';
+import { listen, UnlistenFn } from '@tauri-apps/api/event';
+
+// Copies the snippet with its placeholders rendered and hides the palette
+export async function copyPaletteSnippet(params: GetParams): Promise<void> {
+  await invokeCommand('copy_palette_snippet', params);
+}
+
+export async function hidePalette(): Promise<void> {
+  await invokeCommand('hide_palette', {});
+}
+
+export async function onPaletteOpened(
+  handler: () => void,
+): Promise<UnlistenFn> {
+  return await listen('palette-opened', () => handler());
+}
@@ -0,0 +1,30 @@
+import { invokeCommand } from '@/lib/tauri/invoke';
+
+export type SecretKind =
+  | 'awsAccessKeyId'
+  | 'awsSecretAccessKey'
+  | 'privateKey'
+  | 'jwt'
+  | 'githubToken'
+  | 'slackToken'
+  | 'credential'
+  | 'highEntropyString';
+
+export interface SecretFinding {
+  kind: SecretKind;
+  description: string;
+  line: number;
+  column: number;
+  length: number;
+  preview: string;
+}
+
+export interface SnippetSecrets {
+  snippetId: string;
+  title: string;
+  findings: SecretFinding[];
+}
+
+export async function scanVaultForSecrets(): Promise<SnippetSecrets[]> {
+  return await invokeCommand('scan_vault_for_secrets', {});
+}
@@ -0,0 +1,101 @@
+import { CollectionSchema } from '@/lib/schemas/collection';
+import {
+  Share,
+  ShareAccess,
+  ShareAccessSchema,
+  ShareSchema,
+  UserGroup,
+  UserGroupSchema,
+} from '@/lib/schemas/sharing';
+import { SnippetSchema } from '@/lib/schemas/snippet';
+import { invokeCommand } from '@/lib/tauri/invoke';
+import {
+  DeleteParams,
+  ListParams,
+  PostParams,
+  PutParams,
+} from '@/lib/tauri/types';
+import { z } from 'zod';
+
+/** Set one of `snippetId` and `collectionId`, and one of `userId` and `groupId`. */
+export interface ShareForm {
+  snippetId?: string;
+  collectionId?: string;
+  userId?: string;
+  groupId?: string;
+  access: ShareAccess;
+}
+
+export interface ShareFilter {
+  snippetId?: string;
+  collectionId?: string;
+}
+
+export interface UserGroupForm {
+  name: string;
+  memberIds: string[];
+}
+
+const sharedEntrySchema = z.object({
+  shareId: z.string(),
+  access: ShareAccessSchema,
+  grantedById: z.string(),
+});
+
+export const SharedWithMeSchema = z.object({
+  snippets: z.array(sharedEntrySchema.extend({ snippet: SnippetSchema })),
+  collections: z.array(
+    sharedEntrySchema.extend({ collection: CollectionSchema }),
+  ),
+});
+export type SharedWithMe = z.infer<typeof SharedWithMeSchema>;
+
+export async function shareSnippet(
+  params: PostParams<ShareForm>,
+): Promise<Share> {
+  const response = await invokeCommand('share_snippet', params);
+  return await ShareSchema.parseAsync(response);
+}
+
+export async function listShares(
+  params: ListParams<ShareFilter> = {},
+): Promise<Share[]> {
+  const response = await invokeCommand('list_shares', params);
+  return await ShareSchema.array().parseAsync(response);
+}
+
+export async function listSharedWithMe(): Promise<SharedWithMe> {
+  const response = await invokeCommand('list_shared_with_me', {});
+  return await SharedWithMeSchema.parseAsync(response);
+}
+
+export async function revokeShare(params: DeleteParams): Promise<Share> {
+  const response = await invokeCommand('revoke_share', params);
+  return await ShareSchema.parseAsync(response);
+}
+
+export async function createUserGroup(
+  params: PostParams<UserGroupForm>,
+): Promise<UserGroup> {
+  const response = await invokeCommand('create_user_group', params);
+  return await UserGroupSchema.parseAsync(response);
+}
+
+export async function listUserGroups(): Promise<UserGroup[]> {
+  const response = await invokeCommand('list_user_groups', {});
+  return await UserGroupSchema.array().parseAsync(response);
+}
+
+export async function updateUserGroup(
+  params: PutParams<UserGroupForm>,
+): Promise<UserGroup> {
+  const response = await invokeCommand('update_user_group', params);
+  return await UserGroupSchema.parseAsync(response);
+}
+
+export async function deleteUserGroup(
+  params: DeleteParams,
+): Promise<UserGroup> {
+  const response = await invokeCommand('delete_user_group', params);
+  return await UserGroupSchema.parseAsync(response);
+}
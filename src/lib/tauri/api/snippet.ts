@@ -4,6 +4,7 @@ import {
   SnippetState,
   SnippetStateSchema,
 } from '@/lib/schemas/snippet';
+import { SecretFinding } from '@/lib/tauri/api/secrets';
 import { invokeCommand } from '@/lib/tauri/invoke';
 import {
   DeleteParams,
@@ -13,9 +14,22 @@ import {
   PutParams,
 } from '@/lib/tauri/types';
 
-type SnippetForm = Omit<Snippet, 'id' | 'created_at' | 'updated_at'>;
+type SnippetForm = Omit<Snippet, 'id' | 'created_at' | 'updated_at'> & {
+  isPrivate?: boolean;
+};
 type UpdateSnippetState = Partial<Omit<SnippetState, 'id'>>;
 
+// A snippet just saved, with anything in its code that looks like a secret
+export type SavedSnippet = Snippet & { secretFindings: SecretFinding[] };
+
+export async function parseSavedSnippet(
+  response: unknown,
+): Promise<SavedSnippet> {
+  const snippet = await SnippetSchema.parseAsync(response);
+  const { secretFindings = [] } = response as Partial<SavedSnippet>;
+  return { ...snippet, secretFindings };
+}
+
 export interface SnippetFilter {
   search?: string;
   title?: string;
@@ -24,13 +38,15 @@ export interface SnippetFilter {
   code?: string;
   state?: Partial<Omit<SnippetState, 'id'>>;
   tags?: string[];
+  collectionId?: string;
+  sort?: 'newest' | 'mostUsed';
 }
 
 export async function createSnippet(
   params: PostParams<SnippetForm>,
-): Promise<Snippet> {
+): Promise<SavedSnippet> {
   const response = await invokeCommand('create_snippet', params);
-  return await SnippetSchema.parseAsync(response);
+  return await parseSavedSnippet(response);
 }
 
 export async function getSnippet(params: GetParams): Promise<Snippet> {
@@ -47,9 +63,9 @@ export async function listSnippets(
 
 export async function updateSnippet(
   params: PutParams<SnippetForm>,
-): Promise<Snippet> {
+): Promise<SavedSnippet> {
   const response = await invokeCommand('update_snippet', params);
-  return await SnippetSchema.parseAsync(response);
+  return await parseSavedSnippet(response);
 }
 
 export async function deleteSnippet(params: DeleteParams) {
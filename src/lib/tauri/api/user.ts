@@ -1,16 +1,22 @@
 import { RegisterFormData, User } from '@/lib/schemas/user';
+import { WorkspaceInfo } from '@/lib/schemas/workspace';
 import { invokeCommand } from '@/lib/tauri/invoke';
 import { PostParams } from '@/lib/tauri/types';
 
 type Credentials = {
   email: string;
   password: string;
+  /** Signs into the default workspace when left out. */
+  workspaceId?: string;
 };
 
-type AuthPayload = {
+export type AuthPayload = {
   token: string;
   refresh_token: string;
   user: User;
+  /** The active workspace; null for the default workspace. */
+  workspace?: WorkspaceInfo | null;
+  workspaces: WorkspaceInfo[];
 };
 
 export async function login(
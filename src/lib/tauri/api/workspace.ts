@@ -0,0 +1,86 @@
+import {
+  Workspace,
+  WorkspaceInfo,
+  WorkspaceInfoSchema,
+  WorkspaceMembership,
+  WorkspaceMembershipSchema,
+  WorkspaceRole,
+  WorkspaceSchema,
+} from '@/lib/schemas/workspace';
+import { AuthPayload } from '@/lib/tauri/api/user';
+import { invokeCommand } from '@/lib/tauri/invoke';
+import {
+  DeleteParams,
+  GetParams,
+  PostParams,
+  PutParams,
+} from '@/lib/tauri/types';
+
+export interface WorkspaceForm {
+  name: string;
+}
+
+export interface WorkspaceMemberForm {
+  userId: string;
+  role: WorkspaceRole;
+}
+
+/** Leave `workspaceId` out to switch to the default workspace. */
+export interface SwitchWorkspaceForm {
+  workspaceId?: string;
+}
+
+export async function createWorkspace(
+  params: PostParams<WorkspaceForm>,
+): Promise<WorkspaceInfo> {
+  const response = await invokeCommand('create_workspace', params);
+  return await WorkspaceInfoSchema.parseAsync(response);
+}
+
+export async function listWorkspaces(): Promise<WorkspaceInfo[]> {
+  const response = await invokeCommand('list_workspaces', {});
+  return await WorkspaceInfoSchema.array().parseAsync(response);
+}
+
+export async function renameWorkspace(
+  params: PutParams<WorkspaceForm>,
+): Promise<Workspace> {
+  const response = await invokeCommand('rename_workspace', params);
+  return await WorkspaceSchema.parseAsync(response);
+}
+
+export async function deleteWorkspace(
+  params: DeleteParams,
+): Promise<Workspace> {
+  const response = await invokeCommand('delete_workspace', params);
+  return await WorkspaceSchema.parseAsync(response);
+}
+
+export async function listWorkspaceMembers(
+  params: GetParams,
+): Promise<WorkspaceMembership[]> {
+  const response = await invokeCommand('list_workspace_members', params);
+  return await WorkspaceMembershipSchema.array().parseAsync(response);
+}
+
+export async function setWorkspaceMember(
+  params: PutParams<WorkspaceMemberForm>,
+): Promise<WorkspaceMembership> {
+  const response = await invokeCommand('set_workspace_member', params);
+  return await WorkspaceMembershipSchema.parseAsync(response);
+}
+
+/** `data` is the id of the user to remove. */
+export async function removeWorkspaceMember(
+  params: PutParams<string>,
+): Promise<WorkspaceMembership> {
+  const response = await invokeCommand('remove_workspace_member', params);
+  return await WorkspaceMembershipSchema.parseAsync(response);
+}
+
+/** New tokens for another workspace; later calls must use them. */
+export async function switchWorkspace(
+  params: PostParams<SwitchWorkspaceForm>,
+): Promise<AuthPayload> {
+  return await invokeCommand('switch_workspace', params);
+}
@@ -5,8 +5,10 @@ export async function invokeCommand<T, R>(
   command: string,
   params: T,
 ): Promise<R> {
+  const token = localStorage.getItem('authToken');
   const response: IpcResponse<R> = await invoke<IpcResponse<R>>(command, {
     params,
+    token,
   });
 
   if (response.status === 'Error') {